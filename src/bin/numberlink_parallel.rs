@@ -0,0 +1,75 @@
+//! Compares the time of the uniqueness test by `solve2_parallel` with one and more threads.
+//!
+//! Usage: `numberlink_parallel [n_threads] [repeat]` (default: 4 threads, 3 times)
+//!
+//! Searches up to two answers of each problem in `bench/numberlink` `repeat` times
+//! with one thread (the same as `solve2`) and with `n_threads` threads, checking that both
+//! find the same number of answers and reporting the total time of each.
+
+extern crate puzrs;
+
+use puzrs::numberlink::*;
+use std::env;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::time::{Duration, Instant};
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    let n_threads = args.get(1).map_or(4, |s| s.parse::<usize>().unwrap());
+    let repeat = args.get(2).map_or(3, |s| s.parse::<usize>().unwrap());
+
+    let mut paths = fs::read_dir("bench/numberlink")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let secs = |d: Duration| d.as_secs() as f64 + d.subsec_nanos() as f64 / 1e9;
+    let mut sequential_total = Duration::new(0, 0);
+    let mut parallel_total = Duration::new(0, 0);
+    for path in &paths {
+        let problem =
+            read_penciloid_problem(&mut BufReader::new(File::open(path).unwrap())).unwrap();
+        let topology = Some(&problem.topology);
+        let mut sequential = Duration::new(0, 0);
+        let mut parallel = Duration::new(0, 0);
+        for _ in 0..repeat {
+            let start = Instant::now();
+            let expected = solve2_parallel(&problem.clues, topology, Some(2), false, true, 1, None);
+            sequential += start.elapsed();
+
+            let start = Instant::now();
+            let found = solve2_parallel(
+                &problem.clues,
+                topology,
+                Some(2),
+                false,
+                true,
+                n_threads,
+                None,
+            );
+            parallel += start.elapsed();
+
+            assert_eq!(found.len(), expected.len());
+        }
+        println!(
+            "{:40} 1 thread {:.4}s, {} threads {:.4}s",
+            path.display(),
+            secs(sequential),
+            n_threads,
+            secs(parallel)
+        );
+        sequential_total += sequential;
+        parallel_total += parallel;
+    }
+    println!(
+        "{:40} 1 thread {:.4}s, {} threads {:.4}s (x{:.2})",
+        "total",
+        secs(sequential_total),
+        n_threads,
+        secs(parallel_total),
+        secs(sequential_total) / secs(parallel_total)
+    );
+}
@@ -0,0 +1,116 @@
+//! Compares the solving time with borrowed and shared (`Arc`) dictionaries.
+//!
+//! Usage: `shared_dictionary [repeat]` (default: 5)
+//!
+//! Solves the Slitherlink, Kakuro and Tapa problems in `bench/<genre>` `repeat` times
+//! with the dictionaries held by `SharedRef::Borrowed` and by `SharedRef::Shared`,
+//! reporting the total time of each.
+
+extern crate puzrs;
+
+use puzrs::*;
+use std::env;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+fn problems(dir: &str) -> Vec<PathBuf> {
+    let mut ret = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect::<Vec<_>>();
+    ret.sort();
+    ret
+}
+
+/// Solves the problem in the file, returning the time excluding the file reading.
+type Solver = fn(&PathBuf, bool) -> Duration;
+
+fn slitherlink(path: &PathBuf, shared: bool) -> Duration {
+    let problem =
+        slitherlink::read_penciloid_problem(&mut BufReader::new(File::open(path).unwrap()))
+            .unwrap();
+    let dic = slitherlink::Dictionary::shared();
+    let start = Instant::now();
+    let mut field = if shared {
+        slitherlink::Field::new(&problem, &dic)
+    } else {
+        slitherlink::Field::new(&problem, &*dic)
+    };
+    field.trial_and_error(1);
+    start.elapsed()
+}
+
+fn kakuro(path: &PathBuf, shared: bool) -> Duration {
+    let problem =
+        kakuro::read_penciloid_problem(&mut BufReader::new(File::open(path).unwrap())).unwrap();
+    let dic = kakuro::Dictionary::shared();
+    let start = Instant::now();
+    let mut field = if shared {
+        kakuro::Field::new(&problem, &dic)
+    } else {
+        kakuro::Field::new(&problem, &*dic)
+    };
+    field.check_all();
+    start.elapsed()
+}
+
+fn tapa(path: &PathBuf, shared: bool) -> Duration {
+    let problem =
+        tapa::read_penciloid_problem(&mut BufReader::new(File::open(path).unwrap())).unwrap();
+    let dic = tapa::Dictionary::shared();
+    let consecutive_dic = tapa::ConsecutiveRegionDictionary::shared();
+    let (height, width) = (problem.height(), problem.width());
+    let start = Instant::now();
+    let mut field = if shared {
+        tapa::Field::new(height, width, &dic, &consecutive_dic)
+    } else {
+        tapa::Field::new(height, width, &*dic, &*consecutive_dic)
+    };
+    for y in 0..height {
+        for x in 0..width {
+            if problem[P(y, x)] != tapa::NO_CLUE {
+                field.add_clue(P(y, x), problem[P(y, x)]);
+            }
+        }
+    }
+    field.trial_and_error();
+    start.elapsed()
+}
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    let repeat = args.get(1).map_or(5, |s| s.parse::<usize>().unwrap());
+
+    let genres: [(&str, &str, Solver); 3] = [
+        ("slitherlink", "bench/slitherlink", slitherlink),
+        ("kakuro", "bench/kakuro", kakuro),
+        ("tapa", "bench/tapa", tapa),
+    ];
+    for &(name, dir, solve) in &genres {
+        let paths = problems(dir);
+        let mut borrowed = Duration::new(0, 0);
+        let mut shared = Duration::new(0, 0);
+        // warm up the shared dictionaries and the caches
+        for path in &paths {
+            solve(path, false);
+        }
+        // alternate the modes so that both are measured under the same conditions
+        for _ in 0..repeat {
+            for path in &paths {
+                borrowed += solve(path, false);
+                shared += solve(path, true);
+            }
+        }
+        let secs = |d: Duration| d.as_secs() as f64 + d.subsec_nanos() as f64 / 1e9;
+        println!(
+            "{:12} borrowed {:.4}s, shared {:.4}s ({:+.2}%)",
+            name,
+            secs(borrowed),
+            secs(shared),
+            (secs(shared) / secs(borrowed) - 1.0) * 100.0
+        );
+    }
+}
@@ -0,0 +1,87 @@
+//! Measures the effect of `TwoCellDictionary` on generated Slitherlink problems.
+//!
+//! Usage: `slitherlink_two_cell [n_problems] [seed]` (default: 100 problems, seed 42)
+//!
+//! Generates 8x8 problems with 24 clues which are solved by trial and error of depth 1,
+//! and solves each of them by `Field::solve` with and without the two-cell dictionary,
+//! reporting the number of fully solved problems, decided edges and the solving time.
+
+extern crate puzrs;
+extern crate rand;
+
+use puzrs::slitherlink::*;
+use puzrs::*;
+use rand::prng::XorShiftRng;
+use rand::{Rng, SeedableRng};
+use std::env;
+use std::time::{Duration, Instant};
+
+const HEIGHT: i32 = 8;
+const WIDTH: i32 = 8;
+const N_CLUES: usize = 24;
+
+#[derive(Default)]
+struct Summary {
+    n_solved: usize,
+    n_decided_edges: i64,
+    time: Duration,
+}
+
+fn solve(problem: &Grid<Clue>, two_cell: bool, summary: &mut Summary) {
+    let start = Instant::now();
+    let mut field = Field::new(problem, Dictionary::shared());
+    let mut technique = Technique::new();
+    technique.two_cell_dictionary = two_cell;
+    field.set_technique(technique);
+    if two_cell {
+        field.set_two_cell_dictionary(TwoCellDictionary::shared());
+    }
+    field.solve();
+    summary.time += start.elapsed();
+
+    assert!(!field.inconsistent());
+    if field.fully_solved() {
+        summary.n_solved += 1;
+    }
+    summary.n_decided_edges += field.num_decided_edges() as i64;
+}
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    let n_problems = args.get(1).map_or(100, |s| s.parse::<usize>().unwrap());
+    let seed = args.get(2).map_or(42, |s| s.parse::<u8>().unwrap());
+
+    let mut rng = XorShiftRng::from_seed([seed; 16]);
+    let dic = Dictionary::shared();
+    let opts = GeneratorOption {
+        technique: Technique::new(),
+        search_depth: 1,
+    };
+    // build the dictionaries before measuring
+    TwoCellDictionary::shared();
+
+    let mut without = Summary::default();
+    let mut with = Summary::default();
+    let mut n_generated = 0;
+    while n_generated < n_problems {
+        let mut cells = (0..(HEIGHT * WIDTH)).collect::<Vec<_>>();
+        rng.shuffle(&mut cells);
+        let mut has_clue = Grid::new(HEIGHT, WIDTH, false);
+        for &c in &cells[..N_CLUES] {
+            has_clue[P(c / WIDTH, c % WIDTH)] = true;
+        }
+        if let Some(problem) = generate_with_option(&has_clue, &dic, &opts, &mut rng) {
+            solve(&problem, false, &mut without);
+            solve(&problem, true, &mut with);
+            n_generated += 1;
+        }
+    }
+
+    for &(name, summary) in &[("without", &without), ("with", &with)] {
+        let time = summary.time.as_secs() as f64 + summary.time.subsec_nanos() as f64 / 1e9;
+        println!(
+            "{:8} two-cell dictionary: solved {:3}/{}, decided edges {:7}, time {:.3}s",
+            name, summary.n_solved, n_problems, summary.n_decided_edges, time
+        );
+    }
+}
@@ -0,0 +1,464 @@
+//! Benchmark over the problems checked in under `bench/<genre>/`.
+//!
+//! Each problem file is named `<tier>_<height>x<width>_<id>.txt` (e.g. `hard_18x18_01.txt`),
+//! where `<tier>` is one of `easy`, `medium`, `hard` and `very_hard`.
+//! Every problem is solved `repeat` times and the minimum time is reported together with
+//! whether the problem was solved and the genre-specific counter:
+//!
+//! - Numberlink: `n_steps` of `test_uniqueness` under the rule given in the problem header
+//! - Slitherlink / Masyu / Country Road: the number of decided edges
+//! - Kakuro / Tapa: the number of decided cells
+//!
+//! The results can be saved with `--output` and compared with a later run by `--compare`.
+
+use super::*;
+use {country_road, kakuro, masyu, numberlink, slitherlink, tapa, P};
+
+use super::getopts::{Matches, Options};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+struct BenchmarkOption {
+    corpus: PathBuf,
+    repeat: i32,
+    depth: i32,
+    output: Option<String>,
+    compare: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct BenchmarkResult {
+    name: String,
+    solved: bool,
+    time: f64,
+    steps: u64,
+}
+
+fn corpus_dir(puzzle: Puzzle) -> &'static str {
+    match puzzle {
+        Puzzle::Numberlink => "bench/numberlink",
+        Puzzle::Slitherlink => "bench/slitherlink",
+        Puzzle::Kakuro => "bench/kakuro",
+        Puzzle::Tapa => "bench/tapa",
+        Puzzle::Masyu => "bench/masyu",
+        Puzzle::CountryRoad => "bench/country_road",
+    }
+}
+
+const TIERS: [&str; 4] = ["easy", "medium", "hard", "very_hard"];
+
+/// Returns the tier of the problem `name`, i.e. `name` without the size and the id.
+fn tier_of(name: &str) -> &str {
+    name.rsplitn(3, '_').last().unwrap_or(name)
+}
+
+/// Returns the problem files in `dir` sorted by their names.
+fn list_problems(dir: &Path) -> Result<Vec<PathBuf>, CliError> {
+    let mut ret = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().map(|e| e == "txt").unwrap_or(false) {
+            ret.push(path);
+        }
+    }
+    ret.sort();
+    Ok(ret)
+}
+
+/// Runs `solve` `repeat` times and returns its result with the minimum elapsed time.
+fn measure<F>(repeat: i32, mut solve: F) -> (bool, u64, f64)
+where
+    F: FnMut() -> (bool, u64),
+{
+    let mut best = None;
+    for _ in 0..repeat {
+        let start = Instant::now();
+        let (solved, steps) = solve();
+        let end = start.elapsed();
+        let cost_time = end.as_secs() as f64 + end.subsec_nanos() as f64 / 1e9f64;
+        best = match best {
+            Some((_, _, t)) if t <= cost_time => best,
+            _ => Some((solved, steps, cost_time)),
+        };
+    }
+    best.unwrap()
+}
+
+fn run_problem(
+    puzzle: Puzzle,
+    path: &Path,
+    opts: &BenchmarkOption,
+) -> Result<BenchmarkResult, CliError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let depth = opts.depth;
+    let (solved, steps, time) = match puzzle {
+        Puzzle::Numberlink => {
+            let problem = numberlink::read_penciloid_problem(&mut reader)?;
+            measure(opts.repeat, || {
+                let summary = numberlink::test_uniqueness(
+                    &problem.clues,
+                    Some(&problem.topology),
+                    problem.rule,
+                    None,
+                );
+                (
+                    summary.exhaustive && summary.n_answers == 1,
+                    summary.n_steps,
+                )
+            })
+        }
+        Puzzle::Slitherlink => {
+            let problem = slitherlink::read_penciloid_problem(&mut reader)?;
+            let dic = slitherlink::Dictionary::shared();
+            measure(opts.repeat, || {
+                let mut field = slitherlink::Field::new(&problem, &dic);
+                field.trial_and_error(depth);
+                (
+                    field.fully_solved() && !field.inconsistent(),
+                    field.num_decided_edges() as u64,
+                )
+            })
+        }
+        Puzzle::Kakuro => {
+            let problem = kakuro::read_penciloid_problem(&mut reader)?;
+            let dic = kakuro::Dictionary::shared();
+            let n_cells = (0..problem.height())
+                .flat_map(|y| (0..problem.width()).map(move |x| P(y, x)))
+                .filter(|&pos| matches!(problem[pos], kakuro::Clue::NoClue))
+                .count() as u64;
+            measure(opts.repeat, || {
+                let mut field = kakuro::Field::new(&problem, &dic);
+                field.check_all();
+                (
+                    field.solved() && !field.inconsistent(),
+                    n_cells - field.undecided_cells() as u64,
+                )
+            })
+        }
+        Puzzle::Tapa => {
+            let problem = tapa::read_penciloid_problem(&mut reader)?;
+            let dic = tapa::Dictionary::shared();
+            let consecutive_dic = tapa::ConsecutiveRegionDictionary::shared();
+            measure(opts.repeat, || {
+                let mut field =
+                    tapa::Field::new(problem.height(), problem.width(), &dic, &consecutive_dic);
+                for y in 0..problem.height() {
+                    for x in 0..problem.width() {
+                        if problem[P(y, x)] != tapa::NO_CLUE {
+                            field.add_clue(P(y, x), problem[P(y, x)]);
+                        }
+                    }
+                }
+                field.trial_and_error();
+                (
+                    field.fully_solved() && !field.inconsistent(),
+                    field.decided_cells() as u64,
+                )
+            })
+        }
+        Puzzle::Masyu => {
+            let problem = masyu::read_penciloid_problem(&mut reader)?;
+            measure(opts.repeat, || {
+                let mut field = masyu::Field::new(&problem);
+                field.trial_and_error(depth);
+                (
+                    field.fully_solved() && !field.inconsistent(),
+                    field.num_decided_edges() as u64,
+                )
+            })
+        }
+        Puzzle::CountryRoad => {
+            let problem = country_road::read_penciloid_problem(&mut reader)?;
+            measure(opts.repeat, || {
+                let mut field = country_road::Field::new(&problem);
+                field.trial_and_error(depth);
+                (
+                    field.fully_solved() && !field.inconsistent(),
+                    field.num_decided_edges() as u64,
+                )
+            })
+        }
+    };
+    let name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Ok(BenchmarkResult {
+        name,
+        solved,
+        time,
+        steps,
+    })
+}
+
+fn write_results<W: Write>(writer: &mut W, results: &[BenchmarkResult]) -> io::Result<()> {
+    writeln!(writer, "% name solved time steps")?;
+    for r in results {
+        writeln!(
+            writer,
+            "{} {} {:.6} {}",
+            r.name,
+            if r.solved { 1 } else { 0 },
+            r.time,
+            r.steps
+        )?;
+    }
+    Ok(())
+}
+
+/// Reads results written by `write_results`.
+fn read_results<T: BufRead>(reader: &mut T) -> Result<Vec<BenchmarkResult>, ReadError> {
+    let mut ret = vec![];
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('%') {
+            continue;
+        }
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        if tokens.len() != 4 {
+            return Err(ReadError::InvalidFormat);
+        }
+        ret.push(BenchmarkResult {
+            name: String::from(tokens[0]),
+            solved: tokens[1] == "1",
+            time: tokens[2].parse().map_err(|_| ReadError::InvalidValue)?,
+            steps: tokens[3].parse().map_err(|_| ReadError::InvalidValue)?,
+        });
+    }
+    Ok(ret)
+}
+
+fn print_summary(results: &[BenchmarkResult]) {
+    // unknown tiers follow the known ones
+    let mut tiers: BTreeMap<(usize, &str), (usize, usize, f64, f64)> = BTreeMap::new();
+    for r in results {
+        let tier = tier_of(&r.name);
+        let rank = TIERS.iter().position(|&t| t == tier).unwrap_or(TIERS.len());
+        let entry = tiers.entry((rank, tier)).or_insert((0, 0, 0.0, 0.0));
+        entry.0 += 1;
+        if r.solved {
+            entry.1 += 1;
+        }
+        entry.2 += r.time;
+        entry.3 = entry.3.max(r.time);
+    }
+    println!("tier          count   solved     total[s]       max[s]");
+    for (&(_, tier), &(count, solved, total, max)) in &tiers {
+        println!(
+            "{:<12} {:>6} {:>7.1}% {:>12.6} {:>12.6}",
+            tier,
+            count,
+            solved as f64 / count as f64 * 100.0,
+            total,
+            max
+        );
+    }
+}
+
+/// Prints the comparison of `current` against `base` for the problems in both runs.
+fn print_comparison(base: &[BenchmarkResult], current: &[BenchmarkResult]) {
+    println!("name                          base[s]   current[s]    ratio  notes");
+    let mut total_base = 0.0f64;
+    let mut total_current = 0.0f64;
+    let mut log_ratio_sum = 0.0f64;
+    let mut n_compared = 0;
+    for r in current {
+        let b = match base.iter().find(|b| b.name == r.name) {
+            Some(b) => b,
+            None => continue,
+        };
+        let ratio = r.time / b.time.max(1e-9);
+        let mut notes = vec![];
+        if b.solved != r.solved {
+            notes.push(String::from(if r.solved {
+                "newly solved"
+            } else {
+                "no longer solved"
+            }));
+        }
+        if b.steps != r.steps {
+            notes.push(format!("steps {} -> {}", b.steps, r.steps));
+        }
+        let line = format!(
+            "{:<24} {:>12.6} {:>12.6} {:>8.3}  {}",
+            r.name,
+            b.time,
+            r.time,
+            ratio,
+            notes.join(", ")
+        );
+        println!("{}", line.trim_end());
+        total_base += b.time;
+        total_current += r.time;
+        log_ratio_sum += ratio.max(1e-9).ln();
+        n_compared += 1;
+    }
+    if n_compared > 0 {
+        println!(
+            "total: {:.6}[s] -> {:.6}[s] (ratio {:.3}, geometric mean of ratios {:.3})",
+            total_base,
+            total_current,
+            total_current / total_base.max(1e-9),
+            (log_ratio_sum / n_compared as f64).exp()
+        );
+    }
+}
+
+fn parse_options(matches: &Matches, puzzle: Puzzle) -> Result<BenchmarkOption, CliError> {
+    let corpus = PathBuf::from(
+        matches
+            .opt_str("corpus")
+            .unwrap_or_else(|| String::from(corpus_dir(puzzle))),
+    );
+    let repeat = parse_integer_argument(matches, "repeat", Some(1), Some(1))?;
+    let depth = parse_integer_argument(matches, "depth", Some(1), Some(0))?;
+
+    Ok(BenchmarkOption {
+        corpus,
+        repeat,
+        depth,
+        output: matches.opt_str("output"),
+        compare: matches.opt_str("compare"),
+    })
+}
+
+fn run_benchmark(puzzle: Puzzle, opts: BenchmarkOption) -> Result<(), CliError> {
+    let base = match opts.compare {
+        Some(ref path) => Some(read_results(&mut BufReader::new(File::open(path)?))?),
+        None => None,
+    };
+
+    let mut results = vec![];
+    for path in list_problems(&opts.corpus)? {
+        let res = run_problem(puzzle, &path, &opts)?;
+        println!(
+            "{:<24} {:>8} {:>12.6} {:>12}",
+            res.name,
+            if res.solved { "solved" } else { "unsolved" },
+            res.time,
+            res.steps
+        );
+        results.push(res);
+    }
+    println!();
+    print_summary(&results);
+
+    if let Some(ref path) = opts.output {
+        let mut writer = BufWriter::new(File::create(path)?);
+        write_results(&mut writer, &results)?;
+    }
+    if let Some(ref base) = base {
+        println!();
+        print_comparison(base, &results);
+    }
+    Ok(())
+}
+
+pub fn benchmark_frontend(args: &[String], program: &str, puzzle: Puzzle) -> Result<(), CliError> {
+    let mut options = Options::new();
+    options.optflag("", "help", "Show this help menu");
+    options.optopt(
+        "c",
+        "corpus",
+        "Directory containing the problems (bench/<genre> by default)",
+        "bench/numberlink",
+    );
+    options.optopt("r", "repeat", "Number of runs for each problem", "3");
+    options.optopt(
+        "d",
+        "depth",
+        "Depth of trial and error for loop puzzles",
+        "1",
+    );
+    options.optopt("o", "output", "Save the results to the file", "result.txt");
+    options.optopt(
+        "b",
+        "compare",
+        "Compare the results with those saved by --output",
+        "base.txt",
+    );
+    options.optopt(
+        "",
+        "dictionary",
+        "Slitherlink dictionary file (created if it does not exist)",
+        "dic.bin",
+    );
+
+    let matches = options.parse(args)?;
+
+    if matches.opt_present("help") {
+        let brief = format!("Usage: {} <genre>-bench [options]", program);
+        print!("{}", options.usage(&brief));
+        return Ok(());
+    }
+
+    let opts = parse_options(&matches, puzzle)?;
+    super::load_dictionary_option(&matches, puzzle)?;
+    run_benchmark(puzzle, opts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_benchmark_tier_of() {
+        assert_eq!(tier_of("easy_8x8_01"), "easy");
+        assert_eq!(tier_of("very_hard_25x25_02"), "very_hard");
+    }
+
+    #[test]
+    fn test_benchmark_results_round_trip() {
+        let results = vec![
+            BenchmarkResult {
+                name: String::from("easy_8x8_01"),
+                solved: true,
+                time: 0.125,
+                steps: 42,
+            },
+            BenchmarkResult {
+                name: String::from("hard_18x18_01"),
+                solved: false,
+                time: 1.5,
+                steps: 0,
+            },
+        ];
+        let mut buffer = vec![];
+        write_results(&mut buffer, &results).unwrap();
+        let read = read_results(&mut &buffer[..]).unwrap();
+        assert_eq!(read, results);
+    }
+
+    #[test]
+    fn test_benchmark_corpus_is_readable() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        for &puzzle in &[
+            Puzzle::Numberlink,
+            Puzzle::Slitherlink,
+            Puzzle::Kakuro,
+            Puzzle::Tapa,
+            Puzzle::Masyu,
+            Puzzle::CountryRoad,
+        ] {
+            let problems = list_problems(&root.join(corpus_dir(puzzle))).unwrap();
+            assert!(!problems.is_empty());
+            for path in &problems {
+                let mut reader = BufReader::new(File::open(path).unwrap());
+                let ok = match puzzle {
+                    Puzzle::Numberlink => numberlink::read_penciloid_problem(&mut reader).is_ok(),
+                    Puzzle::Slitherlink => slitherlink::read_penciloid_problem(&mut reader).is_ok(),
+                    Puzzle::Kakuro => kakuro::read_penciloid_problem(&mut reader).is_ok(),
+                    Puzzle::Tapa => tapa::read_penciloid_problem(&mut reader).is_ok(),
+                    Puzzle::Masyu => masyu::read_penciloid_problem(&mut reader).is_ok(),
+                    Puzzle::CountryRoad => {
+                        country_road::read_penciloid_problem(&mut reader).is_ok()
+                    }
+                };
+                assert!(ok, "failed to read {}", path.display());
+            }
+        }
+    }
+}
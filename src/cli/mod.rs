@@ -21,10 +21,17 @@ macro_rules! run_timed {
 use std::env;
 use std::error;
 use std::fmt::{self, Display};
-use std::io;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
 use std::time::Instant;
 
+use io::ReadError;
+use slitherlink;
+
+mod benchmark;
 pub mod nl_generator;
+mod player;
+mod server;
 pub mod tapa_generator;
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
@@ -33,17 +40,22 @@ enum Puzzle {
     Slitherlink,
     Kakuro,
     Tapa,
+    Masyu,
+    CountryRoad,
 }
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 enum Mode {
     Solver,
     Generator,
+    Benchmark,
+    Play,
 }
 
 #[derive(Debug)]
 pub enum CliError {
     Io(io::Error),
     Getopts(getopts::Fail),
+    Read(ReadError),
     InvalidSubcommand,
     MissingOption(&'static str),
     UnrecognizedArgument(&'static str),
@@ -60,11 +72,18 @@ impl From<getopts::Fail> for CliError {
     }
 }
 
+impl From<ReadError> for CliError {
+    fn from(err: ReadError) -> CliError {
+        CliError::Read(err)
+    }
+}
+
 impl fmt::Display for CliError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             CliError::Io(ref err) => Display::fmt(err, f),
             CliError::Getopts(ref err) => Display::fmt(err, f),
+            CliError::Read(ref err) => Display::fmt(err, f),
             CliError::InvalidSubcommand => write!(f, "invalid subcommand"),
             CliError::MissingOption(opt) => write!(f, "missing a required option '{}'", opt),
             CliError::UnrecognizedArgument(opt) => {
@@ -74,15 +93,17 @@ impl fmt::Display for CliError {
     }
 }
 
-impl error::Error for CliError {
-    fn description(&self) -> &str {
-        match *self {
-            CliError::Io(ref err) => err.description(),
-            CliError::Getopts(ref err) => err.description(),
-            CliError::InvalidSubcommand => "invalid subcommand",
-            CliError::MissingOption(_) => "missing a required option",
-            CliError::UnrecognizedArgument(_) => "unrecognized argument",
-        }
+impl error::Error for CliError {}
+
+fn parse_puzzle(name: &str) -> Option<Puzzle> {
+    match name.to_ascii_lowercase().as_str() {
+        "nl" | "numberlink" => Some(Puzzle::Numberlink),
+        "sl" | "slitherlink" => Some(Puzzle::Slitherlink),
+        "kk" | "kakuro" => Some(Puzzle::Kakuro),
+        "tp" | "tapa" => Some(Puzzle::Tapa),
+        "ms" | "masyu" => Some(Puzzle::Masyu),
+        "cr" | "country_road" => Some(Puzzle::CountryRoad),
+        _ => None,
     }
 }
 
@@ -93,16 +114,12 @@ fn parse_subcommand(subcommand: &str) -> Result<(Puzzle, Mode), CliError> {
         return Err(CliError::InvalidSubcommand);
     }
 
-    let puzzle = match tokens[0].to_ascii_lowercase().as_str() {
-        "nl" | "numberlink" => Some(Puzzle::Numberlink),
-        "sl" | "slitherlink" => Some(Puzzle::Slitherlink),
-        "kk" | "kakuro" => Some(Puzzle::Kakuro),
-        "tp" | "tapa" => Some(Puzzle::Tapa),
-        _ => None,
-    };
+    let puzzle = parse_puzzle(tokens[0]);
     let mode = match tokens[1].to_ascii_lowercase().as_str() {
         "sol" | "solver" => Some(Mode::Solver),
         "gen" | "generator" => Some(Mode::Generator),
+        "bench" | "benchmark" => Some(Mode::Benchmark),
+        "play" => Some(Mode::Play),
         _ => None,
     };
 
@@ -133,6 +150,38 @@ fn parse_integer_argument(
     Ok(res)
 }
 
+/// Makes the Slitherlink dictionary cached in `path` the shared one.
+/// If `path` does not exist or holds a dictionary of another version, the dictionary is built
+/// and saved to `path` for the later runs.
+fn load_slitherlink_dictionary(path: &str) -> Result<(), CliError> {
+    if let Ok(file) = File::open(path) {
+        match slitherlink::Dictionary::read_binary(&mut BufReader::new(file)) {
+            Ok(dic) => {
+                slitherlink::Dictionary::set_shared(dic);
+                return Ok(());
+            }
+            Err(err) => eprintln!("warning: rebuilding the dictionary {}: {}", path, err),
+        }
+    }
+    let dic = slitherlink::Dictionary::complete();
+    dic.write_binary(&mut BufWriter::new(File::create(path)?))?;
+    slitherlink::Dictionary::set_shared(dic);
+    Ok(())
+}
+
+/// Loads the dictionary given by `--dictionary`, which is only supported for Slitherlink.
+fn load_dictionary_option(matches: &getopts::Matches, puzzle: Puzzle) -> Result<(), CliError> {
+    match matches.opt_str("dictionary") {
+        Some(path) => {
+            if puzzle != Puzzle::Slitherlink {
+                return Err(CliError::UnrecognizedArgument("dictionary"));
+            }
+            load_slitherlink_dictionary(&path)
+        }
+        None => Ok(()),
+    }
+}
+
 pub fn run_cli() {
     let args: Vec<String> = env::args().collect();
     let program = args[0].clone();
@@ -141,23 +190,40 @@ pub fn run_cli() {
         println!("error: no subcommand given");
         return;
     }
-    // parse subcommand
-    let subcommand = parse_subcommand(&args[1]);
-
-    let result = subcommand.and_then(|subcommand| match subcommand {
-        (Puzzle::Numberlink, Mode::Generator) => {
-            nl_generator::nl_generator_frontend(&args[2..], &program)
-        }
-        (Puzzle::Tapa, Mode::Generator) => {
-            tapa_generator::tapa_generator_frontend(&args[2..], &program)
+    if args[1] == "serve" {
+        if let Err(err) = server::server_frontend(&args[2..], &program) {
+            println!("error: {}", err);
         }
-        _ => unimplemented!(),
-    });
+        return;
+    }
+    // parse subcommand; `play <genre>` is the same as `<genre>-play`
+    let (subcommand, rest) = if args[1] == "play" && args.len() >= 3 {
+        (parse_subcommand(&format!("{}-play", args[2])), &args[3..])
+    } else {
+        (parse_subcommand(&args[1]), &args[2..])
+    };
+
+    let result = subcommand.and_then(|subcommand| run_subcommand(subcommand, rest, &program));
     if result.is_err() {
         println!("error: {}", result.unwrap_err());
     }
 }
 
+fn run_subcommand(
+    subcommand: (Puzzle, Mode),
+    args: &[String],
+    program: &str,
+) -> Result<(), CliError> {
+    match subcommand {
+        (Puzzle::Numberlink, Mode::Generator) => nl_generator::nl_generator_frontend(args, program),
+        (Puzzle::Tapa, Mode::Generator) => tapa_generator::tapa_generator_frontend(args, program),
+        (puzzle, Mode::Benchmark) => benchmark::benchmark_frontend(args, program, puzzle),
+        (puzzle, Mode::Play) => player::player_frontend(args, program, puzzle),
+        // solvers and the other generators are only available as the library and the server
+        _ => Err(CliError::InvalidSubcommand),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -173,18 +239,29 @@ mod tests {
             (Puzzle::Numberlink, Mode::Generator)
         );
         assert_eq!(
-            parse_subcommand("slitherlink-sol").unwrap(),
-            (Puzzle::Slitherlink, Mode::Solver)
+            parse_subcommand("kakuro-gen").unwrap(),
+            (Puzzle::Kakuro, Mode::Generator)
         );
         assert_eq!(
-            parse_subcommand("sl-gen").unwrap(),
-            (Puzzle::Slitherlink, Mode::Generator)
+            parse_subcommand("ms-bench").unwrap(),
+            (Puzzle::Masyu, Mode::Benchmark)
         );
         assert_eq!(
-            parse_subcommand("kakuro-gen").unwrap(),
-            (Puzzle::Kakuro, Mode::Generator)
+            parse_subcommand("country_road-benchmark").unwrap(),
+            (Puzzle::CountryRoad, Mode::Benchmark)
+        );
+        assert_eq!(
+            parse_subcommand("tapa-play").unwrap(),
+            (Puzzle::Tapa, Mode::Play)
         );
         assert!(parse_subcommand("nosuchpuzzle-gen").is_err());
         assert!(parse_subcommand("a-b-c").is_err());
+
+        for &subcommand in &["slitherlink-sol", "sl-gen", "kakuro-gen"] {
+            match parse_subcommand(subcommand).and_then(|s| run_subcommand(s, &[], "puzrs")) {
+                Err(CliError::InvalidSubcommand) => (),
+                _ => panic!("{} must be rejected", subcommand),
+            }
+        }
     }
 }
@@ -1,9 +1,10 @@
 use super::*;
-use rand;
-use {numberlink, Symmetry, P};
+use rand::{self, Rng};
+use {numberlink, Grid, Symmetry, P};
 
 use super::getopts::{Matches, Options};
 use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 
@@ -19,7 +20,11 @@ struct GeneratorOption {
     max_clue: Option<i32>,
     corner: Option<(i32, i32)>,
     use_profiler: bool,
-    prioritized_extension: bool,
+    beam: numberlink::BeamSearchConfig,
+    rule: numberlink::Rule,
+    numbering: numberlink::Numbering,
+    difficulty: Option<(i32, i32)>,
+    solver_threads: i32,
 }
 
 fn parse_symmetry(s: String) -> Result<Symmetry, CliError> {
@@ -40,10 +45,49 @@ fn parse_symmetry(s: String) -> Result<Symmetry, CliError> {
     }
     return Ok(ret);
 }
+fn parse_score(s: String) -> Result<numberlink::FieldScore, CliError> {
+    match s.as_str() {
+        "random" => return Ok(numberlink::FieldScore::Random),
+        "endpoints" => return Ok(numberlink::FieldScore::FewerEndpoints(3)),
+        "decided" => return Ok(numberlink::FieldScore::MostDecided),
+        _ => (),
+    }
+    if let Some(weight) = s.strip_prefix("endpoints:") {
+        if let Ok(weight) = weight.parse::<i32>() {
+            if weight >= 1 {
+                return Ok(numberlink::FieldScore::FewerEndpoints(weight));
+            }
+        }
+    }
+    Err(CliError::UnrecognizedArgument("score"))
+}
+fn parse_seed_selection(s: String) -> Result<numberlink::SeedSelection, CliError> {
+    if s == "random" {
+        return Ok(numberlink::SeedSelection::Random);
+    }
+    if let Some(k) = s.strip_prefix("best:") {
+        if let Ok(k) = k.parse::<i32>() {
+            if k >= 1 {
+                return Ok(numberlink::SeedSelection::BestOf(k));
+            }
+        }
+    }
+    Err(CliError::UnrecognizedArgument("seed-selection"))
+}
+fn parse_numbering(s: String, symmetry: Symmetry) -> Result<numberlink::Numbering, CliError> {
+    match s.as_str() {
+        "random" => Ok(numberlink::Numbering::Random),
+        "reading" => Ok(numberlink::Numbering::ReadingOrder),
+        "length" => Ok(numberlink::Numbering::ByLength),
+        "symmetric" => Ok(numberlink::Numbering::Symmetric(symmetry)),
+        _ => Err(CliError::UnrecognizedArgument("numbering")),
+    }
+}
 fn parse_options(matches: &Matches) -> Result<GeneratorOption, CliError> {
     let height = parse_integer_argument(matches, "height", None, Some(1))?;
     let width = parse_integer_argument(matches, "width", None, Some(1))?;
     let jobs = parse_integer_argument(matches, "jobs", Some(1), Some(1))?;
+    let solver_threads = parse_integer_argument(matches, "solver-threads", Some(1), Some(1))?;
     let no_adjacent_clues = matches.opt_present("no-adjacent-clues");
     let symmetry = matches
         .opt_str("s")
@@ -68,7 +112,46 @@ fn parse_options(matches: &Matches) -> Result<GeneratorOption, CliError> {
         })
         .unwrap_or(Ok(None))?;
     let use_profiler = matches.opt_present("use-profiler");
-    let prioritized_extension = matches.opt_present("prioritized-extension");
+    let default_beam = numberlink::BeamSearchConfig::default();
+    let beam_width = parse_integer_argument(
+        matches,
+        "beam-width",
+        Some(default_beam.beam_width as i32),
+        Some(1),
+    )?;
+    let candidates_per_field = parse_integer_argument(
+        matches,
+        "candidates",
+        Some(default_beam.candidates_per_field as i32),
+        Some(1),
+    )?;
+    let score = matches
+        .opt_str("score")
+        .map(parse_score)
+        .unwrap_or(Ok(default_beam.score))?;
+    let seed_selection = if matches.opt_present("prioritized-extension") {
+        numberlink::SeedSelection::BestOf(5)
+    } else {
+        matches
+            .opt_str("seed-selection")
+            .map(parse_seed_selection)
+            .unwrap_or(Ok(default_beam.seed_selection))?
+    };
+    let beam = numberlink::BeamSearchConfig {
+        beam_width: beam_width as usize,
+        score,
+        candidates_per_field: candidates_per_field as usize,
+        seed_selection,
+    };
+    let rule = if matches.opt_present("fill-all") {
+        numberlink::Rule::FillAll
+    } else {
+        numberlink::Rule::Classic
+    };
+    let numbering = matches
+        .opt_str("numbering")
+        .map(|s| parse_numbering(s, symmetry))
+        .unwrap_or(Ok(numberlink::Numbering::Random))?;
     let corner = match matches.opt_str("corner") {
         Some(s) => {
             let split = s.split(",").collect::<Vec<&str>>();
@@ -88,6 +171,25 @@ fn parse_options(matches: &Matches) -> Result<GeneratorOption, CliError> {
         }
         None => None,
     };
+    let difficulty = match matches.opt_str("difficulty") {
+        Some(s) => {
+            let split = s.split(",").collect::<Vec<&str>>();
+            if split.len() != 2 {
+                return Err(CliError::UnrecognizedArgument("difficulty"));
+            }
+            let lo = split[0]
+                .parse::<i32>()
+                .map_err(|_| CliError::UnrecognizedArgument("difficulty"))?;
+            let hi = split[1]
+                .parse::<i32>()
+                .map_err(|_| CliError::UnrecognizedArgument("difficulty"))?;
+            if !(1 <= lo && lo <= hi) {
+                return Err(CliError::UnrecognizedArgument("difficulty"));
+            }
+            Some((lo, hi))
+        }
+        None => None,
+    };
     Ok(GeneratorOption {
         height,
         width,
@@ -99,125 +201,188 @@ fn parse_options(matches: &Matches) -> Result<GeneratorOption, CliError> {
         max_clue,
         corner,
         use_profiler,
-        prioritized_extension,
+        beam,
+        rule,
+        numbering,
+        difficulty,
+        solver_threads,
     })
 }
 
+/// Accumulated time spent in each stage of the generation, used by the profiler.
+#[derive(Clone, Default)]
+struct Costs {
+    generator: Arc<Mutex<f64>>,
+    pretest: Arc<Mutex<f64>>,
+    exact_test: Arc<Mutex<f64>>,
+}
+
+/// Makes one attempt to generate a problem satisfying `opts`.
+/// The attempt fails as soon as `cancel` is set.
+fn try_generate<R: Rng>(
+    opts: &GeneratorOption,
+    generator: &mut numberlink::PlacementGenerator,
+    costs: &Costs,
+    rng: &mut R,
+    cancel: Option<&AtomicBool>,
+) -> Option<(Grid<numberlink::Clue>, Option<numberlink::Difficulty>)> {
+    let use_profiler = opts.use_profiler;
+    let cost_generator = &costs.generator;
+    let cost_pretest = &costs.pretest;
+    let cost_exact_test = &costs.exact_test;
+
+    let end = numberlink::generate_endpoint_constraint(
+        opts.height,
+        opts.width,
+        opts.empty_width,
+        opts.corner,
+        opts.symmetry,
+        rng,
+    );
+    let opt = numberlink::GeneratorOption {
+        chain_threshold: opts.minimum_path_length,
+        endpoint_constraint: Some(&end),
+        forbid_adjacent_clue: opts.no_adjacent_clues,
+        symmetry: opts.symmetry,
+        clue_limit: opts.max_clue,
+        topology: None,
+        rule: opts.rule,
+        numbering: opts.numbering,
+    };
+
+    let placement = run_timed!(cost_generator, use_profiler, generator.generate(&opt, rng))?;
+
+    // pretest
+    let pretest_res = run_timed!(
+        cost_pretest,
+        use_profiler,
+        numberlink::uniqueness_pretest(&placement, opts.rule)
+    );
+    if !pretest_res {
+        return None;
+    }
+
+    let problem = numberlink::extract_problem(&placement, opts.numbering, rng);
+
+    let unique = run_timed!(
+        cost_exact_test,
+        use_profiler,
+        if opts.solver_threads > 1 {
+            let fill_all = opts.rule == numberlink::Rule::FillAll;
+            let answers = numberlink::solve2_parallel(
+                &problem,
+                None,
+                Some(2),
+                fill_all,
+                !fill_all,
+                opts.solver_threads as usize,
+                cancel,
+            );
+            answers.len() == 1 && !answers.found_not_fully_filled
+        } else {
+            let summary = numberlink::test_uniqueness(&problem, None, opts.rule, cancel);
+            summary.exhaustive && summary.n_answers == 1
+        }
+    );
+    // a cancelled search may have missed the other answers
+    if !unique || cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+        return None;
+    }
+
+    let difficulty = match opts.difficulty {
+        Some((lo, hi)) => {
+            match numberlink::estimate_difficulty(&problem, None, opts.rule, cancel) {
+                Some(d) if lo <= d.level && d.level <= hi => Some(d),
+                _ => return None,
+            }
+        }
+        None => None,
+    };
+    Some((problem, difficulty))
+}
+
+/// Writes `problem` in the format read by `numberlink::read_penciloid_problem`.
+fn write_problem<W: Write>(
+    handle: &mut W,
+    problem: &Grid<numberlink::Clue>,
+    rule: numberlink::Rule,
+    difficulty: Option<&numberlink::Difficulty>,
+) -> io::Result<()> {
+    let height = problem.height();
+    let width = problem.width();
+    if let Some(d) = difficulty {
+        writeln!(handle, "% difficulty: level {}, score {}", d.level, d.score)?;
+    }
+    match rule {
+        numberlink::Rule::Classic => writeln!(handle, "{} {}", height, width)?,
+        numberlink::Rule::FillAll => writeln!(handle, "{} {} rule:fill", height, width)?,
+    }
+    for y in 0..height {
+        for x in 0..width {
+            let numberlink::Clue(c) = problem[P(y, x)];
+            if c >= 1 {
+                write!(handle, "{}{}", c, if x == width - 1 { '\n' } else { ' ' })?;
+            } else {
+                write!(handle, ".{}", if x == width - 1 { '\n' } else { ' ' })?;
+            }
+        }
+    }
+    Ok(())
+}
+
 fn run_generator(opts: GeneratorOption) -> Result<(), CliError> {
-    let height = opts.height;
-    let width = opts.width;
     let mut ths = vec![];
     let gen_probs = Arc::new(Mutex::new(0i64));
 
     // profiling
     let use_profiler = opts.use_profiler;
-    let cost_generator = Arc::new(Mutex::new(0.0f64));
-    let cost_pretest = Arc::new(Mutex::new(0.0f64));
-    let cost_exact_test = Arc::new(Mutex::new(0.0f64));
+    let costs = Costs::default();
 
     for _ in 0..opts.jobs {
         let gen_probs = gen_probs.clone();
-        let cost_generator = cost_generator.clone();
-        let cost_pretest = cost_pretest.clone();
-        let cost_exact_test = cost_exact_test.clone();
+        let costs = costs.clone();
 
         let opts = opts;
 
         ths.push(thread::spawn(move || {
             let start = Instant::now();
 
-            let mut generator = numberlink::PlacementGenerator::new(height, width);
+            let mut generator =
+                numberlink::PlacementGenerator::with_config(opts.height, opts.width, opts.beam);
 
             let mut rng = rand::thread_rng();
             loop {
-                let end = numberlink::generate_endpoint_constraint(
-                    height,
-                    width,
-                    opts.empty_width,
-                    opts.corner,
-                    opts.symmetry,
-                    &mut rng,
-                );
-                let opt = numberlink::GeneratorOption {
-                    chain_threshold: opts.minimum_path_length,
-                    endpoint_constraint: Some(&end),
-                    forbid_adjacent_clue: opts.no_adjacent_clues,
-                    symmetry: opts.symmetry,
-                    clue_limit: opts.max_clue,
-                    prioritized_extension: opts.prioritized_extension,
-                };
-
-                let placement = run_timed!(
-                    cost_generator,
-                    use_profiler,
-                    generator.generate(&opt, &mut rng)
-                );
-                if let Some(placement) = placement {
-                    // pretest
-                    let pretest_res = run_timed!(
-                        cost_pretest,
-                        use_profiler,
-                        numberlink::uniqueness_pretest(&placement)
-                    );
-                    if !pretest_res {
-                        continue;
-                    }
+                if let Some((problem, difficulty)) =
+                    try_generate(&opts, &mut generator, &costs, &mut rng, None)
+                {
+                    let stdin = io::stdout();
+                    let handle = &mut stdin.lock();
 
-                    let problem = numberlink::extract_problem(&placement, &mut rng);
-                    //eprintln!("start solving");
-
-                    let ans = run_timed!(
-                        cost_exact_test,
-                        use_profiler,
-                        numberlink::solve2(&problem, Some(2), false, true)
+                    let end = start.elapsed();
+                    let cost_time =
+                        (end.as_secs() as f64 + end.subsec_nanos() as f64 / 1e9f64) / 60f64;
+                    let mut cnt = gen_probs.lock().unwrap();
+                    *cnt += 1;
+                    eprintln!(
+                        "{} problem(s) in {:.3}[min] ({:.3} [prob/min])",
+                        *cnt,
+                        cost_time,
+                        (*cnt) as f64 / cost_time
                     );
+                    if use_profiler {
+                        let cost_generator = *(costs.generator.lock().unwrap());
+                        let cost_pretest = *(costs.pretest.lock().unwrap());
+                        let cost_exact_test = *(costs.exact_test.lock().unwrap());
+                        let cost_total = cost_generator + cost_pretest + cost_exact_test;
 
-                    if ans.len() == 1 && !ans.found_not_fully_filled {
-                        let stdin = io::stdout();
-                        let handle = &mut stdin.lock();
-
-                        let end = start.elapsed();
-                        let cost_time =
-                            (end.as_secs() as f64 + end.subsec_nanos() as f64 / 1e9f64) / 60f64;
-                        let mut cnt = gen_probs.lock().unwrap();
-                        *cnt += 1;
-                        eprintln!(
-                            "{} problem(s) in {:.3}[min] ({:.3} [prob/min])",
-                            *cnt,
-                            cost_time,
-                            (*cnt) as f64 / cost_time
-                        );
-                        if use_profiler {
-                            let cost_generator = *(cost_generator.lock().unwrap());
-                            let cost_pretest = *(cost_pretest.lock().unwrap());
-                            let cost_exact_test = *(cost_exact_test.lock().unwrap());
-                            let cost_total = cost_generator + cost_pretest + cost_exact_test;
-
-                            eprintln!("Generator: {:.3}[s] ({:.2}%) / Pretest: {:.3}[s] ({:.2}%) / Exact test: {:.3}[s] ({:.2}%)",
-                                      cost_generator, cost_generator / cost_total * 100.0f64,
-                                      cost_pretest, cost_pretest / cost_total * 100.0f64,
-                                      cost_exact_test, cost_exact_test / cost_total * 100.0f64);
-                        }
-
-                        writeln!(handle, "{} {}", height, width).unwrap();
-                        for y in 0..height {
-                            for x in 0..width {
-                                let numberlink::Clue(c) = problem[P(y, x)];
-                                if c >= 1 {
-                                    write!(
-                                        handle,
-                                        "{}{}",
-                                        c,
-                                        if x == width - 1 { '\n' } else { ' ' }
-                                    ).unwrap();
-                                } else {
-                                    write!(handle, ".{}", if x == width - 1 { '\n' } else { ' ' })
-                                        .unwrap();
-                                }
-                            }
-                        }
-                        writeln!(handle).unwrap();
+                        eprintln!("Generator: {:.3}[s] ({:.2}%) / Pretest: {:.3}[s] ({:.2}%) / Exact test: {:.3}[s] ({:.2}%)",
+                                  cost_generator, cost_generator / cost_total * 100.0f64,
+                                  cost_pretest, cost_pretest / cost_total * 100.0f64,
+                                  cost_exact_test, cost_exact_test / cost_total * 100.0f64);
                     }
+
+                    write_problem(handle, &problem, opts.rule, difficulty.as_ref()).unwrap();
+                    writeln!(handle).unwrap();
                 }
             }
         }));
@@ -228,12 +393,40 @@ fn run_generator(opts: GeneratorOption) -> Result<(), CliError> {
     Ok(())
 }
 
-pub fn nl_generator_frontend(args: &[String], program: &str) -> Result<(), CliError> {
+/// Generates a problem with the options given in the same form as the command line of
+/// `nl-gen`, and returns it in the format of `write_problem`.
+/// Returns `None` if `cancel` is set before a problem is found. `--jobs` is ignored.
+pub fn generate_problem(args: &[String], cancel: &AtomicBool) -> Result<Option<String>, CliError> {
+    let matches = generator_options().parse(args)?;
+    let opts = parse_options(&matches)?;
+    let mut generator =
+        numberlink::PlacementGenerator::with_config(opts.height, opts.width, opts.beam);
+    let costs = Costs::default();
+    let mut rng = rand::thread_rng();
+    while !cancel.load(Ordering::Relaxed) {
+        if let Some((problem, difficulty)) =
+            try_generate(&opts, &mut generator, &costs, &mut rng, Some(cancel))
+        {
+            let mut buf = vec![];
+            write_problem(&mut buf, &problem, opts.rule, difficulty.as_ref())?;
+            return Ok(Some(String::from_utf8(buf).unwrap()));
+        }
+    }
+    Ok(None)
+}
+
+fn generator_options() -> Options {
     let mut options = Options::new();
     options.optflag("", "help", "Show this help menu");
     options.optopt("h", "height", "Height of desired problems", "10");
     options.optopt("w", "width", "Width of desired problems", "10");
     options.optopt("j", "jobs", "Number of workers (threads)", "2");
+    options.optopt(
+        "t",
+        "solver-threads",
+        "Number of threads used in the exact uniqueness test of each problem",
+        "4",
+    );
     options.optflag("a", "no-adjacent-clues", "Disallow adjacent clues");
     options.optopt("s", "symmetry", "Force symmetry", "180");
     options.optopt(
@@ -259,9 +452,54 @@ pub fn nl_generator_frontend(args: &[String], program: &str) -> Result<(), CliEr
     options.optflag(
         "r",
         "prioritized-extension",
-        "Use prioritized extension in generator",
+        "Use prioritized extension in generator (same as --seed-selection best:5)",
+    );
+    options.optopt(
+        "b",
+        "beam-width",
+        "Number of fields kept in the beam search",
+        "100",
     );
+    options.optopt(
+        "",
+        "score",
+        "Scoring of fields in the beam search (random, endpoints[:<weight>] or decided)",
+        "endpoints",
+    );
+    options.optopt(
+        "",
+        "candidates",
+        "Number of candidates per field in the beam search, used with --score",
+        "2",
+    );
+    options.optopt(
+        "",
+        "seed-selection",
+        "Choice of the vertex to be extended (random or best:<samples>)",
+        "best:5",
+    );
+    options.optflag(
+        "f",
+        "fill-all",
+        "Require every cell to be filled by a line (Flow rule)",
+    );
+    options.optopt(
+        "",
+        "numbering",
+        "Assignment of clue numbers (random, reading, length or symmetric)",
+        "reading",
+    );
+    options.optopt(
+        "",
+        "difficulty",
+        "Range of difficulty levels, from 1 (forced moves only) to 5 (exhaustive search)",
+        "3,4",
+    );
+    options
+}
 
+pub fn nl_generator_frontend(args: &[String], program: &str) -> Result<(), CliError> {
+    let options = generator_options();
     let matches = options.parse(&args[..])?;
 
     if matches.opt_present("help") {
@@ -0,0 +1,645 @@
+//! Interactive player for testing problems in a terminal.
+//!
+//! The board is drawn with ANSI escape sequences and the commands are read line by line,
+//! so that it works in any terminal without putting it into the raw mode.
+//! Each line may contain several commands, which are applied in order:
+//!
+//! - `w` / `a` / `s` / `d`: move the cursor up / left / down / right
+//! - `o`: put a line on the edge (or paint the cell black in Tapa)
+//! - `x`: mark the edge as blank (or the cell as white in Tapa)
+//! - `1`-`9`: write the number in the cell (Kakuro)
+//! - `.`: erase the mark under the cursor
+//! - `u`: undo the last mark
+//! - `h`: mark a cell or an edge which can be deduced from the current marks
+//! - `c`: check the answer
+//! - `q`: quit
+//!
+//! After each mark, the marks are given to the solver of the genre, and a contradiction
+//! detected by the solver (`inconsistent()`) is reported immediately.
+
+use super::*;
+use grid_loop::{Edge, GridLoop, GridLoopField};
+use {country_road, kakuro, masyu, slitherlink, tapa, Grid, Violation, LP, P};
+
+use super::getopts::Options;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::sync::atomic::AtomicBool;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mark {
+    Unset,
+    /// A line on an edge, or a black cell in Tapa.
+    Line,
+    /// A blank edge, or a white cell in Tapa.
+    Blank,
+    Value(i32),
+}
+
+pub enum Board {
+    Slitherlink(Grid<slitherlink::Clue>),
+    Masyu(Grid<masyu::Clue>),
+    CountryRoad(country_road::Problem),
+    Tapa(Grid<tapa::Clue>),
+    Kakuro(Grid<kakuro::Clue>),
+}
+
+impl Board {
+    pub fn read<T: BufRead>(puzzle: Puzzle, reader: &mut T) -> Result<Board, CliError> {
+        Ok(match puzzle {
+            Puzzle::Slitherlink => Board::Slitherlink(slitherlink::read_penciloid_problem(reader)?),
+            Puzzle::Masyu => Board::Masyu(masyu::read_penciloid_problem(reader)?),
+            Puzzle::CountryRoad => {
+                Board::CountryRoad(country_road::read_penciloid_problem(reader)?)
+            }
+            Puzzle::Tapa => Board::Tapa(tapa::read_penciloid_problem(reader)?),
+            Puzzle::Kakuro => Board::Kakuro(kakuro::read_penciloid_problem(reader)?),
+            Puzzle::Numberlink => return Err(CliError::InvalidSubcommand),
+        })
+    }
+    /// Returns the size of the board in cells.
+    fn size(&self) -> (i32, i32) {
+        match *self {
+            Board::Slitherlink(ref problem) => (problem.height(), problem.width()),
+            Board::Masyu(ref problem) => (problem.height(), problem.width()),
+            Board::CountryRoad(ref problem) => (problem.height(), problem.width()),
+            Board::Tapa(ref problem) => (problem.height(), problem.width()),
+            Board::Kakuro(ref problem) => (problem.height(), problem.width()),
+        }
+    }
+    /// Returns whether the marks are put on the edges rather than the cells.
+    pub fn is_loop(&self) -> bool {
+        match *self {
+            Board::Slitherlink(_) | Board::Masyu(_) | Board::CountryRoad(_) => true,
+            Board::Tapa(_) | Board::Kakuro(_) => false,
+        }
+    }
+    /// Returns the size of the grid of marks.
+    /// Loop genres use lattice positions: the vertices of the lines are at `LP(2y, 2x)`,
+    /// which are the cells in Masyu and Country Road. Otherwise `LP(y, x)` is the cell `P(y, x)`.
+    pub fn marks_size(&self) -> (i32, i32) {
+        let (height, width) = self.size();
+        match *self {
+            Board::Slitherlink(_) => (height * 2 + 1, width * 2 + 1),
+            Board::Masyu(_) | Board::CountryRoad(_) => (height * 2 - 1, width * 2 - 1),
+            Board::Tapa(_) | Board::Kakuro(_) => (height, width),
+        }
+    }
+    pub fn is_markable(&self, pos: LP) -> bool {
+        let LP(y, x) = pos;
+        match *self {
+            Board::Slitherlink(_) | Board::Masyu(_) | Board::CountryRoad(_) => y % 2 != x % 2,
+            Board::Tapa(ref problem) => problem[P(y, x)] == tapa::NO_CLUE,
+            Board::Kakuro(ref problem) => match problem[P(y, x)] {
+                kakuro::Clue::NoClue => true,
+                kakuro::Clue::Clue { .. } => false,
+            },
+        }
+    }
+    pub fn accepts(&self, mark: Mark) -> bool {
+        match (self, mark) {
+            (_, Mark::Unset) => true,
+            (&Board::Kakuro(_), Mark::Value(_)) => true,
+            (&Board::Kakuro(_), _) => false,
+            (_, Mark::Value(_)) => false,
+            _ => true,
+        }
+    }
+    /// Gives `marks` to the solver and returns the marks decided by it, or `None` if the
+    /// solver finds a contradiction. The solver runs its trial and error only if `solve` is set.
+    pub fn deduce(&self, marks: &Grid<Mark>, solve: bool) -> Option<Grid<Mark>> {
+        self.deduce_cancellable(marks, solve, &AtomicBool::new(false))
+    }
+    /// Same as `deduce`, but the trial and error gives up when `cancel` is set.
+    fn deduce_cancellable(
+        &self,
+        marks: &Grid<Mark>,
+        solve: bool,
+        cancel: &AtomicBool,
+    ) -> Option<Grid<Mark>> {
+        match *self {
+            Board::Slitherlink(ref problem) => {
+                let dic = slitherlink::Dictionary::shared();
+                let mut field = slitherlink::Field::new(problem, &dic);
+                apply_edges(&mut field, marks);
+                field.check_all_cell();
+                if solve && !field.inconsistent() {
+                    field.trial_and_error_cancellable(1, cancel);
+                }
+                deduced_edges(&mut field, marks)
+            }
+            Board::Masyu(ref problem) => {
+                let mut field = masyu::Field::new(problem);
+                apply_edges(&mut field, marks);
+                field.check_all_cell();
+                if solve && !field.inconsistent() {
+                    field.trial_and_error_cancellable(1, cancel);
+                }
+                deduced_edges(&mut field, marks)
+            }
+            Board::CountryRoad(ref problem) => {
+                let mut field = country_road::Field::new(problem);
+                apply_edges(&mut field, marks);
+                field.check_all_cell();
+                if solve && !field.inconsistent() {
+                    field.trial_and_error_cancellable(1, cancel);
+                }
+                deduced_edges(&mut field, marks)
+            }
+            Board::Tapa(ref problem) => {
+                let dic = tapa::Dictionary::shared();
+                let consecutive_dic = tapa::ConsecutiveRegionDictionary::shared();
+                let mut field =
+                    tapa::Field::new(problem.height(), problem.width(), &dic, &consecutive_dic);
+                for y in 0..problem.height() {
+                    for x in 0..problem.width() {
+                        if problem[P(y, x)] != tapa::NO_CLUE {
+                            field.add_clue(P(y, x), problem[P(y, x)]);
+                        }
+                    }
+                }
+                for_each_mark(marks, |pos, mark| match mark {
+                    Mark::Line => field.decide(P(pos.0, pos.1), tapa::Cell::Black),
+                    Mark::Blank => field.decide(P(pos.0, pos.1), tapa::Cell::White),
+                    _ => (),
+                });
+                if solve && !field.inconsistent() {
+                    field.trial_and_error_cancellable(cancel);
+                }
+                if field.inconsistent() {
+                    return None;
+                }
+                let mut ret = Grid::new(marks.height(), marks.width(), Mark::Unset);
+                for_each_mark(marks, |pos, _| {
+                    ret[pos] = match field.cell(P(pos.0, pos.1)) {
+                        tapa::Cell::Black => Mark::Line,
+                        tapa::Cell::White => Mark::Blank,
+                        tapa::Cell::Undecided => Mark::Unset,
+                    };
+                });
+                Some(ret)
+            }
+            Board::Kakuro(ref problem) => {
+                let dic = kakuro::Dictionary::shared();
+                let mut field = kakuro::Field::new(problem, &dic);
+                for_each_mark(marks, |pos, mark| {
+                    if let Mark::Value(v) = mark {
+                        field.decide(P(pos.0, pos.1), v);
+                    }
+                });
+                if solve && !field.inconsistent() {
+                    field.check_all();
+                }
+                if field.inconsistent() {
+                    return None;
+                }
+                let mut ret = Grid::new(marks.height(), marks.width(), Mark::Unset);
+                for_each_mark(marks, |pos, _| {
+                    let v = field.val(P(pos.0, pos.1));
+                    if v >= 1 {
+                        ret[pos] = Mark::Value(v);
+                    }
+                });
+                Some(ret)
+            }
+        }
+    }
+    /// Checks the marks as an answer with `check_answer` of the genre.
+    /// The positions of the violations are converted into those of the marks.
+    pub fn check(&self, marks: &Grid<Mark>) -> Vec<(&'static str, Vec<LP>)> {
+        let (height, width) = self.size();
+        let is_line = {
+            let mut is_line = Grid::new(marks.height(), marks.width(), false);
+            for_each_mark(marks, |pos, mark| is_line[pos] = mark == Mark::Line);
+            is_line
+        };
+        let (violations, cell_to_mark): (Vec<Violation>, fn(P) -> LP) = match *self {
+            Board::Slitherlink(ref problem) => {
+                (slitherlink::check_answer(problem, &is_line), LP::of_cell)
+            }
+            Board::Masyu(ref problem) => (masyu::check_answer(problem, &is_line), LP::of_vertex),
+            Board::CountryRoad(ref problem) => {
+                (country_road::check_answer(problem, &is_line), LP::of_vertex)
+            }
+            Board::Tapa(ref problem) => {
+                let mut is_black = Grid::new(height, width, false);
+                for y in 0..height {
+                    for x in 0..width {
+                        is_black[P(y, x)] = is_line[LP(y, x)];
+                    }
+                }
+                (tapa::check_answer(problem, &is_black), |P(y, x)| LP(y, x))
+            }
+            Board::Kakuro(ref problem) => {
+                let mut answer = Grid::new(height, width, 0);
+                for y in 0..height {
+                    for x in 0..width {
+                        if let Mark::Value(v) = marks[LP(y, x)] {
+                            answer[P(y, x)] = v;
+                        }
+                    }
+                }
+                (kakuro::check_answer(problem, &answer), |P(y, x)| LP(y, x))
+            }
+        };
+        violations
+            .into_iter()
+            .map(|v| {
+                let mut positions = v.cells.into_iter().map(cell_to_mark).collect::<Vec<_>>();
+                positions.extend(v.edges);
+                (v.kind.name(), positions)
+            })
+            .collect()
+    }
+    /// Returns an unset position decided by the solver from `marks` together with its mark,
+    /// or `Some(None)` if there is no such position. Returns `None` on a contradiction.
+    pub fn hint(&self, marks: &Grid<Mark>) -> Option<Option<(LP, Mark)>> {
+        self.hint_cancellable(marks, &AtomicBool::new(false))
+    }
+    /// Same as `hint`, but gives up the trial and error when `cancel` is set,
+    /// so that a hint may be missed.
+    pub fn hint_cancellable(
+        &self,
+        marks: &Grid<Mark>,
+        cancel: &AtomicBool,
+    ) -> Option<Option<(LP, Mark)>> {
+        let deduced = self.deduce_cancellable(marks, true, cancel)?;
+        let mut hint = None;
+        for_each_mark(marks, |pos, mark| {
+            if hint.is_none()
+                && mark == Mark::Unset
+                && deduced[pos] != Mark::Unset
+                && self.is_markable(pos)
+            {
+                hint = Some((pos, deduced[pos]));
+            }
+        });
+        Some(hint)
+    }
+    /// Returns the text drawn at `pos` of the grid of marks.
+    fn item(&self, marks: &Grid<Mark>, pos: LP) -> String {
+        let LP(y, x) = pos;
+        let mark = marks[pos];
+        match *self {
+            Board::Slitherlink(ref problem) => match (y % 2, x % 2) {
+                (0, 0) => String::from("+"),
+                (1, 1) => match problem[P(y / 2, x / 2)] {
+                    slitherlink::Clue(c) if c >= 0 => format!(" {} ", c),
+                    _ => String::from("   "),
+                },
+                _ => edge_item(pos, mark, false),
+            },
+            Board::Masyu(ref problem) => match (y % 2, x % 2) {
+                (0, 0) => String::from(match problem[P(y / 2, x / 2)] {
+                    masyu::Clue::White => "o",
+                    masyu::Clue::Black => "@",
+                    masyu::Clue::NoClue => ".",
+                }),
+                (1, 1) => String::from("   "),
+                _ => edge_item(pos, mark, false),
+            },
+            Board::CountryRoad(ref problem) => match (y % 2, x % 2) {
+                (0, 0) => {
+                    let cell = P(y / 2, x / 2);
+                    let room = problem.room(cell);
+                    let first = (0..problem.height())
+                        .flat_map(|y| (0..problem.width()).map(move |x| P(y, x)))
+                        .find(|&p| problem.room(p) == room);
+                    match problem.clue(room) {
+                        country_road::Clue(c) if (0..10).contains(&c) && first == Some(cell) => {
+                            format!("{}", c)
+                        }
+                        _ => String::from("."),
+                    }
+                }
+                (1, 1) => String::from("   "),
+                _ => {
+                    let (c1, c2) = (P(y / 2, x / 2), P((y + 1) / 2, (x + 1) / 2));
+                    edge_item(pos, mark, problem.room(c1) != problem.room(c2))
+                }
+            },
+            Board::Tapa(ref problem) => {
+                let tapa::Clue(c) = problem[P(y, x)];
+                if c >= 0 {
+                    let pattern = tapa::CLUE_VALUES[c as usize]
+                        .iter()
+                        .filter(|&&v| v != -1)
+                        .map(|v| v.to_string())
+                        .collect::<String>();
+                    format!("{:>4}", if c == 0 { String::from("0") } else { pattern })
+                } else {
+                    String::from(match mark {
+                        Mark::Line => "####",
+                        Mark::Blank => "  . ",
+                        _ => "    ",
+                    })
+                }
+            }
+            Board::Kakuro(ref problem) => match problem[P(y, x)] {
+                kakuro::Clue::Clue {
+                    horizontal,
+                    vertical,
+                } => {
+                    let show = |n: i32| if n > 0 { n.to_string() } else { String::new() };
+                    format!("{:>2}\\{:<2}", show(vertical), show(horizontal))
+                }
+                kakuro::Clue::NoClue => match mark {
+                    Mark::Value(v) => format!("  {}  ", v),
+                    _ => String::from("  .  "),
+                },
+            },
+        }
+    }
+}
+
+/// Returns the text of an edge: horizontal edges take three characters.
+/// An unset edge on a border of rooms is drawn with `:`.
+fn edge_item(pos: LP, mark: Mark, border: bool) -> String {
+    let horizontal = pos.0 % 2 == 0;
+    String::from(match (mark, horizontal) {
+        (Mark::Line, true) => "---",
+        (Mark::Line, false) => "|",
+        (Mark::Blank, true) => " x ",
+        (Mark::Blank, false) => "x",
+        (_, true) if border => " : ",
+        (_, false) if border => ":",
+        (_, true) => "   ",
+        (_, false) => " ",
+    })
+}
+
+fn for_each_mark<F: FnMut(LP, Mark)>(marks: &Grid<Mark>, mut f: F) {
+    for y in 0..marks.height() {
+        for x in 0..marks.width() {
+            f(LP(y, x), marks[LP(y, x)]);
+        }
+    }
+}
+
+fn apply_edges<T: GridLoopField>(field: &mut T, marks: &Grid<Mark>) {
+    for_each_mark(marks, |pos, mark| match mark {
+        Mark::Line => GridLoop::decide_edge(field, pos, Edge::Line),
+        Mark::Blank => GridLoop::decide_edge(field, pos, Edge::Blank),
+        _ => (),
+    });
+}
+
+fn deduced_edges<T: GridLoopField>(field: &mut T, marks: &Grid<Mark>) -> Option<Grid<Mark>> {
+    let grid_loop = field.grid_loop();
+    if grid_loop.inconsistent() {
+        return None;
+    }
+    let mut ret = Grid::new(marks.height(), marks.width(), Mark::Unset);
+    for_each_mark(marks, |pos, _| {
+        if pos.0 % 2 != pos.1 % 2 {
+            ret[pos] = match grid_loop.get_edge(pos) {
+                Edge::Line => Mark::Line,
+                Edge::Blank => Mark::Blank,
+                Edge::Undecided => Mark::Unset,
+            };
+        }
+    });
+    Some(ret)
+}
+
+struct Player {
+    board: Board,
+    marks: Grid<Mark>,
+    cursor: LP,
+    /// The marks overwritten by the moves, for undoing them.
+    history: Vec<(LP, Mark)>,
+    violations: Vec<LP>,
+    message: String,
+}
+
+impl Player {
+    fn new(board: Board) -> Player {
+        let (height, width) = board.marks_size();
+        // the first edge in loop genres, or the top-left cell otherwise
+        let cursor = if board.is_loop() { LP(0, 1) } else { LP(0, 0) };
+        Player {
+            board,
+            marks: Grid::new(height, width, Mark::Unset),
+            cursor,
+            history: vec![],
+            violations: vec![],
+            message: String::new(),
+        }
+    }
+    fn move_cursor(&mut self, dy: i32, dx: i32) {
+        let LP(y, x) = self.cursor;
+        let next = LP(y + dy, x + dx);
+        if self.marks.is_valid_lp(next) {
+            self.cursor = next;
+        }
+    }
+    fn put(&mut self, mark: Mark) {
+        let pos = self.cursor;
+        if !self.board.is_markable(pos) || !self.board.accepts(mark) {
+            self.message = String::from("cannot put the mark here");
+            return;
+        }
+        if self.marks[pos] == mark {
+            return;
+        }
+        self.history.push((pos, self.marks[pos]));
+        self.marks[pos] = mark;
+        self.violations.clear();
+        self.update_status();
+    }
+    fn undo(&mut self) {
+        match self.history.pop() {
+            Some((pos, mark)) => {
+                self.marks[pos] = mark;
+                self.cursor = pos;
+                self.violations.clear();
+                self.update_status();
+            }
+            None => self.message = String::from("nothing to undo"),
+        }
+    }
+    fn update_status(&mut self) {
+        self.message = if self.board.deduce(&self.marks, false).is_some() {
+            String::new()
+        } else {
+            String::from("contradiction!")
+        };
+    }
+    fn hint(&mut self) {
+        let hint = match self.board.hint(&self.marks) {
+            Some(hint) => hint,
+            None => {
+                self.message = String::from("contradiction! undo some marks with `u`");
+                return;
+            }
+        };
+        match hint {
+            Some((pos, mark)) => {
+                self.cursor = pos;
+                self.put(mark);
+                self.message = format!("hint: decided ({}, {})", pos.0, pos.1);
+            }
+            None => self.message = String::from("no hint available"),
+        }
+    }
+    fn check(&mut self) {
+        let violations = self.board.check(&self.marks);
+        if violations.is_empty() {
+            self.violations.clear();
+            self.message = String::from("solved!");
+            return;
+        }
+        let mut kinds = vec![];
+        for &(kind, _) in &violations {
+            if !kinds.contains(&kind) {
+                kinds.push(kind);
+            }
+        }
+        self.message = format!("{} violation(s): {}", violations.len(), kinds.join(", "));
+        self.violations = violations.into_iter().flat_map(|v| v.1).collect();
+    }
+    /// Applies the commands in `line`. Returns `false` if the player should quit.
+    fn command(&mut self, line: &str) -> bool {
+        for c in line.chars() {
+            match c {
+                'w' => self.move_cursor(-1, 0),
+                'a' => self.move_cursor(0, -1),
+                's' => self.move_cursor(1, 0),
+                'd' => self.move_cursor(0, 1),
+                'o' => self.put(Mark::Line),
+                'x' => self.put(Mark::Blank),
+                '.' => self.put(Mark::Unset),
+                '1'..='9' => self.put(Mark::Value(c as i32 - '0' as i32)),
+                'u' => self.undo(),
+                'h' => self.hint(),
+                'c' => self.check(),
+                'q' => return false,
+                _ if c.is_whitespace() => (),
+                _ => self.message = format!("unknown command '{}'", c),
+            }
+        }
+        true
+    }
+    fn render<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        write!(out, "\x1b[2J\x1b[H")?;
+        for y in 0..self.marks.height() {
+            for x in 0..self.marks.width() {
+                let pos = LP(y, x);
+                let item = self.board.item(&self.marks, pos);
+                if pos == self.cursor {
+                    write!(out, "\x1b[7m{}\x1b[0m", item)?;
+                } else if self.violations.contains(&pos) {
+                    write!(out, "\x1b[31m{}\x1b[0m", item)?;
+                } else {
+                    write!(out, "{}", item)?;
+                }
+                if !self.board.is_loop() {
+                    write!(out, " ")?;
+                }
+            }
+            writeln!(out)?;
+        }
+        writeln!(out)?;
+        if !self.message.is_empty() {
+            writeln!(out, "{}", self.message)?;
+        }
+        writeln!(
+            out,
+            "wasd: move, o/x/.: mark, 1-9: number, u: undo, h: hint, c: check, q: quit"
+        )?;
+        write!(out, "> ")?;
+        out.flush()
+    }
+    fn run<R: BufRead, W: Write>(&mut self, input: &mut R, out: &mut W) -> io::Result<()> {
+        let mut line = String::new();
+        loop {
+            self.render(out)?;
+            line.clear();
+            if input.read_line(&mut line)? == 0 || !self.command(&line) {
+                break;
+            }
+        }
+        writeln!(out)
+    }
+}
+
+pub fn player_frontend(args: &[String], program: &str, puzzle: Puzzle) -> Result<(), CliError> {
+    let mut options = Options::new();
+    options.optflag("", "help", "Show this help menu");
+    options.optopt(
+        "",
+        "dictionary",
+        "Slitherlink dictionary file (created if it does not exist)",
+        "dic.bin",
+    );
+
+    let matches = options.parse(args)?;
+
+    if matches.opt_present("help") || matches.free.len() != 1 {
+        let brief = format!("Usage: {} play <genre> <file> [options]", program);
+        print!("{}", options.usage(&brief));
+        return Ok(());
+    }
+    super::load_dictionary_option(&matches, puzzle)?;
+
+    let board = Board::read(puzzle, &mut BufReader::new(File::open(&matches.free[0])?))?;
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    Player::new(board).run(&mut stdin.lock(), &mut stdout.lock())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slitherlink_player() -> Player {
+        let src = "3 3\n212\n101\n212\n";
+        Player::new(Board::read(Puzzle::Slitherlink, &mut src.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn test_player_commands() {
+        let mut player = slitherlink_player();
+        assert_eq!(player.cursor, LP(0, 1));
+        assert!(player.command("o"));
+        assert_eq!(player.marks[LP(0, 1)], Mark::Line);
+        assert_eq!(player.message, "");
+
+        // a line around the center 0
+        assert!(player.command("ssddo"));
+        assert_eq!(player.marks[LP(2, 3)], Mark::Line);
+        assert_eq!(player.message, "contradiction!");
+        assert!(player.command("u"));
+        assert_eq!(player.marks[LP(2, 3)], Mark::Unset);
+        assert_eq!(player.message, "");
+
+        // numbers are only for Kakuro, and vertices cannot be marked
+        assert!(player.command("5"));
+        assert_eq!(player.message, "cannot put the mark here");
+        assert!(player.command("wo"));
+        assert_eq!(player.marks[LP(1, 3)], Mark::Unset);
+        assert_eq!(player.message, "cannot put the mark here");
+        assert!(!player.command("q"));
+    }
+
+    #[test]
+    fn test_player_hint_and_check() {
+        let mut player = slitherlink_player();
+        player.command("c");
+        assert!(player.message.contains("no_loop"));
+        assert!(!player.violations.is_empty());
+
+        for _ in 0..30 {
+            player.command("h");
+        }
+        assert_eq!(player.message, "no hint available");
+        player.command("c");
+        assert_eq!(player.message, "solved!");
+
+        let mut out = vec![];
+        player.render(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("+---+"));
+    }
+}
@@ -0,0 +1,968 @@
+//! JSON-over-HTTP service running the solvers and the generators.
+//!
+//! Each request is a `POST /` whose body is a JSON object:
+//!
+//! - `genre`: `nl`, `sl`, `kk`, `tp`, `ms` or `cr` (or their long names as in the subcommands)
+//! - `operation`: `solve`, `check`, `hint` or `generate`
+//! - `problem`: the problem in the same text format as the files given to the CLI
+//! - `answer`: the (partial) answer for `check` and `hint`
+//! - `options`: the options of `generate`, e.g. `{"height": 10, "symmetry": true}`,
+//!   which are passed to the generator as `--height 10 --symmetry`
+//! - `time_limit`: time limit in milliseconds, which cannot exceed that of the server
+//!
+//! Answers are in the schemas of the `json` module of each genre (e.g. `{"lines": edges}`).
+//! The partial answer of `hint` may also have the cells or edges known to be empty, by
+//! `blanks` (in the format of `lines`) in loop genres or `white` (that of `black`) in Tapa.
+//!
+//! `solve` returns `{"solved": bool, "unique": bool, "answer": answer}`, where `answer` is
+//! one of the answers (or `null`). `check` returns the violations of the answer, and `hint`
+//! a position in the lattice of `lines` (or a cell) with the mark to be put there.
+//!
+//! The response is a JSON object with the result, or `{"error": message}` with status 400
+//! (invalid request), 503 (time limit exceeded or too many connections) or 500.
+//! Problems larger than `MAX_BOARD_SIZE` are rejected, and so are the options of `generate`
+//! asking for such boards or more than `MAX_SOLVER_THREADS` threads. Only the flags in
+//! `ALLOWED_FLAGS` may be given by `true`.
+
+use super::*;
+use json::Json;
+use {country_road, json, kakuro, masyu, numberlink, slitherlink, tapa, Grid, LP, P};
+
+use super::getopts::Options;
+use super::player::{Board, Mark};
+use io::next_valid_line;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Maximum size of a request body in bytes.
+const MAX_BODY_SIZE: usize = 1 << 20;
+
+/// Maximum height and width of the boards.
+const MAX_BOARD_SIZE: i32 = 64;
+
+/// Maximum number of threads a request may ask the generators for.
+const MAX_SOLVER_THREADS: i32 = 8;
+
+/// Number of connections waiting for a worker before new ones are refused.
+const MAX_PENDING_CONNECTIONS: usize = 32;
+
+/// Flags of the generators which may be given by `true` in `options`.
+const ALLOWED_FLAGS: &[&str] = &[
+    "fill-all",
+    "no-adjacent-clues",
+    "prioritized-extension",
+    "symmetry",
+    "trial-and-error",
+];
+
+#[derive(Debug, PartialEq)]
+enum RequestError {
+    Invalid(String),
+    TimeLimitExceeded,
+    Internal,
+}
+
+impl RequestError {
+    fn status(&self) -> u16 {
+        match *self {
+            RequestError::Invalid(_) => 400,
+            RequestError::TimeLimitExceeded => 503,
+            RequestError::Internal => 500,
+        }
+    }
+    fn message(&self) -> String {
+        match *self {
+            RequestError::Invalid(ref message) => message.clone(),
+            RequestError::TimeLimitExceeded => String::from("time limit exceeded"),
+            RequestError::Internal => String::from("internal error"),
+        }
+    }
+}
+
+impl From<ReadError> for RequestError {
+    fn from(err: ReadError) -> RequestError {
+        RequestError::Invalid(format!("invalid problem: {}", err))
+    }
+}
+
+impl From<CliError> for RequestError {
+    fn from(err: CliError) -> RequestError {
+        RequestError::Invalid(err.to_string())
+    }
+}
+
+fn invalid<T>(message: &str) -> Result<T, RequestError> {
+    Err(RequestError::Invalid(String::from(message)))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Operation {
+    Solve,
+    Check,
+    Hint,
+    Generate,
+}
+
+struct Request {
+    puzzle: Puzzle,
+    operation: Operation,
+    problem: String,
+    answer: Json,
+    /// The options of `generate` as command line arguments.
+    options: Vec<String>,
+}
+
+impl Request {
+    fn parse(request: &Json) -> Result<Request, RequestError> {
+        let puzzle = match request.get("genre").and_then(Json::as_str) {
+            Some(genre) => match parse_puzzle(genre) {
+                Some(puzzle) => puzzle,
+                None => return invalid("unknown genre"),
+            },
+            None => return invalid("missing `genre`"),
+        };
+        let operation = match request.get("operation").and_then(Json::as_str) {
+            Some("solve") => Operation::Solve,
+            Some("check") => Operation::Check,
+            Some("hint") => Operation::Hint,
+            Some("generate") => Operation::Generate,
+            Some(_) => return invalid("unknown operation"),
+            None => return invalid("missing `operation`"),
+        };
+        let problem = match request.get("problem") {
+            Some(Json::String(problem)) => problem.clone(),
+            Some(_) => return invalid("`problem` must be a string"),
+            None if operation == Operation::Generate => String::new(),
+            None => return invalid("missing `problem`"),
+        };
+        let answer = request.get("answer").cloned().unwrap_or(Json::Null);
+        let mut options = vec![];
+        match request.get("options") {
+            Some(Json::Object(members)) => {
+                for (key, value) in members {
+                    options.extend(option_args(key, value)?);
+                }
+            }
+            Some(_) => return invalid("`options` must be an object"),
+            None => (),
+        }
+        if !problem.is_empty() {
+            check_problem_size(&problem)?;
+        }
+        Ok(Request {
+            puzzle,
+            operation,
+            problem,
+            answer,
+            options,
+        })
+    }
+}
+
+/// Converts an option of `generate` into command line arguments.
+/// Only the flags in `ALLOWED_FLAGS` may be set, and every value goes through `check_option`.
+fn option_args(key: &str, value: &Json) -> Result<Vec<String>, RequestError> {
+    // `=` would smuggle a value past `check_option` (e.g. `--height=70`)
+    if key.is_empty()
+        || key.starts_with('-')
+        || !key.chars().all(|c| c.is_ascii_lowercase() || c == '-')
+    {
+        return Err(RequestError::Invalid(format!("invalid option `{}`", key)));
+    }
+    let is_flag = ALLOWED_FLAGS.contains(&key);
+    let value = match *value {
+        Json::Bool(_) | Json::Null if !is_flag => {
+            return Err(RequestError::Invalid(format!("unknown flag `{}`", key)))
+        }
+        Json::Bool(true) => return Ok(vec![format!("--{}", key)]),
+        Json::Bool(false) | Json::Null => return Ok(vec![]),
+        Json::Number(_) => value.to_string(),
+        Json::String(ref s) => s.clone(),
+        _ => return invalid("invalid value in `options`"),
+    };
+    check_option(key, &value)?;
+    Ok(vec![format!("--{}", key), value])
+}
+
+/// Rejects the options of `generate` which would make the generators too expensive.
+fn check_option(key: &str, value: &str) -> Result<(), RequestError> {
+    let max = match key {
+        "height" | "width" => MAX_BOARD_SIZE,
+        "jobs" | "solver-threads" => MAX_SOLVER_THREADS,
+        _ => return Ok(()),
+    };
+    match value.parse::<i32>() {
+        Ok(n) if n <= max => Ok(()),
+        Ok(_) => Err(RequestError::Invalid(format!(
+            "`{}` must be at most {}",
+            key, max
+        ))),
+        Err(_) => Err(RequestError::Invalid(format!("invalid `{}`", key))),
+    }
+}
+
+/// Rejects problems whose header `height width` exceeds `MAX_BOARD_SIZE`
+/// before the readers allocate the board.
+fn check_problem_size(problem: &str) -> Result<(), RequestError> {
+    let mut line = String::new();
+    if next_valid_line(&mut problem.as_bytes(), &mut line).is_err() {
+        return Ok(());
+    }
+    let mut header = line.split_whitespace().map(|s| s.parse::<i32>());
+    match (header.next(), header.next()) {
+        (Some(Ok(height)), Some(Ok(width)))
+            if height > MAX_BOARD_SIZE || width > MAX_BOARD_SIZE =>
+        {
+            Err(RequestError::Invalid(format!(
+                "the board must be at most {} x {}",
+                MAX_BOARD_SIZE, MAX_BOARD_SIZE
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn position_json(pos: LP) -> Json {
+    Json::from(vec![pos.0, pos.1])
+}
+
+/// Reads a list of positions `[[y, x], ...]`; a missing list is regarded as empty.
+fn read_positions(value: Option<&Json>) -> Result<Vec<LP>, RequestError> {
+    let list = match value {
+        Some(value) => match value.as_array() {
+            Some(list) => list,
+            None => return invalid("positions must be an array"),
+        },
+        None => return Ok(vec![]),
+    };
+    let mut ret = vec![];
+    for item in list {
+        match item.as_array() {
+            Some([y, x]) => match (y.as_i32(), x.as_i32()) {
+                (Some(y), Some(x)) => ret.push(LP(y, x)),
+                _ => return invalid("invalid position"),
+            },
+            _ => return invalid("invalid position"),
+        }
+    }
+    Ok(ret)
+}
+
+fn invalid_answer(err: ReadError) -> RequestError {
+    RequestError::Invalid(format!("invalid answer: {}", err))
+}
+
+/// Reads the member `key` of `answer`, which is in the format of the member `base_key`
+/// of the answers, by `read`.
+fn read_optional_member<T, F>(
+    answer: &Json,
+    key: &str,
+    base_key: &str,
+    read: F,
+) -> Result<Option<T>, RequestError>
+where
+    F: Fn(&Json) -> Result<T, ReadError>,
+{
+    match answer.get(key) {
+        Some(value) => read(&Json::object(vec![(base_key, value.clone())]))
+            .map(Some)
+            .map_err(invalid_answer),
+        None => Ok(None),
+    }
+}
+
+/// Reads a (partial) answer in the schema of the genre, together with the marks of the
+/// empty edges or cells given by `blanks` or `white`, into the grid of marks of `board`.
+/// A missing answer is regarded as empty.
+fn read_marks(board: &Board, answer: &Json) -> Result<Grid<Mark>, RequestError> {
+    let (height, width) = board.marks_size();
+    let mut marks = Grid::new(height, width, Mark::Unset);
+    if let Json::Null = *answer {
+        return Ok(marks);
+    }
+    let (lines, blanks) = match *board {
+        Board::Slitherlink(ref problem) => {
+            let read = |value: &Json| json::slitherlink::answer_from_json(problem, value);
+            (
+                read(answer).map_err(invalid_answer)?,
+                read_optional_member(answer, "blanks", "lines", read)?,
+            )
+        }
+        Board::Masyu(ref problem) => {
+            let read = |value: &Json| json::masyu::answer_from_json(problem, value);
+            (
+                read(answer).map_err(invalid_answer)?,
+                read_optional_member(answer, "blanks", "lines", read)?,
+            )
+        }
+        Board::CountryRoad(ref problem) => {
+            let read = |value: &Json| json::country_road::answer_from_json(problem, value);
+            (
+                read(answer).map_err(invalid_answer)?,
+                read_optional_member(answer, "blanks", "lines", read)?,
+            )
+        }
+        Board::Tapa(ref problem) => {
+            let read = |value: &Json| json::tapa::answer_from_json(problem, value);
+            (
+                read(answer).map_err(invalid_answer)?,
+                read_optional_member(answer, "white", "black", read)?,
+            )
+        }
+        Board::Kakuro(ref problem) => {
+            let values = json::kakuro::answer_from_json(problem, answer).map_err(invalid_answer)?;
+            for y in 0..height {
+                for x in 0..width {
+                    let pos = LP(y, x);
+                    match values[P(y, x)] {
+                        0 => (),
+                        v if board.is_markable(pos) => marks[pos] = Mark::Value(v),
+                        _ => return invalid("value on a clue cell"),
+                    }
+                }
+            }
+            return Ok(marks);
+        }
+    };
+    for y in 0..height {
+        for x in 0..width {
+            let pos = LP(y, x);
+            let blank = blanks.as_ref().is_some_and(|blanks| blanks[pos]);
+            if (lines[pos] || blank) && !board.is_markable(pos) {
+                return invalid("mark on a clue cell");
+            }
+            if lines[pos] {
+                marks[pos] = Mark::Line;
+            } else if blank {
+                marks[pos] = Mark::Blank;
+            }
+        }
+    }
+    Ok(marks)
+}
+
+/// Returns the edges passed by the loop through `vertices` on the lattice of size
+/// `height` x `width`, where the vertex `P(y, x)` is at `LP(2y, 2x)`.
+fn loop_to_lines(vertices: &[P], height: i32, width: i32) -> Grid<bool> {
+    let mut lines = Grid::new(height, width, false);
+    for i in 0..vertices.len() {
+        let P(y1, x1) = vertices[i];
+        let P(y2, x2) = vertices[(i + 1) % vertices.len()];
+        if (y1, x1) != (y2, x2) {
+            lines[LP(y1 + y2, x1 + x2)] = true;
+        }
+    }
+    lines
+}
+
+/// Searches up to `limit` answers of `board` with the solver of the genre
+/// and returns them in the schema of the genre.
+fn search_board(board: &Board, limit: usize, cancel: &AtomicBool) -> Vec<Json> {
+    let (height, width) = board.marks_size();
+    match *board {
+        Board::Slitherlink(ref problem) => {
+            let dic = slitherlink::Dictionary::shared();
+            let mut field = slitherlink::Field::new(problem, &dic);
+            field
+                .search_cancellable(limit, cancel)
+                .iter()
+                .map(|l| json::slitherlink::answer_to_json(&loop_to_lines(l, height, width)))
+                .collect()
+        }
+        Board::Masyu(ref problem) => {
+            let mut field = masyu::Field::new(problem);
+            field
+                .search_cancellable(limit, cancel)
+                .iter()
+                .map(|l| json::masyu::answer_to_json(&loop_to_lines(l, height, width)))
+                .collect()
+        }
+        Board::CountryRoad(ref problem) => {
+            let mut field = country_road::Field::new(problem);
+            field
+                .search_cancellable(limit, cancel)
+                .iter()
+                .map(|l| json::country_road::answer_to_json(&loop_to_lines(l, height, width)))
+                .collect()
+        }
+        Board::Tapa(ref problem) => {
+            let dic = tapa::Dictionary::shared();
+            let consecutive_dic = tapa::ConsecutiveRegionDictionary::shared();
+            let mut field = tapa::Field::new(height, width, &dic, &consecutive_dic);
+            for y in 0..height {
+                for x in 0..width {
+                    if problem[P(y, x)] != tapa::NO_CLUE {
+                        field.add_clue(P(y, x), problem[P(y, x)]);
+                    }
+                }
+            }
+            field
+                .search_cancellable(limit, cancel)
+                .iter()
+                .map(json::tapa::answer_to_json)
+                .collect()
+        }
+        Board::Kakuro(ref problem) => {
+            let dic = kakuro::Dictionary::shared();
+            let field = kakuro::Field::new(problem, &dic);
+            field
+                .search_cancellable(limit, cancel)
+                .iter()
+                .map(json::kakuro::answer_to_json)
+                .collect()
+        }
+    }
+}
+
+/// Returns the result of `solve` from (up to two) answers.
+fn answers_json(mut answers: Vec<Json>) -> Json {
+    let unique = answers.len() == 1;
+    let solved = !answers.is_empty();
+    let answer = if solved {
+        answers.swap_remove(0)
+    } else {
+        Json::Null
+    };
+    Json::object(vec![
+        ("solved", Json::from(solved)),
+        ("unique", Json::from(unique)),
+        ("answer", answer),
+    ])
+}
+
+fn mark_json(board: &Board, mark: Mark) -> Json {
+    let (line_key, blank_key) = if board.is_loop() {
+        ("line", "blank")
+    } else {
+        ("black", "white")
+    };
+    match mark {
+        Mark::Line => Json::from(line_key),
+        Mark::Blank => Json::from(blank_key),
+        Mark::Value(v) => Json::from(v),
+        Mark::Unset => Json::Null,
+    }
+}
+
+fn violations_json(violations: Vec<(&'static str, Vec<LP>)>) -> Json {
+    let solved = violations.is_empty();
+    let violations = violations
+        .into_iter()
+        .map(|(kind, positions)| {
+            let positions = positions.into_iter().map(position_json).collect::<Vec<_>>();
+            Json::object(vec![
+                ("kind", Json::from(kind)),
+                ("positions", Json::from(positions)),
+            ])
+        })
+        .collect::<Vec<_>>();
+    Json::object(vec![
+        ("solved", Json::from(solved)),
+        ("violations", Json::from(violations)),
+    ])
+}
+
+fn run_board(request: &Request, cancel: &AtomicBool) -> Result<Json, RequestError> {
+    let board = Board::read(request.puzzle, &mut request.problem.as_bytes())?;
+    if request.operation == Operation::Solve {
+        let answers = search_board(&board, 2, cancel);
+        if cancel.load(Ordering::Relaxed) {
+            return Err(RequestError::TimeLimitExceeded);
+        }
+        return Ok(answers_json(answers));
+    }
+    let marks = read_marks(&board, &request.answer)?;
+    match request.operation {
+        Operation::Solve | Operation::Generate => unreachable!(),
+        Operation::Check => Ok(violations_json(board.check(&marks))),
+        Operation::Hint => {
+            let hint = board.hint_cancellable(&marks, cancel);
+            if cancel.load(Ordering::Relaxed) {
+                return Err(RequestError::TimeLimitExceeded);
+            }
+            Ok(match hint {
+                Some(Some((pos, mark))) => Json::object(vec![
+                    ("inconsistent", Json::from(false)),
+                    (
+                        "hint",
+                        Json::object(vec![
+                            ("position", position_json(pos)),
+                            ("mark", mark_json(&board, mark)),
+                        ]),
+                    ),
+                ]),
+                Some(None) => Json::object(vec![
+                    ("inconsistent", Json::from(false)),
+                    ("hint", Json::Null),
+                ]),
+                None => Json::object(vec![
+                    ("inconsistent", Json::from(true)),
+                    ("hint", Json::Null),
+                ]),
+            })
+        }
+    }
+}
+
+fn run_numberlink(request: &Request, cancel: &AtomicBool) -> Result<Json, RequestError> {
+    let problem = numberlink::read_penciloid_problem(&mut request.problem.as_bytes())?;
+    let topology = if problem.topology.is_plain() {
+        None
+    } else {
+        Some(&problem.topology)
+    };
+    match request.operation {
+        Operation::Solve => {
+            let option = numberlink::SearchOption {
+                topology,
+                disallow_unused_cell: problem.rule == numberlink::Rule::FillAll,
+                terminate_on_not_fully_filled: false,
+                step_limit: None,
+                cancel: Some(cancel),
+            };
+            let mut answers = vec![];
+            numberlink::solve2_streaming(&problem.clues, &option, |placement| {
+                answers.push(json::numberlink::answer_to_json(placement));
+                answers.len() < 2
+            });
+            if cancel.load(Ordering::Relaxed) {
+                return Err(RequestError::TimeLimitExceeded);
+            }
+            Ok(answers_json(answers))
+        }
+        Operation::Check => {
+            let (height, width) = (problem.clues.height(), problem.clues.width());
+            let mut placement = numberlink::LinePlacement::new(height, width);
+            for pos in read_positions(request.answer.get("lines"))? {
+                let edge = match problem.topology.canonical_edge(pos) {
+                    Some(edge) => edge,
+                    None => return invalid("position out of the board"),
+                };
+                let LP(y, x) = edge;
+                if y % 2 == 0 {
+                    placement.set_right(P(y / 2, x / 2), true);
+                } else {
+                    placement.set_down(P(y / 2, x / 2), true);
+                }
+            }
+            let violations = numberlink::check_answer(
+                &problem.clues,
+                &problem.topology,
+                &placement,
+                problem.rule,
+            )
+            .into_iter()
+            .map(|v| {
+                let mut positions = v.cells.into_iter().map(LP::of_vertex).collect::<Vec<_>>();
+                positions.extend(v.edges);
+                (v.kind.name(), positions)
+            })
+            .collect();
+            Ok(violations_json(violations))
+        }
+        Operation::Hint => invalid("hint is not supported in numberlink"),
+        Operation::Generate => unreachable!(),
+    }
+}
+
+fn run_request(request: &Request, cancel: &AtomicBool) -> Result<Json, RequestError> {
+    if request.operation == Operation::Generate {
+        let problem = match request.puzzle {
+            Puzzle::Numberlink => nl_generator::generate_problem(&request.options, cancel)?,
+            Puzzle::Tapa => tapa_generator::generate_problem(&request.options, cancel)?,
+            _ => return invalid("generate is not supported in this genre"),
+        };
+        return match problem {
+            Some(problem) => Ok(Json::object(vec![("problem", Json::from(problem))])),
+            None => Err(RequestError::TimeLimitExceeded),
+        };
+    }
+    match request.puzzle {
+        Puzzle::Numberlink => run_numberlink(request, cancel),
+        _ => run_board(request, cancel),
+    }
+}
+
+/// Runs `request` within `time_limit` (which may be shortened by the request).
+fn handle_request(request: &Json, time_limit: Duration) -> Result<Json, RequestError> {
+    let time_limit = match request.get("time_limit") {
+        Some(limit) => match limit.as_i32() {
+            Some(limit) if limit > 0 => time_limit.min(Duration::from_millis(limit as u64)),
+            _ => return invalid("invalid `time_limit`"),
+        },
+        None => time_limit,
+    };
+    let request = Request::parse(request)?;
+    let cancel = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::channel();
+    {
+        let cancel = cancel.clone();
+        thread::spawn(move || {
+            let _ = sender.send(run_request(&request, &cancel));
+        });
+    }
+    match receiver.recv_timeout(time_limit) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            cancel.store(true, Ordering::Relaxed);
+            Err(RequestError::TimeLimitExceeded)
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err(RequestError::Internal),
+    }
+}
+
+/// Reads an HTTP request and returns its method, path and body.
+fn read_http_request<R: BufRead>(reader: &mut R) -> Result<(String, String, String), ReadError> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut tokens = line.split_whitespace();
+    let method = String::from(tokens.next().ok_or(ReadError::InvalidFormat)?);
+    let path = String::from(tokens.next().ok_or(ReadError::InvalidFormat)?);
+
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(ReadError::InvalidFormat);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(colon) = header.find(':') {
+            if header[..colon].eq_ignore_ascii_case("content-length") {
+                content_length = header[(colon + 1)..]
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| ReadError::InvalidValue)?;
+            }
+        }
+    }
+    if content_length > MAX_BODY_SIZE {
+        return Err(ReadError::InvalidValue);
+    }
+    let mut body = vec![0u8; content_length];
+    reader.read_exact(&mut body)?;
+    let body = String::from_utf8(body).map_err(|_| ReadError::InvalidFormat)?;
+    Ok((method, path, body))
+}
+
+fn write_http_response<W: Write>(out: &mut W, status: u16, body: &Json) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    };
+    let body = body.to_string();
+    write!(
+        out,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    )?;
+    out.flush()
+}
+
+fn error_json(message: &str) -> Json {
+    Json::object(vec![("error", Json::from(message))])
+}
+
+fn handle_connection(mut stream: TcpStream, time_limit: Duration) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let request = read_http_request(&mut BufReader::new(stream.try_clone()?));
+    let (status, body) = match request {
+        Ok((ref method, ref path, ref body)) if method == "POST" && path == "/" => {
+            let result = Json::parse(body)
+                .map_err(|_| RequestError::Invalid(String::from("invalid JSON")))
+                .and_then(|request| handle_request(&request, time_limit));
+            match result {
+                Ok(result) => (200, result),
+                Err(err) => (err.status(), error_json(&err.message())),
+            }
+        }
+        Ok((_, ref path, _)) if path == "/" => (405, error_json("only POST is allowed")),
+        Ok(_) => (404, error_json("not found")),
+        Err(_) => (400, error_json("invalid HTTP request")),
+    };
+    write_http_response(&mut stream, status, &body)
+}
+
+/// Accepts connections and handles them by `n_workers` threads. Connections arriving while
+/// `MAX_PENDING_CONNECTIONS` others are waiting for a worker are refused with 503.
+fn serve(listener: TcpListener, time_limit: Duration, n_workers: usize) {
+    let (sender, receiver) = mpsc::sync_channel::<TcpStream>(MAX_PENDING_CONNECTIONS);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..n_workers {
+        let receiver = receiver.clone();
+        thread::spawn(move || loop {
+            let stream = match receiver.lock() {
+                Ok(receiver) => match receiver.recv() {
+                    Ok(stream) => stream,
+                    Err(_) => return,
+                },
+                Err(_) => return,
+            };
+            if let Err(err) = handle_connection(stream, time_limit) {
+                eprintln!("error: {}", err);
+            }
+        });
+    }
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(mpsc::TrySendError::Full(mut stream)) = sender.try_send(stream) {
+                    let body = error_json("too many connections");
+                    if let Err(err) = write_http_response(&mut stream, 503, &body) {
+                        eprintln!("error: {}", err);
+                    }
+                }
+            }
+            Err(err) => eprintln!("error: {}", err),
+        }
+    }
+}
+
+pub fn server_frontend(args: &[String], program: &str) -> Result<(), CliError> {
+    let mut options = Options::new();
+    options.optflag("", "help", "Show this help menu");
+    options.optopt("p", "port", "Port to listen on", "8080");
+    options.optopt(
+        "",
+        "host",
+        "Address to listen on (default: 127.0.0.1)",
+        "0.0.0.0",
+    );
+    options.optopt(
+        "t",
+        "time-limit",
+        "Time limit of each request in milliseconds (default: 10000)",
+        "10000",
+    );
+    options.optopt(
+        "",
+        "workers",
+        "Number of requests handled at the same time (default: 4)",
+        "4",
+    );
+
+    let matches = options.parse(args)?;
+
+    if matches.opt_present("help") {
+        let brief = format!("Usage: {} serve [options]", program);
+        print!("{}", options.usage(&brief));
+        return Ok(());
+    }
+
+    let port = parse_integer_argument(&matches, "port", None, Some(0))?;
+    let time_limit = parse_integer_argument(&matches, "time-limit", Some(10000), Some(1))?;
+    let n_workers = parse_integer_argument(&matches, "workers", Some(4), Some(1))?;
+    let host = matches
+        .opt_str("host")
+        .unwrap_or_else(|| String::from("127.0.0.1"));
+
+    let listener = TcpListener::bind((host.as_str(), port as u16))?;
+    eprintln!("listening on {}", listener.local_addr()?);
+    serve(
+        listener,
+        Duration::from_millis(time_limit as u64),
+        n_workers as usize,
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn request(src: &str) -> Result<Json, RequestError> {
+        handle_request(&Json::parse(src).unwrap(), Duration::from_secs(60))
+    }
+
+    #[test]
+    fn test_server_operations() {
+        let problem = r#""problem": "3 3\n212\n101\n212\n""#;
+
+        let solved = request(&format!(
+            r#"{{"genre": "sl", "operation": "solve", {}}}"#,
+            problem
+        ))
+        .unwrap();
+        assert_eq!(solved.get("unique"), Some(&Json::Bool(true)));
+        let answer = solved.get("answer").unwrap().to_string();
+
+        let checked = request(&format!(
+            r#"{{"genre": "sl", "operation": "check", {}, "answer": {}}}"#,
+            problem, answer
+        ))
+        .unwrap();
+        assert_eq!(checked.get("solved"), Some(&Json::Bool(true)));
+        let checked = request(&format!(
+            r#"{{"genre": "slitherlink", "operation": "check", {}, "answer": {{"lines": [[0, 1]]}}}}"#,
+            problem
+        ))
+        .unwrap();
+        assert_eq!(checked.get("solved"), Some(&Json::Bool(false)));
+        assert!(!checked
+            .get("violations")
+            .and_then(Json::as_array)
+            .unwrap()
+            .is_empty());
+
+        let hint = request(&format!(
+            r#"{{"genre": "sl", "operation": "hint", {}}}"#,
+            problem
+        ))
+        .unwrap();
+        assert_eq!(hint.get("inconsistent"), Some(&Json::Bool(false)));
+        assert!(hint.get("hint").and_then(|h| h.get("position")).is_some());
+        let hint = request(&format!(
+            r#"{{"genre": "sl", "operation": "hint", {}, "answer": {{"lines": [], "blanks": [[0, 1], [1, 0]]}}}}"#,
+            problem
+        ))
+        .unwrap();
+        assert_eq!(hint.get("inconsistent"), Some(&Json::Bool(true)));
+
+        let tapa = r#""problem": "3 3\n. . .\n. 8 .\n. . .\n""#;
+        let solved = request(&format!(
+            r#"{{"genre": "tp", "operation": "solve", {}}}"#,
+            tapa
+        ))
+        .unwrap();
+        assert_eq!(
+            solved.get("answer").unwrap().to_string(),
+            r#"{"black":[[true,true,true],[true,false,true],[true,true,true]]}"#
+        );
+
+        let nl = r#""problem": "2 3\n1 . 1\n2 . 2\n""#;
+        let solved = request(&format!(
+            r#"{{"genre": "nl", "operation": "solve", {}}}"#,
+            nl
+        ))
+        .unwrap();
+        assert_eq!(solved.get("unique"), Some(&Json::Bool(true)));
+        let answer = solved.get("answer").unwrap().to_string();
+        assert_eq!(answer, r#"{"lines":[[0,1],[0,3],[2,1],[2,3]]}"#);
+        let checked = request(&format!(
+            r#"{{"genre": "nl", "operation": "check", {}, "answer": {}}}"#,
+            nl, answer
+        ))
+        .unwrap();
+        assert_eq!(checked.get("solved"), Some(&Json::Bool(true)));
+        // the line around the row goes through the warp at the right end
+        let warp = r#""problem": "1 3 rows:0\n1 1 .\n""#;
+        for &(lines, solved) in &[("[[0, 3], [0, 5]]", true), ("[[0, 3]]", false)] {
+            let checked = request(&format!(
+                r#"{{"genre": "nl", "operation": "check", {}, "answer": {{"lines": {}}}}}"#,
+                warp, lines
+            ))
+            .unwrap();
+            assert_eq!(checked.get("solved"), Some(&Json::Bool(solved)));
+        }
+
+        assert!(request(r#"{"genre": "xx", "operation": "solve", "problem": ""}"#).is_err());
+        assert!(request(r#"{"genre": "sl", "operation": "solve"}"#).is_err());
+        assert!(request(r#"{"genre": "sl", "operation": "generate"}"#).is_err());
+        assert!(request(&format!(
+            r#"{{"genre": "sl", "operation": "check", {}, "answer": {{"lines": [[1, 1]]}}}}"#,
+            problem
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_server_limits() {
+        let too_large = format!(
+            r#"{{"genre": "sl", "operation": "solve", "problem": "{} 1\n"}}"#,
+            MAX_BOARD_SIZE + 1
+        );
+        match request(&too_large) {
+            Err(RequestError::Invalid(_)) => (),
+            _ => panic!(),
+        }
+        for options in &[
+            r#"{"height": 1000000, "width": 10}"#,
+            r#"{"height": 10, "width": "1000000"}"#,
+            r#"{"height": 10, "width": 10, "solver-threads": 1000}"#,
+            r#"{"height=70": true}"#,
+            r#"{"height=70": 10}"#,
+            r#"{"use-profiler": true}"#,
+            r#"{"height": true}"#,
+            r#"{"": "10"}"#,
+        ] {
+            let src = format!(
+                r#"{{"genre": "nl", "operation": "generate", "options": {}}}"#,
+                options
+            );
+            match request(&src) {
+                Err(RequestError::Invalid(_)) => (),
+                _ => panic!(),
+            }
+        }
+        let src = r#"{"genre": "nl", "operation": "generate", "options": {"height": 5, "fill-all": true, "symmetry": false}}"#;
+        let parsed = Request::parse(&Json::parse(src).unwrap()).unwrap();
+        assert_eq!(parsed.options, vec!["--height", "5", "--fill-all"]);
+
+        // clues larger than the board would make the solver allocate too much
+        let src =
+            r#"{"genre": "nl", "operation": "solve", "problem": "1 2\n2000000000 2000000000\n"}"#;
+        match request(src) {
+            Err(RequestError::Invalid(_)) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn test_server_cancel() {
+        // the requests give up once cancelled instead of running to the end
+        let cancel = AtomicBool::new(true);
+        for src in &[
+            r#"{"genre": "sl", "operation": "hint", "problem": "3 3\n212\n101\n212\n"}"#,
+            r#"{"genre": "tp", "operation": "hint", "problem": "3 3\n. . .\n. 8 .\n. . .\n"}"#,
+            r#"{"genre": "nl", "operation": "generate", "options": {"height": 6, "width": 6, "solver-threads": 2}}"#,
+        ] {
+            let request = Request::parse(&Json::parse(src).unwrap()).unwrap();
+            match run_request(&request, &cancel) {
+                Err(RequestError::TimeLimitExceeded) => (),
+                _ => panic!(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_server_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener, Duration::from_secs(60), 2));
+
+        let body = r#"{"genre": "sl", "operation": "check", "problem": "1 1\n.\n"}"#;
+        let post = |path: &str, body: &str| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(
+                stream,
+                "POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}",
+                path,
+                body.len(),
+                body
+            )
+            .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let response = post("/", body);
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(r#""kind":"no_loop","positions":[]}]}"#));
+        assert!(post("/", "{").starts_with("HTTP/1.1 400 "));
+        assert!(post("/other", body).starts_with("HTTP/1.1 404 "));
+    }
+}
@@ -3,7 +3,9 @@ use rand;
 use {tapa, Grid, P};
 
 use super::getopts::{Matches, Options};
-use std::io::Write;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 
@@ -89,33 +91,93 @@ fn parse_options(matches: &Matches) -> Result<GeneratorOption, CliError> {
     })
 }
 
-fn run_generator(opts: GeneratorOption) -> Result<(), CliError> {
-    let height = opts.height;
-    let width = opts.width;
+/// Loads the dictionaries from `path` if it exists and holds dictionaries of the current version.
+/// Otherwise, the dictionaries are built and saved to `path` (if given) for the later runs.
+fn load_dictionaries(
+    path: Option<String>,
+) -> Result<(tapa::Dictionary, tapa::ConsecutiveRegionDictionary), CliError> {
+    if let Some(ref path) = path {
+        if let Ok(file) = File::open(path) {
+            let mut reader = BufReader::new(file);
+            let loaded = tapa::Dictionary::read_binary(&mut reader).and_then(|dic| {
+                tapa::ConsecutiveRegionDictionary::read_binary(&mut reader)
+                    .map(|consecutive_dic| (dic, consecutive_dic))
+            });
+            match loaded {
+                Ok(dictionaries) => return Ok(dictionaries),
+                Err(err) => eprintln!("warning: rebuilding the dictionary {}: {}", path, err),
+            }
+        }
+    }
+
+    let dic = tapa::Dictionary::new();
+    let consecutive_dic = tapa::ConsecutiveRegionDictionary::new(&dic);
+    if let Some(ref path) = path {
+        let mut writer = BufWriter::new(File::create(path)?);
+        dic.write_binary(&mut writer)?;
+        consecutive_dic.write_binary(&mut writer)?;
+    }
+    Ok((dic, consecutive_dic))
+}
+
+fn generator_option(opts: &GeneratorOption) -> tapa::GeneratorOption {
+    tapa::GeneratorOption {
+        clue_constraint: Grid::new(opts.height, opts.width, tapa::ClueConstraint::Any),
+        symmetry: opts.symmetry,
+        max_clue: opts.max_clue,
+        use_trial_and_error: opts.trial_and_error,
+        allowed_clues: opts.allowed_clues,
+    }
+}
+
+/// Writes `problem` in the format read by `tapa::read_penciloid_problem`.
+fn write_problem<W: Write>(handle: &mut W, problem: &Grid<tapa::Clue>) -> io::Result<()> {
+    let height = problem.height();
+    let width = problem.width();
+    writeln!(handle, "{} {}", height, width)?;
+    for y in 0..height {
+        for x in 0..width {
+            let tapa::Clue(c) = problem[P(y, x)];
+            if c == 0 {
+                write!(handle, "0...")?;
+            } else if c > 0 {
+                for i in 0..4 {
+                    let v = tapa::CLUE_VALUES[c as usize][i];
+                    if v > 0 {
+                        write!(handle, "{}", v)?;
+                    } else {
+                        write!(handle, ".")?;
+                    }
+                }
+            } else {
+                write!(handle, "....")?;
+            }
+            write!(handle, "{}", if x == width - 1 { '\n' } else { ' ' })?;
+        }
+    }
+    Ok(())
+}
+
+fn run_generator(opts: GeneratorOption, dictionary_path: Option<String>) -> Result<(), CliError> {
     let mut ths = vec![];
     let gen_probs = Arc::new(Mutex::new(0i64));
+    let dictionaries = Arc::new(load_dictionaries(dictionary_path)?);
 
     for _ in 0..opts.jobs {
         let gen_probs = gen_probs.clone();
+        let dictionaries = dictionaries.clone();
 
         let opts = opts;
 
         ths.push(thread::spawn(move || {
             let start = Instant::now();
-            let dic = tapa::Dictionary::new();
-            let consecutive_dic = tapa::ConsecutiveRegionDictionary::new(&dic);
+            let (ref dic, ref consecutive_dic) = *dictionaries;
 
-            let opts = tapa::GeneratorOption {
-                clue_constraint: Grid::new(opts.height, opts.width, tapa::ClueConstraint::Any),
-                symmetry: opts.symmetry,
-                max_clue: opts.max_clue,
-                use_trial_and_error: opts.trial_and_error,
-                allowed_clues: opts.allowed_clues,
-            };
+            let opts = generator_option(&opts);
 
             let mut rng = rand::thread_rng();
             loop {
-                let res = tapa::generate(&opts, &dic, &consecutive_dic, &mut rng);
+                let res = tapa::generate(&opts, dic, consecutive_dic, &mut rng);
 
                 if let Some(problem) = res {
                     let stdin = io::stdout();
@@ -133,27 +195,7 @@ fn run_generator(opts: GeneratorOption) -> Result<(), CliError> {
                         (*cnt) as f64 / cost_time
                     );
 
-                    writeln!(handle, "{} {}", height, width).unwrap();
-                    for y in 0..height {
-                        for x in 0..width {
-                            let tapa::Clue(c) = problem[P(y, x)];
-                            if c == 0 {
-                                write!(handle, "0...").unwrap();
-                            } else if c > 0 {
-                                for i in 0..4 {
-                                    let v = tapa::CLUE_VALUES[c as usize][i];
-                                    if v > 0 {
-                                        write!(handle, "{}", v).unwrap();
-                                    } else {
-                                        write!(handle, ".").unwrap();;
-                                    }
-                                }
-                            } else {
-                                write!(handle, "....").unwrap();
-                            }
-                            write!(handle, "{}", if x == width - 1 { '\n' } else { ' ' }).unwrap();
-                        }
-                    }
+                    write_problem(handle, &problem).unwrap();
                     writeln!(handle).unwrap();
                 }
             }
@@ -165,7 +207,27 @@ fn run_generator(opts: GeneratorOption) -> Result<(), CliError> {
     Ok(())
 }
 
-pub fn tapa_generator_frontend(args: &[String], program: &str) -> Result<(), CliError> {
+/// Generates a problem with the options given in the same form as the command line of
+/// `tapa-gen`, and returns it in the format of `write_problem`.
+/// Returns `None` if `cancel` is set before a problem is found.
+/// The shared dictionaries are used regardless of `--dictionary`, and `--jobs` is ignored.
+pub fn generate_problem(args: &[String], cancel: &AtomicBool) -> Result<Option<String>, CliError> {
+    let matches = generator_options().parse(args)?;
+    let opts = generator_option(&parse_options(&matches)?);
+    let dic = tapa::Dictionary::shared();
+    let consecutive_dic = tapa::ConsecutiveRegionDictionary::shared();
+    let mut rng = rand::thread_rng();
+    while !cancel.load(Ordering::Relaxed) {
+        if let Some(problem) = tapa::generate(&opts, &dic, &consecutive_dic, &mut rng) {
+            let mut buf = vec![];
+            write_problem(&mut buf, &problem)?;
+            return Ok(Some(String::from_utf8(buf).unwrap()));
+        }
+    }
+    Ok(None)
+}
+
+fn generator_options() -> Options {
     let mut options = Options::new();
     options.optflag("", "help", "Show this help menu");
     options.optopt("h", "height", "Height of desired problems", "10");
@@ -181,7 +243,17 @@ pub fn tapa_generator_frontend(args: &[String], program: &str) -> Result<(), Cli
         "Disallowed clue patterns",
         "113,22,4",
     );
+    options.optopt(
+        "",
+        "dictionary",
+        "Dictionary file (created if it does not exist)",
+        "tapa.dic",
+    );
+    options
+}
 
+pub fn tapa_generator_frontend(args: &[String], program: &str) -> Result<(), CliError> {
+    let options = generator_options();
     let matches = options.parse(&args[..])?;
 
     if matches.opt_present("help") {
@@ -191,7 +263,7 @@ pub fn tapa_generator_frontend(args: &[String], program: &str) -> Result<(), Cli
     }
 
     let opts = parse_options(&matches)?;
-    run_generator(opts)
+    run_generator(opts, matches.opt_str("dictionary"))
 }
 
 #[cfg(test)]
@@ -2,10 +2,16 @@ use std::ops::{Index, IndexMut};
 
 mod graph_separation;
 mod pos;
+mod shared_ref;
+mod solver_stats;
+mod violation;
 pub use self::graph_separation::*;
 pub use self::pos::*;
+pub use self::shared_ref::*;
+pub use self::solver_stats::*;
+pub use self::violation::*;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Grid<T: Clone> {
     height: i32,
     width: i32,
@@ -0,0 +1,49 @@
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// Reference to data (typically a dictionary) which is either borrowed or shared by `Arc`.
+/// Structures holding `SharedRef<'static, T>` can be stored for a long time or sent across
+/// threads, while borrowing keeps the existing usage without the overhead of reference counting.
+pub enum SharedRef<'a, T: 'a> {
+    Borrowed(&'a T),
+    Shared(Arc<T>),
+}
+
+impl<'a, T> Deref for SharedRef<'a, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        match *self {
+            SharedRef::Borrowed(r) => r,
+            SharedRef::Shared(ref r) => r,
+        }
+    }
+}
+
+impl<'a, T> Clone for SharedRef<'a, T> {
+    fn clone(&self) -> SharedRef<'a, T> {
+        match *self {
+            SharedRef::Borrowed(r) => SharedRef::Borrowed(r),
+            SharedRef::Shared(ref r) => SharedRef::Shared(r.clone()),
+        }
+    }
+}
+
+impl<'a, T> From<&'a T> for SharedRef<'a, T> {
+    fn from(r: &'a T) -> SharedRef<'a, T> {
+        SharedRef::Borrowed(r)
+    }
+}
+
+impl<'a, T> From<Arc<T>> for SharedRef<'a, T> {
+    fn from(r: Arc<T>) -> SharedRef<'a, T> {
+        SharedRef::Shared(r)
+    }
+}
+
+impl<'a, 'b, T> From<&'b Arc<T>> for SharedRef<'a, T> {
+    fn from(r: &'b Arc<T>) -> SharedRef<'a, T> {
+        SharedRef::Shared(r.clone())
+    }
+}
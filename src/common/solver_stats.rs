@@ -0,0 +1,156 @@
+/// Counters for a single technique of a solver.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TechniqueStats {
+    /// Number of times the technique was applied.
+    pub n_applied: u64,
+    /// Number of applications which made progress.
+    pub n_fired: u64,
+    /// Total progress made by the technique, i.e. the number of decided cells or edges
+    /// (or eliminated candidates, for candidate-based solvers).
+    pub n_decided: u64,
+}
+
+/// Statistics of the work done by a solver field.
+/// Nothing is recorded unless enabled by `set_enabled`.
+/// The work in trial and error (including the discarded one) is also counted.
+///
+/// Every public solver field keeps these statistics, except the internal fields of
+/// Dosufuwa (whose solver is not exported) and of the Numberlink search in `solver2`,
+/// whose work is reported by the number of steps in `SearchSummary` instead.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SolverStats {
+    enabled: bool,
+    techniques: Vec<(&'static str, TechniqueStats)>,
+    n_trials: u64,
+    n_refuted: u64,
+}
+
+impl SolverStats {
+    pub fn new() -> SolverStats {
+        SolverStats::default()
+    }
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+    /// Resets the counters, keeping whether the statistics are enabled.
+    pub fn clear(&mut self) {
+        self.techniques.clear();
+        self.n_trials = 0;
+        self.n_refuted = 0;
+    }
+    /// Records that the technique `name` was applied and made progress of `n_decided`.
+    pub fn record(&mut self, name: &'static str, n_decided: i32) {
+        if !self.enabled {
+            return;
+        }
+        let n_decided = ::std::cmp::max(n_decided, 0) as u64;
+        let stats = self.entry(name);
+        stats.n_applied += 1;
+        if n_decided > 0 {
+            stats.n_fired += 1;
+        }
+        stats.n_decided += n_decided;
+    }
+    /// Records a hypothesis examined in trial and error, which is `refuted` if it led to
+    /// a contradiction.
+    pub fn record_trial(&mut self, refuted: bool) {
+        if !self.enabled {
+            return;
+        }
+        self.n_trials += 1;
+        if refuted {
+            self.n_refuted += 1;
+        }
+    }
+    /// Adds the counters of `other`, e.g. those of a cloned field used for a trial.
+    pub fn merge(&mut self, other: &SolverStats) {
+        if !self.enabled {
+            return;
+        }
+        for &(name, ref s) in &other.techniques {
+            let stats = self.entry(name);
+            stats.n_applied += s.n_applied;
+            stats.n_fired += s.n_fired;
+            stats.n_decided += s.n_decided;
+        }
+        self.n_trials += other.n_trials;
+        self.n_refuted += other.n_refuted;
+    }
+    /// Returns the counters of the technique `name`, which are all 0 if it was never applied.
+    pub fn technique(&self, name: &str) -> TechniqueStats {
+        self.techniques
+            .iter()
+            .find(|&&(n, _)| n == name)
+            .map(|&(_, s)| s)
+            .unwrap_or_default()
+    }
+    /// Returns the counters of the applied techniques in the order of their first application.
+    pub fn techniques(&self) -> &[(&'static str, TechniqueStats)] {
+        &self.techniques
+    }
+    /// Number of hypotheses examined in trial and error.
+    pub fn n_trials(&self) -> u64 {
+        self.n_trials
+    }
+    /// Number of hypotheses which led to a contradiction.
+    pub fn n_refuted(&self) -> u64 {
+        self.n_refuted
+    }
+    fn entry(&mut self, name: &'static str) -> &mut TechniqueStats {
+        let idx = match self.techniques.iter().position(|&(n, _)| n == name) {
+            Some(idx) => idx,
+            None => {
+                self.techniques.push((name, TechniqueStats::default()));
+                self.techniques.len() - 1
+            }
+        };
+        &mut self.techniques[idx].1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solver_stats() {
+        let mut stats = SolverStats::new();
+        stats.record("a", 3);
+        assert_eq!(stats.technique("a"), TechniqueStats::default());
+
+        stats.set_enabled(true);
+        stats.record("a", 3);
+        stats.record("b", 0);
+        stats.record("a", 0);
+        stats.record_trial(true);
+        stats.record_trial(false);
+        assert_eq!(
+            stats.technique("a"),
+            TechniqueStats {
+                n_applied: 2,
+                n_fired: 1,
+                n_decided: 3,
+            }
+        );
+        assert_eq!(stats.techniques().len(), 2);
+        assert_eq!(stats.techniques()[1].0, "b");
+        assert_eq!(stats.n_trials(), 2);
+        assert_eq!(stats.n_refuted(), 1);
+
+        let mut other = SolverStats::new();
+        other.set_enabled(true);
+        other.record("b", 2);
+        other.record_trial(true);
+        stats.merge(&other);
+        assert_eq!(stats.technique("b").n_decided, 2);
+        assert_eq!(stats.n_refuted(), 2);
+
+        stats.clear();
+        assert!(stats.enabled());
+        assert_eq!(stats.techniques().len(), 0);
+        assert_eq!(stats.n_trials(), 0);
+    }
+}
@@ -0,0 +1,152 @@
+use super::{Grid, FOUR_NEIGHBOURS, LP, P};
+
+/// The rule violated by a `Violation`.
+/// The kinds reported by each genre are listed in the documentation of its `check_answer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ViolationKind {
+    Clue,
+    Branch,
+    DeadEnd,
+    NoLoop,
+    MultipleLoops,
+    LineOnClue,
+    AdjacentBlocks,
+    BlackVisited,
+    Unvisited,
+    ThroughUnused,
+    Crossing,
+    ClueDegree,
+    UnusedCell,
+    MismatchedNumbers,
+    Loop,
+    AdjacentUnvisited,
+    RoomEntries,
+    Row,
+    Column,
+    Black,
+    White,
+    Undecided,
+    Area,
+    Balloon,
+    Iron,
+    DisconnectedBlock,
+    Unbalanced,
+    DisconnectedPart,
+    NotCongruent,
+    InvalidValue,
+    Duplicate,
+    Sum,
+    BlackClue,
+    Block2x2,
+    Disconnected,
+    NotCape,
+    CapeWithoutClue,
+    White2x2,
+    Black2x2,
+}
+
+impl ViolationKind {
+    /// The name of the kind in snake case, such as `"dead_end"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            ViolationKind::Clue => "clue",
+            ViolationKind::Branch => "branch",
+            ViolationKind::DeadEnd => "dead_end",
+            ViolationKind::NoLoop => "no_loop",
+            ViolationKind::MultipleLoops => "multiple_loops",
+            ViolationKind::LineOnClue => "line_on_clue",
+            ViolationKind::AdjacentBlocks => "adjacent_blocks",
+            ViolationKind::BlackVisited => "black_visited",
+            ViolationKind::Unvisited => "unvisited",
+            ViolationKind::ThroughUnused => "through_unused",
+            ViolationKind::Crossing => "crossing",
+            ViolationKind::ClueDegree => "clue_degree",
+            ViolationKind::UnusedCell => "unused_cell",
+            ViolationKind::MismatchedNumbers => "mismatched_numbers",
+            ViolationKind::Loop => "loop",
+            ViolationKind::AdjacentUnvisited => "adjacent_unvisited",
+            ViolationKind::RoomEntries => "room_entries",
+            ViolationKind::Row => "row",
+            ViolationKind::Column => "column",
+            ViolationKind::Black => "black",
+            ViolationKind::White => "white",
+            ViolationKind::Undecided => "undecided",
+            ViolationKind::Area => "area",
+            ViolationKind::Balloon => "balloon",
+            ViolationKind::Iron => "iron",
+            ViolationKind::DisconnectedBlock => "disconnected_block",
+            ViolationKind::Unbalanced => "unbalanced",
+            ViolationKind::DisconnectedPart => "disconnected_part",
+            ViolationKind::NotCongruent => "not_congruent",
+            ViolationKind::InvalidValue => "invalid_value",
+            ViolationKind::Duplicate => "duplicate",
+            ViolationKind::Sum => "sum",
+            ViolationKind::BlackClue => "black_clue",
+            ViolationKind::Block2x2 => "block_2x2",
+            ViolationKind::Disconnected => "disconnected",
+            ViolationKind::NotCape => "not_cape",
+            ViolationKind::CapeWithoutClue => "cape_without_clue",
+            ViolationKind::White2x2 => "white_2x2",
+            ViolationKind::Black2x2 => "black_2x2",
+        }
+    }
+}
+
+/// A violation of a rule found in a proposed answer by `check_answer` of each genre.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    /// The cells involved in the violation.
+    pub cells: Vec<P>,
+    /// The edges involved in the violation, in lattice coordinates of the answer.
+    pub edges: Vec<LP>,
+}
+
+impl Violation {
+    pub fn new(kind: ViolationKind, cells: Vec<P>, edges: Vec<LP>) -> Violation {
+        Violation { kind, cells, edges }
+    }
+    pub fn at_cells(kind: ViolationKind, cells: Vec<P>) -> Violation {
+        Violation::new(kind, cells, vec![])
+    }
+}
+
+/// Returns a violation of `kind` for each group of connected cells in `is_member`
+/// other than the largest one.
+pub fn check_connectivity(is_member: &Grid<bool>, kind: ViolationKind) -> Vec<Violation> {
+    let height = is_member.height();
+    let width = is_member.width();
+    let mut visited = Grid::new(height, width, false);
+    let mut groups: Vec<Vec<P>> = vec![];
+    for y in 0..height {
+        for x in 0..width {
+            let start = P(y, x);
+            if !is_member[start] || visited[start] {
+                continue;
+            }
+            let mut group = vec![];
+            let mut stack = vec![start];
+            visited[start] = true;
+            while let Some(pos) = stack.pop() {
+                group.push(pos);
+                for &d in &FOUR_NEIGHBOURS {
+                    let pos2 = pos + d;
+                    if is_member.get_or_default_p(pos2, false) && !visited[pos2] {
+                        visited[pos2] = true;
+                        stack.push(pos2);
+                    }
+                }
+            }
+            groups.push(group);
+        }
+    }
+    let mut ret = vec![];
+    if let Some(largest) = (0..groups.len()).max_by_key(|&i| groups[i].len()) {
+        for (i, group) in groups.into_iter().enumerate() {
+            if i != largest {
+                ret.push(Violation::at_cells(kind, group));
+            }
+        }
+    }
+    ret
+}
@@ -0,0 +1,139 @@
+use super::*;
+use grid_loop::brute_force::{
+    assert_consistent_edges, enumerate_loops, has_edge, is_on_loop, loop_from_vertices,
+};
+use {Grid, FOUR_NEIGHBOURS, LP, P};
+
+use rand::prng::XorShiftRng;
+use rand::{Rng, SeedableRng};
+
+/// Returns whether `answer` satisfies the rules of Country Road for `problem`.
+fn is_answer(problem: &Problem, answer: &Grid<bool>) -> bool {
+    let height = problem.height();
+    let width = problem.width();
+    let n_rooms = problem.n_rooms();
+    let mut n_crossings = vec![0; n_rooms];
+    let mut n_visited = vec![0; n_rooms];
+
+    for y in 0..height {
+        for x in 0..width {
+            let pos = P(y, x);
+            let room = problem.room(pos);
+            let visited = is_on_loop(answer, pos);
+            if visited {
+                n_visited[room] += 1;
+            }
+            for &d in &FOUR_NEIGHBOURS {
+                let pos2 = pos + d;
+                if !answer.is_valid_p(P(pos2.0 * 2, pos2.1 * 2)) || problem.room(pos2) == room {
+                    continue;
+                }
+                if has_edge(answer, LP::of_vertex(pos) + d) {
+                    n_crossings[room] += 1;
+                }
+                if !visited && !is_on_loop(answer, pos2) {
+                    return false;
+                }
+            }
+        }
+    }
+    (0..n_rooms).all(|room| {
+        let Clue(c) = problem.clue(room);
+        (n_rooms == 1 || n_crossings[room] == 2) && (c < 0 || c == n_visited[room])
+    })
+}
+
+/// Enumerates all the answers of `problem` among `loops`, which should be all the loops
+/// on the board.
+pub fn enumerate_answers(problem: &Problem, loops: &[Grid<bool>]) -> Vec<Grid<bool>> {
+    loops
+        .iter()
+        .filter(|answer| is_answer(problem, answer))
+        .cloned()
+        .collect()
+}
+
+/// Divides the board into rooms by merging adjacent cells randomly.
+fn random_rooms<R: Rng>(height: i32, width: i32, n_merges: i32, rng: &mut R) -> Grid<usize> {
+    let mut room = Grid::new(height, width, 0);
+    for i in 0..(height * width) {
+        room[P(i / width, i % width)] = i as usize;
+    }
+    for _ in 0..n_merges {
+        let pos = P(rng.gen_range(0, height), rng.gen_range(0, width));
+        let pos2 = pos + FOUR_NEIGHBOURS[rng.gen_range(0, 4)];
+        if !room.is_valid_p(pos2) {
+            continue;
+        }
+        let (from, to) = (room[pos2], room[pos]);
+        for y in 0..height {
+            for x in 0..width {
+                if room[P(y, x)] == from {
+                    room[P(y, x)] = to;
+                }
+            }
+        }
+    }
+    let mut ids = vec![None; (height * width) as usize];
+    let mut n_rooms = 0;
+    for y in 0..height {
+        for x in 0..width {
+            let r = room[P(y, x)];
+            if ids[r].is_none() {
+                ids[r] = Some(n_rooms);
+                n_rooms += 1;
+            }
+            room[P(y, x)] = ids[r].unwrap();
+        }
+    }
+    room
+}
+
+#[test]
+fn test_random_against_brute_force() {
+    let mut rng = XorShiftRng::from_seed([42u8; 16]);
+
+    for &(height, width) in &[(2, 2), (2, 3), (3, 3), (3, 4), (4, 4), (4, 5)] {
+        let loops = enumerate_loops(&Grid::new(height, width, true));
+        for _ in 0..100 {
+            let n_merges = rng.gen_range(height * width / 2, height * width * 2);
+            let mut problem = Problem::new(&random_rooms(height, width, n_merges, &mut rng));
+            let candidates = enumerate_answers(&problem, &loops);
+            if candidates.is_empty() {
+                continue;
+            }
+
+            // clues from a random answer, some of which are omitted
+            let base = &candidates[rng.gen_range(0, candidates.len())];
+            for room in 0..problem.n_rooms() {
+                if rng.gen::<f64>() < 0.5 {
+                    let n = (0..(height * width))
+                        .map(|i| P(i / width, i % width))
+                        .filter(|&pos| problem.room(pos) == room && is_on_loop(base, pos))
+                        .count();
+                    problem.set_clue(room, Clue(n as i32));
+                }
+            }
+            let answers = enumerate_answers(&problem, &candidates);
+            for answer in &answers {
+                assert!(check_answer(&problem, answer).is_empty());
+            }
+            for _ in 0..10 {
+                let candidate = &loops[rng.gen_range(0, loops.len())];
+                let violations = check_answer(&problem, candidate);
+                assert_eq!(violations.is_empty(), answers.contains(candidate));
+            }
+
+            let mut field = Field::new(&problem);
+            let found = field.search(answers.len() + 1);
+            assert_eq!(found.len(), answers.len());
+            for vertices in &found {
+                assert!(answers.contains(&loop_from_vertices(height, width, vertices)));
+            }
+
+            field.trial_and_error(1);
+            assert!(!field.inconsistent());
+            assert_consistent_edges(|lp| field.get_edge(lp), field.fully_solved(), &answers);
+        }
+    }
+}
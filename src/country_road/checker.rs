@@ -0,0 +1,75 @@
+use super::super::{Grid, Violation, ViolationKind, D, LP, P};
+use super::*;
+use grid_loop::{check_loop, line_degree};
+use FOUR_NEIGHBOURS;
+
+/// Checks whether `answer` is an answer of `problem`.
+/// `answer` has the size `(2 * height - 1) x (2 * width - 1)` and is indexed by lattice
+/// positions (`LP::of_vertex(pos)` for the cell `pos`); it is true exactly on the edges of the loop.
+/// The violations reported are:
+/// - `RoomEntries`: a room (all of its cells) not passed by the loop exactly once,
+///   with the lines crossing its border in `edges`.
+/// - `Clue`: a room whose clue differs from the number of visited cells in it.
+/// - `AdjacentUnvisited`: two unvisited cells adjacent across the border of rooms.
+/// - those of `grid_loop::check_loop`.
+pub fn check_answer(problem: &Problem, answer: &Grid<bool>) -> Vec<Violation> {
+    let height = problem.height();
+    let width = problem.width();
+    assert_eq!(answer.height(), height * 2 - 1);
+    assert_eq!(answer.width(), width * 2 - 1);
+
+    let n_rooms = problem.n_rooms();
+    let mut cells = vec![vec![]; n_rooms];
+    let mut crossings = vec![vec![]; n_rooms];
+    let mut n_visited = vec![0; n_rooms];
+    let mut ret = vec![];
+
+    for y in 0..height {
+        for x in 0..width {
+            let pos = P(y, x);
+            let room = problem.room(pos);
+            let visited = line_degree(answer, pos) > 0;
+            cells[room].push(pos);
+            if visited {
+                n_visited[room] += 1;
+            }
+            for &d in &FOUR_NEIGHBOURS {
+                let pos2 = pos + d;
+                if !(0 <= pos2.y() && pos2.y() < height && 0 <= pos2.x() && pos2.x() < width)
+                    || problem.room(pos2) == room
+                {
+                    continue;
+                }
+                let e = LP::of_vertex(pos) + d;
+                if answer[e] {
+                    crossings[room].push(e);
+                }
+                // each pair is reported once
+                if !visited && line_degree(answer, pos2) == 0 && (d == D(0, 1) || d == D(1, 0)) {
+                    ret.push(Violation::at_cells(
+                        ViolationKind::AdjacentUnvisited,
+                        vec![pos, pos2],
+                    ));
+                }
+            }
+        }
+    }
+    for room in 0..n_rooms {
+        if n_rooms > 1 && crossings[room].len() != 2 {
+            ret.push(Violation::new(
+                ViolationKind::RoomEntries,
+                cells[room].clone(),
+                crossings[room].clone(),
+            ));
+        }
+        let Clue(c) = problem.clue(room);
+        if c >= 0 && c != n_visited[room] {
+            ret.push(Violation::at_cells(
+                ViolationKind::Clue,
+                cells[room].clone(),
+            ));
+        }
+    }
+    ret.extend(check_loop(answer));
+    ret
+}
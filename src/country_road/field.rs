@@ -0,0 +1,559 @@
+use super::super::{Grid, SolverStats, D, LP, P};
+use super::*;
+use grid_loop::{Edge, GridLoop, GridLoopField, LineChain};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use FOUR_NEIGHBOURS;
+
+#[derive(Clone)]
+enum History {
+    Cell(P),
+    Checkpoint,
+}
+
+#[derive(Clone)]
+pub struct Field {
+    grid_loop: GridLoop,
+    problem: Problem,
+    cell: Grid<Cell>,
+    room_cells: Vec<Vec<P>>,
+    room_borders: Vec<Vec<LP>>,
+    history: Vec<History>,
+    stats: SolverStats,
+}
+
+impl Field {
+    pub fn new(problem: &Problem) -> Field {
+        let height = problem.height();
+        let width = problem.width();
+        let n_rooms = problem.n_rooms();
+
+        let mut room_cells = vec![vec![]; n_rooms];
+        let mut room_borders = vec![vec![]; n_rooms];
+        for y in 0..height {
+            for x in 0..width {
+                let pos = P(y, x);
+                let room = problem.room(pos);
+                room_cells[room].push(pos);
+                for &d in &FOUR_NEIGHBOURS {
+                    let pos2 = pos + d;
+                    if 0 <= pos2.0
+                        && pos2.0 < height
+                        && 0 <= pos2.1
+                        && pos2.1 < width
+                        && problem.room(pos2) != room
+                    {
+                        room_borders[room].push(LP::of_vertex(pos) + d);
+                    }
+                }
+            }
+        }
+
+        Field {
+            grid_loop: GridLoop::new(height - 1, width - 1),
+            problem: problem.clone(),
+            cell: Grid::new(height, width, Cell::Undecided),
+            room_cells,
+            room_borders,
+            history: Vec::new(),
+            stats: SolverStats::new(),
+        }
+    }
+    pub fn height(&self) -> i32 {
+        self.problem.height()
+    }
+    pub fn width(&self) -> i32 {
+        self.problem.width()
+    }
+    pub fn inconsistent(&self) -> bool {
+        self.grid_loop.inconsistent()
+    }
+    pub fn set_inconsistent(&mut self) {
+        self.grid_loop.set_inconsistent()
+    }
+    pub fn fully_solved(&self) -> bool {
+        self.grid_loop.fully_solved()
+    }
+    pub fn get_cell(&self, pos: P) -> Cell {
+        self.cell[pos]
+    }
+    pub fn get_edge(&self, pos: LP) -> Edge {
+        self.grid_loop.get_edge(pos)
+    }
+    pub fn get_edge_safe(&self, pos: LP) -> Edge {
+        self.grid_loop.get_edge_safe(pos)
+    }
+    pub fn line_chains(&self) -> Vec<LineChain> {
+        self.grid_loop.line_chains()
+    }
+    pub fn solved_loop(&self) -> Option<Vec<P>> {
+        self.grid_loop.solved_loop()
+    }
+    pub fn num_decided_edges(&self) -> i32 {
+        self.grid_loop.num_decided_edges()
+    }
+    /// Returns the statistics of the rules and the trial and error. The progress is
+    /// measured in decided edges.
+    pub fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+    pub fn set_stats_enabled(&mut self, enabled: bool) {
+        self.stats.set_enabled(enabled);
+    }
+    /// Add a checkpoint.
+    /// Subsequent updates of the field (including the underlying `GridLoop`) are recorded
+    /// until `rollback` is called.
+    pub fn add_checkpoint(&mut self) {
+        self.grid_loop.add_checkpoint();
+        self.history.push(History::Checkpoint);
+    }
+    /// Rollback until the last checkpoint.
+    pub fn rollback(&mut self) {
+        self.grid_loop.rollback();
+        while let Some(entry) = self.history.pop() {
+            match entry {
+                History::Cell(pos) => self.cell[pos] = Cell::Undecided,
+                History::Checkpoint => break,
+            }
+        }
+    }
+    pub fn set_cell(&mut self, pos: P, v: Cell) {
+        let mut handle = GridLoop::get_handle(self);
+        handle.set_cell_internal(pos, v);
+    }
+
+    pub fn check_all_cell(&mut self) {
+        let height = self.height();
+        let width = self.width();
+        let mut handle = GridLoop::get_handle(self);
+        for y in 0..height {
+            for x in 0..width {
+                GridLoop::check(&mut *handle, LP::of_vertex(P(y, x)));
+            }
+        }
+    }
+    pub fn solve(&mut self) {
+        loop {
+            let current_decided_edges = self.grid_loop.num_decided_edges();
+            self.apply_technique("local", Field::check_all_cell);
+            self.apply_technique("inout_rule", GridLoop::apply_inout_rule);
+            self.apply_technique("connectability", GridLoop::check_connectability);
+            if self.inconsistent() || current_decided_edges == self.grid_loop.num_decided_edges() {
+                break;
+            }
+        }
+    }
+    pub fn trial_and_error(&mut self, depth: i32) {
+        self.trial_and_error_cancellable(depth, &AtomicBool::new(false))
+    }
+    /// Same as `trial_and_error`, but gives up when `cancel` is set, keeping the edges
+    /// decided so far.
+    pub fn trial_and_error_cancellable(&mut self, depth: i32, cancel: &AtomicBool) {
+        let height = self.height();
+        let width = self.width();
+
+        if depth == 0 {
+            self.solve();
+            return;
+        }
+        self.trial_and_error_cancellable(depth - 1, cancel);
+
+        loop {
+            let mut updated = false;
+            for y in 0..(height * 2 - 1) {
+                for x in 0..(width * 2 - 1) {
+                    if y % 2 == x % 2 {
+                        continue;
+                    }
+                    if cancel.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    let pos = LP(y, x);
+                    if self.get_edge(pos) != Edge::Undecided || !self.grid_loop.is_root(pos) {
+                        continue;
+                    }
+
+                    if self.is_inconsistent_with_edge(pos, Edge::Line, depth - 1) {
+                        updated = true;
+                        GridLoop::decide_edge(self, pos, Edge::Blank);
+                        self.trial_and_error_cancellable(depth - 1, cancel);
+                    }
+                    if self.get_edge(pos) == Edge::Undecided
+                        && self.is_inconsistent_with_edge(pos, Edge::Blank, depth - 1)
+                    {
+                        updated = true;
+                        GridLoop::decide_edge(self, pos, Edge::Line);
+                        self.trial_and_error_cancellable(depth - 1, cancel);
+                    }
+                    if self.inconsistent() || self.fully_solved() {
+                        return;
+                    }
+                }
+            }
+            if !updated {
+                break;
+            }
+        }
+    }
+    /// Enumerates the answers of the problem, up to `limit` answers.
+    /// Each answer is given as the cells on the loop in order.
+    /// The field is left unchanged.
+    pub fn search(&mut self, limit: usize) -> Vec<Vec<P>> {
+        self.search_cancellable(limit, &AtomicBool::new(false))
+    }
+    /// Same as `search`, but gives up when `cancel` is set, returning the answers found so far.
+    pub fn search_cancellable(&mut self, limit: usize, cancel: &AtomicBool) -> Vec<Vec<P>> {
+        let mut answers = vec![];
+        self.add_checkpoint();
+        self.search_dfs(limit, cancel, &mut answers);
+        self.rollback();
+        answers
+    }
+    /// Checks whether the problem has exactly one answer.
+    pub fn has_unique_answer(&mut self) -> bool {
+        self.search(2).len() == 1
+    }
+    fn search_dfs(&mut self, limit: usize, cancel: &AtomicBool, answers: &mut Vec<Vec<P>>) {
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        self.solve();
+        if self.inconsistent() {
+            return;
+        }
+        if self.fully_solved() {
+            if let Some(answer) = self.solved_loop() {
+                answers.push(answer);
+            }
+            return;
+        }
+        let edge = match self.find_branching_edge() {
+            Some(edge) => edge,
+            None => return,
+        };
+        for &status in &[Edge::Line, Edge::Blank] {
+            if answers.len() >= limit {
+                return;
+            }
+            self.add_checkpoint();
+            GridLoop::decide_edge(self, edge, status);
+            self.search_dfs(limit, cancel, answers);
+            self.rollback();
+        }
+    }
+    /// Finds an undecided edge to branch on, preferring one extending an existing line.
+    fn find_branching_edge(&self) -> Option<LP> {
+        let height = self.height();
+        let width = self.width();
+        let mut ret = None;
+        for y in 0..height {
+            for x in 0..width {
+                let vertex = LP::of_vertex(P(y, x));
+                let (n_line, _) = self.grid_loop.neighbor_summary(vertex);
+                for &d in &FOUR_NEIGHBOURS {
+                    if self.get_edge_safe(vertex + d) == Edge::Undecided {
+                        if n_line == 1 {
+                            return Some(vertex + d);
+                        }
+                        if ret.is_none() {
+                            ret = Some(vertex + d);
+                        }
+                    }
+                }
+            }
+        }
+        ret
+    }
+    /// Checks whether deciding edge `pos` to `status` leads to an inconsistency
+    /// within trial-and-error of depth `depth`. The field is left unchanged.
+    fn is_inconsistent_with_edge(&mut self, pos: LP, status: Edge, depth: i32) -> bool {
+        self.add_checkpoint();
+        GridLoop::decide_edge(self, pos, status);
+        self.trial_and_error(depth);
+        let ret = self.inconsistent();
+        self.rollback();
+        self.stats.record_trial(ret);
+        ret
+    }
+    /// Applies `technique`, recording how many edges it decided.
+    fn apply_technique<F: FnOnce(&mut Field)>(&mut self, name: &'static str, technique: F) {
+        let decided_edges = self.num_decided_edges();
+        technique(self);
+        let n_decided = self.num_decided_edges() - decided_edges;
+        self.stats.record(name, n_decided);
+    }
+
+    fn set_cell_internal(&mut self, pos: P, v: Cell) {
+        let current = self.cell[pos];
+        if current != Cell::Undecided {
+            if current != v {
+                self.set_inconsistent();
+            }
+            return;
+        }
+
+        self.cell[pos] = v;
+        if !self.history.is_empty() {
+            self.history.push(History::Cell(pos));
+        }
+        GridLoop::check(self, LP::of_vertex(pos));
+        if v == Cell::Unvisited {
+            // two adjacent cells in different rooms can't be both unvisited
+            let room = self.problem.room(pos);
+            for &d in &FOUR_NEIGHBOURS {
+                let pos2 = pos + d;
+                if self.cell.is_valid_p(pos2) && self.problem.room(pos2) != room {
+                    self.set_cell_internal(pos2, Cell::Visited);
+                }
+            }
+        }
+    }
+    fn inspect_room(&mut self, room: usize) {
+        // the loop enters the room exactly once (unless the whole board is one room)
+        if !self.room_borders[room].is_empty() {
+            let mut n_line = 0;
+            let mut n_undecided = 0;
+            for &e in &self.room_borders[room] {
+                match self.get_edge(e) {
+                    Edge::Line => n_line += 1,
+                    Edge::Undecided => n_undecided += 1,
+                    Edge::Blank => (),
+                }
+            }
+            if n_line > 2 || n_line + n_undecided < 2 {
+                self.set_inconsistent();
+                return;
+            }
+            if n_line == 2 || n_line + n_undecided == 2 {
+                let status = if n_line == 2 { Edge::Blank } else { Edge::Line };
+                for i in 0..self.room_borders[room].len() {
+                    let e = self.room_borders[room][i];
+                    if self.get_edge(e) == Edge::Undecided {
+                        GridLoop::decide_edge(self, e, status);
+                    }
+                }
+            }
+        }
+
+        // the number of visited cells in the room
+        let Clue(n) = self.problem.clue(room);
+        if n < 0 {
+            return;
+        }
+        let mut n_visited = 0;
+        let mut n_undecided = 0;
+        for &pos in &self.room_cells[room] {
+            match self.cell[pos] {
+                Cell::Visited => n_visited += 1,
+                Cell::Undecided => n_undecided += 1,
+                Cell::Unvisited => (),
+            }
+        }
+        if n_visited > n || n_visited + n_undecided < n {
+            self.set_inconsistent();
+            return;
+        }
+        if n_visited == n || n_visited + n_undecided == n {
+            let v = if n_visited == n {
+                Cell::Unvisited
+            } else {
+                Cell::Visited
+            };
+            for i in 0..self.room_cells[room].len() {
+                let pos = self.room_cells[room][i];
+                if self.cell[pos] == Cell::Undecided {
+                    self.set_cell_internal(pos, v);
+                }
+            }
+        }
+    }
+}
+
+impl GridLoopField for Field {
+    fn grid_loop(&mut self) -> &mut GridLoop {
+        &mut self.grid_loop
+    }
+    fn check_neighborhood(&mut self, pos: LP) {
+        if pos.0 % 2 == 0 {
+            GridLoop::check(self, pos + D(0, -1));
+            GridLoop::check(self, pos + D(0, 1));
+        } else {
+            GridLoop::check(self, pos + D(-1, 0));
+            GridLoop::check(self, pos + D(1, 0));
+        }
+    }
+    fn inspect(&mut self, pos: LP) {
+        if !pos.is_vertex() {
+            return;
+        }
+        let cell_pos = pos.as_vertex();
+        let (n_line, n_undecided) = self.grid_loop.neighbor_summary(pos);
+        match self.cell[cell_pos] {
+            Cell::Undecided => {
+                if n_line >= 1 {
+                    self.set_cell_internal(cell_pos, Cell::Visited);
+                } else if n_undecided <= 1 {
+                    self.set_cell_internal(cell_pos, Cell::Unvisited);
+                }
+            }
+            Cell::Visited => {
+                if n_line + n_undecided < 2 {
+                    self.set_inconsistent();
+                    return;
+                }
+                if n_line + n_undecided == 2 {
+                    for &d in &FOUR_NEIGHBOURS {
+                        if self.get_edge_safe(pos + d) == Edge::Undecided {
+                            GridLoop::decide_edge(self, pos + d, Edge::Line);
+                        }
+                    }
+                }
+            }
+            Cell::Unvisited => {
+                for &d in &FOUR_NEIGHBOURS {
+                    GridLoop::decide_edge(self, pos + d, Edge::Blank);
+                }
+            }
+        }
+        let room = self.problem.room(cell_pos);
+        self.inspect_room(room);
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let height = self.height();
+        let width = self.width();
+        for y in 0..(2 * height - 1) {
+            for x in 0..(2 * width - 1) {
+                match (y % 2, x % 2) {
+                    (0, 0) => match self.cell[P(y / 2, x / 2)] {
+                        Cell::Undecided => write!(f, "+")?,
+                        Cell::Visited => write!(f, "o")?,
+                        Cell::Unvisited => write!(f, ".")?,
+                    },
+                    (0, 1) => match self.get_edge(LP(y, x)) {
+                        Edge::Line => write!(f, "---")?,
+                        Edge::Blank => write!(f, " x ")?,
+                        Edge::Undecided => write!(f, "   ")?,
+                    },
+                    (1, 0) => match self.get_edge(LP(y, x)) {
+                        Edge::Line => write!(f, "|")?,
+                        Edge::Blank => write!(f, "x")?,
+                        Edge::Undecided => write!(f, " ")?,
+                    },
+                    (1, 1) => write!(f, "   ")?,
+                    _ => unreachable!(),
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problem_from_rooms(rooms: &[Vec<usize>], clues: &[(usize, i32)]) -> Problem {
+        let mut problem = Problem::new(&::common::vec_to_grid(&rooms.to_vec()));
+        for &(room, n) in clues {
+            problem.set_clue(room, Clue(n));
+        }
+        problem
+    }
+
+    #[test]
+    fn test_adjacent_unvisited() {
+        let problem = problem_from_rooms(&[vec![0, 0, 1], vec![0, 0, 1], vec![2, 2, 1]], &[]);
+        let mut field = Field::new(&problem);
+        field.set_cell(P(1, 1), Cell::Unvisited);
+
+        assert!(!field.inconsistent());
+        assert_eq!(field.get_edge(LP(2, 1)), Edge::Blank);
+        assert_eq!(field.get_edge(LP(2, 3)), Edge::Blank);
+        assert_eq!(field.get_cell(P(1, 2)), Cell::Visited);
+        assert_eq!(field.get_cell(P(2, 1)), Cell::Visited);
+    }
+
+    #[test]
+    fn test_room_clue() {
+        {
+            let problem =
+                problem_from_rooms(&[vec![0, 0, 1], vec![0, 0, 1], vec![2, 2, 1]], &[(1, 0)]);
+            let mut field = Field::new(&problem);
+            field.check_all_cell();
+
+            // the loop can't enter room 1
+            assert!(field.inconsistent());
+        }
+        {
+            let problem =
+                problem_from_rooms(&[vec![0, 0, 1], vec![0, 0, 1], vec![2, 2, 1]], &[(1, 3)]);
+            let mut field = Field::new(&problem);
+            field.check_all_cell();
+
+            assert!(!field.inconsistent());
+            assert_eq!(field.get_cell(P(0, 2)), Cell::Visited);
+            assert_eq!(field.get_cell(P(2, 2)), Cell::Visited);
+            assert_eq!(field.get_edge(LP(0, 3)), Edge::Line);
+            assert_eq!(field.get_edge(LP(1, 4)), Edge::Line);
+            assert_eq!(field.get_edge(LP(3, 4)), Edge::Line);
+            assert_eq!(field.get_edge(LP(4, 3)), Edge::Line);
+        }
+    }
+
+    #[test]
+    fn test_room_entered_once() {
+        let problem =
+            problem_from_rooms(&[vec![0, 0, 1, 1], vec![0, 0, 1, 1], vec![0, 0, 1, 1]], &[]);
+        let mut field = Field::new(&problem);
+        GridLoop::decide_edge(&mut field, LP(0, 3), Edge::Line);
+        GridLoop::decide_edge(&mut field, LP(4, 3), Edge::Line);
+
+        assert!(!field.inconsistent());
+        assert_eq!(field.get_edge(LP(2, 3)), Edge::Blank);
+    }
+
+    #[test]
+    fn test_problem() {
+        let problem = problem_from_rooms(
+            &[
+                vec![0, 0, 1, 1, 1, 7],
+                vec![0, 0, 1, 2, 2, 7],
+                vec![3, 3, 4, 2, 2, 7],
+                vec![3, 3, 4, 4, 5, 7],
+                vec![3, 6, 6, 5, 5, 8],
+                vec![6, 6, 6, 8, 8, 8],
+            ],
+            &[(0, 4), (5, 1), (6, 2)],
+        );
+        let mut field = Field::new(&problem);
+        let answers = field.search(2);
+        assert_eq!(answers.len(), 1);
+        assert_eq!(answers[0].len(), 29);
+        assert!(field.has_unique_answer());
+
+        // `search` leaves the field unchanged
+        assert_eq!(field.num_decided_edges(), 0);
+        assert!(!field.inconsistent());
+
+        let cancel = AtomicBool::new(true);
+        assert!(field.search_cancellable(2, &cancel).is_empty());
+
+        let problem = problem_from_rooms(
+            &[
+                vec![0, 0, 1, 1, 1, 7],
+                vec![0, 0, 1, 2, 2, 7],
+                vec![3, 3, 4, 2, 2, 7],
+                vec![3, 3, 4, 4, 5, 7],
+                vec![3, 6, 6, 5, 5, 8],
+                vec![6, 6, 6, 8, 8, 8],
+            ],
+            &[(0, 4), (6, 2)],
+        );
+        let mut field = Field::new(&problem);
+        assert!(!field.has_unique_answer());
+    }
+}
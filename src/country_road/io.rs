@@ -0,0 +1,37 @@
+use std::io::BufRead;
+
+use super::*;
+use io::{read_grid, ReadError};
+
+/// Reads a Country Road problem.
+/// The header is followed by the room id of each cell and then by the clue grid,
+/// in which a number placed at any cell of a room is the clue of the room.
+pub fn read_penciloid_problem<T: BufRead>(reader: &mut T) -> Result<Problem, ReadError> {
+    let room = read_grid(
+        reader,
+        |token: &str| token.parse::<usize>().map_err(|_| ReadError::InvalidValue),
+        0,
+    )?;
+    let mut ret = Problem::new(&room);
+
+    let mut buffer = String::new();
+    for y in 0..room.height() {
+        ::io::next_valid_line(reader, &mut buffer)?;
+        let mut row = buffer.trim_end().split(' ');
+
+        for x in 0..room.width() {
+            let token = row.next().ok_or(ReadError::InvalidFormat)?;
+            if token == "." {
+                continue;
+            }
+            let n = token.parse::<i32>().map_err(|_| ReadError::InvalidValue)?;
+            let room_id = ret.room(P(y, x));
+            if ret.clue(room_id) != NO_CLUE && ret.clue(room_id) != Clue(n) {
+                return Err(ReadError::InvalidValue);
+            }
+            ret.set_clue(room_id, Clue(n));
+        }
+    }
+
+    Ok(ret)
+}
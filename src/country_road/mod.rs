@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod brute_force;
+mod checker;
+mod field;
+mod io;
+
+pub use self::checker::*;
+pub use self::field::*;
+pub use self::io::*;
+
+use super::{Grid, P};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clue(pub i32);
+
+pub const NO_CLUE: Clue = Clue(-1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    Undecided,
+    Visited,
+    Unvisited,
+}
+
+/// A Country Road problem.
+/// Each cell belongs to the room `room(pos)`, and each room may have a clue representing
+/// the number of cells in the room visited by the loop.
+#[derive(Debug, Clone)]
+pub struct Problem {
+    room: Grid<usize>,
+    clue: Vec<Clue>,
+}
+
+impl Problem {
+    /// Creates a problem without clues. The room ids are renumbered into 0, 1, ... keeping
+    /// their order, so ids which are already consecutive from 0 are left unchanged.
+    pub fn new(room: &Grid<usize>) -> Problem {
+        let mut ids = (0..room.height())
+            .flat_map(|y| (0..room.width()).map(move |x| P(y, x)))
+            .map(|pos| room[pos])
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids.dedup();
+        let mut compact = Grid::new(room.height(), room.width(), 0);
+        for y in 0..room.height() {
+            for x in 0..room.width() {
+                compact[P(y, x)] = ids.binary_search(&room[P(y, x)]).unwrap();
+            }
+        }
+        Problem {
+            room: compact,
+            clue: vec![NO_CLUE; ids.len()],
+        }
+    }
+    pub fn height(&self) -> i32 {
+        self.room.height()
+    }
+    pub fn width(&self) -> i32 {
+        self.room.width()
+    }
+    pub fn n_rooms(&self) -> usize {
+        self.clue.len()
+    }
+    pub fn room(&self, pos: P) -> usize {
+        self.room[pos]
+    }
+    pub fn clue(&self, room: usize) -> Clue {
+        self.clue[room]
+    }
+    pub fn set_clue(&mut self, room: usize, clue: Clue) {
+        self.clue[room] = clue;
+    }
+}
@@ -0,0 +1,149 @@
+use super::field::Field;
+use super::{check_answer, Cell};
+use {Grid, D, FOUR_NEIGHBOURS, P};
+
+use rand::prng::XorShiftRng;
+use rand::{Rng, SeedableRng};
+
+/// Returns whether the balloons and irons in `answer` obey the gravity.
+fn is_stable(answer: &Grid<Cell>) -> bool {
+    (0..answer.height()).all(|y| {
+        (0..answer.width()).all(|x| {
+            let pos = P(y, x);
+            match answer[pos] {
+                Cell::Balloon => {
+                    let above = answer.get_or_default_p(pos + D(-1, 0), Cell::Black);
+                    above == Cell::Black || above == Cell::Balloon
+                }
+                Cell::Iron => {
+                    let below = answer.get_or_default_p(pos + D(1, 0), Cell::Black);
+                    below == Cell::Black || below == Cell::Iron
+                }
+                _ => true,
+            }
+        })
+    })
+}
+
+fn enumerate_dfs(areas: &[Vec<P>], current: &mut Grid<Cell>, answers: &mut Vec<Grid<Cell>>) {
+    let id = match areas
+        .iter()
+        .position(|area| area.iter().all(|&p| current[p] == Cell::Empty))
+    {
+        Some(id) => id,
+        None => {
+            if is_stable(current) {
+                answers.push(current.clone());
+            }
+            return;
+        }
+    };
+    for &balloon in &areas[id] {
+        for &iron in &areas[id] {
+            if balloon == iron {
+                continue;
+            }
+            current[balloon] = Cell::Balloon;
+            current[iron] = Cell::Iron;
+            enumerate_dfs(areas, current, answers);
+            current[balloon] = Cell::Empty;
+            current[iron] = Cell::Empty;
+        }
+    }
+}
+
+/// Enumerates all the answers of the problem given by `is_black` and `areas`.
+pub fn enumerate_answers(is_black: &Grid<bool>, areas: &[Vec<P>]) -> Vec<Grid<Cell>> {
+    let height = is_black.height();
+    let width = is_black.width();
+    let mut current = Grid::new(height, width, Cell::Empty);
+    for y in 0..height {
+        for x in 0..width {
+            if is_black[P(y, x)] {
+                current[P(y, x)] = Cell::Black;
+            }
+        }
+    }
+    let mut answers = vec![];
+    if areas.iter().all(|area| area.len() >= 2) {
+        enumerate_dfs(areas, &mut current, &mut answers);
+    }
+    answers
+}
+
+/// Divides the white cells into areas by merging adjacent cells randomly.
+fn random_areas<R: Rng>(is_black: &Grid<bool>, n_merges: i32, rng: &mut R) -> Vec<Vec<P>> {
+    let height = is_black.height();
+    let width = is_black.width();
+    let mut area = Grid::new(height, width, 0);
+    for i in 0..(height * width) {
+        area[P(i / width, i % width)] = i as usize;
+    }
+    for _ in 0..n_merges {
+        let pos = P(rng.gen_range(0, height), rng.gen_range(0, width));
+        let pos2 = pos + FOUR_NEIGHBOURS[rng.gen_range(0, 4)];
+        if !area.is_valid_p(pos2) || is_black[pos] || is_black[pos2] {
+            continue;
+        }
+        let (from, to) = (area[pos2], area[pos]);
+        for y in 0..height {
+            for x in 0..width {
+                if area[P(y, x)] == from {
+                    area[P(y, x)] = to;
+                }
+            }
+        }
+    }
+    let mut ret = vec![vec![]; (height * width) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            if !is_black[P(y, x)] {
+                ret[area[P(y, x)]].push(P(y, x));
+            }
+        }
+    }
+    ret.into_iter().filter(|a| !a.is_empty()).collect()
+}
+
+#[test]
+fn test_random_against_brute_force() {
+    let mut rng = XorShiftRng::from_seed([42u8; 16]);
+
+    for &(height, width) in &[(2, 2), (2, 3), (3, 3), (3, 4), (4, 4), (4, 5), (5, 5)] {
+        let mut n_tested = 0;
+        while n_tested < 100 {
+            let mut is_black = Grid::new(height, width, false);
+            for y in 0..height {
+                for x in 0..width {
+                    is_black[P(y, x)] = rng.gen::<f64>() < 0.2;
+                }
+            }
+            let n_merges = rng.gen_range(height * width / 2, height * width * 2);
+            let areas = random_areas(&is_black, n_merges, &mut rng);
+            let answers = enumerate_answers(&is_black, &areas);
+
+            let mut field = Field::new(&is_black, &areas);
+            field.inspect_initial();
+            field.trial_and_error(1);
+            if answers.is_empty() {
+                assert!(!field.fully_solved() || field.inconsistent());
+                continue;
+            }
+            n_tested += 1;
+            assert!(!field.inconsistent());
+            for answer in &answers {
+                assert!(check_answer(&is_black, &areas, answer).is_empty());
+            }
+            for answer in &answers {
+                for y in 0..height {
+                    for x in 0..width {
+                        let actual = field.get_cell(P(y, x));
+                        assert!(actual == Cell::Undecided || actual == answer[P(y, x)]);
+                        assert!(!field.fully_solved() || actual == answer[P(y, x)]);
+                    }
+                }
+            }
+            assert!(!field.fully_solved() || answers.len() == 1);
+        }
+    }
+}
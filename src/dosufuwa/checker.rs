@@ -0,0 +1,59 @@
+use super::super::{Grid, Violation, ViolationKind, D, P};
+use super::*;
+
+/// Checks whether `answer` is an answer of the problem given by `is_black` and `areas`.
+/// The violations reported are:
+/// - `Black`: a cell which is `Black` in `answer` but not in the problem, or vice versa.
+/// - `Undecided`: an `Undecided` cell.
+/// - `Area`: an area which does not have exactly one balloon and one iron.
+/// - `Balloon`: a balloon without a black cell, the border or another balloon above it.
+/// - `Iron`: an iron without a black cell, the border or another iron below it.
+pub fn check_answer(
+    is_black: &Grid<bool>,
+    areas: &[Vec<P>],
+    answer: &Grid<Cell>,
+) -> Vec<Violation> {
+    let height = is_black.height();
+    let width = is_black.width();
+    let mut ret = vec![];
+
+    for y in 0..height {
+        for x in 0..width {
+            let pos = P(y, x);
+            let cell = answer[pos];
+            if is_black[pos] != (cell == Cell::Black) {
+                ret.push(Violation::at_cells(ViolationKind::Black, vec![pos]));
+            } else if cell == Cell::Undecided {
+                ret.push(Violation::at_cells(ViolationKind::Undecided, vec![pos]));
+            }
+        }
+    }
+    for area in areas {
+        let n_balloons = area.iter().filter(|&&p| answer[p] == Cell::Balloon).count();
+        let n_irons = area.iter().filter(|&&p| answer[p] == Cell::Iron).count();
+        if n_balloons != 1 || n_irons != 1 {
+            ret.push(Violation::at_cells(ViolationKind::Area, area.clone()));
+        }
+    }
+    for y in 0..height {
+        for x in 0..width {
+            let pos = P(y, x);
+            match answer[pos] {
+                Cell::Balloon => {
+                    let above = answer.get_or_default_p(pos + D(-1, 0), Cell::Black);
+                    if above != Cell::Black && above != Cell::Balloon {
+                        ret.push(Violation::at_cells(ViolationKind::Balloon, vec![pos]));
+                    }
+                }
+                Cell::Iron => {
+                    let below = answer.get_or_default_p(pos + D(1, 0), Cell::Black);
+                    if below != Cell::Black && below != Cell::Iron {
+                        ret.push(Violation::at_cells(ViolationKind::Iron, vec![pos]));
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    ret
+}
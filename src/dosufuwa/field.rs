@@ -80,6 +80,9 @@ impl Field {
     pub fn num_decided(&self) -> i32 {
         self.num_decided
     }
+    pub fn get_cell(&self, pos: P) -> Cell {
+        self.cell[pos]
+    }
 
     fn inspect_area_balloon(&mut self, id: usize) {
         let area = &self.areas_balloon[id];
@@ -95,7 +98,7 @@ impl Field {
         if cell == Cell::Balloon {
             return;
         }
-        if cell != Cell::Undecided {
+        if cell != Cell::Undecided || !self.maybe_balloon[pos] {
             self.set_inconsistent();
             return;
         }
@@ -117,6 +120,7 @@ impl Field {
     pub fn decide_no_balloon(&mut self, pos: P) {
         let cell = self.cell[pos];
         if cell == Cell::Balloon {
+            self.set_inconsistent();
             return;
         }
         if !self.maybe_balloon[pos] {
@@ -151,7 +155,7 @@ impl Field {
         if cell == Cell::Iron {
             return;
         }
-        if cell != Cell::Undecided {
+        if cell != Cell::Undecided || !self.maybe_iron[pos] {
             self.set_inconsistent();
             return;
         }
@@ -174,6 +178,7 @@ impl Field {
     pub fn decide_no_iron(&mut self, pos: P) {
         let cell = self.cell[pos];
         if cell == Cell::Iron {
+            self.set_inconsistent();
             return;
         }
         if !self.maybe_iron[pos] {
@@ -1,5 +1,10 @@
+#[cfg(test)]
+mod brute_force;
+mod checker;
 mod field;
 
+pub use self::checker::*;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Cell {
     Undecided,
@@ -0,0 +1,222 @@
+use super::*;
+use std::collections::HashSet;
+use {Grid, D, FOUR_NEIGHBOURS, LP, P};
+
+use rand::prng::XorShiftRng;
+use rand::{Rng, SeedableRng};
+
+fn cells_of(width: i32, mask: u32) -> Vec<P> {
+    (0..32)
+        .filter(|&i| (mask >> i) & 1 != 0)
+        .map(|i| P(i / width, i % width))
+        .collect()
+}
+
+fn is_connected(cells: &[P]) -> bool {
+    let mut visited = vec![false; cells.len()];
+    let mut stack = vec![0];
+    visited[0] = true;
+    while let Some(i) = stack.pop() {
+        for j in 0..cells.len() {
+            let D(dy, dx) = cells[i] - cells[j];
+            if !visited[j] && dy.abs() + dx.abs() == 1 {
+                visited[j] = true;
+                stack.push(j);
+            }
+        }
+    }
+    visited.iter().all(|&v| v)
+}
+
+/// Returns the canonical form of the shape `cells` up to translation.
+fn normalize(cells: &[(i32, i32)]) -> Vec<(i32, i32)> {
+    let min_y = cells.iter().map(|c| c.0).min().unwrap();
+    let min_x = cells.iter().map(|c| c.1).min().unwrap();
+    let mut ret = cells
+        .iter()
+        .map(|&(y, x)| (y - min_y, x - min_x))
+        .collect::<Vec<_>>();
+    ret.sort();
+    ret
+}
+
+fn is_congruent(a: &[P], b: &[P]) -> bool {
+    let target = normalize(&b.iter().map(|&P(y, x)| (y, x)).collect::<Vec<_>>());
+    (0..8).any(|t| {
+        let transformed = a
+            .iter()
+            .map(|&P(y, x)| {
+                let (y, x) = if t & 4 != 0 { (x, y) } else { (y, x) };
+                (
+                    if t & 2 != 0 { -y } else { y },
+                    if t & 1 != 0 { -x } else { x },
+                )
+            })
+            .collect::<Vec<_>>();
+        normalize(&transformed) == target
+    })
+}
+
+/// Returns whether the cells `block` form a valid block of an answer to the problem.
+fn is_valid_block(color: &Grid<Color>, clue: &Grid<Clue>, block: &[P]) -> bool {
+    let black = block
+        .iter()
+        .cloned()
+        .filter(|&p| color[p] == Color::Black)
+        .collect::<Vec<_>>();
+    let white = block
+        .iter()
+        .cloned()
+        .filter(|&p| color[p] == Color::White)
+        .collect::<Vec<_>>();
+    black.len() == white.len()
+        && !black.is_empty()
+        && block
+            .iter()
+            .all(|&p| clue[p] == NO_CLUE || clue[p] as usize == black.len())
+        && is_connected(&black)
+        && is_connected(&white)
+        && is_congruent(&black, &white)
+}
+
+/// Enumerates the connected sets of cells in `available` containing the cell `start`.
+fn connected_sets(width: i32, available: u32, start: i32) -> Vec<u32> {
+    let mut seen = HashSet::new();
+    let mut stack = vec![1u32 << start];
+    seen.insert(1u32 << start);
+    while let Some(mask) = stack.pop() {
+        for P(y, x) in cells_of(width, mask) {
+            for &d in &FOUR_NEIGHBOURS {
+                let P(y2, x2) = P(y, x) + d;
+                if y2 < 0 || x2 < 0 || x2 >= width {
+                    continue;
+                }
+                let i = y2 * width + x2;
+                if i >= 32 || (available >> i) & 1 == 0 || (mask >> i) & 1 != 0 {
+                    continue;
+                }
+                let next = mask | (1u32 << i);
+                if seen.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+    }
+    seen.into_iter().collect()
+}
+
+fn enumerate_dfs(
+    color: &Grid<Color>,
+    clue: &Grid<Clue>,
+    available: u32,
+    block_id: &mut Grid<usize>,
+    n_blocks: usize,
+    answers: &mut Vec<Grid<usize>>,
+) {
+    if available == 0 {
+        answers.push(block_id.clone());
+        return;
+    }
+    let width = color.width();
+    let start = available.trailing_zeros() as i32;
+    for mask in connected_sets(width, available, start) {
+        let block = cells_of(width, mask);
+        if !is_valid_block(color, clue, &block) {
+            continue;
+        }
+        for &p in &block {
+            block_id[p] = n_blocks;
+        }
+        enumerate_dfs(
+            color,
+            clue,
+            available & !mask,
+            block_id,
+            n_blocks + 1,
+            answers,
+        );
+    }
+}
+
+/// Enumerates all the answers of the problem. Each answer is given as the block id of
+/// each cell.
+pub fn enumerate_answers(color: &Grid<Color>, clue: &Grid<Clue>) -> Vec<Grid<usize>> {
+    let height = color.height();
+    let width = color.width();
+    let mut answers = vec![];
+    enumerate_dfs(
+        color,
+        clue,
+        (1u32 << (height * width)) - 1,
+        &mut Grid::new(height, width, 0),
+        0,
+        &mut answers,
+    );
+    answers
+}
+
+#[test]
+fn test_random_against_brute_force() {
+    let mut rng = XorShiftRng::from_seed([42u8; 16]);
+
+    for &(height, width) in &[(2, 2), (2, 3), (2, 4), (2, 5), (3, 4), (4, 3)] {
+        let mut n_tested = 0;
+        while n_tested < 50 {
+            let mut color = Grid::new(height, width, Color::White);
+            for y in 0..height {
+                for x in 0..width {
+                    if rng.gen::<f64>() < 0.5 {
+                        color[P(y, x)] = Color::Black;
+                    }
+                }
+            }
+            let mut clue = Grid::new(height, width, NO_CLUE);
+            let candidates = enumerate_answers(&color, &clue);
+            if candidates.is_empty() {
+                continue;
+            }
+            n_tested += 1;
+
+            // clues from a random answer, some of which are omitted
+            let base = &candidates[rng.gen_range(0, candidates.len())];
+            for y in 0..height {
+                for x in 0..width {
+                    if rng.gen::<f64>() < 0.3 {
+                        let size = (0..height)
+                            .flat_map(|y2| (0..width).map(move |x2| P(y2, x2)))
+                            .filter(|&p| base[p] == base[P(y, x)])
+                            .count();
+                        clue[P(y, x)] = size as i32 / 2;
+                    }
+                }
+            }
+            let answers = enumerate_answers(&color, &clue);
+            for candidate in &candidates {
+                let violations = check_answer(&color, &clue, candidate);
+                assert_eq!(violations.is_empty(), answers.contains(candidate));
+            }
+
+            let mut field = Field::new(&color, &clue);
+            field.trial_and_error(1);
+            assert!(!field.inconsistent());
+            for answer in &answers {
+                for y in 0..(height * 2 - 1) {
+                    for x in 0..(width * 2 - 1) {
+                        if y % 2 == x % 2 {
+                            continue;
+                        }
+                        let c1 = P(y / 2, x / 2);
+                        let c2 = P((y + 1) / 2, (x + 1) / 2);
+                        let expected = if answer[c1] != answer[c2] {
+                            Border::Line
+                        } else {
+                            Border::Blank
+                        };
+                        let actual = field.border(LP(y, x));
+                        assert!(actual == Border::Undecided || actual == expected);
+                    }
+                }
+            }
+        }
+    }
+}
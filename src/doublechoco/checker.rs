@@ -0,0 +1,122 @@
+use super::super::{Grid, Violation, ViolationKind, P};
+use super::*;
+use std::collections::BTreeMap;
+
+/// Checks whether the division into blocks `answer`, which gives the block id of each cell,
+/// is an answer of the problem.
+/// The violations reported are (all given by the cells of the block):
+/// - `DisconnectedBlock`: a block whose cells are not connected.
+/// - `Unbalanced`: a block whose white and black parts differ in size.
+/// - `DisconnectedPart`: a block whose white or black part is not connected.
+/// - `NotCongruent`: a block whose white and black parts have different shapes.
+/// - `Clue`: a block with a clue differing from the size of its parts.
+pub fn check_answer(
+    color: &Grid<Color>,
+    clue: &Grid<Clue>,
+    answer: &Grid<usize>,
+) -> Vec<Violation> {
+    let height = color.height();
+    let width = color.width();
+    let mut blocks = BTreeMap::new();
+    for y in 0..height {
+        for x in 0..width {
+            blocks
+                .entry(answer[P(y, x)])
+                .or_insert_with(Vec::new)
+                .push(P(y, x));
+        }
+    }
+
+    let mut ret = vec![];
+    for block in blocks.values() {
+        let black = block
+            .iter()
+            .cloned()
+            .filter(|&p| color[p] == Color::Black)
+            .collect::<Vec<_>>();
+        let white = block
+            .iter()
+            .cloned()
+            .filter(|&p| color[p] == Color::White)
+            .collect::<Vec<_>>();
+        if !is_connected(block) {
+            ret.push(Violation::at_cells(
+                ViolationKind::DisconnectedBlock,
+                block.clone(),
+            ));
+        }
+        if black.len() != white.len() {
+            ret.push(Violation::at_cells(
+                ViolationKind::Unbalanced,
+                block.clone(),
+            ));
+        } else if !is_connected(&black) || !is_connected(&white) {
+            ret.push(Violation::at_cells(
+                ViolationKind::DisconnectedPart,
+                block.clone(),
+            ));
+        } else if !is_congruent(&black, &white) {
+            ret.push(Violation::at_cells(
+                ViolationKind::NotCongruent,
+                block.clone(),
+            ));
+        }
+        if block
+            .iter()
+            .any(|&p| clue[p] != NO_CLUE && clue[p] as usize != black.len())
+        {
+            ret.push(Violation::at_cells(ViolationKind::Clue, block.clone()));
+        }
+    }
+    ret
+}
+
+fn is_connected(cells: &[P]) -> bool {
+    if cells.is_empty() {
+        return false;
+    }
+    let mut visited = vec![false; cells.len()];
+    let mut stack = vec![0];
+    visited[0] = true;
+    while let Some(i) = stack.pop() {
+        for j in 0..cells.len() {
+            let P(y1, x1) = cells[i];
+            let P(y2, x2) = cells[j];
+            if !visited[j] && (y1 - y2).abs() + (x1 - x2).abs() == 1 {
+                visited[j] = true;
+                stack.push(j);
+            }
+        }
+    }
+    visited.iter().all(|&v| v)
+}
+
+/// Returns the canonical form of the shape `cells` up to translation.
+fn normalize(cells: &[(i32, i32)]) -> Vec<(i32, i32)> {
+    let min_y = cells.iter().map(|c| c.0).min().unwrap();
+    let min_x = cells.iter().map(|c| c.1).min().unwrap();
+    let mut ret = cells
+        .iter()
+        .map(|&(y, x)| (y - min_y, x - min_x))
+        .collect::<Vec<_>>();
+    ret.sort();
+    ret
+}
+
+/// Returns whether the shapes `a` and `b` are congruent up to rotation and reflection.
+fn is_congruent(a: &[P], b: &[P]) -> bool {
+    let target = normalize(&b.iter().map(|&P(y, x)| (y, x)).collect::<Vec<_>>());
+    (0..8).any(|t| {
+        let transformed = a
+            .iter()
+            .map(|&P(y, x)| {
+                let (y, x) = if t & 4 != 0 { (x, y) } else { (y, x) };
+                (
+                    if t & 2 != 0 { -y } else { y },
+                    if t & 1 != 0 { -x } else { x },
+                )
+            })
+            .collect::<Vec<_>>();
+        normalize(&transformed) == target
+    })
+}
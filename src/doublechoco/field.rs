@@ -1,6 +1,6 @@
 use super::*;
 use std::cell::Cell;
-use {Grid, D, FOUR_NEIGHBOURS, LP, P};
+use {Grid, SolverStats, D, FOUR_NEIGHBOURS, LP, P};
 
 #[derive(Clone)]
 pub struct Field {
@@ -12,6 +12,7 @@ pub struct Field {
     frozen: Grid<bool>,
     num_decided_borders: i32,
     inconsistent: bool,
+    stats: SolverStats,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -63,6 +64,7 @@ impl Field {
             frozen: Grid::new(height, width, false),
             num_decided_borders: 0,
             inconsistent: false,
+            stats: SolverStats::new(),
         }
     }
     pub fn height(&self) -> i32 {
@@ -80,6 +82,14 @@ impl Field {
     pub fn border(&self, pos: LP) -> Border {
         self.border[pos]
     }
+    /// Returns the statistics of the techniques, counted in decided borders, and the trial
+    /// and error.
+    pub fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+    pub fn set_stats_enabled(&mut self, enabled: bool) {
+        self.stats.set_enabled(enabled);
+    }
     pub fn decide_border(&mut self, pos: LP, border: Border) {
         if self.border[pos] != Border::Undecided {
             if self.border[pos] != border {
@@ -566,17 +576,22 @@ impl Field {
         loop {
             let last_num_decided = self.num_decided_borders;
 
-            self.check_connected_components();
+            self.apply_technique("connected_components", Field::check_connected_components);
             if self.inconsistent() {
                 return;
             }
-            for y in 0..height {
-                for x in 0..width {
-                    self.inspect(P(y, x));
-                    if self.inconsistent() {
-                        return;
+            self.apply_technique("block", |field| {
+                for y in 0..height {
+                    for x in 0..width {
+                        field.inspect(P(y, x));
+                        if field.inconsistent() {
+                            return;
+                        }
                     }
                 }
+            });
+            if self.inconsistent() {
+                return;
             }
 
             if last_num_decided == self.num_decided_borders {
@@ -606,9 +621,10 @@ impl Field {
                         continue;
                     }
                     {
-                        let mut field_line = self.clone();
+                        let mut field_line = self.clone_for_trial();
                         field_line.decide_border(pos, Border::Line);
                         field_line.trial_and_error(depth - 1);
+                        self.record_trial(&field_line);
 
                         if field_line.inconsistent() {
                             updated = true;
@@ -617,9 +633,10 @@ impl Field {
                         }
                     }
                     {
-                        let mut field_blank = self.clone();
+                        let mut field_blank = self.clone_for_trial();
                         field_blank.decide_border(pos, Border::Blank);
                         field_blank.trial_and_error(depth - 1);
+                        self.record_trial(&field_blank);
 
                         if field_blank.inconsistent() {
                             updated = true;
@@ -634,6 +651,24 @@ impl Field {
             }
         }
     }
+    /// Applies `technique` and records the number of borders decided by it.
+    fn apply_technique<F: FnOnce(&mut Field)>(&mut self, name: &'static str, technique: F) {
+        let decided_borders = self.num_decided_borders;
+        technique(self);
+        let n_decided = self.num_decided_borders - decided_borders;
+        self.stats.record(name, n_decided);
+    }
+    /// Returns a clone of this field for a trial, whose statistics start from zero.
+    fn clone_for_trial(&self) -> Field {
+        let mut ret = self.clone();
+        ret.stats.clear();
+        ret
+    }
+    /// Adds the work done in `trial`, made by `clone_for_trial`, to the statistics.
+    fn record_trial(&mut self, trial: &Field) {
+        self.stats.merge(&trial.stats);
+        self.stats.record_trial(trial.inconsistent());
+    }
 }
 
 #[cfg(test)]
@@ -1,5 +1,9 @@
+#[cfg(test)]
+mod brute_force;
+mod checker;
 mod field;
 
+pub use self::checker::*;
 pub use self::field::*;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
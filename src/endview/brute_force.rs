@@ -0,0 +1,135 @@
+use super::*;
+use {Grid, D, P};
+
+use rand::prng::XorShiftRng;
+use rand::{Rng, SeedableRng};
+
+const CLUE_LOCS: [ClueLoc; 4] = [ClueLoc::Left, ClueLoc::Right, ClueLoc::Top, ClueLoc::Bottom];
+
+/// Returns the clue at `loc` / `idx` given by `answer`.
+fn compute_clue(answer: &Grid<Value>, loc: ClueLoc, idx: i32) -> Clue {
+    let size = answer.height();
+    let (start, dir) = match loc {
+        ClueLoc::Left => (P(idx, 0), D(0, 1)),
+        ClueLoc::Right => (P(idx, size - 1), D(0, -1)),
+        ClueLoc::Top => (P(0, idx), D(1, 0)),
+        ClueLoc::Bottom => (P(size - 1, idx), D(-1, 0)),
+    };
+    let mut pos = start;
+    while answer.is_valid_p(pos) {
+        if answer[pos] != EMPTY {
+            return Clue(answer[pos].0);
+        }
+        pos = pos + dir;
+    }
+    NO_CLUE
+}
+
+fn enumerate_dfs(
+    n_alpha: i32,
+    current: &mut Grid<Value>,
+    pos: i32,
+    answers: &mut Vec<Grid<Value>>,
+) {
+    let size = current.height();
+    if pos == size * size {
+        answers.push(current.clone());
+        return;
+    }
+    let P(y, x) = P(pos / size, pos % size);
+    for v in -1..n_alpha {
+        let val = if v < 0 { EMPTY } else { Value(v) };
+        if val != EMPTY
+            && ((0..x).any(|x2| current[P(y, x2)] == val)
+                || (0..y).any(|y2| current[P(y2, x)] == val))
+        {
+            continue;
+        }
+        current[P(y, x)] = val;
+        // each letter must still fit in the rest of the row / column
+        let n_row = (0..=x).filter(|&x2| current[P(y, x2)] != EMPTY).count() as i32;
+        let n_column = (0..=y).filter(|&y2| current[P(y2, x)] != EMPTY).count() as i32;
+        if n_row + (size - 1 - x) >= n_alpha && n_column + (size - 1 - y) >= n_alpha {
+            enumerate_dfs(n_alpha, current, pos + 1, answers);
+        }
+    }
+    current[P(y, x)] = UNDECIDED;
+}
+
+/// Enumerates all the grids of the given size in which each row and column contains
+/// each of `n_alpha` letters exactly once.
+pub fn enumerate_grids(size: i32, n_alpha: i32) -> Vec<Grid<Value>> {
+    let mut answers = vec![];
+    enumerate_dfs(
+        n_alpha,
+        &mut Grid::new(size, size, UNDECIDED),
+        0,
+        &mut answers,
+    );
+    answers
+}
+
+/// Enumerates all the answers of `problem` among `grids`.
+pub fn enumerate_answers(problem: &Problem, grids: &[Grid<Value>]) -> Vec<Grid<Value>> {
+    grids
+        .iter()
+        .filter(|answer| {
+            CLUE_LOCS.iter().all(|&loc| {
+                (0..problem.size()).all(|i| {
+                    let clue = problem.get_clue(loc, i);
+                    clue == NO_CLUE || clue == compute_clue(answer, loc, i)
+                })
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn test_random_against_brute_force() {
+    let mut rng = XorShiftRng::from_seed([42u8; 16]);
+
+    for &(size, n_alpha) in &[(3, 2), (4, 2), (4, 3), (5, 3)] {
+        let grids = enumerate_grids(size, n_alpha);
+        for _ in 0..100 {
+            // clues from a random grid, some of which are omitted
+            let base = &grids[rng.gen_range(0, grids.len())];
+            let mut problem = Problem::new(size, n_alpha);
+            for &loc in &CLUE_LOCS {
+                for i in 0..size {
+                    if rng.gen::<f64>() < 0.4 {
+                        problem.set_clue(loc, i, compute_clue(base, loc, i));
+                    }
+                }
+            }
+            let answers = enumerate_answers(&problem, &grids);
+            for answer in &answers {
+                assert!(check_answer(&problem, answer).is_empty());
+            }
+            for _ in 0..10 {
+                let candidate = &grids[rng.gen_range(0, grids.len())];
+                let violations = check_answer(&problem, candidate);
+                assert_eq!(violations.is_empty(), answers.contains(candidate));
+            }
+
+            let mut field = Field::from_problem(&problem);
+            field.trial_and_error();
+            assert!(!field.inconsistent());
+            for answer in &answers {
+                for y in 0..size {
+                    for x in 0..size {
+                        let actual = field.get_value(P(y, x));
+                        let expected = answer[P(y, x)];
+                        assert!(
+                            actual == UNDECIDED
+                                || actual == expected
+                                || (actual == SOME && expected != EMPTY)
+                        );
+                        assert!(!field.is_solved() || actual == expected);
+                    }
+                }
+            }
+            assert!(!field.is_solved() || answers.len() == 1);
+        }
+    }
+}
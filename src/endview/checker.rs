@@ -0,0 +1,55 @@
+use super::super::{Grid, Violation, ViolationKind, D, P};
+use super::*;
+
+/// Checks whether `answer` is an answer of `problem`.
+/// The violations reported are:
+/// - `Row` / `Column`: a line which does not contain each letter exactly once,
+///   or which contains a cell that is neither a letter nor `EMPTY`.
+/// - `Clue`: a clue which differs from the first letter seen from its side.
+pub fn check_answer(problem: &Problem, answer: &Grid<Value>) -> Vec<Violation> {
+    let size = problem.size();
+    let n_alpha = problem.n_alpha();
+    let mut ret = vec![];
+
+    for i in 0..size {
+        for &(kind, start, dir) in &[
+            (ViolationKind::Row, P(i, 0), D(0, 1)),
+            (ViolationKind::Column, P(0, i), D(1, 0)),
+        ] {
+            let cells = (0..size).map(|j| start + dir * j).collect::<Vec<_>>();
+            let mut count = vec![0; n_alpha as usize];
+            let mut valid = true;
+            for &p in &cells {
+                let Value(v) = answer[p];
+                if 0 <= v && v < n_alpha {
+                    count[v as usize] += 1;
+                } else if answer[p] != EMPTY {
+                    valid = false;
+                }
+            }
+            if !valid || count.iter().any(|&c| c != 1) {
+                ret.push(Violation::at_cells(kind, cells));
+            }
+        }
+    }
+    for &loc in &[ClueLoc::Left, ClueLoc::Right, ClueLoc::Top, ClueLoc::Bottom] {
+        for i in 0..size {
+            let clue = problem.get_clue(loc, i);
+            if clue == NO_CLUE {
+                continue;
+            }
+            let (start, dir) = match loc {
+                ClueLoc::Left => (P(i, 0), D(0, 1)),
+                ClueLoc::Right => (P(i, size - 1), D(0, -1)),
+                ClueLoc::Top => (P(0, i), D(1, 0)),
+                ClueLoc::Bottom => (P(size - 1, i), D(-1, 0)),
+            };
+            let cells = (0..size).map(|j| start + dir * j).collect::<Vec<_>>();
+            let seen = cells.iter().map(|&p| answer[p]).find(|&v| v != EMPTY);
+            if seen != Some(Value(clue.0)) {
+                ret.push(Violation::at_cells(ViolationKind::Clue, cells));
+            }
+        }
+    }
+    ret
+}
@@ -1,4 +1,4 @@
-use super::super::{Grid, P};
+use super::super::{Grid, SolverStats, P};
 use super::*;
 
 #[derive(Clone)]
@@ -11,6 +11,7 @@ pub struct Field {
     clue_back: Vec<Clue>,
     total_cands: i32,
     inconsistent: bool,
+    stats: SolverStats,
 }
 
 impl Field {
@@ -25,6 +26,7 @@ impl Field {
             clue_back: vec![NO_CLUE; (2 * size) as usize],
             total_cands: size * size * (n_alpha + 1),
             inconsistent: false,
+            stats: SolverStats::new(),
         }
     }
     pub fn from_problem(problem: &Problem) -> Field {
@@ -49,6 +51,13 @@ impl Field {
     pub fn total_cands(&self) -> i32 {
         self.total_cands
     }
+    /// Returns the statistics of the techniques, counted in eliminated candidates.
+    pub fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+    pub fn set_stats_enabled(&mut self, enabled: bool) {
+        self.stats.set_enabled(enabled);
+    }
     pub fn is_solved(&self) -> bool {
         self.total_cands == self.size * self.n_alpha
     }
@@ -126,11 +135,11 @@ impl Field {
         loop {
             let current_cands = self.total_cands();
 
-            self.hidden_candidate();
+            self.apply_technique("hidden_candidate", |field| field.hidden_candidate());
             if self.inconsistent() {
                 return;
             }
-            self.fishy_method();
+            self.apply_technique("fishy_method", |field| field.fishy_method());
             if self.inconsistent() {
                 return;
             }
@@ -174,6 +183,7 @@ impl Field {
 
                     let mut field_cloned = self.clone();
                     field_cloned.decide(pos, Value(i));
+                    self.stats.record_trial(field_cloned.inconsistent());
 
                     if !field_cloned.inconsistent() {
                         valid_cands.push((Value(i), field_cloned));
@@ -182,6 +192,7 @@ impl Field {
                 if val != SOME {
                     let mut field_cloned = self.clone();
                     field_cloned.decide(pos, EMPTY);
+                    self.stats.record_trial(field_cloned.inconsistent());
                     if !field_cloned.inconsistent() {
                         valid_cands.push((EMPTY, field_cloned));
                     }
@@ -192,7 +203,9 @@ impl Field {
                     return false;
                 }
                 if valid_cands.len() == 1 {
-                    let only_cand = valid_cands.pop().unwrap();
+                    let mut only_cand = valid_cands.pop().unwrap();
+                    // keep the statistics recorded so far, including the trials above
+                    ::std::mem::swap(&mut only_cand.1.stats, &mut self.stats);
                     *self = only_cand.1;
                     is_update = true;
                 }
@@ -200,6 +213,13 @@ impl Field {
         }
         is_update
     }
+    /// Applies `technique` and records the number of candidates eliminated by it.
+    fn apply_technique<F: FnOnce(&mut Field)>(&mut self, name: &'static str, technique: F) {
+        let total_cands = self.total_cands;
+        technique(self);
+        let n_eliminated = total_cands - self.total_cands;
+        self.stats.record(name, n_eliminated);
+    }
     /// Returns `pos`-th cell of group `gid`.
     fn group(&self, gid: i32, pos: i32) -> P {
         if gid < self.size {
@@ -1,8 +1,12 @@
 use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
 
+#[cfg(test)]
+mod brute_force;
+mod checker;
 mod field;
 mod generator;
 
+pub use self::checker::*;
 pub use self::field::*;
 pub use self::generator::*;
 
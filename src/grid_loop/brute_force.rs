@@ -0,0 +1,121 @@
+//! Brute-force enumeration of loops, used as a test oracle on tiny boards.
+
+use super::Edge;
+use {Grid, FOUR_NEIGHBOURS, LP, P};
+
+/// Enumerates all the loops on the grid graph whose vertices are the positions `pos` with
+/// `allowed[pos]`. Each loop is represented as a grid indexed by lattice positions
+/// (`P(2y, 2x)` for the vertex `P(y, x)`) which is true exactly on the edges of the loop.
+pub fn enumerate_loops(allowed: &Grid<bool>) -> Vec<Grid<bool>> {
+    let height = allowed.height();
+    let width = allowed.width();
+    let mut ret = vec![];
+    let mut path = vec![];
+    let mut visited = Grid::new(height, width, false);
+
+    for y in 0..height {
+        for x in 0..width {
+            if allowed[P(y, x)] {
+                path.push(P(y, x));
+                visited[P(y, x)] = true;
+                enumerate_dfs(allowed, &mut path, &mut visited, &mut ret);
+                visited[P(y, x)] = false;
+                path.pop();
+            }
+        }
+    }
+    ret
+}
+
+fn vertex_id(width: i32, P(y, x): P) -> i32 {
+    y * width + x
+}
+
+fn enumerate_dfs(
+    allowed: &Grid<bool>,
+    path: &mut Vec<P>,
+    visited: &mut Grid<bool>,
+    loops: &mut Vec<Grid<bool>>,
+) {
+    let width = allowed.width();
+    let start = path[0];
+    let last = path[path.len() - 1];
+    for &d in &FOUR_NEIGHBOURS {
+        let next = last + d;
+        if !allowed.is_valid_p(next) || !allowed[next] {
+            continue;
+        }
+        if next == start {
+            // each loop is found in both directions; only one of them is reported
+            if path.len() >= 4 && vertex_id(width, path[1]) < vertex_id(width, last) {
+                let mut lp = Grid::new(allowed.height() * 2 - 1, width * 2 - 1, false);
+                for i in 0..path.len() {
+                    let P(y1, x1) = path[i];
+                    let P(y2, x2) = path[(i + 1) % path.len()];
+                    lp[P(y1 + y2, x1 + x2)] = true;
+                }
+                loops.push(lp);
+            }
+        } else if !visited[next] && vertex_id(width, next) > vertex_id(width, start) {
+            visited[next] = true;
+            path.push(next);
+            enumerate_dfs(allowed, path, visited, loops);
+            path.pop();
+            visited[next] = false;
+        }
+    }
+}
+
+/// Returns whether the vertex `pos` is passed by the loop `lp`.
+pub fn is_on_loop(lp: &Grid<bool>, pos: P) -> bool {
+    FOUR_NEIGHBOURS.iter().any(|&d| {
+        let LP(y, x) = LP::of_vertex(pos) + d;
+        lp.get_or_default_p(P(y, x), false)
+    })
+}
+
+/// Returns whether the loop `lp` passes the edge `pos`.
+pub fn has_edge(lp: &Grid<bool>, LP(y, x): LP) -> bool {
+    lp.get_or_default_p(P(y, x), false)
+}
+
+/// Converts a loop given as the sequence of its vertices (e.g. an answer found by `search`)
+/// into the representation used in `enumerate_loops`.
+pub fn loop_from_vertices(height: i32, width: i32, vertices: &[P]) -> Grid<bool> {
+    let mut ret = Grid::new(height * 2 - 1, width * 2 - 1, false);
+    let n = vertices.len();
+    for i in 0..n {
+        let P(y1, x1) = vertices[i];
+        let P(y2, x2) = vertices[(i + 1) % n];
+        if (y1, x1) != (y2, x2) {
+            ret[P(y1 + y2, x1 + x2)] = true;
+        }
+    }
+    ret
+}
+
+/// Asserts that every edge decided in `get_edge` agrees with all the `answers`.
+/// If `answers` consists of a single loop, `fully_solved` must imply that the loop is found.
+pub fn assert_consistent_edges<F>(get_edge: F, fully_solved: bool, answers: &[Grid<bool>])
+where
+    F: Fn(LP) -> Edge,
+{
+    assert!(!answers.is_empty() || !fully_solved);
+    for answer in answers {
+        for y in 0..answer.height() {
+            for x in 0..answer.width() {
+                if y % 2 == x % 2 {
+                    continue;
+                }
+                let expected = if answer[P(y, x)] {
+                    Edge::Line
+                } else {
+                    Edge::Blank
+                };
+                let actual = get_edge(LP(y, x));
+                assert!(actual == Edge::Undecided || actual == expected);
+                assert!(!fully_solved || actual == expected);
+            }
+        }
+    }
+}
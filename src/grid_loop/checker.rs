@@ -0,0 +1,111 @@
+use super::super::{Grid, Violation, ViolationKind, D, LP, P};
+use FOUR_NEIGHBOURS;
+
+/// Returns the number of lines in `lines` incident to the vertex `pos`.
+/// `lines` is indexed by lattice positions (`LP::of_vertex(pos)` for the vertex `pos`) and
+/// is true exactly on the edges passed by lines.
+pub fn line_degree(lines: &Grid<bool>, pos: P) -> i32 {
+    incident_lines(lines, LP::of_vertex(pos)).len() as i32
+}
+
+fn incident_lines(lines: &Grid<bool>, vertex: LP) -> Vec<LP> {
+    FOUR_NEIGHBOURS
+        .iter()
+        .map(|&d| vertex + d)
+        .filter(|&e| lines.is_valid_lp(e) && lines[e])
+        .collect()
+}
+
+/// Checks whether the lines in `lines` (see `line_degree`) form a single loop.
+/// The violations reported are:
+/// - `Branch`: a vertex (given in `cells` in vertex coordinates) with three or more lines.
+/// - `DeadEnd`: a vertex with only one line.
+/// - `MultipleLoops`: a group of connected lines other than the largest one.
+/// - `NoLoop`: there are no lines at all.
+pub fn check_loop(lines: &Grid<bool>) -> Vec<Violation> {
+    let mut ret = vec![];
+    for y in 0..((lines.height() + 2) / 2) {
+        for x in 0..((lines.width() + 2) / 2) {
+            let pos = P(y, x);
+            let incident = incident_lines(lines, LP::of_vertex(pos));
+            if incident.len() >= 3 {
+                ret.push(Violation::new(ViolationKind::Branch, vec![pos], incident));
+            } else if incident.len() == 1 {
+                ret.push(Violation::new(ViolationKind::DeadEnd, vec![pos], incident));
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<LP>> = vec![];
+    let mut visited = Grid::new(lines.height(), lines.width(), false);
+    for y in 0..lines.height() {
+        for x in 0..lines.width() {
+            let start = LP(y, x);
+            if !start.is_edge() || !lines[start] || visited[start] {
+                continue;
+            }
+            let mut group = vec![];
+            let mut stack = vec![start];
+            visited[start] = true;
+            while let Some(e) = stack.pop() {
+                group.push(e);
+                let d = if e.y() % 2 == 0 { D(0, 1) } else { D(1, 0) };
+                for &vertex in &[e - d, e + d] {
+                    for e2 in incident_lines(lines, vertex) {
+                        if !visited[e2] {
+                            visited[e2] = true;
+                            stack.push(e2);
+                        }
+                    }
+                }
+            }
+            groups.push(group);
+        }
+    }
+    if groups.is_empty() {
+        ret.push(Violation::new(ViolationKind::NoLoop, vec![], vec![]));
+    } else {
+        let largest = (0..groups.len()).max_by_key(|&i| groups[i].len()).unwrap();
+        for (i, group) in groups.into_iter().enumerate() {
+            if i != largest {
+                ret.push(Violation::new(ViolationKind::MultipleLoops, vec![], group));
+            }
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use grid_loop::brute_force::loop_from_vertices;
+
+    #[test]
+    fn test_check_loop() {
+        let square = loop_from_vertices(3, 4, &[P(0, 0), P(0, 1), P(1, 1), P(1, 0)]);
+        assert_eq!(check_loop(&square), vec![]);
+
+        let mut lines = square.clone();
+        lines[LP(0, 3)] = true;
+        lines[LP(0, 5)] = true;
+        let violations = check_loop(&lines);
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].kind, ViolationKind::Branch);
+        assert_eq!(violations[0].cells, vec![P(0, 1)]);
+        assert_eq!(violations[0].edges.len(), 3);
+        assert_eq!(violations[1].kind, ViolationKind::DeadEnd);
+        assert_eq!(violations[1].cells, vec![P(0, 3)]);
+
+        let mut lines = square.clone();
+        for &e in &[LP(2, 5), LP(4, 5), LP(3, 4), LP(3, 6)] {
+            lines[e] = true;
+        }
+        let violations = check_loop(&lines);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].kind, ViolationKind::MultipleLoops);
+        assert_eq!(violations[0].edges.len(), 4);
+
+        let empty = Grid::new(5, 7, false);
+        assert_eq!(check_loop(&empty)[0].kind, ViolationKind::NoLoop);
+    }
+}
@@ -6,6 +6,12 @@ use std::ops::{Deref, DerefMut, Index, IndexMut};
 use std::mem;
 use FOUR_NEIGHBOURS;
 
+#[cfg(test)]
+pub mod brute_force;
+mod checker;
+
+pub use self::checker::*;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Edge {
     Undecided,
@@ -18,6 +24,23 @@ struct EdgeId(usize);
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct VtxId(usize);
 
+/// A chain of decided lines.
+/// `vertices` lists the vertices (in vertex coordinates) in the order along the chain.
+/// If `is_loop` is true, the chain is closed and the first vertex is repeated at the end.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineChain {
+    pub vertices: Vec<P>,
+    pub is_loop: bool,
+}
+impl LineChain {
+    pub fn end_points(&self) -> (P, P) {
+        (self.vertices[0], self.vertices[self.vertices.len() - 1])
+    }
+    pub fn num_edges(&self) -> usize {
+        self.vertices.len() - 1
+    }
+}
+
 #[derive(Clone, Copy)]
 struct GridLoopItem {
     edge_status: Edge,
@@ -27,6 +50,17 @@ struct GridLoopItem {
     chain_size: i32,
 }
 
+#[derive(Clone)]
+enum History {
+    Item(EdgeId, GridLoopItem),
+    Checkpoint {
+        inconsistent: bool,
+        fully_solved: bool,
+        decided_line: i32,
+        decided_edge: i32,
+    },
+}
+
 #[derive(Clone)]
 pub struct GridLoop {
     grid: Grid<GridLoopItem>,
@@ -35,6 +69,8 @@ pub struct GridLoop {
     decided_line: i32,
     decided_edge: i32,
     queue: FiniteSearchQueue,
+    history: Vec<History>,
+    avoid_small_loop: bool,
 }
 impl Index<EdgeId> for GridLoop {
     type Output = GridLoopItem;
@@ -92,6 +128,8 @@ impl GridLoop {
             decided_line: 0,
             decided_edge: 0,
             queue: FiniteSearchQueue::new((1 + (height * 2 + 1) * (width * 2 + 1)) as usize),
+            history: Vec::new(),
+            avoid_small_loop: true,
         };
 
         ret.queue.start();
@@ -177,6 +215,50 @@ impl GridLoop {
     pub fn set_inconsistent(&mut self) {
         self.inconsistent = true;
     }
+    /// Add a checkpoint.
+    /// Until the checkpoint is rolled back, every update of the edges is recorded
+    /// so that `rollback` can restore the current state without cloning the field.
+    pub fn add_checkpoint(&mut self) {
+        assert!(!self.queue.is_started());
+        self.history.push(History::Checkpoint {
+            inconsistent: self.inconsistent,
+            fully_solved: self.fully_solved,
+            decided_line: self.decided_line,
+            decided_edge: self.decided_edge,
+        });
+    }
+    /// Rollback until the last checkpoint.
+    pub fn rollback(&mut self) {
+        assert!(!self.queue.is_started());
+        while let Some(entry) = self.history.pop() {
+            match entry {
+                History::Item(id, item) => self[id] = item,
+                History::Checkpoint {
+                    inconsistent,
+                    fully_solved,
+                    decided_line,
+                    decided_edge,
+                } => {
+                    self.inconsistent = inconsistent;
+                    self.fully_solved = fully_solved;
+                    self.decided_line = decided_line;
+                    self.decided_edge = decided_edge;
+                    break;
+                }
+            }
+        }
+    }
+    pub fn has_checkpoint(&self) -> bool {
+        !self.history.is_empty()
+    }
+    pub fn avoid_small_loop(&self) -> bool {
+        self.avoid_small_loop
+    }
+    /// Enables or disables the rule preventing a line from closing a loop which does not contain
+    /// all the lines. Even if disabled, such a loop is detected as an inconsistency once closed.
+    pub fn set_avoid_small_loop(&mut self, avoid_small_loop: bool) {
+        self.avoid_small_loop = avoid_small_loop;
+    }
     pub fn decide_edge<T: GridLoopField>(field: &mut T, pos: LP, status: Edge) {
         if !field.grid_loop().is_valid_lp(pos) {
             if status != Edge::Blank {
@@ -608,8 +690,62 @@ impl GridLoop {
         let id = std::cmp::min(id_cand1.0, id_cand2.0);
         return self.grid.lp(id);
     }
+    /// Enumerates all chains of decided lines.
+    /// Chains are listed in the order of their root edges.
+    pub fn line_chains(&self) -> Vec<LineChain> {
+        let mut ret = vec![];
+        for y in 0..self.grid.height() {
+            for x in 0..self.grid.width() {
+                let pos = LP(y, x);
+                if pos.is_edge() && self.get_edge(pos) == Edge::Line && self.is_root(pos) {
+                    ret.push(self.trace_chain(EdgeId(self.grid.index_lp(pos))));
+                }
+            }
+        }
+        ret
+    }
+    /// Returns the vertices on the loop in the order along the loop if the loop is fully solved.
+    /// The first vertex is repeated at the end.
+    pub fn solved_loop(&self) -> Option<Vec<P>> {
+        if !self.fully_solved || self.inconsistent {
+            return None;
+        }
+        let mut chains = self.line_chains();
+        if chains.len() != 1 || !chains[0].is_loop {
+            return None;
+        }
+        Some(chains.swap_remove(0).vertices)
+    }
 
     // private accessor
+    fn trace_chain(&self, root: EdgeId) -> LineChain {
+        let (start, end) = self[root].chain_end_points;
+        let size = self[root].chain_size;
+        let mut vertices = vec![self.grid.lp(start.0).as_vertex()];
+        let mut current = self.grid.lp(start.0);
+        let mut last_edge = LP(-1, -1);
+        for _ in 0..size {
+            let mut next = None;
+            for &d in &FOUR_NEIGHBOURS {
+                let e = current + d;
+                if e != last_edge && self.get_edge_safe(e) == Edge::Line {
+                    next = Some(d);
+                    break;
+                }
+            }
+            let d = match next {
+                Some(d) => d,
+                None => break,
+            };
+            last_edge = current + d;
+            current = current + d * 2;
+            vertices.push(current.as_vertex());
+        }
+        LineChain {
+            vertices,
+            is_loop: start == end,
+        }
+    }
     fn another_end_id(&self, origin: VtxId, edge: EdgeId) -> VtxId {
         let edge_data = self[edge];
         VtxId((edge_data.chain_end_points.0).0 + (edge_data.chain_end_points.1).0 - origin.0)
@@ -624,6 +760,13 @@ impl GridLoop {
     }
 
     // private modifier
+    fn update_item(&mut self, id: EdgeId, item: GridLoopItem) {
+        if self.has_checkpoint() {
+            let prev = self[id];
+            self.history.push(History::Item(id, prev));
+        }
+        self[id] = item;
+    }
     fn queue_pop_all<T: GridLoopField>(field: &mut T) {
         while !field.grid_loop().queue.empty() {
             let id = field.grid_loop().queue.pop();
@@ -655,8 +798,13 @@ impl GridLoop {
         let gl = field.grid_loop();
         let mut pt = edge;
         let mut sz = 0;
+        let mut is_closed = false;
         loop {
-            gl[pt].edge_status = status;
+            let mut item = gl[pt];
+            item.edge_status = status;
+            gl.update_item(pt, item);
+            is_closed |=
+                gl.is_end_of_chain(pt) && item.chain_end_points.0 == item.chain_end_points.1;
             pt = gl[pt].chain_next;
             sz += 1;
             if pt == edge {
@@ -666,6 +814,16 @@ impl GridLoop {
         gl.decided_edge += sz;
         if status == Edge::Line {
             gl.decided_line += sz;
+
+            // an undecided chain can be closed if it was formed while no line was decided
+            if is_closed {
+                if gl.decided_line != sz {
+                    gl.inconsistent = true;
+                } else {
+                    gl.fully_solved = true;
+                    GridLoop::has_fully_solved(field);
+                }
+            }
         }
     }
     fn check_chain_neighborhood<T: GridLoopField>(field: &mut T, edge: EdgeId) {
@@ -750,10 +908,12 @@ impl GridLoop {
         if end1_vertex == end2_vertex {
             if status == Edge::Undecided {
                 if field.grid_loop().decided_line != 0 {
-                    GridLoop::decide_chain(field, edge1, Edge::Blank);
-                    GridLoop::decide_chain(field, edge2, Edge::Blank);
-                    GridLoop::check_chain_neighborhood(field, edge1);
-                    GridLoop::check_chain_neighborhood(field, edge2);
+                    if field.grid_loop().avoid_small_loop {
+                        GridLoop::decide_chain(field, edge1, Edge::Blank);
+                        GridLoop::decide_chain(field, edge2, Edge::Blank);
+                        GridLoop::check_chain_neighborhood(field, edge1);
+                        GridLoop::check_chain_neighborhood(field, edge2);
+                    }
                     return;
                 }
             } else if status == Edge::Line {
@@ -788,8 +948,8 @@ impl GridLoop {
         end1_item.chain_another_end_edge = end2_edge;
         end2_item.chain_another_end_edge = end1_edge;
 
-        grid_loop[end1_edge] = end1_item;
-        grid_loop[end2_edge] = end2_item;
+        grid_loop.update_item(end1_edge, end1_item);
+        grid_loop.update_item(end2_edge, end2_item);
 
         grid_loop.queue.push(end1_vertex.0);
         grid_loop.queue.push(end2_vertex.0);
@@ -838,7 +998,9 @@ impl GridLoop {
                     && field.grid_loop().is_end_of_chain_vertex(ud, vid)
                 {
                     let ud_another_end = field.grid_loop().another_end_id(vid, ud);
-                    if line_size == field.grid_loop().decided_line || another_end != ud_another_end
+                    if !field.grid_loop().avoid_small_loop
+                        || line_size == field.grid_loop().decided_line
+                        || another_end != ud_another_end
                     {
                         if cand == -1 {
                             cand = ud.0 as i32;
@@ -1162,6 +1324,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_avoid_small_loop() {
+        for &avoid in &[true, false] {
+            let mut grid_loop = GridLoop::new(3, 3);
+            grid_loop.set_avoid_small_loop(avoid);
+            GridLoop::decide_edge(&mut grid_loop, LP(0, 5), Edge::Line);
+            GridLoop::decide_edge(&mut grid_loop, LP(2, 3), Edge::Line);
+            GridLoop::decide_edge(&mut grid_loop, LP(3, 4), Edge::Line);
+            GridLoop::decide_edge(&mut grid_loop, LP(4, 3), Edge::Line);
+
+            if avoid {
+                assert_eq!(grid_loop.get_edge(LP(3, 2)), Edge::Blank);
+            } else {
+                assert_eq!(grid_loop.get_edge(LP(3, 2)), Edge::Undecided);
+                GridLoop::decide_edge(&mut grid_loop, LP(3, 2), Edge::Line);
+                assert!(grid_loop.inconsistent());
+            }
+        }
+    }
+
     #[test]
     fn test_fully_solved() {
         #[cfg_attr(rustfmt, rustfmt_skip)]
@@ -1237,6 +1419,106 @@ mod tests {
         assert_eq!(field.inconsistent(), false);
     }
 
+    fn assert_same_grid_loop(a: &GridLoop, b: &GridLoop) {
+        assert_eq!(a.inconsistent(), b.inconsistent());
+        assert_eq!(a.fully_solved(), b.fully_solved());
+        assert_eq!(a.num_decided_edges(), b.num_decided_edges());
+        assert_eq!(a.num_decided_lines(), b.num_decided_lines());
+        for y in 0..(a.height() * 2 + 1) {
+            for x in 0..(a.width() * 2 + 1) {
+                let pos = LP(y, x);
+                if pos.is_edge() {
+                    assert_eq!(a.get_edge(pos), b.get_edge(pos), "Comparing at {:?}", pos);
+                    assert_eq!(a.get_root(pos), b.get_root(pos), "Comparing at {:?}", pos);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rollback() {
+        let mut field = GridLoop::new(4, 4);
+        GridLoop::decide_edge(&mut field, LP(3, 4), Edge::Line);
+        GridLoop::decide_edge(&mut field, LP(4, 3), Edge::Blank);
+        let reference = field.clone();
+
+        field.add_checkpoint();
+        GridLoop::decide_edge(&mut field, LP(2, 3), Edge::Line);
+        GridLoop::decide_edge(&mut field, LP(5, 4), Edge::Line);
+        GridLoop::apply_inout_rule(&mut field);
+        {
+            let reference_inner = field.clone();
+
+            field.add_checkpoint();
+            GridLoop::decide_edge(&mut field, LP(3, 2), Edge::Line);
+            GridLoop::decide_edge(&mut field, LP(3, 2), Edge::Blank);
+            assert!(field.inconsistent());
+            field.rollback();
+
+            assert_same_grid_loop(&field, &reference_inner);
+        }
+        field.rollback();
+        assert!(!field.has_checkpoint());
+        assert_same_grid_loop(&field, &reference);
+
+        // the field must be reusable after rollback
+        let mut reference = reference;
+        GridLoop::decide_edge(&mut field, LP(3, 6), Edge::Line);
+        GridLoop::decide_edge(&mut reference, LP(3, 6), Edge::Line);
+        assert_same_grid_loop(&field, &reference);
+    }
+
+    fn assert_chain(chain: &LineChain, expected: &[P], is_loop: bool) {
+        assert_eq!(chain.is_loop, is_loop);
+        let mut vertices = chain.vertices.clone();
+        if is_loop {
+            assert_eq!(vertices[0], vertices[vertices.len() - 1]);
+            vertices.pop();
+            let start = vertices.iter().position(|&v| v == expected[0]).unwrap();
+            vertices.rotate_left(start);
+        }
+        if vertices[0] != expected[0] || (is_loop && vertices[1] != expected[1]) {
+            vertices.reverse();
+            if is_loop {
+                vertices.rotate_right(1);
+            }
+        }
+        assert_eq!(&vertices[..], expected);
+    }
+
+    #[test]
+    fn test_line_chains() {
+        let mut field = GridLoop::new(3, 3);
+        GridLoop::decide_edge(&mut field, LP(2, 3), Edge::Line);
+        GridLoop::decide_edge(&mut field, LP(3, 2), Edge::Line);
+        GridLoop::decide_edge(&mut field, LP(6, 5), Edge::Line);
+
+        let chains = field.line_chains();
+        assert_eq!(chains.len(), 2);
+        assert_chain(&chains[0], &[P(1, 2), P(1, 1), P(2, 1)], false);
+        // the line at the corner is extended automatically
+        assert_chain(&chains[1], &[P(3, 2), P(3, 3), P(2, 3)], false);
+        assert_eq!(field.solved_loop(), None);
+    }
+
+    #[test]
+    fn test_solved_loop() {
+        let mut field = GridLoop::new(3, 3);
+        GridLoop::decide_edge(&mut field, LP(2, 5), Edge::Line);
+        GridLoop::decide_edge(&mut field, LP(3, 4), Edge::Line);
+        GridLoop::decide_edge(&mut field, LP(3, 6), Edge::Line);
+        GridLoop::decide_edge(&mut field, LP(4, 5), Edge::Line);
+        assert!(field.fully_solved());
+
+        let chains = field.line_chains();
+        assert_eq!(chains.len(), 1);
+        assert_chain(&chains[0], &[P(1, 2), P(1, 3), P(2, 3), P(2, 2)], true);
+
+        let path = field.solved_loop().unwrap();
+        assert_eq!(path.len(), 5);
+        assert_eq!(path, chains[0].vertices);
+    }
+
     #[test]
     fn test_loop_connection() {
         #[cfg_attr(rustfmt, rustfmt_skip)]
@@ -1,6 +1,6 @@
 use std::error;
 use std::fmt::{self, Display};
-use std::io::{self, BufRead};
+use std::io::{self, BufRead, Read, Write};
 
 use {Grid, P};
 
@@ -103,6 +103,75 @@ where
     Ok(ret)
 }
 
+/// Writes the header of a binary table file: the 4-byte `tag` identifying its kind and
+/// the `version` of its contents.
+pub fn write_binary_header<W: Write>(
+    writer: &mut W,
+    tag: &[u8; 4],
+    version: u32,
+) -> io::Result<()> {
+    writer.write_all(tag)?;
+    writer.write_all(&encode_u32(version))
+}
+
+/// Reads a header and checks that it is equal to `tag` and `version`, so that files
+/// written by a build with different contents (e.g. stale caches) are rejected.
+pub fn read_binary_header<R: Read>(
+    reader: &mut R,
+    tag: &[u8; 4],
+    version: u32,
+) -> Result<(), ReadError> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    if &buf[0..4] == tag && decode_u32(&buf[4..8]) == version {
+        Ok(())
+    } else {
+        Err(ReadError::InvalidFormat)
+    }
+}
+
+/// Writes `table` as its length followed by the elements and their checksum,
+/// all in little endian.
+pub fn write_u32_table<W: Write>(writer: &mut W, table: &[u32]) -> io::Result<()> {
+    let mut buf = Vec::with_capacity((table.len() + 2) * 4);
+    for &v in [table.len() as u32].iter().chain(table.iter()) {
+        buf.extend_from_slice(&encode_u32(v));
+    }
+    let checksum = u32_checksum(&buf[4..]);
+    buf.extend_from_slice(&encode_u32(checksum));
+    writer.write_all(&buf)
+}
+
+/// Reads a table written by `write_u32_table`, whose length must be `len`.
+pub fn read_u32_table<R: Read>(reader: &mut R, len: usize) -> Result<Vec<u32>, ReadError> {
+    let mut header = [0u8; 4];
+    reader.read_exact(&mut header)?;
+    if decode_u32(&header) as usize != len {
+        return Err(ReadError::InvalidFormat);
+    }
+    let mut buf = vec![0u8; len * 4 + 4];
+    reader.read_exact(&mut buf)?;
+    if decode_u32(&buf[(len * 4)..]) != u32_checksum(&buf[..(len * 4)]) {
+        return Err(ReadError::InvalidValue);
+    }
+    Ok(buf[..(len * 4)].chunks(4).map(decode_u32).collect())
+}
+
+/// FNV-1a hash of `bytes`.
+fn u32_checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c9dc5u32, |acc, &b| {
+        (acc ^ b as u32).wrapping_mul(0x01000193)
+    })
+}
+
+fn encode_u32(v: u32) -> [u8; 4] {
+    [v as u8, (v >> 8) as u8, (v >> 16) as u8, (v >> 24) as u8]
+}
+
+fn decode_u32(bytes: &[u8]) -> u32 {
+    (0..4).fold(0u32, |acc, i| acc | ((bytes[i] as u32) << (8 * i)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -123,4 +192,32 @@ p q r s
         assert_eq!(grid.width(), 4);
         assert_eq!(grid[P(1, 2)], "z".to_string());
     }
+
+    #[test]
+    fn test_u32_table() {
+        let table = vec![0, 1, 0xdeadbeef, 0xffffffff];
+        let mut buf = vec![];
+        write_binary_header(&mut buf, b"TEST", 1).unwrap();
+        write_u32_table(&mut buf, &table).unwrap();
+        assert_eq!(buf.len(), 32);
+
+        let mut src = &buf[..];
+        assert!(read_binary_header(&mut src, b"TEST", 1).is_ok());
+        assert_eq!(read_u32_table(&mut src, 4).unwrap(), table);
+
+        let mut src = &buf[..];
+        assert!(read_binary_header(&mut src, b"TSET", 1).is_err());
+        let mut src = &buf[..];
+        assert!(read_binary_header(&mut src, b"TEST", 2).is_err());
+        let mut src = &buf[8..];
+        assert!(read_u32_table(&mut src, 3).is_err());
+        let mut src = &buf[8..28];
+        assert!(read_u32_table(&mut src, 4).is_err());
+
+        // a table of the right size with corrupted contents
+        let mut corrupted = buf.clone();
+        corrupted[14] ^= 1;
+        let mut src = &corrupted[8..];
+        assert!(read_u32_table(&mut src, 4).is_err());
+    }
 }
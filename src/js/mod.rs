@@ -3,6 +3,8 @@ use rand::SeedableRng;
 
 static mut SHARED_ARRAY: [u8; 1 << 16] = [0; 1 << 16];
 
+/// `numbering` selects the assignment of clue numbers: 0 for random, 1 for reading order
+/// and 2 for chain length. Other values are treated as random.
 #[no_mangle]
 pub extern "C" fn numberlink_generate(
     height: i32,
@@ -12,6 +14,8 @@ pub extern "C" fn numberlink_generate(
     corner_clue_high: i32,
     minimum_chain_length: i32,
     forbid_adjacent_clue: bool,
+    fill_all_cells: bool,
+    numbering: i32,
     seed1: f64,
     seed2: f64,
 ) -> *const u8 {
@@ -37,7 +41,17 @@ pub extern "C" fn numberlink_generate(
             forbid_adjacent_clue,
             symmetry: Symmetry::none(),
             clue_limit: None,
-            prioritized_extension: false,
+            topology: None,
+            rule: if fill_all_cells {
+                numberlink::Rule::FillAll
+            } else {
+                numberlink::Rule::Classic
+            },
+            numbering: match numbering {
+                1 => numberlink::Numbering::ReadingOrder,
+                2 => numberlink::Numbering::ByLength,
+                _ => numberlink::Numbering::Random,
+            },
         };
         if let Some(problem) = generator.generate_and_test(&cond, &mut rng) {
             unsafe {
@@ -0,0 +1,108 @@
+//! Country Road.
+//!
+//! - problem: `{"rooms": rows, "clues": array}`, where each cell of `rooms` is the id
+//!   of its room (0, 1, ... without gaps) and `clues[id]` is the clue of the room
+//!   (a number at most the number of cells, or `null`)
+//! - answer: `{"lines": edges}` on the lattice of size `(2 * height - 1) x (2 * width - 1)`
+
+use super::grid::*;
+use super::Json;
+use country_road::{Clue, Problem, NO_CLUE};
+use io::ReadError;
+use {Grid, P};
+
+pub fn problem_to_json(problem: &Problem) -> Json {
+    let mut rooms = Grid::new(problem.height(), problem.width(), 0);
+    for y in 0..problem.height() {
+        for x in 0..problem.width() {
+            rooms[P(y, x)] = problem.room(P(y, x));
+        }
+    }
+    let clues = (0..problem.n_rooms())
+        .map(|room| {
+            let Clue(c) = problem.clue(room);
+            optional_to_json(if c >= 0 { Some(c) } else { None })
+        })
+        .collect::<Vec<_>>();
+    Json::object(vec![
+        ("rooms", grid_to_json(&rooms, |&room| Json::from(room))),
+        ("clues", Json::Array(clues)),
+    ])
+}
+
+pub fn problem_from_json(value: &Json) -> Result<Problem, ReadError> {
+    let rooms = grid_from_json(member(value, "rooms")?, 0, |cell| {
+        let room = int_from_json(cell)?;
+        if room >= 0 {
+            Ok(room as usize)
+        } else {
+            Err(ReadError::InvalidValue)
+        }
+    })?;
+    let n_cells = rooms.height() * rooms.width();
+    let mut problem = Problem::new(&rooms);
+    for y in 0..rooms.height() {
+        for x in 0..rooms.width() {
+            if rooms[P(y, x)] != problem.room(P(y, x)) {
+                // the ids are not consecutive from 0
+                return Err(ReadError::InvalidValue);
+            }
+        }
+    }
+    let clues = array_from_json(member(value, "clues")?)?;
+    if clues.len() != problem.n_rooms() {
+        return Err(ReadError::InvalidFormat);
+    }
+    for (room, clue) in clues.iter().enumerate() {
+        let clue = match optional_from_json(clue)? {
+            Some(c) if 0 <= c && c <= n_cells => Clue(c),
+            Some(_) => return Err(ReadError::InvalidValue),
+            None => NO_CLUE,
+        };
+        problem.set_clue(room, clue);
+    }
+    Ok(problem)
+}
+
+pub fn answer_to_json(answer: &Grid<bool>) -> Json {
+    Json::object(vec![("lines", edges_to_json(answer))])
+}
+
+pub fn answer_from_json(problem: &Problem, value: &Json) -> Result<Grid<bool>, ReadError> {
+    edges_from_json(
+        member(value, "lines")?,
+        problem.height() * 2 - 1,
+        problem.width() * 2 - 1,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use LP;
+
+    #[test]
+    fn test_country_road_json() {
+        let src = r#"{"rooms": [[0, 0, 1], [2, 2, 1]], "clues": [2, null, 0]}"#;
+        let problem = problem_from_json(&Json::parse(src).unwrap()).unwrap();
+        assert_eq!(problem.n_rooms(), 3);
+        assert_eq!(problem.room(P(1, 2)), 1);
+        assert_eq!(problem.clue(0), Clue(2));
+        assert_eq!(problem.clue(1), NO_CLUE);
+        assert_eq!(problem_to_json(&problem), Json::parse(src).unwrap());
+
+        let mut answer = Grid::new(3, 5, false);
+        answer[LP(1, 0)] = true;
+        let read = answer_from_json(&problem, &answer_to_json(&answer)).unwrap();
+        assert!((0..15).all(|i| read[i] == answer[i]));
+
+        for src in &[
+            r#"{"rooms": [[0, 1]], "clues": [1]}"#,
+            r#"{"rooms": [[0, 2]], "clues": [1, null, null]}"#,
+            r#"{"rooms": [[0, 2000000000]], "clues": [null, null]}"#,
+            r#"{"rooms": [[0, 1]], "clues": [3, null]}"#,
+        ] {
+            assert!(problem_from_json(&Json::parse(src).unwrap()).is_err());
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Dosun-Fuwari.
+//!
+//! - problem: `{"black": rows, "areas": array}`, where each cell of `black` is `true` for
+//!   a black cell and each area is an array of the positions of its cells
+//! - answer: `{"cells": rows}`, where each cell is `"balloon"`, `"iron"`, `"empty"`,
+//!   `"black"` or `null` (undecided)
+
+use super::grid::*;
+use super::Json;
+use dosufuwa::Cell;
+use io::ReadError;
+use {Grid, P};
+
+pub fn problem_to_json(is_black: &Grid<bool>, areas: &[Vec<P>]) -> Json {
+    let areas = areas
+        .iter()
+        .map(|area| Json::Array(area.iter().map(|&P(y, x)| position_to_json(y, x)).collect()))
+        .collect::<Vec<_>>();
+    Json::object(vec![
+        ("black", grid_to_json(is_black, |&b| Json::from(b))),
+        ("areas", Json::Array(areas)),
+    ])
+}
+
+/// Reads a problem into `(is_black, areas)`.
+pub fn problem_from_json(value: &Json) -> Result<(Grid<bool>, Vec<Vec<P>>), ReadError> {
+    let is_black = grid_from_json(member(value, "black")?, false, |cell| {
+        cell.as_bool().ok_or(ReadError::InvalidValue)
+    })?;
+    let mut areas = vec![];
+    for area in array_from_json(member(value, "areas")?)? {
+        let mut cells = vec![];
+        for cell in array_from_json(area)? {
+            let (y, x) = position_from_json(cell)?;
+            if !is_black.is_valid_p(P(y, x)) {
+                return Err(ReadError::InvalidValue);
+            }
+            cells.push(P(y, x));
+        }
+        areas.push(cells);
+    }
+    Ok((is_black, areas))
+}
+
+pub fn answer_to_json(answer: &Grid<Cell>) -> Json {
+    let cells = grid_to_json(answer, |&cell| match cell {
+        Cell::Undecided => Json::Null,
+        Cell::Black => Json::from("black"),
+        Cell::Empty => Json::from("empty"),
+        Cell::Balloon => Json::from("balloon"),
+        Cell::Iron => Json::from("iron"),
+    });
+    Json::object(vec![("cells", cells)])
+}
+
+pub fn answer_from_json(is_black: &Grid<bool>, value: &Json) -> Result<Grid<Cell>, ReadError> {
+    sized_grid_from_json(
+        member(value, "cells")?,
+        is_black.height(),
+        is_black.width(),
+        Cell::Undecided,
+        |cell| match *cell {
+            Json::Null => Ok(Cell::Undecided),
+            _ => match cell.as_str() {
+                Some("black") => Ok(Cell::Black),
+                Some("empty") => Ok(Cell::Empty),
+                Some("balloon") => Ok(Cell::Balloon),
+                Some("iron") => Ok(Cell::Iron),
+                _ => Err(ReadError::InvalidValue),
+            },
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dosufuwa_json() {
+        let src = r#"{"black": [[false, true], [false, false]],
+            "areas": [[[0, 0], [1, 0]], [[1, 1]]]}"#;
+        let (is_black, areas) = problem_from_json(&Json::parse(src).unwrap()).unwrap();
+        assert!(is_black[P(0, 1)]);
+        assert_eq!(areas, vec![vec![P(0, 0), P(1, 0)], vec![P(1, 1)]]);
+        assert_eq!(
+            problem_to_json(&is_black, &areas),
+            Json::parse(src).unwrap()
+        );
+
+        let mut answer = Grid::new(2, 2, Cell::Empty);
+        answer[P(0, 0)] = Cell::Balloon;
+        answer[P(0, 1)] = Cell::Black;
+        answer[P(1, 1)] = Cell::Iron;
+        let read = answer_from_json(&is_black, &answer_to_json(&answer)).unwrap();
+        assert!((0..4).all(|i| read[i] == answer[i]));
+
+        let src = r#"{"black": [[false]], "areas": [[[0, 1]]]}"#;
+        assert!(problem_from_json(&Json::parse(src).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+//! Double Choco.
+//!
+//! - problem: `{"colors": rows, "clues": rows}`, where each cell of `colors` is `"white"` or
+//!   `"black"`, and each cell of `clues` is a number or `null`
+//! - answer: `{"blocks": rows}`, where each cell is the id of the block containing it
+
+use super::grid::*;
+use super::Json;
+use doublechoco::{Clue, Color, NO_CLUE};
+use io::ReadError;
+use Grid;
+
+pub fn problem_to_json(color: &Grid<Color>, clue: &Grid<Clue>) -> Json {
+    let colors = grid_to_json(color, |&c| match c {
+        Color::White => Json::from("white"),
+        Color::Black => Json::from("black"),
+    });
+    let clues = grid_to_json(clue, |&c| {
+        optional_to_json(if c == NO_CLUE { None } else { Some(c) })
+    });
+    Json::object(vec![("colors", colors), ("clues", clues)])
+}
+
+/// Reads a problem into `(color, clue)`.
+pub fn problem_from_json(value: &Json) -> Result<(Grid<Color>, Grid<Clue>), ReadError> {
+    let color = grid_from_json(member(value, "colors")?, Color::White, |cell| {
+        match cell.as_str() {
+            Some("white") => Ok(Color::White),
+            Some("black") => Ok(Color::Black),
+            _ => Err(ReadError::InvalidValue),
+        }
+    })?;
+    let clue = sized_grid_from_json(
+        member(value, "clues")?,
+        color.height(),
+        color.width(),
+        NO_CLUE,
+        |cell| match optional_from_json(cell)? {
+            Some(n) if n > 0 => Ok(n),
+            Some(_) => Err(ReadError::InvalidValue),
+            None => Ok(NO_CLUE),
+        },
+    )?;
+    Ok((color, clue))
+}
+
+pub fn answer_to_json(answer: &Grid<usize>) -> Json {
+    Json::object(vec![("blocks", grid_to_json(answer, |&b| Json::from(b)))])
+}
+
+pub fn answer_from_json(color: &Grid<Color>, value: &Json) -> Result<Grid<usize>, ReadError> {
+    sized_grid_from_json(
+        member(value, "blocks")?,
+        color.height(),
+        color.width(),
+        0,
+        |cell| match int_from_json(cell)? {
+            b if b >= 0 => Ok(b as usize),
+            _ => Err(ReadError::InvalidValue),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use P;
+
+    #[test]
+    fn test_doublechoco_json() {
+        let src = r#"{"colors": [["white", "black"], ["white", "black"]],
+            "clues": [[2, null], [null, null]]}"#;
+        let (color, clue) = problem_from_json(&Json::parse(src).unwrap()).unwrap();
+        assert_eq!(color[P(0, 1)], Color::Black);
+        assert_eq!(clue[P(0, 0)], 2);
+        assert_eq!(clue[P(1, 0)], NO_CLUE);
+        assert_eq!(problem_to_json(&color, &clue), Json::parse(src).unwrap());
+
+        let mut answer = Grid::new(2, 2, 0);
+        answer[P(1, 0)] = 1;
+        answer[P(1, 1)] = 1;
+        let read = answer_from_json(&color, &answer_to_json(&answer)).unwrap();
+        assert!((0..4).all(|i| read[i] == answer[i]));
+
+        let src = r#"{"colors": [["white"]], "clues": [[null, null]]}"#;
+        assert!(problem_from_json(&Json::parse(src).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,125 @@
+//! Easy as ABC (End View).
+//!
+//! - problem: `{"size": n, "letters": k, "clues": {"left": l, "right": r, "top": t,
+//!   "bottom": b}}`, where each of `l` (`r`) lists the clues of the rows from the top and
+//!   each of `t` (`b`) lists those of the columns from the left; a clue is the index of the
+//!   letter (0 for A, 1 for B, ...) or `null`
+//! - answer: `{"values": rows}`, where each cell is the index of the letter, `null` for
+//!   an empty cell or `"?"` for an undecided cell
+
+use super::grid::*;
+use super::Json;
+use endview::{Clue, ClueLoc, Problem, Value, EMPTY, NO_CLUE, UNDECIDED};
+use io::ReadError;
+use Grid;
+
+const CLUE_LOCS: [(&str, ClueLoc); 4] = [
+    ("left", ClueLoc::Left),
+    ("right", ClueLoc::Right),
+    ("top", ClueLoc::Top),
+    ("bottom", ClueLoc::Bottom),
+];
+
+pub fn problem_to_json(problem: &Problem) -> Json {
+    let clues = CLUE_LOCS
+        .iter()
+        .map(|&(key, loc)| {
+            let clues = (0..problem.size())
+                .map(|i| {
+                    let Clue(c) = problem.get_clue(loc, i);
+                    optional_to_json(if c >= 0 { Some(c) } else { None })
+                })
+                .collect::<Vec<_>>();
+            (key, Json::Array(clues))
+        })
+        .collect::<Vec<_>>();
+    Json::object(vec![
+        ("size", Json::from(problem.size())),
+        ("letters", Json::from(problem.n_alpha())),
+        ("clues", Json::object(clues)),
+    ])
+}
+
+pub fn problem_from_json(value: &Json) -> Result<Problem, ReadError> {
+    let size = int_from_json(member(value, "size")?)?;
+    let n_alpha = int_from_json(member(value, "letters")?)?;
+    if size <= 0 || n_alpha <= 0 || n_alpha > size {
+        return Err(ReadError::InvalidValue);
+    }
+    let mut problem = Problem::new(size, n_alpha);
+    let clues = member(value, "clues")?;
+    for &(key, loc) in &CLUE_LOCS {
+        let list = array_from_json(member(clues, key)?)?;
+        if list.len() != size as usize {
+            return Err(ReadError::InvalidFormat);
+        }
+        for (i, clue) in list.iter().enumerate() {
+            let clue = match optional_from_json(clue)? {
+                Some(c) if 0 <= c && c < n_alpha => Clue(c),
+                Some(_) => return Err(ReadError::InvalidValue),
+                None => NO_CLUE,
+            };
+            problem.set_clue(loc, i as i32, clue);
+        }
+    }
+    Ok(problem)
+}
+
+pub fn answer_to_json(answer: &Grid<Value>) -> Json {
+    let values = grid_to_json(answer, |&v| match v {
+        Value(n) if n >= 0 => Json::from(n),
+        EMPTY => Json::Null,
+        _ => Json::from("?"),
+    });
+    Json::object(vec![("values", values)])
+}
+
+pub fn answer_from_json(problem: &Problem, value: &Json) -> Result<Grid<Value>, ReadError> {
+    sized_grid_from_json(
+        member(value, "values")?,
+        problem.size(),
+        problem.size(),
+        UNDECIDED,
+        |cell| match *cell {
+            Json::Null => Ok(EMPTY),
+            Json::String(ref s) if s == "?" => Ok(UNDECIDED),
+            _ => match int_from_json(cell)? {
+                n if 0 <= n && n < problem.n_alpha() => Ok(Value(n)),
+                _ => Err(ReadError::InvalidValue),
+            },
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use P;
+
+    #[test]
+    fn test_endview_json() {
+        let src = r#"{"size": 3, "letters": 2, "clues": {"left": [0, null, null],
+            "right": [null, null, 1], "top": [null, 1, null], "bottom": [null, null, null]}}"#;
+        let problem = problem_from_json(&Json::parse(src).unwrap()).unwrap();
+        assert_eq!(problem.get_clue(ClueLoc::Left, 0), Clue(0));
+        assert_eq!(problem.get_clue(ClueLoc::Right, 2), Clue(1));
+        assert_eq!(problem.get_clue(ClueLoc::Bottom, 0), NO_CLUE);
+        assert_eq!(problem_to_json(&problem), Json::parse(src).unwrap());
+
+        let mut answer = Grid::new(3, 3, EMPTY);
+        answer[P(0, 0)] = Value(0);
+        answer[P(0, 1)] = Value(1);
+        answer[P(2, 2)] = UNDECIDED;
+        let json = answer_to_json(&answer);
+        assert_eq!(
+            json.to_string(),
+            r#"{"values":[[0,1,null],[null,null,null],[null,null,"?"]]}"#
+        );
+        let read = answer_from_json(&problem, &json).unwrap();
+        assert!((0..9).all(|i| read[i] == answer[i]));
+
+        let src = r#"{"size": 2, "letters": 2, "clues": {"left": [2, null],
+            "right": [null, null], "top": [null, null], "bottom": [null, null]}}"#;
+        assert!(problem_from_json(&Json::parse(src).unwrap()).is_err());
+    }
+}
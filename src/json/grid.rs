@@ -0,0 +1,124 @@
+use super::Json;
+use io::ReadError;
+use {Grid, LP, P};
+
+/// Returns the member `key` of `value`, which must exist.
+pub fn member<'a>(value: &'a Json, key: &str) -> Result<&'a Json, ReadError> {
+    value.get(key).ok_or(ReadError::InvalidFormat)
+}
+
+pub fn int_from_json(value: &Json) -> Result<i32, ReadError> {
+    value.as_i32().ok_or(ReadError::InvalidValue)
+}
+
+pub fn array_from_json(value: &Json) -> Result<&[Json], ReadError> {
+    value.as_array().ok_or(ReadError::InvalidFormat)
+}
+
+pub fn position_to_json(y: i32, x: i32) -> Json {
+    Json::from(vec![y, x])
+}
+
+/// Reads a position `[y, x]`.
+pub fn position_from_json(value: &Json) -> Result<(i32, i32), ReadError> {
+    match array_from_json(value)? {
+        [y, x] => Ok((int_from_json(y)?, int_from_json(x)?)),
+        _ => Err(ReadError::InvalidFormat),
+    }
+}
+
+/// Writes `grid` as an array of rows, converting each cell by `f`.
+pub fn grid_to_json<T: Clone, F: Fn(&T) -> Json>(grid: &Grid<T>, f: F) -> Json {
+    Json::Array(
+        (0..grid.height())
+            .map(|y| Json::Array((0..grid.width()).map(|x| f(&grid[P(y, x)])).collect()))
+            .collect(),
+    )
+}
+
+/// Reads a non-empty array of rows of the same non-zero length, converting each cell by `f`.
+pub fn grid_from_json<T: Clone, F: Fn(&Json) -> Result<T, ReadError>>(
+    value: &Json,
+    default: T,
+    f: F,
+) -> Result<Grid<T>, ReadError> {
+    let rows = array_from_json(value)?;
+    let height = rows.len();
+    let width = match rows.first() {
+        Some(row) => array_from_json(row)?.len(),
+        None => return Err(ReadError::InvalidFormat),
+    };
+    if width == 0 {
+        return Err(ReadError::InvalidFormat);
+    }
+    let mut ret = Grid::new(height as i32, width as i32, default);
+    for (y, row) in rows.iter().enumerate() {
+        let row = array_from_json(row)?;
+        if row.len() != width {
+            return Err(ReadError::InvalidFormat);
+        }
+        for (x, cell) in row.iter().enumerate() {
+            ret[P(y as i32, x as i32)] = f(cell)?;
+        }
+    }
+    Ok(ret)
+}
+
+/// Same as `grid_from_json`, but the grid must be of size `height` x `width`.
+pub fn sized_grid_from_json<T: Clone, F: Fn(&Json) -> Result<T, ReadError>>(
+    value: &Json,
+    height: i32,
+    width: i32,
+    default: T,
+    f: F,
+) -> Result<Grid<T>, ReadError> {
+    let ret = grid_from_json(value, default, f)?;
+    if ret.height() == height && ret.width() == width {
+        Ok(ret)
+    } else {
+        Err(ReadError::InvalidFormat)
+    }
+}
+
+/// Writes the edges of `lines` (a grid of lattice positions) having a line
+/// as an array of positions `[y, x]`.
+pub fn edges_to_json(lines: &Grid<bool>) -> Json {
+    let mut ret = vec![];
+    for y in 0..lines.height() {
+        for x in 0..lines.width() {
+            if y % 2 != x % 2 && lines[LP(y, x)] {
+                ret.push(position_to_json(y, x));
+            }
+        }
+    }
+    Json::Array(ret)
+}
+
+/// Reads an array of edges written by `edges_to_json` into a grid of lattice positions
+/// of size `height` x `width`.
+pub fn edges_from_json(value: &Json, height: i32, width: i32) -> Result<Grid<bool>, ReadError> {
+    let mut ret = Grid::new(height, width, false);
+    for edge in array_from_json(value)? {
+        let (y, x) = position_from_json(edge)?;
+        if !ret.is_valid_lp(LP(y, x)) || y % 2 == x % 2 {
+            return Err(ReadError::InvalidValue);
+        }
+        ret[LP(y, x)] = true;
+    }
+    Ok(ret)
+}
+
+/// Writes an optional number, where `None` is `null`.
+pub fn optional_to_json(n: Option<i32>) -> Json {
+    match n {
+        Some(n) => Json::from(n),
+        None => Json::Null,
+    }
+}
+
+pub fn optional_from_json(value: &Json) -> Result<Option<i32>, ReadError> {
+    match *value {
+        Json::Null => Ok(None),
+        _ => int_from_json(value).map(Some),
+    }
+}
@@ -0,0 +1,99 @@
+//! Kakuro.
+//!
+//! - problem: `{"clues": rows}`, where each cell is `null` for a cell to be filled, or
+//!   `{"horizontal": h, "vertical": v}` for a clue cell with the sums of the cells on its
+//!   right and below (0 if there are no such cells)
+//! - answer: `{"values": rows}`, where each cell is its number (1-9), or 0 for clue cells
+
+use super::grid::*;
+use super::Json;
+use io::ReadError;
+use kakuro::Clue;
+use Grid;
+
+pub fn problem_to_json(problem: &Grid<Clue>) -> Json {
+    let clues = grid_to_json(problem, |&clue| match clue {
+        Clue::NoClue => Json::Null,
+        Clue::Clue {
+            horizontal,
+            vertical,
+        } => Json::object(vec![
+            ("horizontal", Json::from(horizontal)),
+            ("vertical", Json::from(vertical)),
+        ]),
+    });
+    Json::object(vec![("clues", clues)])
+}
+
+pub fn problem_from_json(value: &Json) -> Result<Grid<Clue>, ReadError> {
+    grid_from_json(member(value, "clues")?, Clue::NoClue, |cell| {
+        if let Json::Null = *cell {
+            return Ok(Clue::NoClue);
+        }
+        let horizontal = int_from_json(member(cell, "horizontal")?)?;
+        let vertical = int_from_json(member(cell, "vertical")?)?;
+        if horizontal < 0 || vertical < 0 {
+            return Err(ReadError::InvalidValue);
+        }
+        Ok(Clue::Clue {
+            horizontal,
+            vertical,
+        })
+    })
+}
+
+pub fn answer_to_json(answer: &Grid<i32>) -> Json {
+    Json::object(vec![("values", grid_to_json(answer, |&v| Json::from(v)))])
+}
+
+pub fn answer_from_json(problem: &Grid<Clue>, value: &Json) -> Result<Grid<i32>, ReadError> {
+    sized_grid_from_json(
+        member(value, "values")?,
+        problem.height(),
+        problem.width(),
+        0,
+        |cell| {
+            let v = int_from_json(cell)?;
+            if (0..10).contains(&v) {
+                Ok(v)
+            } else {
+                Err(ReadError::InvalidValue)
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use P;
+
+    #[test]
+    fn test_kakuro_json() {
+        let src = r#"{"clues": [[{"horizontal": 0, "vertical": 0}, {"horizontal": 0, "vertical": 4}],
+            [{"horizontal": 3, "vertical": 0}, null]]}"#;
+        let problem = problem_from_json(&Json::parse(src).unwrap()).unwrap();
+        match problem[P(0, 1)] {
+            Clue::Clue {
+                horizontal,
+                vertical,
+            } => assert_eq!((horizontal, vertical), (0, 4)),
+            Clue::NoClue => panic!(),
+        }
+        assert_eq!(problem_to_json(&problem), Json::parse(src).unwrap());
+
+        let mut answer = Grid::new(2, 2, 0);
+        answer[P(1, 1)] = 3;
+        let json = answer_to_json(&answer);
+        assert_eq!(json.to_string(), r#"{"values":[[0,0],[0,3]]}"#);
+        let read = answer_from_json(&problem, &json).unwrap();
+        assert!((0..4).all(|i| read[i] == answer[i]));
+
+        let src = r#"{"values": [[0, 0, 0], [0, 3, 0]]}"#;
+        assert!(answer_from_json(&problem, &Json::parse(src).unwrap()).is_err());
+        let src = r#"{"values": [[0, 0], [0, 10]]}"#;
+        assert!(answer_from_json(&problem, &Json::parse(src).unwrap()).is_err());
+        let src = r#"{"clues": [[{"horizontal": 3}]]}"#;
+        assert!(problem_from_json(&Json::parse(src).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,65 @@
+//! Masyu.
+//!
+//! - problem: `{"clues": rows}`, where each cell is `"white"`, `"black"` or `null`
+//! - answer: `{"lines": edges}` on the lattice of size `(2 * height - 1) x (2 * width - 1)`
+
+use super::grid::*;
+use super::Json;
+use io::ReadError;
+use masyu::Clue;
+use Grid;
+
+pub fn problem_to_json(problem: &Grid<Clue>) -> Json {
+    let clues = grid_to_json(problem, |&clue| match clue {
+        Clue::White => Json::from("white"),
+        Clue::Black => Json::from("black"),
+        Clue::NoClue => Json::Null,
+    });
+    Json::object(vec![("clues", clues)])
+}
+
+pub fn problem_from_json(value: &Json) -> Result<Grid<Clue>, ReadError> {
+    grid_from_json(member(value, "clues")?, Clue::NoClue, |cell| match *cell {
+        Json::Null => Ok(Clue::NoClue),
+        _ => match cell.as_str() {
+            Some("white") => Ok(Clue::White),
+            Some("black") => Ok(Clue::Black),
+            _ => Err(ReadError::InvalidValue),
+        },
+    })
+}
+
+pub fn answer_to_json(answer: &Grid<bool>) -> Json {
+    Json::object(vec![("lines", edges_to_json(answer))])
+}
+
+pub fn answer_from_json(problem: &Grid<Clue>, value: &Json) -> Result<Grid<bool>, ReadError> {
+    edges_from_json(
+        member(value, "lines")?,
+        problem.height() * 2 - 1,
+        problem.width() * 2 - 1,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {LP, P};
+
+    #[test]
+    fn test_masyu_json() {
+        let src = r#"{"clues": [["white", null, null], [null, null, "black"]]}"#;
+        let problem = problem_from_json(&Json::parse(src).unwrap()).unwrap();
+        assert_eq!(problem[P(0, 0)], Clue::White);
+        assert_eq!(problem[P(1, 2)], Clue::Black);
+        assert_eq!(problem_to_json(&problem), Json::parse(src).unwrap());
+
+        let mut answer = Grid::new(3, 5, false);
+        answer[LP(0, 1)] = true;
+        answer[LP(1, 4)] = true;
+        let read = answer_from_json(&problem, &answer_to_json(&answer)).unwrap();
+        assert!((0..15).all(|i| read[i] == answer[i]));
+
+        assert!(problem_from_json(&Json::parse(r#"{"clues": [["gray"]]}"#).unwrap()).is_err());
+    }
+}
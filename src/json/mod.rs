@@ -0,0 +1,31 @@
+//! Minimal JSON support for exchanging problems and answers with other programs.
+//!
+//! Each genre module has `problem_to_json` / `problem_from_json` and `answer_to_json` /
+//! `answer_from_json` (which takes the problem to know the size of the board).
+//! The schema of each genre is described in its module. Common conventions:
+//!
+//! - A grid is an array of rows, and a row is an array of cells. `null` means no clue.
+//! - A position is `[y, x]`.
+//! - Lines are given by `lines`, an array of lattice positions of the edges
+//!   passed by lines: `[2y + 1, 2x]` and `[2y, 2x + 1]` are the edges between vertices
+//!   (in Slitherlink) or cells (in other genres) `(y, x)` and `(y + 1, x)` / `(y, x + 1)`.
+//!
+//! Members not mentioned in the schema are ignored when reading.
+
+mod grid;
+mod value;
+
+pub mod country_road;
+pub mod dosufuwa;
+pub mod doublechoco;
+pub mod endview;
+pub mod kakuro;
+pub mod masyu;
+pub mod numberlink;
+pub mod nurimisaki;
+pub mod simple_loop;
+pub mod slitherlink;
+pub mod tapa;
+pub mod yajilin;
+
+pub use self::value::*;
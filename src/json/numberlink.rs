@@ -0,0 +1,224 @@
+//! Numberlink.
+//!
+//! - problem: `{"clues": rows}`, where each cell is a number (1, 2, ..., at most the number
+//!   of cells), `null` or `"unused"` (a cell which lines cannot pass)
+//! - variant problem: the problem with the optional members
+//!   `"topology": {"rows": [y, ...], "columns": [x, ...], "bridges": [[y, x], ...]}`
+//!   (the rows and columns which wrap around and the bridge cells, each of which may be
+//!   omitted if empty) and `"rule": "classic"` (default) or `"fill"`
+//! - answer: `{"lines": edges}` on the lattice of size `(2 * height) x (2 * width)`, where
+//!   `[2y, 2 * width - 1]` and `[2 * height - 1, 2x]` are the warps of row `y` and column `x`
+//!   (see `Topology`)
+
+use super::grid::*;
+use super::Json;
+use io::ReadError;
+use numberlink::{Clue, LinePlacement, Rule, Topology, VariantProblem, NO_CLUE, UNUSED};
+use {Grid, LP, P};
+
+fn clues_to_json(problem: &Grid<Clue>) -> Json {
+    grid_to_json(problem, |&clue| {
+        if clue == UNUSED {
+            Json::from("unused")
+        } else if clue == NO_CLUE {
+            Json::Null
+        } else {
+            Json::from(clue.0)
+        }
+    })
+}
+
+pub fn problem_to_json(problem: &Grid<Clue>) -> Json {
+    Json::object(vec![("clues", clues_to_json(problem))])
+}
+
+pub fn problem_from_json(value: &Json) -> Result<Grid<Clue>, ReadError> {
+    let ret = grid_from_json(member(value, "clues")?, NO_CLUE, |cell| match *cell {
+        Json::Null => Ok(NO_CLUE),
+        Json::String(ref s) if s == "unused" => Ok(UNUSED),
+        _ => match int_from_json(cell)? {
+            n if n > 0 => Ok(Clue(n)),
+            _ => Err(ReadError::InvalidValue),
+        },
+    })?;
+    let n_cells = ret.height() * ret.width();
+    for y in 0..ret.height() {
+        for x in 0..ret.width() {
+            if ret[P(y, x)].0 > n_cells {
+                return Err(ReadError::InvalidValue);
+            }
+        }
+    }
+    Ok(ret)
+}
+
+/// Writes `problem`, omitting `topology` of a plain board and the default rule.
+pub fn variant_problem_to_json(problem: &VariantProblem) -> Json {
+    let mut members = vec![("clues", clues_to_json(&problem.clues))];
+    let topology = &problem.topology;
+    if !topology.is_plain() {
+        let rows = (0..topology.height())
+            .filter(|&y| topology.warp_row(y))
+            .collect::<Vec<_>>();
+        let columns = (0..topology.width())
+            .filter(|&x| topology.warp_column(x))
+            .collect::<Vec<_>>();
+        let mut bridges = vec![];
+        for y in 0..topology.height() {
+            for x in 0..topology.width() {
+                if topology.bridge(P(y, x)) {
+                    bridges.push(position_to_json(y, x));
+                }
+            }
+        }
+        members.push((
+            "topology",
+            Json::object(vec![
+                ("rows", Json::from(rows)),
+                ("columns", Json::from(columns)),
+                ("bridges", Json::Array(bridges)),
+            ]),
+        ));
+    }
+    if problem.rule == Rule::FillAll {
+        members.push(("rule", Json::from("fill")));
+    }
+    Json::object(members)
+}
+
+pub fn variant_problem_from_json(value: &Json) -> Result<VariantProblem, ReadError> {
+    let clues = problem_from_json(value)?;
+    let (height, width) = (clues.height(), clues.width());
+    let mut topology = Topology::new(height, width);
+    if let Some(spec) = value.get("topology") {
+        let read_list = |key: &str, limit: i32| -> Result<Vec<i32>, ReadError> {
+            let mut ret = vec![];
+            if let Some(list) = spec.get(key) {
+                for n in array_from_json(list)? {
+                    match int_from_json(n)? {
+                        n if 0 <= n && n < limit => ret.push(n),
+                        _ => return Err(ReadError::InvalidValue),
+                    }
+                }
+            }
+            Ok(ret)
+        };
+        for y in read_list("rows", height)? {
+            topology.set_warp_row(y, true);
+        }
+        for x in read_list("columns", width)? {
+            topology.set_warp_column(x, true);
+        }
+        if let Some(bridges) = spec.get("bridges") {
+            for pos in array_from_json(bridges)? {
+                let (y, x) = position_from_json(pos)?;
+                if !clues.is_valid_p(P(y, x)) || clues[P(y, x)] != NO_CLUE {
+                    return Err(ReadError::InvalidValue);
+                }
+                topology.set_bridge(P(y, x), true);
+            }
+        }
+    }
+    let rule = match value.get("rule").map(Json::as_str) {
+        None | Some(Some("classic")) => Rule::Classic,
+        Some(Some("fill")) => Rule::FillAll,
+        Some(_) => return Err(ReadError::InvalidValue),
+    };
+    Ok(VariantProblem {
+        clues,
+        topology,
+        rule,
+    })
+}
+
+pub fn answer_to_json(answer: &LinePlacement) -> Json {
+    let mut lines = Grid::new(answer.height() * 2, answer.width() * 2, false);
+    for y in 0..lines.height() {
+        for x in 0..lines.width() {
+            if y % 2 != x % 2 {
+                lines[LP(y, x)] = answer.get(LP(y, x));
+            }
+        }
+    }
+    Json::object(vec![("lines", edges_to_json(&lines))])
+}
+
+pub fn answer_from_json(problem: &Grid<Clue>, value: &Json) -> Result<LinePlacement, ReadError> {
+    let height = problem.height();
+    let width = problem.width();
+    let lines = edges_from_json(member(value, "lines")?, height * 2, width * 2)?;
+    let mut ret = LinePlacement::new(height, width);
+    for y in 0..height {
+        for x in 0..width {
+            ret.set_right(P(y, x), lines[LP(y * 2, x * 2 + 1)]);
+            ret.set_down(P(y, x), lines[LP(y * 2 + 1, x * 2)]);
+        }
+    }
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numberlink_json() {
+        let src = r#"{"clues": [[1, null, 1], ["unused", 2, 2]]}"#;
+        let problem = problem_from_json(&Json::parse(src).unwrap()).unwrap();
+        assert!(problem[P(0, 0)] == Clue(1));
+        assert!(problem[P(0, 1)] == NO_CLUE);
+        assert!(problem[P(1, 0)] == UNUSED);
+        assert_eq!(problem_to_json(&problem), Json::parse(src).unwrap());
+
+        let mut answer = LinePlacement::new(2, 3);
+        answer.set_right(P(0, 0), true);
+        answer.set_right(P(0, 1), true);
+        answer.set_right(P(1, 1), true);
+        answer.set_down(P(1, 2), true);
+        let json = answer_to_json(&answer);
+        assert_eq!(json.to_string(), r#"{"lines":[[0,1],[0,3],[2,3],[3,4]]}"#);
+        let read = answer_from_json(&problem, &json).unwrap();
+        for y in 0..2 {
+            for x in 0..3 {
+                assert_eq!(read.right(P(y, x)), answer.right(P(y, x)));
+                assert_eq!(read.down(P(y, x)), answer.down(P(y, x)));
+            }
+        }
+
+        for src in &[
+            r#"{"clues": [[0]]}"#,
+            r#"{"clues": [[2000000000, 2000000000]]}"#,
+        ] {
+            assert!(problem_from_json(&Json::parse(src).unwrap()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_numberlink_variant_json() {
+        let src = r#"{"clues":[[1,null,1],[2,null,2]],"topology":{"rows":[1],"columns":[],"bridges":[[0,1]]},"rule":"fill"}"#;
+        let problem = variant_problem_from_json(&Json::parse(src).unwrap()).unwrap();
+        assert!(problem.topology.warp_row(1));
+        assert!(!problem.topology.warp_row(0));
+        assert!(problem.topology.bridge(P(0, 1)));
+        assert_eq!(problem.rule, Rule::FillAll);
+        let json = variant_problem_to_json(&problem);
+        assert_eq!(json.to_string(), src);
+        let read = variant_problem_from_json(&json).unwrap();
+        assert_eq!(read.topology, problem.topology);
+        assert_eq!(read.rule, problem.rule);
+
+        let src = r#"{"clues": [[1, 1]]}"#;
+        let problem = variant_problem_from_json(&Json::parse(src).unwrap()).unwrap();
+        assert!(problem.topology.is_plain());
+        assert_eq!(problem.rule, Rule::Classic);
+        assert_eq!(variant_problem_to_json(&problem), Json::parse(src).unwrap());
+
+        for src in &[
+            r#"{"clues": [[1, 1]], "topology": {"rows": [1]}}"#,
+            r#"{"clues": [[1, 1]], "topology": {"bridges": [[0, 0]]}}"#,
+            r#"{"clues": [[1, 1]], "rule": "other"}"#,
+        ] {
+            assert!(variant_problem_from_json(&Json::parse(src).unwrap()).is_err());
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Nurimisaki.
+//!
+//! - problem: `{"clues": rows}`, where each cell is `null`, a cape clue with a number
+//!   (1, 2, ...) or `"?"` for a cape clue without numbers
+//! - answer: `{"white": rows}`, where each cell is `true` for a white cell
+
+use super::grid::*;
+use super::Json;
+use io::ReadError;
+use Grid;
+
+pub fn problem_to_json(problem: &Grid<Option<i32>>) -> Json {
+    let clues = grid_to_json(problem, |&clue| match clue {
+        Some(n) if n > 0 => Json::from(n),
+        Some(_) => Json::from("?"),
+        None => Json::Null,
+    });
+    Json::object(vec![("clues", clues)])
+}
+
+pub fn problem_from_json(value: &Json) -> Result<Grid<Option<i32>>, ReadError> {
+    grid_from_json(member(value, "clues")?, None, |cell| match *cell {
+        Json::Null => Ok(None),
+        Json::String(ref s) if s == "?" => Ok(Some(0)),
+        _ => match int_from_json(cell)? {
+            n if n > 0 => Ok(Some(n)),
+            _ => Err(ReadError::InvalidValue),
+        },
+    })
+}
+
+pub fn answer_to_json(is_white: &Grid<bool>) -> Json {
+    Json::object(vec![("white", grid_to_json(is_white, |&w| Json::from(w)))])
+}
+
+pub fn answer_from_json(
+    problem: &Grid<Option<i32>>,
+    value: &Json,
+) -> Result<Grid<bool>, ReadError> {
+    sized_grid_from_json(
+        member(value, "white")?,
+        problem.height(),
+        problem.width(),
+        false,
+        |cell| cell.as_bool().ok_or(ReadError::InvalidValue),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use P;
+
+    #[test]
+    fn test_nurimisaki_json() {
+        let src = r#"{"clues": [[3, null], [null, "?"]]}"#;
+        let problem = problem_from_json(&Json::parse(src).unwrap()).unwrap();
+        assert_eq!(problem[P(0, 0)], Some(3));
+        assert_eq!(problem[P(1, 1)], Some(0));
+        assert_eq!(problem_to_json(&problem), Json::parse(src).unwrap());
+
+        let mut is_white = Grid::new(2, 2, true);
+        is_white[P(1, 0)] = false;
+        let read = answer_from_json(&problem, &answer_to_json(&is_white)).unwrap();
+        assert!((0..4).all(|i| read[i] == is_white[i]));
+
+        let src = r#"{"white": [[true, true]]}"#;
+        assert!(answer_from_json(&problem, &Json::parse(src).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,53 @@
+//! Simple Loop.
+//!
+//! - problem: `{"black": rows}`, where each cell is `true` for a black cell
+//! - answer: `{"lines": edges}` on the lattice of size `(2 * height - 1) x (2 * width - 1)`
+
+use super::grid::*;
+use super::Json;
+use io::ReadError;
+use Grid;
+
+pub fn problem_to_json(is_black: &Grid<bool>) -> Json {
+    Json::object(vec![("black", grid_to_json(is_black, |&b| Json::from(b)))])
+}
+
+pub fn problem_from_json(value: &Json) -> Result<Grid<bool>, ReadError> {
+    grid_from_json(member(value, "black")?, false, |cell| {
+        cell.as_bool().ok_or(ReadError::InvalidValue)
+    })
+}
+
+pub fn answer_to_json(answer: &Grid<bool>) -> Json {
+    Json::object(vec![("lines", edges_to_json(answer))])
+}
+
+pub fn answer_from_json(is_black: &Grid<bool>, value: &Json) -> Result<Grid<bool>, ReadError> {
+    edges_from_json(
+        member(value, "lines")?,
+        is_black.height() * 2 - 1,
+        is_black.width() * 2 - 1,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {LP, P};
+
+    #[test]
+    fn test_simple_loop_json() {
+        let src = r#"{"black": [[false, false], [false, true]]}"#;
+        let is_black = problem_from_json(&Json::parse(src).unwrap()).unwrap();
+        assert!(is_black[P(1, 1)]);
+        assert_eq!(problem_to_json(&is_black), Json::parse(src).unwrap());
+
+        let mut answer = Grid::new(3, 3, false);
+        answer[LP(0, 1)] = true;
+        answer[LP(1, 0)] = true;
+        let read = answer_from_json(&is_black, &answer_to_json(&answer)).unwrap();
+        assert!((0..9).all(|i| read[i] == answer[i]));
+
+        assert!(problem_from_json(&Json::parse(r#"{"black": [[1]]}"#).unwrap()).is_err());
+    }
+}
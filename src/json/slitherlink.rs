@@ -0,0 +1,76 @@
+//! Slitherlink.
+//!
+//! - problem: `{"clues": rows}`, where each cell is a number (0-3) or `null`
+//! - answer: `{"lines": edges}` on the lattice of size `(2 * height + 1) x (2 * width + 1)`
+
+use super::grid::*;
+use super::Json;
+use io::ReadError;
+use slitherlink::Clue;
+use Grid;
+
+pub fn problem_to_json(problem: &Grid<Clue>) -> Json {
+    let clues = grid_to_json(problem, |&Clue(c)| {
+        optional_to_json(if c >= 0 { Some(c) } else { None })
+    });
+    Json::object(vec![("clues", clues)])
+}
+
+pub fn problem_from_json(value: &Json) -> Result<Grid<Clue>, ReadError> {
+    grid_from_json(
+        member(value, "clues")?,
+        Clue(-1),
+        |cell| match optional_from_json(cell)? {
+            Some(c) if (0..4).contains(&c) => Ok(Clue(c)),
+            Some(_) => Err(ReadError::InvalidValue),
+            None => Ok(Clue(-1)),
+        },
+    )
+}
+
+pub fn answer_to_json(answer: &Grid<bool>) -> Json {
+    Json::object(vec![("lines", edges_to_json(answer))])
+}
+
+pub fn answer_from_json(problem: &Grid<Clue>, value: &Json) -> Result<Grid<bool>, ReadError> {
+    edges_from_json(
+        member(value, "lines")?,
+        problem.height() * 2 + 1,
+        problem.width() * 2 + 1,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {LP, P};
+
+    #[test]
+    fn test_slitherlink_json() {
+        let src = r#"{"clues": [[2, null], [null, 0]]}"#;
+        let problem = problem_from_json(&Json::parse(src).unwrap()).unwrap();
+        assert_eq!(problem[P(0, 0)], Clue(2));
+        assert_eq!(problem[P(0, 1)], Clue(-1));
+        assert_eq!(problem_to_json(&problem), Json::parse(src).unwrap());
+
+        let mut answer = Grid::new(5, 5, false);
+        answer[LP(0, 1)] = true;
+        answer[LP(3, 4)] = true;
+        let json = answer_to_json(&answer);
+        assert_eq!(json.to_string(), r#"{"lines":[[0,1],[3,4]]}"#);
+        let read = answer_from_json(&problem, &json).unwrap();
+        assert!((0..25).all(|i| read[i] == answer[i]));
+
+        for src in &[
+            r#"{"clues": [[4]]}"#,
+            r#"{"clues": [[1, 2], [3]]}"#,
+            r#"{"clues": []}"#,
+            r#"{}"#,
+        ] {
+            assert!(problem_from_json(&Json::parse(src).unwrap()).is_err());
+        }
+        for src in &[r#"{"lines": [[1, 1]]}"#, r#"{"lines": [[0, 5]]}"#] {
+            assert!(answer_from_json(&problem, &Json::parse(src).unwrap()).is_err());
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Tapa.
+//!
+//! - problem: `{"clues": rows}`, where each cell is `null` or the array of the numbers of
+//!   the clue in ascending order (e.g. `[1, 3]`; `[0]` for the clue 0)
+//! - answer: `{"black": rows}`, where each cell is `true` for a black cell
+
+use super::grid::*;
+use super::Json;
+use io::ReadError;
+use tapa::{clue_pattern_to_id, Clue, CLUE_TYPES, CLUE_VALUES, NO_CLUE};
+use Grid;
+
+pub fn problem_to_json(problem: &Grid<Clue>) -> Json {
+    let clues = grid_to_json(problem, |&Clue(id)| {
+        if id < 0 || id as usize >= CLUE_TYPES {
+            return Json::Null;
+        }
+        let numbers = CLUE_VALUES[id as usize]
+            .iter()
+            .cloned()
+            .filter(|&n| n != -1)
+            .collect::<Vec<_>>();
+        if numbers.is_empty() {
+            Json::from(vec![0])
+        } else {
+            Json::from(numbers)
+        }
+    });
+    Json::object(vec![("clues", clues)])
+}
+
+pub fn problem_from_json(value: &Json) -> Result<Grid<Clue>, ReadError> {
+    grid_from_json(member(value, "clues")?, NO_CLUE, |cell| {
+        if let Json::Null = *cell {
+            return Ok(NO_CLUE);
+        }
+        let numbers = array_from_json(cell)?
+            .iter()
+            .map(int_from_json)
+            .collect::<Result<Vec<_>, _>>()?;
+        clue_pattern_to_id(&numbers).ok_or(ReadError::InvalidValue)
+    })
+}
+
+pub fn answer_to_json(is_black: &Grid<bool>) -> Json {
+    Json::object(vec![("black", grid_to_json(is_black, |&b| Json::from(b)))])
+}
+
+pub fn answer_from_json(problem: &Grid<Clue>, value: &Json) -> Result<Grid<bool>, ReadError> {
+    sized_grid_from_json(
+        member(value, "black")?,
+        problem.height(),
+        problem.width(),
+        false,
+        |cell| cell.as_bool().ok_or(ReadError::InvalidValue),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use P;
+
+    #[test]
+    fn test_tapa_json() {
+        let src = r#"{"clues": [[[1, 1, 3], null], [[0], [8]]]}"#;
+        let problem = problem_from_json(&Json::parse(src).unwrap()).unwrap();
+        assert_eq!(problem[P(0, 0)], clue_pattern_to_id(&[3, 1, 1]).unwrap());
+        assert_eq!(problem[P(0, 1)], NO_CLUE);
+        assert_eq!(problem_to_json(&problem), Json::parse(src).unwrap());
+
+        let mut is_black = Grid::new(2, 2, false);
+        is_black[P(0, 1)] = true;
+        let json = answer_to_json(&is_black);
+        assert_eq!(
+            json.to_string(),
+            r#"{"black":[[false,true],[false,false]]}"#
+        );
+        let read = answer_from_json(&problem, &json).unwrap();
+        assert!((0..4).all(|i| read[i] == is_black[i]));
+
+        let src = r#"{"clues": [[[2, 5]]]}"#;
+        assert!(problem_from_json(&Json::parse(src).unwrap()).is_err());
+    }
+}
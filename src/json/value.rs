@@ -0,0 +1,364 @@
+use std::fmt;
+
+use io::ReadError;
+
+/// A JSON value. The members of an object keep their order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parses `src`, which must contain exactly one value (surrounded by optional whitespace).
+    pub fn parse(src: &str) -> Result<Json, ReadError> {
+        let mut parser = Parser {
+            src: src.as_bytes(),
+            pos: 0,
+            depth: 0,
+        };
+        let ret = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos == parser.src.len() {
+            Ok(ret)
+        } else {
+            Err(ReadError::InvalidFormat)
+        }
+    }
+    /// Creates an object from `(key, value)` pairs.
+    pub fn object(members: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            members
+                .into_iter()
+                .map(|(k, v)| (String::from(k), v))
+                .collect(),
+        )
+    }
+    /// Returns the member `key` of an object, or `None` if `self` is not an object or
+    /// does not have the member.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref members) => members.iter().find(|m| m.0 == key).map(|m| &m.1),
+            _ => None,
+        }
+    }
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Json::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+    /// Returns the value as an `i32` if it is an integer in the range of `i32`.
+    pub fn as_i32(&self) -> Option<i32> {
+        match *self {
+            Json::Number(n) if n.fract() == 0.0 && i32::MIN as f64 <= n && n <= i32::MAX as f64 => {
+                Some(n as i32)
+            }
+            _ => None,
+        }
+    }
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Json::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match *self {
+            Json::Array(ref a) => Some(a),
+            _ => None,
+        }
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Json {
+        Json::Bool(b)
+    }
+}
+impl From<i32> for Json {
+    fn from(n: i32) -> Json {
+        Json::Number(n as f64)
+    }
+}
+impl From<u64> for Json {
+    fn from(n: u64) -> Json {
+        Json::Number(n as f64)
+    }
+}
+impl From<usize> for Json {
+    fn from(n: usize) -> Json {
+        Json::Number(n as f64)
+    }
+}
+impl<'a> From<&'a str> for Json {
+    fn from(s: &'a str) -> Json {
+        Json::String(String::from(s))
+    }
+}
+impl From<String> for Json {
+    fn from(s: String) -> Json {
+        Json::String(s)
+    }
+}
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(v: Vec<T>) -> Json {
+        Json::Array(v.into_iter().map(Into::into).collect())
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// Writes the value in the compact form.
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) => {
+                if n.fract() == 0.0 && n.abs() < 1e15 {
+                    write!(f, "{}", n as i64)
+                } else if n.is_finite() {
+                    write!(f, "{}", n)
+                } else {
+                    write!(f, "null")
+                }
+            }
+            Json::String(ref s) => write_string(f, s),
+            Json::Array(ref a) => {
+                write!(f, "[")?;
+                for (i, v) in a.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(ref members) => {
+                write!(f, "{{")?;
+                for (i, (k, v)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, k)?;
+                    write!(f, ":{}", v)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Maximum nesting depth of arrays and objects, which keeps the recursion of `Parser` bounded.
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+    /// The number of values being parsed, including the current one.
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.src.len() && (self.src[self.pos] as char).is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.src.get(self.pos).cloned()
+    }
+    fn expect(&mut self, c: u8) -> Result<(), ReadError> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(ReadError::InvalidFormat)
+        }
+    }
+    fn keyword(&mut self, word: &str, value: Json) -> Result<Json, ReadError> {
+        if self.src[self.pos..].starts_with(word.as_bytes()) {
+            self.pos += word.len();
+            Ok(value)
+        } else {
+            Err(ReadError::InvalidFormat)
+        }
+    }
+    fn value(&mut self) -> Result<Json, ReadError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(ReadError::InvalidFormat);
+        }
+        self.depth += 1;
+        let ret = self.nested_value();
+        self.depth -= 1;
+        ret
+    }
+    fn nested_value(&mut self) -> Result<Json, ReadError> {
+        match self.peek() {
+            Some(b'n') => self.keyword("null", Json::Null),
+            Some(b't') => self.keyword("true", Json::Bool(true)),
+            Some(b'f') => self.keyword("false", Json::Bool(false)),
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b'[') => {
+                self.pos += 1;
+                let mut ret = vec![];
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(Json::Array(ret));
+                }
+                loop {
+                    ret.push(self.value()?);
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(Json::Array(ret));
+                        }
+                        _ => return Err(ReadError::InvalidFormat),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut ret = vec![];
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(Json::Object(ret));
+                }
+                loop {
+                    if self.peek() != Some(b'"') {
+                        return Err(ReadError::InvalidFormat);
+                    }
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    ret.push((key, self.value()?));
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            return Ok(Json::Object(ret));
+                        }
+                        _ => return Err(ReadError::InvalidFormat),
+                    }
+                }
+            }
+            Some(c) if c == b'-' || (c as char).is_ascii_digit() => self.number(),
+            _ => Err(ReadError::InvalidFormat),
+        }
+    }
+    fn number(&mut self) -> Result<Json, ReadError> {
+        let start = self.pos;
+        while self.pos < self.src.len() {
+            match self.src[self.pos] {
+                b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E' => self.pos += 1,
+                _ => break,
+            }
+        }
+        let token = String::from_utf8_lossy(&self.src[start..self.pos]);
+        token
+            .parse::<f64>()
+            .map(Json::Number)
+            .map_err(|_| ReadError::InvalidValue)
+    }
+    /// Parses a string starting at the current position (which must be `"`).
+    fn string(&mut self) -> Result<String, ReadError> {
+        self.pos += 1;
+        let mut ret: Vec<u8> = vec![];
+        loop {
+            let c = *self.src.get(self.pos).ok_or(ReadError::InvalidFormat)?;
+            self.pos += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let e = *self.src.get(self.pos).ok_or(ReadError::InvalidFormat)?;
+                    self.pos += 1;
+                    let unescaped = match e {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let hex = self
+                                .src
+                                .get(self.pos..(self.pos + 4))
+                                .ok_or(ReadError::InvalidFormat)?;
+                            self.pos += 4;
+                            let code = u32::from_str_radix(&String::from_utf8_lossy(hex), 16)
+                                .map_err(|_| ReadError::InvalidFormat)?;
+                            // surrogate pairs are not supported
+                            ::std::char::from_u32(code).ok_or(ReadError::InvalidValue)?
+                        }
+                        _ => return Err(ReadError::InvalidFormat),
+                    };
+                    let mut buf = [0u8; 4];
+                    ret.extend_from_slice(unescaped.encode_utf8(&mut buf).as_bytes());
+                }
+                c => ret.push(c),
+            }
+        }
+        String::from_utf8(ret).map_err(|_| ReadError::InvalidFormat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        let src = r#" {"genre": "nl", "size": [3, -4.5], "ok": true,
+            "none": null, "text": "a\"b\\c\ndé", "empty": {}, "list": []} "#;
+        let value = Json::parse(src).unwrap();
+        assert_eq!(value.get("genre").and_then(Json::as_str), Some("nl"));
+        assert_eq!(
+            value.get("size").and_then(Json::as_array).unwrap()[0].as_i32(),
+            Some(3)
+        );
+        assert_eq!(value.get("ok").and_then(Json::as_bool), Some(true));
+        assert_eq!(
+            value.get("text").and_then(Json::as_str),
+            Some("a\"b\\c\nd\u{e9}")
+        );
+        assert_eq!(value.get("missing"), None);
+
+        let written = value.to_string();
+        assert_eq!(
+            written,
+            r#"{"genre":"nl","size":[3,-4.5],"ok":true,"none":null,"text":"a\"b\\c\ndé","empty":{},"list":[]}"#
+        );
+        assert_eq!(Json::parse(&written).unwrap(), value);
+
+        for src in &["", "{", "[1,]", "{\"a\" 1}", "tru", "1 2", "\"abc"] {
+            assert!(Json::parse(src).is_err());
+        }
+
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(Json::parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(Json::parse(&nested(MAX_DEPTH + 1)).is_err());
+        assert!(Json::parse(&nested(1_000_000)).is_err());
+    }
+}
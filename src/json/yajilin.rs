@@ -0,0 +1,97 @@
+//! Yajilin.
+//!
+//! - problem: `{"clues": rows}`, where each cell is `null`, `"empty"` (a clue cell without
+//!   any number) or an arrow clue `{"up": n}` (also `"left"`, `"down"` and `"right"`)
+//! - answer: `{"lines": edges}` on the lattice of size `(2 * height - 1) x (2 * width - 1)`;
+//!   the black cells are not included
+
+use super::grid::*;
+use super::Json;
+use io::ReadError;
+use yajilin::Clue;
+use Grid;
+
+fn clue_to_json(clue: Clue) -> Json {
+    let (direction, n) = match clue {
+        Clue::NoClue => return Json::Null,
+        Clue::Empty => return Json::from("empty"),
+        Clue::Up(n) => ("up", n),
+        Clue::Left(n) => ("left", n),
+        Clue::Down(n) => ("down", n),
+        Clue::Right(n) => ("right", n),
+    };
+    Json::object(vec![(direction, Json::from(n))])
+}
+
+fn clue_from_json(value: &Json) -> Result<Clue, ReadError> {
+    match *value {
+        Json::Null => return Ok(Clue::NoClue),
+        Json::String(ref s) if s == "empty" => return Ok(Clue::Empty),
+        _ => (),
+    }
+    let (direction, n) = match *value {
+        Json::Object(ref members) if members.len() == 1 => (&members[0].0, &members[0].1),
+        _ => return Err(ReadError::InvalidFormat),
+    };
+    let n = int_from_json(n)?;
+    if n < 0 {
+        return Err(ReadError::InvalidValue);
+    }
+    match direction.as_str() {
+        "up" => Ok(Clue::Up(n)),
+        "left" => Ok(Clue::Left(n)),
+        "down" => Ok(Clue::Down(n)),
+        "right" => Ok(Clue::Right(n)),
+        _ => Err(ReadError::InvalidValue),
+    }
+}
+
+pub fn problem_to_json(problem: &Grid<Clue>) -> Json {
+    Json::object(vec![("clues", grid_to_json(problem, |&c| clue_to_json(c)))])
+}
+
+pub fn problem_from_json(value: &Json) -> Result<Grid<Clue>, ReadError> {
+    grid_from_json(member(value, "clues")?, Clue::NoClue, clue_from_json)
+}
+
+pub fn answer_to_json(answer: &Grid<bool>) -> Json {
+    Json::object(vec![("lines", edges_to_json(answer))])
+}
+
+pub fn answer_from_json(problem: &Grid<Clue>, value: &Json) -> Result<Grid<bool>, ReadError> {
+    edges_from_json(
+        member(value, "lines")?,
+        problem.height() * 2 - 1,
+        problem.width() * 2 - 1,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {LP, P};
+
+    #[test]
+    fn test_yajilin_json() {
+        let src = r#"{"clues": [[{"up": 0}, null, "empty"], [null, {"right": 2}, {"left": 1}]]}"#;
+        let problem = problem_from_json(&Json::parse(src).unwrap()).unwrap();
+        assert_eq!(problem[P(0, 0)], Clue::Up(0));
+        assert_eq!(problem[P(0, 2)], Clue::Empty);
+        assert_eq!(problem[P(1, 1)], Clue::Right(2));
+        assert_eq!(problem_to_json(&problem), Json::parse(src).unwrap());
+
+        let mut answer = Grid::new(3, 5, false);
+        answer[LP(2, 1)] = true;
+        let read = answer_from_json(&problem, &answer_to_json(&answer)).unwrap();
+        assert!((0..15).all(|i| read[i] == answer[i]));
+
+        for src in &[
+            r#"{"clues": [[{"up": -1}]]}"#,
+            r#"{"clues": [[{"north": 1}]]}"#,
+            r#"{"clues": [[{"up": 1, "down": 1}]]}"#,
+            r#"{"clues": [["full"]]}"#,
+        ] {
+            assert!(problem_from_json(&Json::parse(src).unwrap()).is_err());
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use super::*;
+use {Grid, P};
+
+use rand::prng::XorShiftRng;
+use rand::{Rng, SeedableRng};
+
+/// Returns the sum required for the group `g`.
+fn group_sum(problem: &Grid<Clue>, shape: &FieldShape, g: usize) -> i32 {
+    match shape.clue_locations[g] {
+        ClueLocation::Horizontal(h) => match problem[h] {
+            Clue::Clue { horizontal, .. } => horizontal,
+            Clue::NoClue => panic!("unexpected condition"),
+        },
+        ClueLocation::Vertical(v) => match problem[v] {
+            Clue::Clue { vertical, .. } => vertical,
+            Clue::NoClue => panic!("unexpected condition"),
+        },
+    }
+}
+
+fn is_valid_group(answer: &Grid<i32>, shape: &FieldShape, g: usize, sum: i32) -> bool {
+    let mut used = 0u32;
+    let mut total = 0;
+    let mut n_undecided = 0;
+    for c in shape.group_to_cells[g] {
+        let v = answer[c];
+        if v == UNDECIDED {
+            n_undecided += 1;
+            continue;
+        }
+        if used & (1 << v) != 0 {
+            return false;
+        }
+        used |= 1 << v;
+        total += v;
+    }
+    if n_undecided == 0 {
+        total == sum
+    } else {
+        total + n_undecided <= sum && sum <= total + n_undecided * MAX_VAL
+    }
+}
+
+fn enumerate_dfs(
+    problem: &Grid<Clue>,
+    shape: &FieldShape,
+    current: &mut Grid<i32>,
+    loc: usize,
+    answers: &mut Vec<Grid<i32>>,
+) {
+    let n_cells = (problem.height() * problem.width()) as usize;
+    if loc == n_cells {
+        answers.push(current.clone());
+        return;
+    }
+    if shape.has_clue[loc] {
+        enumerate_dfs(problem, shape, current, loc + 1, answers);
+        return;
+    }
+    let (g1, g2) = shape.cell_to_groups[loc];
+    for v in 1..(MAX_VAL + 1) {
+        current[loc] = v;
+        if is_valid_group(current, shape, g1, group_sum(problem, shape, g1))
+            && is_valid_group(current, shape, g2, group_sum(problem, shape, g2))
+        {
+            enumerate_dfs(problem, shape, current, loc + 1, answers);
+        }
+    }
+    current[loc] = UNDECIDED;
+}
+
+/// Enumerates all the answers of `problem`. Clue cells are 0 in the answers.
+pub fn enumerate_answers(problem: &Grid<Clue>) -> Vec<Grid<i32>> {
+    let height = problem.height();
+    let width = problem.width();
+    let mut has_clue = Grid::new(height, width, false);
+    let mut current = Grid::new(height, width, UNDECIDED);
+    for y in 0..height {
+        for x in 0..width {
+            if let Clue::Clue { .. } = problem[P(y, x)] {
+                has_clue[P(y, x)] = true;
+                current[P(y, x)] = 0;
+            }
+        }
+    }
+    let shape = FieldShape::new(&has_clue);
+    let mut answers = vec![];
+    enumerate_dfs(problem, &shape, &mut current, 0, &mut answers);
+    answers
+}
+
+/// Fills the non-clue cells with random numbers, avoiding duplicates in each group.
+fn random_answer<R: Rng>(has_clue: &Grid<bool>, rng: &mut R) -> Grid<i32> {
+    let height = has_clue.height();
+    let width = has_clue.width();
+    let mut answer = Grid::new(height, width, 0);
+    for y in 0..height {
+        for x in 0..width {
+            if has_clue[P(y, x)] {
+                continue;
+            }
+            loop {
+                let v = rng.gen_range(1, MAX_VAL + 1);
+                let left = (0..x)
+                    .rev()
+                    .take_while(|&x2| !has_clue[P(y, x2)])
+                    .any(|x2| answer[P(y, x2)] == v);
+                let up = (0..y)
+                    .rev()
+                    .take_while(|&y2| !has_clue[P(y2, x)])
+                    .any(|y2| answer[P(y2, x)] == v);
+                if !left && !up {
+                    answer[P(y, x)] = v;
+                    break;
+                }
+            }
+        }
+    }
+    answer
+}
+
+#[test]
+fn test_random_against_brute_force() {
+    let mut rng = XorShiftRng::from_seed([42u8; 16]);
+    let dic = Dictionary::default();
+
+    for &(height, width) in &[(3, 3), (3, 4), (4, 4), (4, 5)] {
+        for _ in 0..100 {
+            // the first row and column are clue cells
+            let mut has_clue = Grid::new(height, width, false);
+            for y in 0..height {
+                for x in 0..width {
+                    has_clue[P(y, x)] = y == 0 || x == 0 || rng.gen::<f64>() < 0.15;
+                }
+            }
+            let problem = answer_to_problem(&random_answer(&has_clue, &mut rng));
+            let answers = enumerate_answers(&problem);
+            assert!(!answers.is_empty());
+            for answer in &answers {
+                assert!(check_answer(&problem, answer).is_empty());
+            }
+            for _ in 0..10 {
+                let candidate = random_answer(&has_clue, &mut rng);
+                let violations = check_answer(&problem, &candidate);
+                assert_eq!(violations.is_empty(), answers.contains(&candidate));
+            }
+
+            let mut field = Field::new(&problem, &dic);
+            field.check_all();
+            assert!(!field.inconsistent());
+            for answer in &answers {
+                for y in 0..height {
+                    for x in 0..width {
+                        let pos = P(y, x);
+                        if has_clue[pos] {
+                            continue;
+                        }
+                        let actual = field.val(pos);
+                        assert!(actual == UNDECIDED || actual == answer[pos]);
+                        assert!(!field.solved() || actual == answer[pos]);
+                    }
+                }
+            }
+            assert!(!field.solved() || answers.len() == 1);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use super::super::{Grid, Violation, ViolationKind, P};
+use super::*;
+
+/// Checks whether `answer` is an answer of `problem`. Values in clue cells are ignored.
+/// The violations reported are:
+/// - `InvalidValue`: a non-clue cell whose value is not in `1..=9`.
+/// - `Duplicate`: a group containing the same value more than once.
+/// - `Sum`: a group whose values do not add up to its clue.
+pub fn check_answer(problem: &Grid<Clue>, answer: &Grid<i32>) -> Vec<Violation> {
+    let height = problem.height();
+    let width = problem.width();
+    let mut has_clue = Grid::new(height, width, false);
+    for y in 0..height {
+        for x in 0..width {
+            has_clue[P(y, x)] = match problem[P(y, x)] {
+                Clue::Clue { .. } => true,
+                Clue::NoClue => false,
+            };
+        }
+    }
+    let shape = FieldShape::new(&has_clue);
+    let mut ret = vec![];
+
+    for y in 0..height {
+        for x in 0..width {
+            let v = answer[P(y, x)];
+            if !has_clue[P(y, x)] && !(1..=MAX_VAL).contains(&v) {
+                ret.push(Violation::at_cells(
+                    ViolationKind::InvalidValue,
+                    vec![P(y, x)],
+                ));
+            }
+        }
+    }
+    for (grp, &loc) in shape.group_to_cells.iter().zip(&shape.clue_locations) {
+        let cells = grp.map(|i| answer.p(i)).collect::<Vec<_>>();
+        let sum = match (loc, problem[loc_cell(loc)]) {
+            (ClueLocation::Horizontal(_), Clue::Clue { horizontal, .. }) => horizontal,
+            (ClueLocation::Vertical(_), Clue::Clue { vertical, .. }) => vertical,
+            (_, Clue::NoClue) => panic!("unexpected condition"),
+        };
+        let mut used = 0u32;
+        let mut duplicate = false;
+        let mut total = 0;
+        for &p in &cells {
+            let v = answer[p];
+            if (1..=MAX_VAL).contains(&v) {
+                duplicate |= used & (1 << v) != 0;
+                used |= 1 << v;
+            }
+            total += v;
+        }
+        if duplicate {
+            ret.push(Violation::at_cells(ViolationKind::Duplicate, cells.clone()));
+        }
+        if total != sum {
+            ret.push(Violation::at_cells(ViolationKind::Sum, cells));
+        }
+    }
+    ret
+}
+
+fn loc_cell(loc: ClueLocation) -> usize {
+    match loc {
+        ClueLocation::Horizontal(c) | ClueLocation::Vertical(c) => c,
+    }
+}
@@ -1,4 +1,5 @@
 use super::{Cand, CAND_ALL, MAX_SUM, MAX_VAL};
+use std::sync::{Arc, OnceLock};
 
 // (length, sum, available vals) -> (imperative vals, allowed vals)
 pub struct Dictionary {
@@ -47,6 +48,13 @@ impl Dictionary {
         }
         Dictionary { data: data }
     }
+    /// Returns the process-wide dictionary, which is built by `default` on the first call.
+    pub fn shared() -> Arc<Dictionary> {
+        static SHARED: OnceLock<Arc<Dictionary>> = OnceLock::new();
+        SHARED
+            .get_or_init(|| Arc::new(Dictionary::default()))
+            .clone()
+    }
     pub fn limited() -> Dictionary {
         let mut data =
             vec![IMPOSSIBLE; ((MAX_VAL + 1) * (MAX_SUM + 1) * (1 << MAX_VAL as i32)) as usize];
@@ -1,5 +1,6 @@
-use super::super::{FiniteSearchQueue, Grid, P};
+use super::super::{FiniteSearchQueue, Grid, SharedRef, SolverStats, P};
 use super::*;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 #[derive(Clone, Copy)]
 struct FieldGrp {
@@ -7,8 +8,9 @@ struct FieldGrp {
     unmet_sum: i32,
     unused: Cand,
 }
+#[derive(Clone)]
 pub struct Field<'a> {
-    dic: &'a Dictionary,
+    dic: SharedRef<'a, Dictionary>,
     shape: FieldShape,
     grps: Vec<FieldGrp>,
     val: Vec<i32>,
@@ -19,9 +21,12 @@ pub struct Field<'a> {
     total_cands: u32,
     queue: FiniteSearchQueue,
     technique: FieldTechnique,
+    stats: SolverStats,
 }
 impl<'a> Field<'a> {
-    pub fn new(problem: &Grid<Clue>, dic: &'a Dictionary) -> Field<'a> {
+    /// Creates a field with a borrowed dictionary or a shared one (`Arc<Dictionary>`).
+    /// The latter gives `Field<'static>`, which can be sent across threads.
+    pub fn new<T: Into<SharedRef<'a, Dictionary>>>(problem: &Grid<Clue>, dic: T) -> Field<'a> {
         let n_cells = (problem.height() * problem.width()) as usize;
         let mut has_clue = Grid::new(problem.height(), problem.width(), false);
         let mut n_nonclue_cells = 0;
@@ -78,7 +83,7 @@ impl<'a> Field<'a> {
         }
 
         Field {
-            dic: dic,
+            dic: dic.into(),
             shape: shape,
             grps: grps,
             val: vec![UNDECIDED; n_cells],
@@ -89,6 +94,7 @@ impl<'a> Field<'a> {
             total_cands: n_nonclue_cells * 9,
             queue: FiniteSearchQueue::new(n_groups),
             technique: FieldTechnique::new(),
+            stats: SolverStats::new(),
         }
     }
     pub fn inconsistent(&self) -> bool {
@@ -118,6 +124,13 @@ impl<'a> Field<'a> {
     pub fn set_technique(&mut self, technique: FieldTechnique) {
         self.technique = technique;
     }
+    /// Returns the statistics of the techniques, counted in eliminated candidates.
+    pub fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+    pub fn set_stats_enabled(&mut self, enabled: bool) {
+        self.stats.set_enabled(enabled);
+    }
     pub fn decide(&mut self, pos: P, val: i32) {
         let loc = self.location(pos);
 
@@ -140,6 +153,66 @@ impl<'a> Field<'a> {
         }
         self.queue.finish();
     }
+    /// Enumerates the answers of the problem, up to `limit` answers.
+    /// Each answer is given as the value of each cell, which is 0 for clue cells.
+    pub fn search(&self, limit: usize) -> Vec<Grid<i32>> {
+        self.search_cancellable(limit, &AtomicBool::new(false))
+    }
+    /// Same as `search`, but gives up when `cancel` is set, returning the answers found so far.
+    pub fn search_cancellable(&self, limit: usize, cancel: &AtomicBool) -> Vec<Grid<i32>> {
+        let mut answers = vec![];
+        self.clone().search_dfs(limit, cancel, &mut answers);
+        answers
+    }
+    fn search_dfs(&mut self, limit: usize, cancel: &AtomicBool, answers: &mut Vec<Grid<i32>>) {
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        self.check_all();
+        if self.inconsistent {
+            return;
+        }
+        if self.solved {
+            let mut answer = Grid::new(self.height(), self.width(), 0);
+            for i in 0..self.val.len() {
+                if self.val[i] != UNDECIDED {
+                    answer[i] = self.val[i];
+                }
+            }
+            answers.push(answer);
+            return;
+        }
+        // branch on the cell with the fewest candidates
+        let mut branch = None;
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let loc = self.location(P(y, x));
+                if self.shape.has_clue[loc] || self.val[loc] != UNDECIDED {
+                    continue;
+                }
+                let n_cands = self.cand[loc].count_set_cands();
+                match branch {
+                    Some((_, n)) if n <= n_cands => (),
+                    _ => branch = Some((P(y, x), n_cands)),
+                }
+            }
+        }
+        let pos = match branch {
+            Some((pos, _)) => pos,
+            None => return,
+        };
+        let cand = self.cand[self.location(pos)];
+        for val in 1..(MAX_VAL + 1) {
+            if answers.len() >= limit {
+                return;
+            }
+            if cand.is_set(val) {
+                let mut trial = self.clone();
+                trial.decide(pos, val);
+                trial.search_dfs(limit, cancel, answers);
+            }
+        }
+    }
     fn location(&self, pos: P) -> usize {
         self.shape.has_clue.index_p(pos)
     }
@@ -216,6 +289,7 @@ impl<'a> Field<'a> {
 
         // unique position technique
         if self.technique.unique_position && !imperative.is_empty() {
+            let total_cands = self.total_cands;
             let mut uniq = Cand(0);
             let mut mult = Cand(0);
             for c in self.shape.group_to_cells[gid] {
@@ -233,20 +307,24 @@ impl<'a> Field<'a> {
                     }
                 }
             }
+            self.record_technique("unique_position", total_cands);
         }
 
         // candidate limitation
         if self.technique.dictionary {
+            let total_cands = self.total_cands;
             for c in self.shape.group_to_cells[gid] {
                 if self.val[c] == UNDECIDED {
                     self.limit_cand(c, allowed);
                 }
             }
+            self.record_technique("dictionary", total_cands);
         }
 
         // two-cells propagation (TODO: improve complexity)
         let grp = self.grps[gid];
         if self.technique.two_cells_propagation && grp.unmet_num == 2 {
+            let total_cands = self.total_cands;
             let mut c1 = None;
             let mut c2 = None;
             for c in self.shape.group_to_cells[gid] {
@@ -278,10 +356,12 @@ impl<'a> Field<'a> {
             }
             self.limit_cand(c1, c1_lim);
             self.limit_cand(c2, c2_lim);
+            self.record_technique("two_cells_propagation", total_cands);
         }
 
         // naked pair (TODO: improve complexity)
         if self.technique.naked_pair {
+            let total_cands = self.total_cands;
             for c in self.shape.group_to_cells[gid] {
                 if self.val[c] != -1 || self.cand[c].count_set_cands() != 2 {
                     continue;
@@ -300,10 +380,12 @@ impl<'a> Field<'a> {
                     }
                 }
             }
+            self.record_technique("naked_pair", total_cands);
         }
 
         // min-max method
         if self.technique.min_max {
+            let total_cands = self.total_cands;
             let grp = self.grps[gid];
             let mut min_sum = 0;
             let mut max_sum = 0;
@@ -338,14 +420,21 @@ impl<'a> Field<'a> {
             for i in 0..update_size {
                 self.limit_cand(update_list[i].0, update_list[i].1);
             }
+            self.record_technique("min_max", total_cands);
         }
     }
+    /// Records that the technique `name` was applied, given `total_cands` before applying it.
+    fn record_technique(&mut self, name: &'static str, total_cands: u32) {
+        let n_eliminated = (total_cands - self.total_cands) as i32;
+        self.stats.record(name, n_eliminated);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use common;
+    use std::thread;
 
     #[test]
     fn test_field() {
@@ -385,6 +474,40 @@ mod tests {
         assert_eq!(field.total_cands(), 4);
     }
 
+    #[test]
+    fn test_search() {
+        let dic = Dictionary::default();
+        let mut problem_base = Grid::new(3, 3, Clue::NoClue);
+        problem_base[P(0, 0)] = Clue::Clue {
+            horizontal: -1,
+            vertical: -1,
+        };
+        for i in 1..3 {
+            problem_base[P(0, i)] = Clue::Clue {
+                horizontal: -1,
+                vertical: 4,
+            };
+            problem_base[P(i, 0)] = Clue::Clue {
+                horizontal: 4,
+                vertical: -1,
+            };
+        }
+
+        let field = Field::new(&problem_base, &dic);
+        let answers = field.search(3);
+        assert_eq!(answers.len(), 2);
+        for answer in &answers {
+            assert_eq!(answer[P(0, 0)], 0);
+            assert_eq!(answer[P(1, 1)] + answer[P(1, 2)], 4);
+            assert_eq!(answer[P(1, 1)], answer[P(2, 2)]);
+        }
+        assert_eq!(field.search(1).len(), 1);
+        assert_eq!(field.undecided_cells(), 4);
+
+        let cancel = AtomicBool::new(true);
+        assert!(field.search_cancellable(3, &cancel).is_empty());
+    }
+
     #[test]
     fn test_inconsistent_field() {
         let dic = Dictionary::default();
@@ -415,4 +538,37 @@ mod tests {
 
         assert_eq!(field.inconsistent(), true);
     }
+
+    #[test]
+    fn test_shared_dictionary() {
+        let mut problem_base = Grid::new(2, 3, Clue::NoClue);
+        problem_base[P(0, 0)] = Clue::Clue {
+            horizontal: -1,
+            vertical: -1,
+        };
+        problem_base[P(0, 1)] = Clue::Clue {
+            horizontal: -1,
+            vertical: 2,
+        };
+        problem_base[P(0, 2)] = Clue::Clue {
+            horizontal: -1,
+            vertical: 1,
+        };
+        problem_base[P(1, 0)] = Clue::Clue {
+            horizontal: 3,
+            vertical: -1,
+        };
+
+        let mut field: Field<'static> = Field::new(&problem_base, Dictionary::shared());
+        let field = thread::spawn(move || {
+            field.check_all();
+            field
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(field.val(P(1, 1)), 2);
+        assert_eq!(field.val(P(1, 2)), 1);
+        assert!(field.solved());
+    }
 }
@@ -29,6 +29,7 @@ pub enum ClueLocation {
     Horizontal(usize),
     Vertical(usize),
 }
+#[derive(Clone)]
 pub struct FieldShape {
     pub has_clue: Grid<bool>,
     pub cell_to_groups: Grid<(usize, usize)>,
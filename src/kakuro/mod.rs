@@ -1,5 +1,8 @@
 use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
 
+#[cfg(test)]
+mod brute_force;
+mod checker;
 mod dictionary;
 mod evaluator;
 mod field;
@@ -148,6 +151,7 @@ impl FieldTechnique {
         }
     }
 }
+pub use self::checker::*;
 pub use self::dictionary::Dictionary;
 pub use self::evaluator::*;
 pub use self::field::*;
@@ -5,6 +5,8 @@ mod common;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod cli;
 #[cfg(not(target_arch = "wasm32"))]
+pub mod country_road;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod dosufuwa;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod doublechoco;
@@ -15,18 +17,26 @@ pub mod grid_loop;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod io;
 #[cfg(not(target_arch = "wasm32"))]
+pub mod json;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod kakuro;
 #[cfg(not(target_arch = "wasm32"))]
+pub mod masyu;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod numberlink;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod nurimisaki;
 #[cfg(not(target_arch = "wasm32"))]
+pub mod simple_loop;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod slitherlink;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod tapa;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod yajilin;
 
+#[cfg(target_arch = "wasm32")]
+mod country_road;
 #[cfg(target_arch = "wasm32")]
 mod dosufuwa;
 #[cfg(target_arch = "wasm32")]
@@ -38,10 +48,14 @@ mod grid_loop;
 #[cfg(target_arch = "wasm32")]
 mod kakuro;
 #[cfg(target_arch = "wasm32")]
+mod masyu;
+#[cfg(target_arch = "wasm32")]
 mod numberlink;
 #[cfg(target_arch = "wasm32")]
 mod nurimisaki;
 #[cfg(target_arch = "wasm32")]
+mod simple_loop;
+#[cfg(target_arch = "wasm32")]
 mod slitherlink;
 #[cfg(target_arch = "wasm32")]
 mod tapa;
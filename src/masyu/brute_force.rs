@@ -0,0 +1,102 @@
+use super::*;
+use grid_loop::brute_force::{assert_consistent_edges, enumerate_loops, has_edge};
+use {Grid, D, FOUR_NEIGHBOURS, LP, P};
+
+use rand::prng::XorShiftRng;
+use rand::{Rng, SeedableRng};
+
+/// Returns the directions in which the loop `answer` leaves the cell `pos`.
+fn loop_directions(answer: &Grid<bool>, pos: P) -> Vec<D> {
+    FOUR_NEIGHBOURS
+        .iter()
+        .filter(|&&d| has_edge(answer, LP::of_vertex(pos) + d))
+        .cloned()
+        .collect()
+}
+
+fn is_turn(answer: &Grid<bool>, pos: P) -> bool {
+    let dirs = loop_directions(answer, pos);
+    dirs.len() == 2 && dirs[0] != dirs[1] * -1
+}
+
+fn is_straight(answer: &Grid<bool>, pos: P) -> bool {
+    let dirs = loop_directions(answer, pos);
+    dirs.len() == 2 && dirs[0] == dirs[1] * -1
+}
+
+/// Returns whether the clue at `pos` is satisfied by `answer`.
+fn is_satisfied(answer: &Grid<bool>, pos: P, clue: Clue) -> bool {
+    match clue {
+        Clue::NoClue => true,
+        Clue::Black => {
+            is_turn(answer, pos)
+                && loop_directions(answer, pos)
+                    .iter()
+                    .all(|&d| is_straight(answer, pos + d))
+        }
+        Clue::White => {
+            is_straight(answer, pos)
+                && loop_directions(answer, pos)
+                    .iter()
+                    .any(|&d| is_turn(answer, pos + d))
+        }
+    }
+}
+
+/// Enumerates all the answers of `problem` among `loops`, which should be all the loops
+/// on the board.
+pub fn enumerate_answers(problem: &Grid<Clue>, loops: &[Grid<bool>]) -> Vec<Grid<bool>> {
+    loops
+        .iter()
+        .filter(|answer| {
+            (0..problem.height()).all(|y| {
+                (0..problem.width()).all(|x| is_satisfied(answer, P(y, x), problem[P(y, x)]))
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn test_random_against_brute_force() {
+    let mut rng = XorShiftRng::from_seed([42u8; 16]);
+
+    for &(height, width) in &[(2, 2), (2, 3), (3, 3), (3, 4), (4, 4), (4, 5), (5, 5)] {
+        let loops = enumerate_loops(&Grid::new(height, width, true));
+        for _ in 0..100 {
+            // pearls satisfied by a random loop, some of which are omitted
+            let base = &loops[rng.gen_range(0, loops.len())];
+            let mut problem = Grid::new(height, width, Clue::NoClue);
+            for y in 0..height {
+                for x in 0..width {
+                    if rng.gen::<f64>() < 0.5 {
+                        continue;
+                    }
+                    for &clue in &[Clue::Black, Clue::White] {
+                        if is_satisfied(base, P(y, x), clue) {
+                            problem[P(y, x)] = clue;
+                        }
+                    }
+                }
+            }
+            let answers = enumerate_answers(&problem, &loops);
+            for answer in &answers {
+                assert!(check_answer(&problem, answer).is_empty());
+            }
+            for _ in 0..10 {
+                let candidate = &loops[rng.gen_range(0, loops.len())];
+                let violations = check_answer(&problem, candidate);
+                assert_eq!(violations.is_empty(), answers.contains(candidate));
+            }
+
+            let mut field = Field::new(&problem);
+            field.solve();
+            assert!(!field.inconsistent());
+            assert_consistent_edges(|lp| field.get_edge(lp), field.fully_solved(), &answers);
+
+            field.trial_and_error(1);
+            assert!(!field.inconsistent());
+            assert_consistent_edges(|lp| field.get_edge(lp), field.fully_solved(), &answers);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use super::super::{Grid, Violation, ViolationKind, D, LP, P};
+use super::*;
+use grid_loop::check_loop;
+use FOUR_NEIGHBOURS;
+
+/// Checks whether `answer` is an answer of `problem`.
+/// `answer` has the size `(2 * height - 1) x (2 * width - 1)` and is indexed by lattice
+/// positions (`LP::of_vertex(pos)` for the cell `pos`); it is true exactly on the edges of the loop.
+/// The violations reported are `Black` and `White` (an unsatisfied pearl) and those of
+/// `grid_loop::check_loop`.
+pub fn check_answer(problem: &Grid<Clue>, answer: &Grid<bool>) -> Vec<Violation> {
+    let height = problem.height();
+    let width = problem.width();
+    assert_eq!(answer.height(), height * 2 - 1);
+    assert_eq!(answer.width(), width * 2 - 1);
+
+    let mut ret = vec![];
+    for y in 0..height {
+        for x in 0..width {
+            let pos = P(y, x);
+            let dirs = directions(answer, pos);
+            let satisfied = match problem[pos] {
+                Clue::NoClue => continue,
+                Clue::Black => is_turn(&dirs) && dirs.iter().all(|&d| is_straight(answer, pos + d)),
+                Clue::White => {
+                    is_straight_dirs(&dirs)
+                        && dirs.iter().any(|&d| is_turn(&directions(answer, pos + d)))
+                }
+            };
+            if !satisfied {
+                let kind = if problem[pos] == Clue::Black {
+                    ViolationKind::Black
+                } else {
+                    ViolationKind::White
+                };
+                let edges = dirs.iter().map(|&d| LP::of_vertex(pos) + d).collect();
+                ret.push(Violation::new(kind, vec![pos], edges));
+            }
+        }
+    }
+    ret.extend(check_loop(answer));
+    ret
+}
+
+/// Returns the directions in which the lines leave the cell `pos`.
+fn directions(answer: &Grid<bool>, pos: P) -> Vec<D> {
+    FOUR_NEIGHBOURS
+        .iter()
+        .filter(|&&d| {
+            let e = LP::of_vertex(pos) + d;
+            answer.is_valid_lp(e) && answer[e]
+        })
+        .cloned()
+        .collect()
+}
+
+fn is_turn(dirs: &[D]) -> bool {
+    dirs.len() == 2 && dirs[0] != dirs[1] * -1
+}
+
+fn is_straight_dirs(dirs: &[D]) -> bool {
+    dirs.len() == 2 && dirs[0] == dirs[1] * -1
+}
+
+fn is_straight(answer: &Grid<bool>, pos: P) -> bool {
+    is_straight_dirs(&directions(answer, pos))
+}
@@ -0,0 +1,512 @@
+use super::super::{Grid, SolverStats, D, LP, P};
+use super::*;
+use grid_loop::{Edge, GridLoop, GridLoopField, LineChain};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use FOUR_NEIGHBOURS;
+
+#[derive(Clone)]
+enum History {
+    Clue(P),
+    Checkpoint,
+}
+
+#[derive(Clone)]
+pub struct Field {
+    grid_loop: GridLoop,
+    clue: Grid<Clue>,
+    history: Vec<History>,
+    stats: SolverStats,
+}
+
+impl Field {
+    pub fn new(clue: &Grid<Clue>) -> Field {
+        Field {
+            grid_loop: GridLoop::new(clue.height() - 1, clue.width() - 1),
+            clue: clue.clone(),
+            history: Vec::new(),
+            stats: SolverStats::new(),
+        }
+    }
+    pub fn height(&self) -> i32 {
+        self.clue.height()
+    }
+    pub fn width(&self) -> i32 {
+        self.clue.width()
+    }
+    pub fn inconsistent(&self) -> bool {
+        self.grid_loop.inconsistent()
+    }
+    pub fn set_inconsistent(&mut self) {
+        self.grid_loop.set_inconsistent()
+    }
+    pub fn fully_solved(&self) -> bool {
+        self.grid_loop.fully_solved()
+    }
+    pub fn get_clue(&self, pos: P) -> Clue {
+        self.clue[pos]
+    }
+    pub fn get_clue_safe(&self, pos: P) -> Clue {
+        if self.clue.is_valid_p(pos) {
+            self.clue[pos]
+        } else {
+            Clue::NoClue
+        }
+    }
+    pub fn add_clue(&mut self, pos: P, clue: Clue) {
+        if self.clue[pos] != Clue::NoClue {
+            if self.clue[pos] != clue {
+                self.grid_loop.set_inconsistent();
+            }
+        } else {
+            self.clue[pos] = clue;
+            if !self.history.is_empty() {
+                self.history.push(History::Clue(pos));
+            }
+
+            let mut handle = GridLoop::get_handle(self);
+            GridLoop::check(&mut *handle, LP::of_vertex(pos));
+        }
+    }
+    pub fn get_edge(&self, pos: LP) -> Edge {
+        self.grid_loop.get_edge(pos)
+    }
+    pub fn get_edge_safe(&self, pos: LP) -> Edge {
+        self.grid_loop.get_edge_safe(pos)
+    }
+    pub fn line_chains(&self) -> Vec<LineChain> {
+        self.grid_loop.line_chains()
+    }
+    pub fn solved_loop(&self) -> Option<Vec<P>> {
+        self.grid_loop.solved_loop()
+    }
+    pub fn num_decided_edges(&self) -> i32 {
+        self.grid_loop.num_decided_edges()
+    }
+    /// Returns the statistics of the work done so far, counted in decided edges.
+    pub fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+    pub fn set_stats_enabled(&mut self, enabled: bool) {
+        self.stats.set_enabled(enabled);
+    }
+    /// Add a checkpoint.
+    /// Subsequent updates of the field (including the underlying `GridLoop`) are recorded
+    /// until `rollback` is called.
+    pub fn add_checkpoint(&mut self) {
+        self.grid_loop.add_checkpoint();
+        self.history.push(History::Checkpoint);
+    }
+    /// Rollback until the last checkpoint.
+    pub fn rollback(&mut self) {
+        self.grid_loop.rollback();
+        while let Some(entry) = self.history.pop() {
+            match entry {
+                History::Clue(pos) => self.clue[pos] = Clue::NoClue,
+                History::Checkpoint => break,
+            }
+        }
+    }
+
+    pub fn check_all_cell(&mut self) {
+        let height = self.height();
+        let width = self.width();
+        let mut handle = GridLoop::get_handle(self);
+        for y in 0..height {
+            for x in 0..width {
+                GridLoop::check(&mut *handle, LP::of_vertex(P(y, x)));
+            }
+        }
+    }
+    pub fn solve(&mut self) {
+        loop {
+            let current_decided_edges = self.grid_loop.num_decided_edges();
+            self.apply_technique("local", Field::check_all_cell);
+            self.apply_technique("inout_rule", GridLoop::apply_inout_rule);
+            self.apply_technique("connectability", GridLoop::check_connectability);
+            if self.inconsistent() || current_decided_edges == self.grid_loop.num_decided_edges() {
+                break;
+            }
+        }
+    }
+    pub fn trial_and_error(&mut self, depth: i32) {
+        self.trial_and_error_cancellable(depth, &AtomicBool::new(false))
+    }
+    /// Same as `trial_and_error`, but gives up when `cancel` is set, keeping the edges
+    /// decided so far.
+    pub fn trial_and_error_cancellable(&mut self, depth: i32, cancel: &AtomicBool) {
+        let height = self.height();
+        let width = self.width();
+
+        if depth == 0 {
+            self.solve();
+            return;
+        }
+        self.trial_and_error_cancellable(depth - 1, cancel);
+
+        loop {
+            let mut updated = false;
+            for y in 0..(height * 2 - 1) {
+                for x in 0..(width * 2 - 1) {
+                    if y % 2 == x % 2 {
+                        continue;
+                    }
+                    if cancel.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    let pos = LP(y, x);
+                    if self.get_edge(pos) != Edge::Undecided || !self.grid_loop.is_root(pos) {
+                        continue;
+                    }
+
+                    if self.is_inconsistent_with_edge(pos, Edge::Line, depth - 1) {
+                        updated = true;
+                        GridLoop::decide_edge(self, pos, Edge::Blank);
+                        self.trial_and_error_cancellable(depth - 1, cancel);
+                    }
+                    if self.get_edge(pos) == Edge::Undecided
+                        && self.is_inconsistent_with_edge(pos, Edge::Blank, depth - 1)
+                    {
+                        updated = true;
+                        GridLoop::decide_edge(self, pos, Edge::Line);
+                        self.trial_and_error_cancellable(depth - 1, cancel);
+                    }
+                    if self.inconsistent() || self.fully_solved() {
+                        return;
+                    }
+                }
+            }
+            if !updated {
+                break;
+            }
+        }
+    }
+    /// Enumerates the answers of the problem, up to `limit` answers.
+    /// Each answer is given as the cells on the loop in order.
+    /// The field is left unchanged.
+    pub fn search(&mut self, limit: usize) -> Vec<Vec<P>> {
+        self.search_cancellable(limit, &AtomicBool::new(false))
+    }
+    /// Same as `search`, but gives up when `cancel` is set, returning the answers found so far.
+    pub fn search_cancellable(&mut self, limit: usize, cancel: &AtomicBool) -> Vec<Vec<P>> {
+        let mut answers = vec![];
+        self.add_checkpoint();
+        self.search_dfs(limit, cancel, &mut answers);
+        self.rollback();
+        answers
+    }
+    /// Checks whether the problem has exactly one answer.
+    pub fn has_unique_answer(&mut self) -> bool {
+        self.search(2).len() == 1
+    }
+    fn search_dfs(&mut self, limit: usize, cancel: &AtomicBool, answers: &mut Vec<Vec<P>>) {
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        self.solve();
+        if self.inconsistent() {
+            return;
+        }
+        if self.fully_solved() {
+            if let Some(answer) = self.solved_loop() {
+                answers.push(answer);
+            }
+            return;
+        }
+        let edge = match self.find_branching_edge() {
+            Some(edge) => edge,
+            None => return,
+        };
+        for &status in &[Edge::Line, Edge::Blank] {
+            if answers.len() >= limit {
+                return;
+            }
+            self.add_checkpoint();
+            GridLoop::decide_edge(self, edge, status);
+            self.search_dfs(limit, cancel, answers);
+            self.rollback();
+        }
+    }
+    /// Finds an undecided edge to branch on, preferring one extending an existing line.
+    fn find_branching_edge(&self) -> Option<LP> {
+        let height = self.height();
+        let width = self.width();
+        let mut ret = None;
+        for y in 0..height {
+            for x in 0..width {
+                let vertex = LP::of_vertex(P(y, x));
+                let (n_line, _) = self.grid_loop.neighbor_summary(vertex);
+                for &d in &FOUR_NEIGHBOURS {
+                    if self.get_edge_safe(vertex + d) == Edge::Undecided {
+                        if n_line == 1 {
+                            return Some(vertex + d);
+                        }
+                        if ret.is_none() {
+                            ret = Some(vertex + d);
+                        }
+                    }
+                }
+            }
+        }
+        ret
+    }
+    /// Checks whether deciding edge `pos` to `status` leads to an inconsistency
+    /// within trial-and-error of depth `depth`. The field is left unchanged.
+    fn is_inconsistent_with_edge(&mut self, pos: LP, status: Edge, depth: i32) -> bool {
+        self.add_checkpoint();
+        GridLoop::decide_edge(self, pos, status);
+        self.trial_and_error(depth);
+        let ret = self.inconsistent();
+        self.rollback();
+        self.stats.record_trial(ret);
+        ret
+    }
+    /// Applies `technique` and records the number of edges decided by it.
+    fn apply_technique<F: FnOnce(&mut Field)>(&mut self, name: &'static str, technique: F) {
+        let decided_edges = self.num_decided_edges();
+        technique(self);
+        let n_decided = self.num_decided_edges() - decided_edges;
+        self.stats.record(name, n_decided);
+    }
+
+    fn inspect_black(&mut self, pos: LP) {
+        let cell = pos.as_vertex();
+        for &d in &FOUR_NEIGHBOURS {
+            let edge1 = self.get_edge_safe(pos + d);
+            let edge2 = self.get_edge_safe(pos + d * 3);
+
+            if edge1 == Edge::Line {
+                // the loop goes straight at the next cell and turns at the pearl
+                GridLoop::decide_edge(self, pos + d * 3, Edge::Line);
+                GridLoop::decide_edge(self, pos - d, Edge::Blank);
+            }
+            if edge1 == Edge::Blank
+                || edge2 == Edge::Blank
+                || self.get_clue_safe(cell + d) == Clue::Black
+            {
+                // the loop can't leave the pearl toward `d`, so it must leave toward `-d`
+                GridLoop::decide_edge(self, pos + d, Edge::Blank);
+                GridLoop::decide_edge(self, pos - d, Edge::Line);
+                GridLoop::decide_edge(self, pos - d * 3, Edge::Line);
+            }
+        }
+    }
+    fn inspect_white(&mut self, pos: LP) {
+        let cell = pos.as_vertex();
+        for &d in &[D(0, 1), D(1, 0)] {
+            let dr = d.rotate_clockwise();
+            let edge_p1 = self.get_edge_safe(pos + d);
+            let edge_m1 = self.get_edge_safe(pos - d);
+            let edge_p3 = self.get_edge_safe(pos + d * 3);
+            let edge_m3 = self.get_edge_safe(pos - d * 3);
+
+            if edge_p1 == Edge::Blank
+                || edge_m1 == Edge::Blank
+                || (edge_p3 == Edge::Line && edge_m3 == Edge::Line)
+                || (self.get_clue_safe(cell + d) == Clue::White
+                    && self.get_clue_safe(cell - d) == Clue::White)
+            {
+                // the loop can't go straight along `d`
+                GridLoop::decide_edge(self, pos + d, Edge::Blank);
+                GridLoop::decide_edge(self, pos - d, Edge::Blank);
+                GridLoop::decide_edge(self, pos + dr, Edge::Line);
+                GridLoop::decide_edge(self, pos - dr, Edge::Line);
+            } else if edge_p1 == Edge::Line || edge_m1 == Edge::Line {
+                GridLoop::decide_edge(self, pos + d, Edge::Line);
+                GridLoop::decide_edge(self, pos - d, Edge::Line);
+
+                // the loop must turn at either of the neighboring cells
+                if edge_p3 == Edge::Line {
+                    GridLoop::decide_edge(self, pos - d * 3, Edge::Blank);
+                }
+                if edge_m3 == Edge::Line {
+                    GridLoop::decide_edge(self, pos + d * 3, Edge::Blank);
+                }
+            }
+        }
+    }
+}
+
+impl GridLoopField for Field {
+    fn grid_loop(&mut self) -> &mut GridLoop {
+        &mut self.grid_loop
+    }
+    fn check_neighborhood(&mut self, pos: LP) {
+        if pos.0 % 2 == 0 {
+            GridLoop::check(self, pos + D(0, -1));
+            GridLoop::check(self, pos + D(0, 1));
+            GridLoop::check(self, pos + D(0, -3));
+            GridLoop::check(self, pos + D(0, 3));
+        } else {
+            GridLoop::check(self, pos + D(-1, 0));
+            GridLoop::check(self, pos + D(1, 0));
+            GridLoop::check(self, pos + D(-3, 0));
+            GridLoop::check(self, pos + D(3, 0));
+        }
+    }
+    fn inspect(&mut self, pos: LP) {
+        if !pos.is_vertex() {
+            return;
+        }
+        match self.clue[pos.as_vertex()] {
+            Clue::NoClue => (),
+            Clue::Black => self.inspect_black(pos),
+            Clue::White => self.inspect_white(pos),
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let height = self.height();
+        let width = self.width();
+        for y in 0..(2 * height - 1) {
+            for x in 0..(2 * width - 1) {
+                match (y % 2, x % 2) {
+                    (0, 0) => match self.clue[P(y / 2, x / 2)] {
+                        Clue::NoClue => write!(f, "+")?,
+                        Clue::Black => write!(f, "B")?,
+                        Clue::White => write!(f, "W")?,
+                    },
+                    (0, 1) => match self.get_edge(LP(y, x)) {
+                        Edge::Line => write!(f, "---")?,
+                        Edge::Blank => write!(f, " x ")?,
+                        Edge::Undecided => write!(f, "   ")?,
+                    },
+                    (1, 0) => match self.get_edge(LP(y, x)) {
+                        Edge::Line => write!(f, "|")?,
+                        Edge::Blank => write!(f, "x")?,
+                        Edge::Undecided => write!(f, " ")?,
+                    },
+                    (1, 1) => write!(f, "   ")?,
+                    _ => unreachable!(),
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problem_from_str(rows: &[&str]) -> Grid<Clue> {
+        let height = rows.len() as i32;
+        let width = rows[0].len() as i32;
+        let mut ret = Grid::new(height, width, Clue::NoClue);
+        for y in 0..height {
+            let mut row_iter = rows[y as usize].chars();
+            for x in 0..width {
+                ret[P(y, x)] = match row_iter.next().unwrap() {
+                    'B' => Clue::Black,
+                    'W' => Clue::White,
+                    _ => Clue::NoClue,
+                };
+            }
+        }
+        ret
+    }
+
+    #[test]
+    fn test_black_pearl() {
+        {
+            // black pearl at the corner
+            let problem = problem_from_str(&["B....", ".....", ".....", ".....", "....."]);
+            let mut field = Field::new(&problem);
+            field.check_all_cell();
+
+            assert!(!field.inconsistent());
+            assert_eq!(field.get_edge(LP(0, 1)), Edge::Line);
+            assert_eq!(field.get_edge(LP(0, 3)), Edge::Line);
+            assert_eq!(field.get_edge(LP(1, 0)), Edge::Line);
+            assert_eq!(field.get_edge(LP(3, 0)), Edge::Line);
+        }
+        {
+            // black pearl near the border
+            let problem = problem_from_str(&[".....", "..B..", ".....", ".....", "....."]);
+            let mut field = Field::new(&problem);
+            field.check_all_cell();
+
+            assert!(!field.inconsistent());
+            assert_eq!(field.get_edge(LP(1, 4)), Edge::Blank);
+            assert_eq!(field.get_edge(LP(3, 4)), Edge::Line);
+            assert_eq!(field.get_edge(LP(5, 4)), Edge::Line);
+        }
+        {
+            // adjacent black pearls
+            let problem =
+                problem_from_str(&[".......", ".......", "..BB...", ".......", "......."]);
+            let mut field = Field::new(&problem);
+            field.check_all_cell();
+
+            assert!(!field.inconsistent());
+            assert_eq!(field.get_edge(LP(4, 5)), Edge::Blank);
+            assert_eq!(field.get_edge(LP(4, 1)), Edge::Line);
+            assert_eq!(field.get_edge(LP(4, 3)), Edge::Line);
+            assert_eq!(field.get_edge(LP(4, 7)), Edge::Line);
+            assert_eq!(field.get_edge(LP(4, 9)), Edge::Line);
+        }
+    }
+
+    #[test]
+    fn test_white_pearl() {
+        {
+            // white pearl on the border
+            let problem = problem_from_str(&["..W..", ".....", ".....", ".....", "....."]);
+            let mut field = Field::new(&problem);
+            field.check_all_cell();
+
+            assert!(!field.inconsistent());
+            assert_eq!(field.get_edge(LP(0, 3)), Edge::Line);
+            assert_eq!(field.get_edge(LP(0, 5)), Edge::Line);
+            assert_eq!(field.get_edge(LP(1, 4)), Edge::Blank);
+        }
+        {
+            // three white pearls in a row
+            let problem = problem_from_str(&[".....", ".....", ".WWW.", ".....", "....."]);
+            let mut field = Field::new(&problem);
+            field.check_all_cell();
+
+            assert!(!field.inconsistent());
+            for x in 1..4 {
+                assert_eq!(field.get_edge(LP(3, x * 2)), Edge::Line);
+                assert_eq!(field.get_edge(LP(5, x * 2)), Edge::Line);
+            }
+        }
+    }
+
+    #[test]
+    fn test_problem() {
+        let problem =
+            problem_from_str(&["......", "...W.W", "W..W..", ".B.BW.", "W.W.W.", "......"]);
+        {
+            let mut field = Field::new(&problem);
+            field.solve();
+
+            assert!(!field.inconsistent());
+            assert!(!field.fully_solved());
+        }
+        {
+            let mut field = Field::new(&problem);
+            field.trial_and_error(1);
+
+            assert!(!field.inconsistent());
+            assert!(field.fully_solved());
+            assert_eq!(field.get_edge(LP(6, 3)), Edge::Line);
+            assert_eq!(field.get_edge(LP(8, 5)), Edge::Line);
+            assert_eq!(field.solved_loop().map(|l| l.len()), Some(27));
+        }
+        {
+            let mut field = Field::new(&problem);
+            let answers = field.search(2);
+            assert_eq!(answers.len(), 1);
+            assert_eq!(answers[0].len(), 27);
+            assert!(!field.fully_solved());
+
+            let cancel = AtomicBool::new(true);
+            assert!(field.search_cancellable(2, &cancel).is_empty());
+        }
+    }
+}
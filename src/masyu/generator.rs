@@ -0,0 +1,149 @@
+use super::super::{Grid, D, LP, P};
+use super::*;
+use grid_loop::Edge;
+
+use rand::Rng;
+
+/// Generates a Masyu problem with pearls placed only at the cells `has_clue` specifies.
+/// Pearls and their colors are determined by simulated annealing so that the problem
+/// is solved by `Field::solve` only. Not every cell in `has_clue` necessarily has a pearl.
+pub fn generate<R: Rng>(has_clue: &Grid<bool>, rng: &mut R) -> Option<Grid<Clue>> {
+    let height = has_clue.height();
+    let width = has_clue.width();
+    let max_step = height * width * 10;
+
+    let mut current_problem = Grid::new(height, width, Clue::NoClue);
+    let mut prev_score = 0;
+    let mut temperature = 5.0f64;
+    let mut n_clues = 0;
+
+    let mut last_field = Field::new(&current_problem);
+
+    for _ in 0..max_step {
+        let mut update_cand = vec![];
+        for y in 0..height {
+            for x in 0..width {
+                let pos = P(y, x);
+                let prev_clue = current_problem[pos];
+                if !has_clue[pos]
+                    || (prev_clue != Clue::NoClue && !has_undecided_nearby(&last_field, pos))
+                {
+                    continue;
+                }
+                for &c in &[Clue::NoClue, Clue::Black, Clue::White] {
+                    if c != prev_clue {
+                        update_cand.push((pos, c));
+                    }
+                }
+            }
+        }
+
+        rng.shuffle(&mut update_cand);
+
+        let mut updated = false;
+        for &(pos, c) in &update_cand {
+            let prev_clue = current_problem[pos];
+            current_problem[pos] = c;
+
+            let mut field = if prev_clue == Clue::NoClue {
+                let mut field = last_field.clone();
+                field.add_clue(pos, c);
+                field
+            } else {
+                // a pearl can't be removed from a field, so the field is rebuilt
+                Field::new(&current_problem)
+            };
+            field.solve();
+
+            let new_n_clues = n_clues - if prev_clue == Clue::NoClue { 0 } else { 1 }
+                + if c == Clue::NoClue { 0 } else { 1 };
+            let current_score = field.num_decided_edges() - new_n_clues * 4;
+
+            if field.inconsistent()
+                || (prev_score >= current_score
+                    && rng.gen::<f64>()
+                        >= ((current_score - prev_score) as f64 / temperature).exp())
+            {
+                current_problem[pos] = prev_clue;
+                continue;
+            }
+
+            updated = true;
+            prev_score = current_score;
+            n_clues = new_n_clues;
+
+            if field.fully_solved() {
+                return Some(current_problem);
+            }
+
+            last_field = field;
+            break;
+        }
+
+        if !updated {
+            break;
+        }
+
+        temperature *= 0.995f64;
+    }
+
+    None
+}
+
+fn has_undecided_nearby(field: &Field, pos: P) -> bool {
+    let lp = LP::of_vertex(pos);
+
+    let neighbor_size: i32 = 7;
+    for dy in -neighbor_size..(neighbor_size + 1) {
+        let dx_max = neighbor_size - dy.abs();
+        for dx in -dx_max..(dx_max + 1) {
+            if (dy & 1) != (dx & 1) && field.get_edge_safe(lp + D(dy, dx)) == Edge::Undecided {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+
+    #[test]
+    fn test_generator() {
+        let mut rng = rand::thread_rng();
+        let placement = ::common::vec_to_grid(&vec![
+            vec![false, false, true, false, false, false],
+            vec![false, false, false, false, true, false],
+            vec![true, false, false, true, false, false],
+            vec![false, false, true, false, false, true],
+            vec![false, true, false, false, false, false],
+            vec![false, false, false, true, false, false],
+        ]);
+
+        let mut succeeded = false;
+        for _ in 0..10 {
+            if let Some(problem) = generate(&placement, &mut rng) {
+                succeeded = true;
+
+                for y in 0..placement.height() {
+                    for x in 0..placement.width() {
+                        let pos = P(y, x);
+                        if !placement[pos] {
+                            assert_eq!(problem[pos], Clue::NoClue);
+                        }
+                    }
+                }
+
+                let mut field = Field::new(&problem);
+                field.solve();
+                assert!(!field.inconsistent());
+                assert!(field.fully_solved());
+
+                break;
+            }
+        }
+        assert!(succeeded);
+    }
+}
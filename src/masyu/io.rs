@@ -0,0 +1,20 @@
+use std::io::BufRead;
+
+use super::*;
+use common::Grid;
+use io::{read_grid, ReadError};
+
+/// Reads a Masyu problem.
+/// Each cell is represented by `B` (black pearl), `W` (white pearl) or `.` (no pearl).
+pub fn read_penciloid_problem<T: BufRead>(reader: &mut T) -> Result<Grid<Clue>, ReadError> {
+    read_grid(
+        reader,
+        |token: &str| match token {
+            "." => Ok(Clue::NoClue),
+            "B" | "b" => Ok(Clue::Black),
+            "W" | "w" => Ok(Clue::White),
+            _ => Err(ReadError::InvalidValue),
+        },
+        Clue::NoClue,
+    )
+}
@@ -0,0 +1,18 @@
+#[cfg(test)]
+mod brute_force;
+mod checker;
+mod field;
+mod generator;
+mod io;
+
+pub use self::checker::*;
+pub use self::field::*;
+pub use self::generator::*;
+pub use self::io::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Clue {
+    NoClue,
+    Black,
+    White,
+}
@@ -0,0 +1,707 @@
+use super::*;
+use {Grid, Symmetry, ViolationKind, D, FOUR_NEIGHBOURS, LP, P};
+
+use rand::prng::XorShiftRng;
+use rand::{Rng, SeedableRng};
+
+fn enumerate_dfs(
+    problem: &Grid<Clue>,
+    ends: &[(P, P)],
+    idx: usize,
+    pos: P,
+    used: &mut Grid<bool>,
+    lines: &mut Grid<bool>,
+    answers: &mut Vec<Grid<bool>>,
+) {
+    if idx == ends.len() {
+        answers.push(lines.clone());
+        return;
+    }
+    let goal = ends[idx].1;
+    for &d in &FOUR_NEIGHBOURS {
+        let next = pos + d;
+        if !problem.is_valid_p(next) || used[next] {
+            continue;
+        }
+        if next != goal && problem[next] != NO_CLUE {
+            continue;
+        }
+        let LP(ly, lx) = LP::of_vertex(pos) + d;
+        lines[P(ly, lx)] = true;
+        used[next] = true;
+        if next == goal {
+            if idx + 1 == ends.len() {
+                answers.push(lines.clone());
+            } else {
+                let start = ends[idx + 1].0;
+                enumerate_dfs(problem, ends, idx + 1, start, used, lines, answers);
+            }
+        } else {
+            enumerate_dfs(problem, ends, idx, next, used, lines, answers);
+        }
+        used[next] = false;
+        lines[P(ly, lx)] = false;
+    }
+}
+
+/// Enumerates all the answers of `problem`, where some cells may be left unused.
+/// Each answer is given as a grid indexed by lattice positions which is true exactly on the
+/// lines.
+pub fn enumerate_answers(problem: &Grid<Clue>) -> Vec<Grid<bool>> {
+    let height = problem.height();
+    let width = problem.width();
+    let mut ends: Vec<(P, P)> = vec![];
+    let mut first_end = vec![];
+    for y in 0..height {
+        for x in 0..width {
+            let Clue(c) = problem[P(y, x)];
+            if c <= 0 {
+                continue;
+            }
+            let c = c as usize;
+            if first_end.len() <= c {
+                first_end.resize(c + 1, None);
+            }
+            match first_end[c] {
+                None => first_end[c] = Some(P(y, x)),
+                Some(p) => ends.push((p, P(y, x))),
+            }
+        }
+    }
+    let mut answers = vec![];
+    let mut used = Grid::new(height, width, false);
+    for y in 0..height {
+        for x in 0..width {
+            used[P(y, x)] = problem[P(y, x)] == UNUSED;
+        }
+    }
+    for &(a, _) in &ends {
+        used[a] = true;
+    }
+    let mut lines = Grid::new(height * 2 - 1, width * 2 - 1, false);
+    if ends.is_empty() {
+        answers.push(lines);
+    } else {
+        let start = ends[0].0;
+        enumerate_dfs(
+            problem,
+            &ends,
+            0,
+            start,
+            &mut used,
+            &mut lines,
+            &mut answers,
+        );
+    }
+    answers
+}
+
+struct VariantEnumerator<'a> {
+    problem: &'a Grid<Clue>,
+    topology: &'a Topology,
+    ends: Vec<(P, P)>,
+    /// bit 1: passed horizontally, bit 2: passed vertically
+    used: Grid<u8>,
+    lines: Grid<bool>,
+    answers: Vec<Grid<bool>>,
+}
+
+impl<'a> VariantEnumerator<'a> {
+    fn dfs(&mut self, idx: usize, pos: P, last_d: D) {
+        let goal = self.ends[idx].1;
+        for &d in &FOUR_NEIGHBOURS {
+            // lines go straight through bridges
+            if self.topology.bridge(pos) && d != last_d {
+                continue;
+            }
+            let next = match self.topology.neighbour(pos, d) {
+                Some(next) => next,
+                None => continue,
+            };
+            let edge = self
+                .topology
+                .canonical_edge(LP::of_vertex(pos) + d)
+                .unwrap();
+            let mask = if !self.topology.bridge(next) {
+                3
+            } else if d.0 == 0 {
+                1
+            } else {
+                2
+            };
+            if self.lines[edge] || (self.used[next] & mask) != 0 {
+                continue;
+            }
+            if next != goal && self.problem[next] != NO_CLUE {
+                continue;
+            }
+            self.lines[edge] = true;
+            self.used[next] |= mask;
+            if next == goal {
+                if idx + 1 == self.ends.len() {
+                    let height = self.problem.height();
+                    let width = self.problem.width();
+                    let bridges_passed = (0..height).all(|y| {
+                        (0..width)
+                            .all(|x| !self.topology.bridge(P(y, x)) || self.used[P(y, x)] == 3)
+                    });
+                    if bridges_passed {
+                        self.answers.push(self.lines.clone());
+                    }
+                } else {
+                    let start = self.ends[idx + 1].0;
+                    self.dfs(idx + 1, start, D(0, 0));
+                }
+            } else {
+                self.dfs(idx, next, d);
+            }
+            self.used[next] &= !mask;
+            self.lines[edge] = false;
+        }
+    }
+}
+
+/// Enumerates all the answers of `problem` on a board with bridges and warps, where some cells
+/// may be left unused. Each answer is given as a grid indexed by lattice positions (including
+/// the warps beyond the bottom or right border) which is true exactly on the lines.
+pub fn enumerate_variant_answers(problem: &Grid<Clue>, topology: &Topology) -> Vec<Grid<bool>> {
+    let height = problem.height();
+    let width = problem.width();
+    let mut ends: Vec<(P, P)> = vec![];
+    let mut first_end = vec![];
+    for y in 0..height {
+        for x in 0..width {
+            let Clue(c) = problem[P(y, x)];
+            if c <= 0 {
+                continue;
+            }
+            let c = c as usize;
+            if first_end.len() <= c {
+                first_end.resize(c + 1, None);
+            }
+            match first_end[c] {
+                None => first_end[c] = Some(P(y, x)),
+                Some(p) => ends.push((p, P(y, x))),
+            }
+        }
+    }
+    let mut used = Grid::new(height, width, 0u8);
+    for y in 0..height {
+        for x in 0..width {
+            if problem[P(y, x)] == UNUSED {
+                used[P(y, x)] = 3;
+            }
+        }
+    }
+    for &(a, _) in &ends {
+        used[a] = 3;
+    }
+    let mut enumerator = VariantEnumerator {
+        problem,
+        topology,
+        ends,
+        used,
+        lines: Grid::new(height * 2, width * 2, false),
+        answers: vec![],
+    };
+    if enumerator.ends.is_empty() {
+        let has_bridge = (0..height).any(|y| (0..width).any(|x| topology.bridge(P(y, x))));
+        if !has_bridge {
+            enumerator.answers.push(enumerator.lines.clone());
+        }
+    } else {
+        let start = enumerator.ends[0].0;
+        enumerator.dfs(0, start, D(0, 0));
+    }
+    enumerator.answers
+}
+
+/// Returns whether every cell which is not `UNUSED` is passed by a line in `answer`.
+fn is_fully_filled(problem: &Grid<Clue>, answer: &Grid<bool>) -> bool {
+    (0..problem.height()).all(|y| {
+        (0..problem.width()).all(|x| {
+            problem[P(y, x)] == UNUSED
+                || FOUR_NEIGHBOURS.iter().any(|&d| {
+                    let LP(ly, lx) = LP::of_vertex(P(y, x)) + d;
+                    answer.get_or_default_p(P(ly, lx), false)
+                })
+        })
+    })
+}
+
+/// Returns whether every cell which is neither `UNUSED` nor a bridge is passed by a line in
+/// `answer` given by `enumerate_variant_answers`.
+fn is_variant_fully_filled(problem: &Grid<Clue>, topology: &Topology, answer: &Grid<bool>) -> bool {
+    (0..problem.height()).all(|y| {
+        (0..problem.width()).all(|x| {
+            problem[P(y, x)] == UNUSED
+                || topology.bridge(P(y, x))
+                || FOUR_NEIGHBOURS.iter().any(|&d| {
+                    match topology.canonical_edge(LP::of_vertex(P(y, x)) + d) {
+                        Some(e) => answer[e],
+                        None => false,
+                    }
+                })
+        })
+    })
+}
+
+fn is_same_placement(answer: &Grid<bool>, placement: &LinePlacement) -> bool {
+    (0..answer.height()).all(|y| {
+        (0..answer.width()).all(|x| y % 2 == x % 2 || answer[P(y, x)] == placement.get(LP(y, x)))
+    })
+}
+
+/// Generates a board with random bridges and warps.
+fn random_topology<R: Rng>(height: i32, width: i32, rng: &mut R) -> Topology {
+    let mut topology = Topology::new(height, width);
+    for y in 0..height {
+        topology.set_warp_row(y, rng.gen::<f64>() < 0.3);
+    }
+    for x in 0..width {
+        topology.set_warp_column(x, rng.gen::<f64>() < 0.3);
+    }
+    for y in 0..height {
+        for x in 0..width {
+            topology.set_bridge(P(y, x), rng.gen::<f64>() < 0.1);
+        }
+    }
+    topology
+}
+
+/// Generates a problem on `topology` whose clues are the ends of random walks.
+fn random_variant_problem<R: Rng>(topology: &Topology, rng: &mut R) -> Grid<Clue> {
+    let height = topology.height();
+    let width = topology.width();
+    let mut problem = Grid::new(height, width, NO_CLUE);
+    let mut used = Grid::new(height, width, false);
+    for y in 0..height {
+        for x in 0..width {
+            used[P(y, x)] = topology.bridge(P(y, x));
+        }
+    }
+    let n_lines = rng.gen_range(1, 4);
+    for n in 1..(n_lines + 1) {
+        let mut pos = P(rng.gen_range(0, height), rng.gen_range(0, width));
+        if used[pos] {
+            continue;
+        }
+        let start = pos;
+        used[pos] = true;
+        for _ in 0..rng.gen_range(1, height * width) {
+            if let Some(next) = topology.neighbour(pos, FOUR_NEIGHBOURS[rng.gen_range(0, 4)]) {
+                if !used[next] {
+                    used[next] = true;
+                    pos = next;
+                }
+            }
+        }
+        if pos == start {
+            continue;
+        }
+        problem[start] = Clue(n);
+        problem[pos] = Clue(n);
+    }
+    for y in 0..height {
+        for x in 0..width {
+            if !used[P(y, x)] && rng.gen::<f64>() < 0.1 {
+                problem[P(y, x)] = UNUSED;
+            }
+        }
+    }
+    problem
+}
+
+/// Generates a problem whose clues are the ends of random walks.
+fn random_problem<R: Rng>(height: i32, width: i32, rng: &mut R) -> Grid<Clue> {
+    let mut problem = Grid::new(height, width, NO_CLUE);
+    let mut used = Grid::new(height, width, false);
+    let n_lines = rng.gen_range(1, 4);
+    for n in 1..(n_lines + 1) {
+        let mut pos = P(rng.gen_range(0, height), rng.gen_range(0, width));
+        if used[pos] {
+            continue;
+        }
+        let start = pos;
+        used[pos] = true;
+        for _ in 0..rng.gen_range(1, height * width) {
+            let next = pos + FOUR_NEIGHBOURS[rng.gen_range(0, 4)];
+            if problem.is_valid_p(next) && !used[next] {
+                used[next] = true;
+                pos = next;
+            }
+        }
+        if pos == start {
+            continue;
+        }
+        problem[start] = Clue(n);
+        problem[pos] = Clue(n);
+    }
+    for y in 0..height {
+        for x in 0..width {
+            if !used[P(y, x)] && rng.gen::<f64>() < 0.1 {
+                problem[P(y, x)] = UNUSED;
+            }
+        }
+    }
+    problem
+}
+
+#[test]
+fn test_random_against_brute_force() {
+    let mut rng = XorShiftRng::from_seed([42u8; 16]);
+
+    for &(height, width) in &[(2, 2), (2, 3), (3, 3), (3, 4), (4, 4), (4, 5), (5, 5)] {
+        for _ in 0..100 {
+            let problem = random_problem(height, width, &mut rng);
+            let plain = Topology::new(height, width);
+            let all_answers = enumerate_answers(&problem);
+
+            for &disallow_unused_cell in &[false, true] {
+                let answers = all_answers
+                    .iter()
+                    .filter(|answer| !disallow_unused_cell || is_fully_filled(&problem, answer))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let found = solve2(&problem, None, disallow_unused_cell, false);
+                let found_parallel =
+                    solve2_parallel(&problem, None, None, disallow_unused_cell, false, 3, None);
+                assert_eq!(found_parallel.len(), found.len());
+
+                let rule = if disallow_unused_cell {
+                    Rule::FillAll
+                } else {
+                    Rule::Classic
+                };
+                for i in 0..found.len() {
+                    assert!(check_answer(&problem, &plain, &found[i], rule).is_empty());
+                    assert!(answers.iter().any(|a| is_same_placement(a, &found[i])));
+                    assert!(answers
+                        .iter()
+                        .any(|a| is_same_placement(a, &found_parallel[i])));
+                }
+                if disallow_unused_cell {
+                    assert_eq!(found.len(), answers.len());
+                } else {
+                    // `solve2` only reports answers without detours, but one of them exists
+                    // whenever the problem has an answer.
+                    assert_eq!(answers.is_empty(), found.len() == 0);
+                    if answers.len() == 1 {
+                        assert_eq!(found.len(), 1);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_check_answer() {
+    // 1 and 2 crossing at the center
+    let mut problem = Grid::new(3, 3, NO_CLUE);
+    problem[P(0, 1)] = Clue(1);
+    problem[P(2, 1)] = Clue(1);
+    problem[P(1, 0)] = Clue(2);
+    problem[P(1, 2)] = Clue(2);
+    let mut placement = LinePlacement::new(3, 3);
+    placement.set_down(P(0, 1), true);
+    placement.set_down(P(1, 1), true);
+    placement.set_right(P(1, 0), true);
+    placement.set_right(P(1, 1), true);
+
+    let plain = Topology::new(3, 3);
+    let violations = check_answer(&problem, &plain, &placement, Rule::Classic);
+    let kinds = violations.iter().map(|v| v.kind).collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![ViolationKind::Crossing, ViolationKind::MismatchedNumbers]
+    );
+    assert_eq!(violations[0].cells, vec![P(1, 1)]);
+    assert_eq!(violations[0].edges.len(), 4);
+    assert_eq!(violations[1].cells.len(), 5);
+
+    let violations = check_answer(&problem, &plain, &placement, Rule::FillAll);
+    assert_eq!(
+        violations
+            .iter()
+            .filter(|v| v.kind == ViolationKind::UnusedCell)
+            .count(),
+        4
+    );
+
+    // the same lines are valid if the center is a bridge
+    let mut bridged = Topology::new(3, 3);
+    bridged.set_bridge(P(1, 1), true);
+    assert!(check_answer(&problem, &bridged, &placement, Rule::Classic).is_empty());
+    placement.set_down(P(1, 1), false);
+    let kinds = check_answer(&problem, &bridged, &placement, Rule::Classic)
+        .iter()
+        .map(|v| v.kind)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![ViolationKind::DeadEnd, ViolationKind::ClueDegree]
+    );
+}
+
+#[test]
+fn test_check_answer_warp() {
+    // "1 3 rows:0" with the line going around the row
+    let problem = read_penciloid_problem(&mut "1 3 rows:0\n1 1 .\n".as_bytes()).unwrap();
+    let mut placement = LinePlacement::new(1, 3);
+    placement.set_right(P(0, 1), true);
+    placement.set_right(P(0, 2), true);
+    for &rule in &[Rule::Classic, Rule::FillAll] {
+        assert!(check_answer(&problem.clues, &problem.topology, &placement, rule).is_empty());
+    }
+    let kinds = check_answer(
+        &problem.clues,
+        &Topology::new(1, 3),
+        &placement,
+        Rule::Classic,
+    )
+    .iter()
+    .map(|v| v.kind)
+    .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![ViolationKind::ClueDegree, ViolationKind::DeadEnd]
+    );
+
+    // a line around a torus without ends is a loop
+    let mut problem = read_penciloid_problem(&mut "2 2 torus\n1 .\n1 .\n".as_bytes()).unwrap();
+    problem.clues[P(1, 0)] = NO_CLUE;
+    let mut placement = LinePlacement::new(2, 2);
+    placement.set_right(P(0, 0), true);
+    placement.set_right(P(0, 1), true);
+    let kinds = check_answer(&problem.clues, &problem.topology, &placement, Rule::Classic)
+        .iter()
+        .map(|v| v.kind)
+        .collect::<Vec<_>>();
+    assert_eq!(kinds, vec![ViolationKind::ClueDegree, ViolationKind::Loop]);
+}
+
+#[test]
+fn test_variant_random_against_brute_force() {
+    let mut rng = XorShiftRng::from_seed([43u8; 16]);
+
+    for &(height, width) in &[(2, 2), (2, 3), (3, 3), (3, 4), (4, 4)] {
+        for _ in 0..100 {
+            let topology = random_topology(height, width, &mut rng);
+            if topology.is_plain() {
+                // covered by `test_random_against_brute_force`
+                continue;
+            }
+            let problem = random_variant_problem(&topology, &mut rng);
+            let all_answers = enumerate_variant_answers(&problem, &topology);
+
+            for &disallow_unused_cell in &[false, true] {
+                let answers = all_answers
+                    .iter()
+                    .filter(|answer| {
+                        !disallow_unused_cell
+                            || is_variant_fully_filled(&problem, &topology, answer)
+                    })
+                    .collect::<Vec<_>>();
+                let option = SearchOption {
+                    topology: Some(&topology),
+                    disallow_unused_cell,
+                    terminate_on_not_fully_filled: false,
+                    step_limit: None,
+                    cancel: None,
+                };
+                let mut found = vec![];
+                let summary = solve2_streaming(&problem, &option, |answer| {
+                    found.push(answer.clone());
+                    true
+                });
+
+                assert!(summary.exhaustive);
+                assert_eq!(found.len(), answers.len());
+                let rule = if disallow_unused_cell {
+                    Rule::FillAll
+                } else {
+                    Rule::Classic
+                };
+                for answer in &found {
+                    assert!(answers.iter().any(|a| is_same_placement(a, answer)));
+                    assert!(check_answer(&problem, &topology, answer, rule).is_empty());
+                }
+
+                let found_parallel = solve2_parallel(
+                    &problem,
+                    Some(&topology),
+                    None,
+                    disallow_unused_cell,
+                    false,
+                    2,
+                    None,
+                );
+                assert_eq!(found_parallel.len(), answers.len());
+                for answer in &found_parallel.answers {
+                    assert!(answers.iter().any(|a| is_same_placement(a, answer)));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_generator_uniqueness_under_rules() {
+    let mut rng = XorShiftRng::from_seed([44u8; 16]);
+    let (height, width) = (5, 5);
+    let mut generator = PlacementGenerator::new(height, width);
+
+    for &rule in &[Rule::Classic, Rule::FillAll] {
+        let opt = GeneratorOption {
+            chain_threshold: 3,
+            endpoint_constraint: None,
+            forbid_adjacent_clue: false,
+            symmetry: Symmetry::none(),
+            clue_limit: None,
+            topology: None,
+            rule,
+            numbering: Numbering::Random,
+        };
+        let mut n_generated = 0;
+        while n_generated < 5 {
+            let problem = match generator.generate_and_test(&opt, &mut rng) {
+                Some(problem) => problem,
+                None => continue,
+            };
+            n_generated += 1;
+            assert!(estimate_difficulty(&problem, None, rule, None).is_some());
+
+            let answers = enumerate_answers(&problem);
+            match rule {
+                // even answers with detours are counted here
+                Rule::Classic => {
+                    assert_eq!(answers.len(), 1);
+                    assert!(is_fully_filled(&problem, &answers[0]));
+                }
+                Rule::FillAll => {
+                    let n_filled = answers
+                        .iter()
+                        .filter(|answer| is_fully_filled(&problem, answer))
+                        .count();
+                    assert_eq!(n_filled, 1);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_generator_uniqueness_on_variants() {
+    let mut rng = XorShiftRng::from_seed([47u8; 16]);
+    let (height, width) = (4, 5);
+    let torus = Topology::torus(height, width);
+    let mut warped = Topology::new(height, width);
+    warped.set_warp_row(1, true);
+    warped.set_warp_column(2, true);
+    let mut bridged = Topology::new(height, width);
+    bridged.set_bridge(P(1, 2), true);
+    bridged.set_bridge(P(2, 1), true);
+    let mut generator = PlacementGenerator::new(height, width);
+
+    for topology in &[torus, warped, bridged] {
+        let opt = GeneratorOption {
+            chain_threshold: 2,
+            endpoint_constraint: None,
+            forbid_adjacent_clue: false,
+            symmetry: Symmetry::none(),
+            clue_limit: None,
+            topology: Some(topology),
+            rule: Rule::Classic,
+            numbering: Numbering::Random,
+        };
+        let mut n_generated = 0;
+        while n_generated < 3 {
+            let problem = match generator.generate_and_test(&opt, &mut rng) {
+                Some(problem) => problem,
+                None => continue,
+            };
+            n_generated += 1;
+
+            let answers = enumerate_variant_answers(&problem, topology);
+            assert_eq!(answers.len(), 1);
+            assert!(is_variant_fully_filled(&problem, topology, &answers[0]));
+        }
+    }
+}
+
+#[test]
+fn test_generator_beam_search_configs() {
+    let mut rng = XorShiftRng::from_seed([45u8; 16]);
+    let configs = [
+        BeamSearchConfig {
+            beam_width: 10,
+            score: FieldScore::FewerEndpoints(3),
+            candidates_per_field: 3,
+            seed_selection: SeedSelection::Random,
+        },
+        BeamSearchConfig {
+            beam_width: 20,
+            score: FieldScore::MostDecided,
+            candidates_per_field: 2,
+            seed_selection: SeedSelection::BestOf(3),
+        },
+    ];
+    let opt = GeneratorOption {
+        chain_threshold: 3,
+        endpoint_constraint: None,
+        forbid_adjacent_clue: false,
+        symmetry: Symmetry::none(),
+        clue_limit: None,
+        topology: None,
+        rule: Rule::Classic,
+        numbering: Numbering::Random,
+    };
+
+    for &config in &configs {
+        let mut generator = PlacementGenerator::with_config(5, 5, config);
+        let mut n_generated = 0;
+        while n_generated < 3 {
+            if let Some(problem) = generator.generate_and_test(&opt, &mut rng) {
+                n_generated += 1;
+                assert_eq!(enumerate_answers(&problem).len(), 1);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_extract_problem_numbering() {
+    let mut rng = XorShiftRng::from_seed([46u8; 16]);
+    let mut placement = LinePlacement::new(3, 4);
+    for y in 0..3 {
+        placement.set_right(P(y, 0), true);
+        placement.set_right(P(y, 2), true);
+    }
+    placement.set_right(P(1, 1), true);
+
+    let numbers = |problem: &Grid<Clue>| {
+        let mut ret = vec![];
+        for y in 0..3 {
+            for x in 0..4 {
+                if problem[P(y, x)] != NO_CLUE {
+                    ret.push(problem[P(y, x)].0);
+                }
+            }
+        }
+        ret
+    };
+
+    let problem = extract_problem(&placement, Numbering::ReadingOrder, &mut rng);
+    assert_eq!(numbers(&problem), vec![1, 1, 2, 2, 3, 3, 4, 4, 5, 5]);
+    let problem = extract_problem(&placement, Numbering::ByLength, &mut rng);
+    assert_eq!(numbers(&problem), vec![2, 2, 3, 3, 1, 1, 4, 4, 5, 5]);
+    let mut symmetry = Symmetry::none();
+    symmetry.horizontal = true;
+    let problem = extract_problem(&placement, Numbering::Symmetric(symmetry), &mut rng);
+    assert_eq!(numbers(&problem), vec![1, 1, 3, 3, 5, 5, 2, 2, 4, 4]);
+
+    let mut numbers = numbers(&extract_problem(&placement, Numbering::Random, &mut rng));
+    numbers.sort();
+    assert_eq!(numbers, vec![1, 1, 2, 2, 3, 3, 4, 4, 5, 5]);
+}
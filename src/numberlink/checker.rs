@@ -0,0 +1,160 @@
+use super::super::{Grid, Violation, ViolationKind, D, LP, P};
+use super::*;
+use FOUR_NEIGHBOURS;
+
+/// Returns the lines from `pos` as pairs of the direction and the edge, following warps.
+fn incident_lines(topology: &Topology, answer: &LinePlacement, pos: P) -> Vec<(D, LP)> {
+    FOUR_NEIGHBOURS
+        .iter()
+        .filter_map(|&d| match topology.canonical_edge(LP::of_vertex(pos) + d) {
+            Some(e) if answer.get(e) => Some((d, e)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the bit of the line passing a cell in direction `d` (1 for horizontal lines
+/// and 2 for vertical ones), which tells the two lines on a bridge apart.
+fn axis_bit(d: D) -> u8 {
+    if d.0 == 0 {
+        1
+    } else {
+        2
+    }
+}
+
+/// Checks whether `answer` is an answer of `problem` on the board `topology` under `rule`.
+/// Edges are given in the coordinates of `LinePlacement::get` (see `Topology::canonical_edge`).
+/// A bridge cell must be passed by a horizontal line and a vertical one.
+/// The violations reported are:
+/// - `Branch`: a cell with three lines.
+/// - `Crossing`: a cell other than a bridge with four lines.
+/// - `DeadEnd`: a cell without a clue where a line ends.
+/// - `ClueDegree`: a clue cell which is not an end of exactly one line.
+/// - `ThroughUnused`: an `UNUSED` cell passed by a line.
+/// - `MismatchedNumbers`: a line connecting different numbers (given by all its cells).
+/// - `Loop`: a line forming a loop (given by all its cells).
+/// - `UnusedCell`: a cell not passed by any line under `Rule::FillAll`, or a bridge not
+///   passed in either direction.
+pub fn check_answer(
+    problem: &Grid<Clue>,
+    topology: &Topology,
+    answer: &LinePlacement,
+    rule: Rule,
+) -> Vec<Violation> {
+    let height = problem.height();
+    let width = problem.width();
+    let mut ret = vec![];
+
+    for y in 0..height {
+        for x in 0..width {
+            let pos = P(y, x);
+            let incident = incident_lines(topology, answer, pos);
+            if topology.bridge(pos) {
+                for &bit in &[1, 2] {
+                    let passing = incident
+                        .iter()
+                        .filter(|&&(d, _)| axis_bit(d) == bit)
+                        .map(|&(_, e)| e)
+                        .collect::<Vec<_>>();
+                    if passing.len() == 1 {
+                        ret.push(Violation::new(ViolationKind::DeadEnd, vec![pos], passing));
+                    } else if passing.is_empty() {
+                        ret.push(Violation::at_cells(ViolationKind::UnusedCell, vec![pos]));
+                    }
+                }
+                continue;
+            }
+            let incident = incident.into_iter().map(|(_, e)| e).collect::<Vec<_>>();
+            let clue = problem[pos];
+            if clue == UNUSED {
+                if !incident.is_empty() {
+                    ret.push(Violation::new(
+                        ViolationKind::ThroughUnused,
+                        vec![pos],
+                        incident,
+                    ));
+                }
+                continue;
+            }
+            if incident.len() == 4 {
+                ret.push(Violation::new(ViolationKind::Crossing, vec![pos], incident));
+            } else if incident.len() == 3 {
+                ret.push(Violation::new(ViolationKind::Branch, vec![pos], incident));
+            } else if clue != NO_CLUE {
+                if incident.len() != 1 {
+                    ret.push(Violation::new(
+                        ViolationKind::ClueDegree,
+                        vec![pos],
+                        incident,
+                    ));
+                }
+            } else if incident.len() == 1 {
+                ret.push(Violation::new(ViolationKind::DeadEnd, vec![pos], incident));
+            } else if incident.is_empty() && rule == Rule::FillAll {
+                ret.push(Violation::at_cells(ViolationKind::UnusedCell, vec![pos]));
+            }
+        }
+    }
+
+    // the lines are traced by the nodes (cell, bits), where a bridge has a node for each
+    // direction and the other cells have a node with both bits
+    let node_bits = |pos: P, d: D| {
+        if topology.bridge(pos) {
+            axis_bit(d)
+        } else {
+            3
+        }
+    };
+    let mut visited = Grid::new(height, width, 0u8);
+    for y in 0..height {
+        for x in 0..width {
+            for &(d0, _) in &incident_lines(topology, answer, P(y, x)) {
+                let start = (P(y, x), node_bits(P(y, x), d0));
+                if visited[start.0] & start.1 != 0 {
+                    continue;
+                }
+                let mut cells = vec![];
+                let mut edges = vec![];
+                let mut stack = vec![start];
+                visited[start.0] |= start.1;
+                while let Some((pos, bits)) = stack.pop() {
+                    cells.push(pos);
+                    for (d, e) in incident_lines(topology, answer, pos) {
+                        if axis_bit(d) & bits == 0 {
+                            continue;
+                        }
+                        if !edges.contains(&e) {
+                            edges.push(e);
+                        }
+                        let pos2 = topology.neighbour(pos, d).unwrap();
+                        let bits2 = node_bits(pos2, d);
+                        if visited[pos2] & bits2 == 0 {
+                            visited[pos2] |= bits2;
+                            stack.push((pos2, bits2));
+                        }
+                    }
+                }
+                let mut numbers = cells
+                    .iter()
+                    .map(|&p| problem[p])
+                    .filter(|&c| c != NO_CLUE && c != UNUSED)
+                    .map(|Clue(c)| c)
+                    .collect::<Vec<_>>();
+                numbers.sort();
+                numbers.dedup();
+                if numbers.len() >= 2 {
+                    ret.push(Violation::new(
+                        ViolationKind::MismatchedNumbers,
+                        cells.clone(),
+                        edges.clone(),
+                    ));
+                }
+                if edges.len() >= cells.len() {
+                    ret.push(Violation::new(ViolationKind::Loop, cells, edges));
+                }
+            }
+        }
+    }
+    ret
+}
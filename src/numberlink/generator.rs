@@ -18,7 +18,69 @@ pub struct GeneratorOption<'a> {
     pub forbid_adjacent_clue: bool,
     pub symmetry: Symmetry,
     pub clue_limit: Option<i32>,
-    pub prioritized_extension: bool,
+    pub topology: Option<&'a Topology>,
+    /// The rule set under which generated problems must have a unique answer.
+    pub rule: Rule,
+    pub numbering: Numbering,
+}
+
+/// Policy of assigning clue numbers to lines in `extract_problem`.
+#[derive(Clone, Copy, Debug)]
+pub enum Numbering {
+    /// Numbers are assigned at random.
+    Random,
+    /// Lines are numbered in the reading order of their first endpoints.
+    ReadingOrder,
+    /// Longer lines get smaller numbers. Lines of the same length are numbered in reading order.
+    ByLength,
+    /// Lines mapped to each other by the symmetry get consecutive numbers.
+    /// Such groups of lines are numbered in reading order.
+    Symmetric(Symmetry),
+}
+
+/// Strategy of choosing the seed (the vertex to be extended next) in a field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SeedSelection {
+    /// A seed chosen uniformly at random.
+    Random,
+    /// The seed with the largest complexity among the given number of random samples.
+    BestOf(i32),
+}
+
+/// Scoring of fields, which decides the fields surviving in the beam.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldScore {
+    /// No scoring: the surviving fields are chosen at random.
+    Random,
+    /// Prefers fields with fewer endpoints relative to the number of decided edges, weighting
+    /// endpoints by the given factor. Larger factors lead to fewer and longer lines,
+    /// but the generation fails more often.
+    FewerEndpoints(i32),
+    /// Prefers fields with more decided edges, which are closer to completion.
+    MostDecided,
+}
+
+/// Configuration of the beam search in `PlacementGenerator`.
+#[derive(Clone, Copy, Debug)]
+pub struct BeamSearchConfig {
+    /// The number of fields kept in the beam.
+    pub beam_width: usize,
+    pub score: FieldScore,
+    /// The number of candidate fields generated per surviving field, from which the best ones
+    /// according to `score` are kept. Ignored if `score` is `FieldScore::Random`.
+    pub candidates_per_field: usize,
+    pub seed_selection: SeedSelection,
+}
+
+impl Default for BeamSearchConfig {
+    fn default() -> BeamSearchConfig {
+        BeamSearchConfig {
+            beam_width: 100,
+            score: FieldScore::Random,
+            candidates_per_field: 2,
+            seed_selection: SeedSelection::Random,
+        }
+    }
 }
 
 pub fn generate_endpoint_constraint<R: Rng>(
@@ -92,11 +154,14 @@ pub struct PlacementGenerator {
     next_fields: Vec<AnswerField>,
     height: i32,
     width: i32,
-    beam_width: usize,
+    config: BeamSearchConfig,
 }
 
 impl PlacementGenerator {
     pub fn new(height: i32, width: i32) -> PlacementGenerator {
+        PlacementGenerator::with_config(height, width, BeamSearchConfig::default())
+    }
+    pub fn with_config(height: i32, width: i32, config: BeamSearchConfig) -> PlacementGenerator {
         let template = AnswerField::new(
             height,
             width,
@@ -106,17 +171,36 @@ impl PlacementGenerator {
                 forbid_adjacent_clue: false,
                 symmetry: Symmetry::none(),
                 clue_limit: None,
-                prioritized_extension: false,
+                topology: None,
+                rule: Rule::Classic,
+                numbering: Numbering::Random,
             },
         );
-        let beam_width = 100;
+        let beam_width = config.beam_width;
+        let n_candidates = PlacementGenerator::n_candidates(&config);
         PlacementGenerator {
-            pool: vec![template; beam_width * 2 + 1],
+            pool: vec![template; beam_width + n_candidates + 1],
             active_fields: Vec::with_capacity(beam_width),
-            next_fields: Vec::with_capacity(beam_width),
+            next_fields: Vec::with_capacity(n_candidates),
             height,
             width,
-            beam_width,
+            config,
+        }
+    }
+    /// Returns the number of candidate fields generated in each step of the beam search.
+    fn n_candidates(config: &BeamSearchConfig) -> usize {
+        match config.score {
+            FieldScore::Random => config.beam_width,
+            _ => config.beam_width * config.candidates_per_field,
+        }
+    }
+    fn score(field: &AnswerField, score: FieldScore) -> i32 {
+        match score {
+            FieldScore::Random => 0,
+            FieldScore::FewerEndpoints(weight) => {
+                field.decided_edges() - weight * field.endpoints()
+            }
+            FieldScore::MostDecided => field.decided_edges(),
         }
     }
     pub fn generate<R: Rng>(
@@ -124,7 +208,8 @@ impl PlacementGenerator {
         opt: &GeneratorOption,
         rng: &mut R,
     ) -> Option<LinePlacement> {
-        let beam_width = self.beam_width;
+        let beam_width = self.config.beam_width;
+        let n_candidates = PlacementGenerator::n_candidates(&self.config);
         let height = self.height;
         let width = self.width;
         let fields = &mut self.active_fields;
@@ -182,8 +267,8 @@ impl PlacementGenerator {
             }
 
             let fields_next = &mut self.next_fields;
-            'outer: for _ in 0..(5 * fields.len()) {
-                if fields_next.len() >= beam_width || fields.len() == 0 {
+            'outer: for _ in 0..(5 * fields.len() * (n_candidates / beam_width)) {
+                if fields_next.len() >= n_candidates || fields.len() == 0 {
                     break;
                 }
 
@@ -200,10 +285,9 @@ impl PlacementGenerator {
                 if !field.has_seed() {
                     continue;
                 }
-                let cd = if opt.prioritized_extension {
-                    field.best_seed(5, rng)
-                } else {
-                    field.random_seed(rng)
+                let cd = match self.config.seed_selection {
+                    SeedSelection::Random => field.random_seed(rng),
+                    SeedSelection::BestOf(k) => field.best_seed(k, rng),
                 };
 
                 let update = PlacementGenerator::choose_update(&field, cd, rng);
@@ -221,13 +305,18 @@ impl PlacementGenerator {
                 }
 
                 if !field.has_seed() {
-                    if !check_answer_validity(&field) {
+                    let line_placement = field.as_line_placement();
+                    let valid = if field.is_plain() {
+                        check_answer_validity(&field)
+                    } else {
+                        // no line may touch itself, including across warps
+                        line_placement.extract_chain_groups().is_some()
+                    };
+                    if !valid {
                         self.pool.push(field);
                         continue 'outer;
                     }
 
-                    let line_placement = field.as_line_placement();
-
                     self.pool.push(field);
                     // release used fields
                     for used in fields.drain(0..) {
@@ -247,6 +336,13 @@ impl PlacementGenerator {
             for old in fields.drain(0..) {
                 self.pool.push(old);
             }
+            if fields_next.len() > beam_width {
+                let score = self.config.score;
+                fields_next.sort_by_key(|f| -PlacementGenerator::score(f, score));
+                for dropped in fields_next.drain(beam_width..) {
+                    self.pool.push(dropped);
+                }
+            }
 
             ::std::mem::swap(fields, fields_next);
         }
@@ -258,10 +354,14 @@ impl PlacementGenerator {
         rng: &mut R,
     ) -> Option<Grid<Clue>> {
         if let Some(placement) = self.generate(opt, rng) {
-            if uniqueness_pretest(&placement) {
-                let problem = extract_problem(&placement, rng);
-                let ans = solve2(&problem, Some(2), false, true);
-                if ans.len() == 1 && !ans.found_not_fully_filled {
+            let plain = match opt.topology {
+                Some(topology) => topology.is_plain(),
+                None => true,
+            };
+            // the pretest relies on the shape of lines on plain boards
+            if !plain || uniqueness_pretest(&placement, opt.rule) {
+                let problem = extract_problem(&placement, opt.numbering, rng);
+                if has_unique_answer(&problem, opt.topology, opt.rule) {
                     return Some(problem);
                 }
             }
@@ -278,7 +378,7 @@ impl PlacementGenerator {
                 return;
             }
         }
-        if is_entangled(field) {
+        if field.is_plain() && is_entangled(field) {
             field.set_invalid();
             return;
         }
@@ -377,9 +477,13 @@ fn is_entangled(field: &AnswerField) -> bool {
     false
 }
 
-/// Extract a problem from `placement`.
-/// Clue numbers are randomly assigned using `rng`.
-pub fn extract_problem<R: Rng>(placement: &LinePlacement, rng: &mut R) -> Grid<Clue> {
+/// Extract a problem from `placement`, which must be a valid answer (no line touches itself).
+/// Clue numbers are assigned according to `numbering`; `rng` is used only for `Numbering::Random`.
+pub fn extract_problem<R: Rng>(
+    placement: &LinePlacement,
+    numbering: Numbering,
+    rng: &mut R,
+) -> Grid<Clue> {
     let height = placement.height();
     let width = placement.width();
     let groups = match placement.extract_chain_groups() {
@@ -387,18 +491,75 @@ pub fn extract_problem<R: Rng>(placement: &LinePlacement, rng: &mut R) -> Grid<C
         None => panic!(),
     };
 
-    let mut max_id = 0;
+    let mut max_id = -1;
     for y in 0..height {
         for x in 0..width {
             max_id = ::std::cmp::max(max_id, groups[P(y, x)]);
         }
     }
-
-    let mut shuffler = vec![0; (max_id + 1) as usize];
-    for i in 0..(max_id + 1) {
-        shuffler[i as usize] = i;
+    let n_lines = (max_id + 1) as usize;
+
+    // `order` lists line ids in the order of their numbers
+    let mut order = (0..(max_id + 1)).collect::<Vec<_>>();
+    match numbering {
+        Numbering::Random => rng.shuffle(&mut order),
+        Numbering::ReadingOrder => (),
+        Numbering::ByLength => {
+            let mut length = vec![0; n_lines];
+            for y in 0..height {
+                for x in 0..width {
+                    let id = groups[P(y, x)];
+                    if id >= 0 {
+                        length[id as usize] += 1;
+                    }
+                }
+            }
+            order.sort_by_key(|&id| -length[id as usize]);
+        }
+        Numbering::Symmetric(symmetry) => {
+            // `orbit[i]` is the smallest id among the lines mapped to each other with line `i`
+            let mut orbit = (0..(max_id + 1)).collect::<Vec<_>>();
+            for y in 0..height {
+                for x in 0..width {
+                    let pos = P(y, x);
+                    if !placement.is_endpoint(pos) {
+                        continue;
+                    }
+                    let mut images = vec![];
+                    if symmetry.tetrad && height == width {
+                        images.push(P(x, width - 1 - y));
+                    }
+                    if symmetry.dyad || symmetry.tetrad {
+                        images.push(P(height - 1 - y, width - 1 - x));
+                    }
+                    if symmetry.horizontal {
+                        images.push(P(height - 1 - y, x));
+                    }
+                    if symmetry.vertical {
+                        images.push(P(y, width - 1 - x));
+                    }
+                    for img in images {
+                        if !placement.is_endpoint(img) {
+                            continue;
+                        }
+                        let a = orbit[groups[pos] as usize];
+                        let b = orbit[groups[img] as usize];
+                        let (lo, hi) = if a < b { (a, b) } else { (b, a) };
+                        for o in orbit.iter_mut() {
+                            if *o == hi {
+                                *o = lo;
+                            }
+                        }
+                    }
+                }
+            }
+            order.sort_by_key(|&id| (orbit[id as usize], id));
+        }
+    }
+    let mut shuffler = vec![0; n_lines];
+    for (i, &id) in order.iter().enumerate() {
+        shuffler[id as usize] = i as i32;
     }
-    rng.shuffle(&mut shuffler);
 
     let mut ret = Grid::new(height, width, NO_CLUE);
     for y in 0..height {
@@ -416,7 +577,7 @@ pub fn extract_problem<R: Rng>(placement: &LinePlacement, rng: &mut R) -> Grid<C
 /// Check whether the problem obtained from `placement` *may have* unique solution.
 /// If `false` is returned, the problem is guaranteed to have several solutions.
 /// However, even if `true` is returned, it is still possible that the problem has several solutions.
-pub fn uniqueness_pretest(placement: &LinePlacement) -> bool {
+pub fn uniqueness_pretest(placement: &LinePlacement, rule: Rule) -> bool {
     let height = placement.height();
     let width = placement.width();
     let ids = match placement.extract_chain_groups() {
@@ -424,7 +585,7 @@ pub fn uniqueness_pretest(placement: &LinePlacement) -> bool {
         None => return false,
     };
 
-    if !uniqueness_pretest_horizontal(&ids) {
+    if !uniqueness_pretest_horizontal(&ids, rule) {
         return false;
     }
     if height == width {
@@ -436,14 +597,14 @@ pub fn uniqueness_pretest(placement: &LinePlacement) -> bool {
             }
         }
 
-        if !uniqueness_pretest_horizontal(&ids_fliped) {
+        if !uniqueness_pretest_horizontal(&ids_fliped, rule) {
             return false;
         }
     }
 
     true
 }
-fn uniqueness_pretest_horizontal(ids: &Grid<i32>) -> bool {
+fn uniqueness_pretest_horizontal(ids: &Grid<i32>, rule: Rule) -> bool {
     let height = ids.height();
     let width = ids.width();
 
@@ -503,8 +664,7 @@ fn uniqueness_pretest_horizontal(ids: &Grid<i32>) -> bool {
             }
         }
 
-        let ans = solve2(&screen_problem, Some(2), false, true);
-        if ans.len() >= 2 || ans.found_not_fully_filled {
+        if !has_unique_answer(&screen_problem, None, rule) {
             return false;
         }
     }
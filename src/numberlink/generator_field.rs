@@ -20,16 +20,19 @@ pub struct AnswerField {
     chain_union: Grid<usize>,      // height * width
     chain_connectivity: Grid<i32>, // height * width
     chain_length: Grid<i32>,       // height * width
-    field: Grid<Edge>,             // (2 * height - 1) * (2 * width - 1)
+    field: Grid<Edge>,             // (2 * height) * (2 * width)
     seed_idx: Grid<i32>,
     seeds: Vec<LP>,
     seed_count: usize,
     endpoint_constraint: Grid<Endpoint>,
     endpoints: i32,
     endpoint_forced_cells: i32,
+    decided_edges: i32,
     chain_threshold: i32,
     forbid_adjacent_clue: bool,
     symmetry: Symmetry,
+    topology: Topology,
+    plain: bool,
     invalid: bool,
     search_queue: FiniteSearchQueue,
 }
@@ -43,13 +46,17 @@ enum Cnt<T> {
 
 impl AnswerField {
     pub fn new(height: i32, width: i32, opt: &GeneratorOption) -> AnswerField {
+        let topology = match opt.topology {
+            Some(topology) => topology.clone(),
+            None => Topology::new(height, width),
+        };
         let mut ret = AnswerField {
             height,
             width,
             chain_union: Grid::new(height, width, 0),
             chain_connectivity: Grid::new(height, width, -1),
             chain_length: Grid::new(height, width, 0),
-            field: Grid::new(2 * height - 1, 2 * width - 1, Edge::Undecided),
+            field: Grid::new(2 * height, 2 * width, Edge::Undecided),
             seed_idx: Grid::new(2 * height - 1, 2 * width - 1, -1),
             seeds: vec![LP(0, 0); (height * width) as usize],
             seed_count: 0,
@@ -58,14 +65,39 @@ impl AnswerField {
                 None => Grid::new(height, width, Endpoint::Any),
             },
             endpoints: 0,
+            decided_edges: 0,
             endpoint_forced_cells: 0,
             chain_threshold: opt.chain_threshold,
             forbid_adjacent_clue: opt.forbid_adjacent_clue,
             symmetry: opt.symmetry,
+            plain: topology.is_plain(),
+            topology,
             invalid: false,
             search_queue: FiniteSearchQueue::new((height * width) as usize),
         };
 
+        for y in 0..(height * 2) {
+            for x in 0..(width * 2) {
+                // edges beyond the bottom or right border, which exist only as warps
+                if (y == height * 2 - 1 || x == width * 2 - 1)
+                    && ret.topology.canonical_edge(LP(y, x)).is_none()
+                {
+                    ret.field[LP(y, x)] = Edge::Blank;
+                }
+            }
+        }
+        for y in 0..height {
+            for x in 0..width {
+                let pos = P(y, x);
+                if ret.topology.bridge(pos) {
+                    if ret.endpoint_constraint[pos] == Endpoint::Forced {
+                        ret.invalid = true;
+                    }
+                    ret.endpoint_constraint[pos] = Endpoint::Prohibited;
+                }
+            }
+        }
+
         for idx in 0..((height * width) as usize) {
             ret.chain_union[idx] = idx;
             if ret.endpoint_constraint[idx] == Endpoint::Forced {
@@ -73,18 +105,83 @@ impl AnswerField {
             }
         }
 
-        ret.seeds[0] = LP(0, 0);
-        ret.seeds[1] = LP(0, 2 * width - 2);
-        ret.seeds[2] = LP(2 * height - 2, 0);
-        ret.seeds[3] = LP(2 * height - 2, 2 * width - 2);
-        ret.seed_count = 4;
-        ret.seed_idx[LP(0, 0)] = 0;
-        ret.seed_idx[LP(0, 2 * width - 2)] = 1;
-        ret.seed_idx[LP(2 * height - 2, 0)] = 2;
-        ret.seed_idx[LP(2 * height - 2, 2 * width - 2)] = 3;
+        for &pos in &[
+            LP(0, 0),
+            LP(0, 2 * width - 2),
+            LP(2 * height - 2, 0),
+            LP(2 * height - 2, 2 * width - 2),
+        ] {
+            if !ret.topology.bridge(pos.as_vertex()) {
+                ret.seeds[ret.seed_count] = pos;
+                ret.seed_idx[pos] = ret.seed_count as i32;
+                ret.seed_count += 1;
+            }
+        }
+        if !ret.plain {
+            ret.place_bridges();
+        }
         ret
     }
 
+    /// Places the lines going straight through bridges.
+    fn place_bridges(&mut self) {
+        let height = self.height;
+        let width = self.width;
+        let mut n_bridges = 0;
+        let mut n_passes = 0;
+        for y in 0..height {
+            for x in 0..width {
+                let pos = P(y, x);
+                if !self.topology.bridge(pos) {
+                    continue;
+                }
+                n_bridges += 1;
+                for &d in &FOUR_NEIGHBOURS {
+                    match self.topology.canonical_edge(LP::of_vertex(pos) + d) {
+                        Some(e) => self.field[e] = Edge::Line,
+                        None => self.invalid = true,
+                    }
+                }
+            }
+        }
+        if self.invalid {
+            return;
+        }
+        for y in 0..height {
+            for x in 0..width {
+                let pos = P(y, x);
+                if self.topology.bridge(pos) {
+                    continue;
+                }
+                for &d in &[D(0, 1), D(1, 0)] {
+                    // the cell at the other end of the line through consecutive bridges
+                    let mut end = pos;
+                    let mut length = 0;
+                    loop {
+                        end = match self.topology.neighbour(end, d) {
+                            Some(next) => next,
+                            None => break,
+                        };
+                        length += 1;
+                        if !self.topology.bridge(end) {
+                            break;
+                        }
+                    }
+                    if length >= 2 {
+                        n_passes += length - 1;
+                        if self.connect(pos, end, length).is_none() {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+        if n_passes != n_bridges * 2 {
+            // some bridges are on a loop consisting only of bridges
+            self.invalid = true;
+        }
+    }
+
     pub fn height(&self) -> i32 {
         self.height
     }
@@ -97,11 +194,25 @@ impl AnswerField {
     pub fn set_invalid(&mut self) {
         self.invalid = true;
     }
+    /// Returns the number of endpoints of the lines decided so far.
+    pub fn endpoints(&self) -> i32 {
+        self.endpoints
+    }
+    /// Returns the number of edges decided so far.
+    pub fn decided_edges(&self) -> i32 {
+        self.decided_edges
+    }
     pub fn endpoint_forced_cells(&self) -> i32 {
         self.endpoint_forced_cells
     }
 
     pub fn get_edge(&self, pos: LP) -> Edge {
+        // warps are stored as edges beyond the bottom or right border
+        let LP(y, x) = pos;
+        let pos = LP(
+            if y == -1 { self.height * 2 - 1 } else { y },
+            if x == -1 { self.width * 2 - 1 } else { x },
+        );
         if self.field.is_valid_lp(pos) {
             self.field[pos]
         } else {
@@ -159,7 +270,8 @@ impl AnswerField {
     /// Returns whether vertex `cd` is a seed
     pub fn is_seed(&self, pos: LP) -> bool {
         let nb = self.count_neighbor(pos);
-        nb == (0, 2) || (nb.0 == 1 && nb.1 > 0)
+        // boards with warps may have no corners, so any empty cell can be a seed
+        nb == (0, 2) || (nb.0 == 1 && nb.1 > 0) || (!self.plain && nb.0 == 0 && nb.1 > 0)
     }
 
     /// Copy `src` into this `AnswerField`.
@@ -176,13 +288,22 @@ impl AnswerField {
 
         self.endpoint_constraint.copy_from(&src.endpoint_constraint);
         self.endpoints = src.endpoints;
+        self.decided_edges = src.decided_edges;
         self.endpoint_forced_cells = src.endpoint_forced_cells;
         self.chain_threshold = src.chain_threshold;
         self.forbid_adjacent_clue = src.forbid_adjacent_clue;
         self.symmetry = src.symmetry;
+        if !(self.plain && src.plain) {
+            self.topology.clone_from(&src.topology);
+        }
+        self.plain = src.plain;
         self.invalid = src.invalid;
     }
 
+    pub fn is_plain(&self) -> bool {
+        self.plain
+    }
+
     /// Returns the representative node of the union containing `x` in `chain_connectivity`.
     /// Performs path compression to reduce complexity.
     fn root_mut(&mut self, x: usize) -> usize {
@@ -322,7 +443,44 @@ impl AnswerField {
         }
     }
 
+    /// Connects the chains ending at `end1` and `end2` by a line of `length` edges.
+    /// Returns the other end of the chain on the side of `end1`, or `None` if a loop is formed.
+    fn connect(&mut self, end1: P, end2: P, length: i32) -> Option<usize> {
+        let end1_id = self.chain_union.index_p(end1);
+        let end2_id = self.chain_union.index_p(end2);
+        let another_end1_id = self.chain_union[end1_id];
+        let another_end2_id = self.chain_union[end2_id];
+
+        if another_end1_id == end2_id {
+            // invalid: a self-loop will be formed
+            self.invalid = true;
+            return None;
+        }
+
+        let new_length = self.chain_length[end1_id] + self.chain_length[end2_id] + length;
+
+        self.chain_union[another_end1_id] = another_end2_id;
+        self.chain_union[another_end2_id] = another_end1_id;
+        self.chain_length[another_end1_id] = new_length;
+        self.chain_length[another_end2_id] = new_length;
+
+        self.join(another_end1_id, another_end2_id);
+        self.root_mut(another_end1_id);
+        self.root_mut(another_end2_id);
+
+        Some(another_end1_id)
+    }
+
     fn decide_int(&mut self, pos: LP, state: Edge) {
+        let pos = match self.topology.canonical_edge(pos) {
+            Some(pos) => pos,
+            None => {
+                if state == Edge::Line {
+                    self.invalid = true;
+                }
+                return;
+            }
+        };
         let current = self.field[pos];
         if current != Edge::Undecided {
             if current != state {
@@ -331,65 +489,40 @@ impl AnswerField {
             return;
         }
         self.field[pos] = state;
+        self.decided_edges += 1;
 
-        let LP(y, x) = pos;
+        let LP(y, _) = pos;
+        let (end1, end2) = self.topology.edge_ends(pos);
 
         // update chain information
         if state == Edge::Line {
-            let end1 = P(y / 2, x / 2);
-            let end2 = P((y + 1) / 2, (x + 1) / 2);
-
-            let end1_id = self.chain_union.index_p(end1);
-            let end2_id = self.chain_union.index_p(end2);
-            let another_end1_id = self.chain_union[end1_id];
-            let another_end2_id = self.chain_union[end2_id];
-
-            if another_end1_id == end2_id {
-                // invalid: a self-loop will be formed
-                self.invalid = true;
-                return;
-            }
-
-            let new_length = self.chain_length[end1_id] + self.chain_length[end2_id] + 1;
-
-            self.chain_union[another_end1_id] = another_end2_id;
-            self.chain_union[another_end2_id] = another_end1_id;
-            self.chain_length[another_end1_id] = new_length;
-            self.chain_length[another_end2_id] = new_length;
-
-            self.join(another_end1_id, another_end2_id);
-            self.root_mut(another_end1_id);
-            self.root_mut(another_end2_id);
-
-            if new_length < self.chain_threshold {
-                let pos = self.chain_union.p(another_end1_id);
-                self.extend_chain(pos);
+            match self.connect(end1, end2, 1) {
+                Some(another_end1_id) => {
+                    if self.chain_length[another_end1_id] < self.chain_threshold {
+                        let pos = self.chain_union.p(another_end1_id);
+                        self.extend_chain(pos);
+                    }
+                }
+                None => return,
             }
         }
 
         // check incident vertices
-        if y % 2 == 1 {
-            if self.count_neighbor(pos + D(-1, 0)) == (1, 0) {
-                self.endpoints += 1;
-            }
-            if self.count_neighbor(pos + D(1, 0)) == (1, 0) {
-                self.endpoints += 1;
-            }
-            self.inspect(pos + D(-1, 0));
-            self.inspect(pos + D(1, 0));
-        } else {
-            if self.count_neighbor(pos + D(0, -1)) == (1, 0) {
-                self.endpoints += 1;
-            }
-            if self.count_neighbor(pos + D(0, 1)) == (1, 0) {
-                self.endpoints += 1;
-            }
-            self.inspect(pos + D(0, -1));
-            self.inspect(pos + D(0, 1));
+        let end1 = LP::of_vertex(end1);
+        let end2 = LP::of_vertex(end2);
+        if self.count_neighbor(end1) == (1, 0) {
+            self.endpoints += 1;
         }
+        if self.count_neighbor(end2) == (1, 0) {
+            self.endpoints += 1;
+        }
+        self.inspect(end1);
+        self.inspect(end2);
 
         // check for canonization rule
-        if state == Edge::Line {
+        if state == Edge::Line && !self.plain {
+            self.canonize_around(pos);
+        } else if state == Edge::Line {
             if y % 2 == 1 {
                 let related = [pos + D(0, -2), pos + D(-1, -1), pos + D(1, -1)];
                 for i in 0..3 {
@@ -424,6 +557,44 @@ impl AnswerField {
         }
     }
 
+    /// Canonization rule on boards with bridges or warps, which is the same as the one on
+    /// plain boards but follows warps: of the other three edges of a 2x2 square of cells
+    /// containing line `pos`, at most one can have a line. Squares with a bridge are skipped.
+    fn canonize_around(&mut self, pos: LP) {
+        let LP(y, _) = pos;
+        let d = if y % 2 == 1 { D(1, 0) } else { D(0, 1) };
+        let (end1, end2) = self.topology.edge_ends(pos);
+        for &s in &[d.rotate_clockwise(), d.rotate_counterclockwise()] {
+            let (side1, side2) = match (
+                self.topology.neighbour(end1, s),
+                self.topology.neighbour(end2, s),
+            ) {
+                (Some(side1), Some(side2)) => (side1, side2),
+                _ => continue,
+            };
+            if self.topology.neighbour(side1, d) != Some(side2) {
+                continue;
+            }
+            let cells = [end1, end2, side1, side2];
+            if cells.iter().any(|&c| self.topology.bridge(c))
+                || (0..4).any(|i| (0..i).any(|j| cells[i] == cells[j]))
+            {
+                continue;
+            }
+            let related = [
+                LP::of_vertex(side1) + d,
+                LP::of_vertex(end1) + s,
+                LP::of_vertex(end2) + s,
+            ];
+            for i in 0..3 {
+                if self.get_edge(related[i]) == Edge::Line {
+                    self.decide(related[(i + 1) % 3], Edge::Blank);
+                    self.decide(related[(i + 2) % 3], Edge::Blank);
+                }
+            }
+        }
+    }
+
     /// Inspect all vertices
     pub fn inspect_all(&mut self) {
         assert_eq!(self.search_queue.is_started(), false);
@@ -452,6 +623,9 @@ impl AnswerField {
     }
 
     fn inspect_int(&mut self, pos: LP) {
+        if self.topology.bridge(pos.as_vertex()) {
+            return;
+        }
         let (line, undecided) = self.count_neighbor(pos);
         if line == 0 {
             if undecided == 0 {
@@ -483,9 +657,10 @@ impl AnswerField {
                     let minimum_len = self.chain_threshold - self.chain_length[pos.as_vertex()];
                     for &d in &FOUR_NEIGHBOURS {
                         if self.get_edge(pos + d) == Edge::Undecided {
-                            let a = self.chain_union.p(self.chain_union[pos.as_vertex() + d]);
+                            let pos2 = self.topology.neighbour(pos.as_vertex(), d).unwrap();
+                            let a = self.chain_union.p(self.chain_union[pos2]);
                             if self.count_neighbor(LP::of_vertex(a)) == (1, 0)
-                                && self.chain_length[pos.as_vertex() + d] < minimum_len
+                                && self.chain_length[pos2] < minimum_len
                             {
                                 self.decide(pos + d, Edge::Blank);
                             }
@@ -545,7 +720,8 @@ impl AnswerField {
         if self.forbid_adjacent_clue && line + undecided == 2 {
             for &d in &FOUR_NEIGHBOURS {
                 if self.get_edge(pos + d) != Edge::Blank {
-                    let nb = self.count_neighbor(pos + d * 2);
+                    let pos2 = self.topology.neighbour(pos.as_vertex(), d).unwrap();
+                    let nb = self.count_neighbor(LP::of_vertex(pos2));
                     if nb.0 + nb.1 == 2 {
                         self.decide(pos + d, Edge::Line);
                     }
@@ -702,15 +878,11 @@ impl AnswerField {
 
         for y in 0..height {
             for x in 0..width {
-                if y < height - 1 {
-                    if self.get_edge(LP(y * 2 + 1, x * 2)) == Edge::Line {
-                        ret.set_down(P(y, x), true);
-                    }
+                if self.get_edge(LP(y * 2 + 1, x * 2)) == Edge::Line {
+                    ret.set_down(P(y, x), true);
                 }
-                if x < width - 1 {
-                    if self.get_edge(LP(y * 2, x * 2 + 1)) == Edge::Line {
-                        ret.set_right(P(y, x), true);
-                    }
+                if self.get_edge(LP(y * 2, x * 2 + 1)) == Edge::Line {
+                    ret.set_right(P(y, x), true);
                 }
             }
         }
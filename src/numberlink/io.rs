@@ -2,23 +2,156 @@ use std::io::BufRead;
 
 use super::*;
 use common::Grid;
-use io::{read_grid, ReadError};
+use io::{next_valid_line, ReadError};
 
-pub fn read_penciloid_problem<T: BufRead>(reader: &mut T) -> Result<Grid<Clue>, ReadError> {
-    read_grid(
-        reader,
-        |token: &str| {
-            if token == "." {
-                Ok(NO_CLUE)
-            } else {
-                let n = token.parse::<i32>().map_err(|_| ReadError::InvalidValue)?;
-                if n <= 0 {
-                    Err(ReadError::InvalidValue)
-                } else {
-                    Ok(Clue(n))
+/// A problem together with the board shape and the rule set specified in its header.
+pub struct VariantProblem {
+    pub clues: Grid<Clue>,
+    pub topology: Topology,
+    pub rule: Rule,
+}
+
+/// Reads a problem, which may have bridges and warps.
+/// A bridge cell is denoted by `+` and an `UNUSED` cell by `#`.
+/// The header `height width` may be followed by `torus`, or by `rows:y1,y2,...` and
+/// `columns:x1,x2,...` listing the rows and columns which wrap around.
+/// The rule set is given by `rule:classic` (default) or `rule:fill`.
+pub fn read_penciloid_problem<T: BufRead>(reader: &mut T) -> Result<VariantProblem, ReadError> {
+    let mut buffer = String::new();
+
+    next_valid_line(reader, &mut buffer)?;
+    let mut header = buffer.split_whitespace();
+    let height = header
+        .next()
+        .ok_or(ReadError::InvalidFormat)?
+        .parse::<i32>()
+        .map_err(|_| ReadError::InvalidValue)?;
+    let width = header
+        .next()
+        .ok_or(ReadError::InvalidFormat)?
+        .parse::<i32>()
+        .map_err(|_| ReadError::InvalidValue)?;
+    if height <= 0 || width <= 0 {
+        return Err(ReadError::InvalidValue);
+    }
+
+    let mut topology = Topology::new(height, width);
+    let mut rule = Rule::Classic;
+    for option in header {
+        match option {
+            "torus" => {
+                // merged with the warps given by `rows:` / `columns:` in any order
+                for y in 0..height {
+                    topology.set_warp_row(y, true);
+                }
+                for x in 0..width {
+                    topology.set_warp_column(x, true);
                 }
+                continue;
             }
-        },
-        NO_CLUE,
-    )
+            "rule:classic" => {
+                rule = Rule::Classic;
+                continue;
+            }
+            "rule:fill" => {
+                rule = Rule::FillAll;
+                continue;
+            }
+            _ => (),
+        }
+        let (is_row, list) = if let Some(list) = option.strip_prefix("rows:") {
+            (true, list)
+        } else if let Some(list) = option.strip_prefix("columns:") {
+            (false, list)
+        } else {
+            return Err(ReadError::InvalidFormat);
+        };
+        for token in list.split(',') {
+            let n = token.parse::<i32>().map_err(|_| ReadError::InvalidValue)?;
+            let limit = if is_row { height } else { width };
+            if n < 0 || n >= limit {
+                return Err(ReadError::InvalidValue);
+            }
+            if is_row {
+                topology.set_warp_row(n, true);
+            } else {
+                topology.set_warp_column(n, true);
+            }
+        }
+    }
+
+    let mut problem = Grid::new(height, width, NO_CLUE);
+    for y in 0..height {
+        next_valid_line(reader, &mut buffer)?;
+        let mut row = buffer.trim_end().split(' ');
+
+        for x in 0..width {
+            let token = row.next().ok_or(ReadError::InvalidFormat)?;
+            if token == "." {
+                continue;
+            }
+            if token == "+" {
+                topology.set_bridge(P(y, x), true);
+                continue;
+            }
+            if token == "#" {
+                problem[P(y, x)] = UNUSED;
+                continue;
+            }
+            let n = token.parse::<i32>().map_err(|_| ReadError::InvalidValue)?;
+            // a clue never exceeds the number of cells; larger ones would blow up the solver
+            if n <= 0 || n as i64 > height as i64 * width as i64 {
+                return Err(ReadError::InvalidValue);
+            }
+            problem[P(y, x)] = Clue(n);
+        }
+    }
+
+    Ok(VariantProblem {
+        clues: problem,
+        topology,
+        rule,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_variant_problem() {
+        let src = "% comment\n3 4 rows:1 columns:0,3\n1 . . 2\n. + . #\n2 . . 1\n";
+        let problem = read_penciloid_problem(&mut src.as_bytes()).unwrap();
+        let topology = &problem.topology;
+        assert!(problem.clues[P(0, 3)] == Clue(2));
+        assert!(problem.clues[P(1, 1)] == NO_CLUE);
+        assert!(problem.clues[P(1, 3)] == UNUSED);
+        assert_eq!(problem.rule, Rule::Classic);
+        assert!(topology.bridge(P(1, 1)));
+        assert!(!topology.warp_row(0));
+        assert!(topology.warp_row(1));
+        assert!(topology.warp_column(0));
+        assert!(!topology.warp_column(1));
+
+        let src = "2 2 torus rule:fill\n1 .\n. 1\n";
+        let problem = read_penciloid_problem(&mut src.as_bytes()).unwrap();
+        assert_eq!(problem.topology, Topology::torus(2, 2));
+        assert_eq!(problem.rule, Rule::FillAll);
+        for src in &[
+            "2 2 rows:0 torus\n1 .\n. 1\n",
+            "2 2 torus columns:1\n1 .\n. 1\n",
+        ] {
+            let problem = read_penciloid_problem(&mut src.as_bytes()).unwrap();
+            assert_eq!(problem.topology, Topology::torus(2, 2));
+        }
+
+        let src = "2 2\n1 .\n. 1\n";
+        let problem = read_penciloid_problem(&mut src.as_bytes()).unwrap();
+        assert!(problem.topology.is_plain());
+        assert_eq!(problem.rule, Rule::Classic);
+        let src = "2 2 rows:2\n1 .\n. 1\n";
+        assert!(read_penciloid_problem(&mut src.as_bytes()).is_err());
+        let src = "1 2\n2000000000 2000000000\n";
+        assert!(read_penciloid_problem(&mut src.as_bytes()).is_err());
+    }
 }
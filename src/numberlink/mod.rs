@@ -1,14 +1,20 @@
 use std::ops::Index;
 
+#[cfg(test)]
+mod brute_force;
+mod checker;
 mod generator;
 mod generator_field;
 mod io;
 mod solver2;
+mod topology;
 
+pub use self::checker::*;
 pub use self::generator::*;
 use self::generator_field::*;
 pub use self::io::*;
 pub use self::solver2::*;
+pub use self::topology::*;
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub struct Clue(pub i32);
@@ -16,9 +22,22 @@ pub struct Clue(pub i32);
 pub const NO_CLUE: Clue = Clue(0);
 pub const UNUSED: Clue = Clue(-1);
 
+/// Rule sets of Numberlink.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rule {
+    /// Cells may be left empty (the classic Numberlink).
+    /// A problem is considered valid if its unique answer nevertheless fills every cell.
+    Classic,
+    /// Every cell which is not `UNUSED` must be passed by a line (Flow).
+    FillAll,
+}
+
 use super::{Grid, D, LP, P};
 use FOUR_NEIGHBOURS;
 
+/// Lines placed on a board.
+/// `right(P(y, width - 1))` and `down(P(height - 1, x))` are the warps of row `y`
+/// and column `x` respectively (see `Topology`).
 #[derive(Clone)]
 pub struct LinePlacement {
     right: Grid<bool>,
@@ -28,8 +47,8 @@ pub struct LinePlacement {
 impl LinePlacement {
     pub fn new(height: i32, width: i32) -> LinePlacement {
         LinePlacement {
-            right: Grid::new(height, width - 1, false),
-            down: Grid::new(height - 1, width, false),
+            right: Grid::new(height, width, false),
+            down: Grid::new(height, width, false),
         }
     }
     pub fn height(&self) -> i32 {
@@ -58,21 +77,29 @@ impl LinePlacement {
             _ => panic!(),
         }
     }
+    /// Returns whether edge `pos` has a line, where edges beyond the top or left border
+    /// are the warps.
     pub fn get_checked(&self, pos: LP) -> bool {
         let LP(y, x) = pos;
-        if 0 <= y && y < self.height() * 2 - 1 && 0 <= x && x < self.width() * 2 - 1 {
-            self.get(pos)
+        let y = if y == -1 { self.height() * 2 - 1 } else { y };
+        let x = if x == -1 { self.width() * 2 - 1 } else { x };
+        if 0 <= y && y < self.height() * 2 && 0 <= x && x < self.width() * 2 {
+            self.get(LP(y, x))
         } else {
             false
         }
     }
     pub fn isolated(&self, pos: P) -> bool {
-        !(self.right(pos + D(0, -1))
-            || self.right(pos)
-            || self.down(pos + D(-1, 0))
-            || self.down(pos))
+        self.n_lines(pos) == 0
     }
     pub fn is_endpoint(&self, pos: P) -> bool {
+        self.n_lines(pos) == 1
+    }
+    /// Returns whether `pos` is a bridge cell passed by two lines.
+    pub fn is_crossing(&self, pos: P) -> bool {
+        self.n_lines(pos) == 4
+    }
+    fn n_lines(&self, pos: P) -> i32 {
         let mut n_lines = 0;
         let pos_vtx = LP::of_vertex(pos);
         for &d in &FOUR_NEIGHBOURS {
@@ -80,9 +107,18 @@ impl LinePlacement {
                 n_lines += 1;
             }
         }
-        n_lines == 1
+        n_lines
     }
-    pub fn extract_chain_groups(&self) -> Option<Grid<i32>> {
+    /// Returns the cell next to `pos` in direction `d`, following warps.
+    fn next_cell(&self, pos: P, d: D) -> P {
+        let height = self.height();
+        let width = self.width();
+        let P(y, x) = pos + d;
+        P((y + height) % height, (x + width) % width)
+    }
+    /// Assigns ids to the chains starting from endpoints.
+    /// Cells not reachable from any endpoint, as well as bridge cells, are left -1.
+    pub fn chain_ids(&self) -> Grid<i32> {
         let height = self.height();
         let width = self.width();
         let mut ids = Grid::new(height, width, -1);
@@ -93,15 +129,20 @@ impl LinePlacement {
                 let pos = P(y, x);
                 if self.is_endpoint(pos) && ids[pos] == -1 {
                     // traverse chain
-                    let mut l = P(-1, -1);
+                    let mut last_d = D(0, 0);
                     let mut c = pos;
 
                     'traverse: loop {
+                        if self.is_crossing(c) {
+                            // a line goes straight through a bridge
+                            c = self.next_cell(c, last_d);
+                            continue 'traverse;
+                        }
                         ids[c] = last_id;
                         for &d in &FOUR_NEIGHBOURS {
-                            if c + d != l && self.get_checked(LP::of_vertex(c) + d) {
-                                l = c;
-                                c = c + d;
+                            if d != last_d * -1 && self.get_checked(LP::of_vertex(c) + d) {
+                                last_d = d;
+                                c = self.next_cell(c, d);
                                 continue 'traverse;
                             }
                         }
@@ -111,18 +152,32 @@ impl LinePlacement {
                 }
             }
         }
+        ids
+    }
+    pub fn extract_chain_groups(&self) -> Option<Grid<i32>> {
+        let height = self.height();
+        let width = self.width();
+        let ids = self.chain_ids();
 
+        // bridge cells, belonging to two chains, are left -1
         for y in 0..height {
             for x in 0..width {
                 let pos = P(y, x);
-                if ids[pos] == -1 {
-                    return None;
+                if self.is_crossing(pos) {
+                    continue;
                 }
-                if y < height - 1 && (ids[pos] == ids[pos + D(1, 0)]) != self.down(pos) {
+                if ids[pos] == -1 {
                     return None;
                 }
-                if x < width - 1 && (ids[pos] == ids[pos + D(0, 1)]) != self.right(pos) {
-                    return None;
+                for &(d, line) in &[(D(1, 0), self.down(pos)), (D(0, 1), self.right(pos))] {
+                    let P(y2, x2) = pos + d;
+                    if (y2 == height || x2 == width) && !line {
+                        continue;
+                    }
+                    let pos2 = self.next_cell(pos, d);
+                    if !self.is_crossing(pos2) && (ids[pos] == ids[pos2]) != line {
+                        return None;
+                    }
                 }
             }
         }
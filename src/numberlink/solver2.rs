@@ -1,6 +1,9 @@
-use super::super::{Grid, D, LP, P};
+use super::super::{Grid, SolverStats, D, LP, P};
 use super::*;
 use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 enum Edge {
@@ -9,6 +12,7 @@ enum Edge {
     Blank,
 }
 
+#[derive(Clone)]
 enum History {
     AnotherEnd(i32, i32),
     Edge(LP),
@@ -18,20 +22,27 @@ enum History {
     Checkpoint,
 }
 
+#[derive(Clone)]
 struct SolverField {
-    another_end: Grid<i32>,        // height * width
+    another_end: Vec<i32>,      // height * width + (number of bridges)
+    bridge_vertical: Grid<i32>, // height * width
+    bridge_cells: Vec<P>,
+    plain: bool,
+    /// Whether only answers in the canonical form (without detours) are searched.
+    /// This is valid only on plain boards where cells may be left unused.
+    canonical: bool,
     has_clue: Grid<bool>,          // height * width
     unused: Grid<bool>,            // height * width
     down_left: Grid<bool>,         // height * width
     down_right: Grid<bool>,        // height * width
     left_clue_distance: Grid<i32>, // height * width
-    edge: Grid<Edge>,              // (2 * height - 1) * (2 * width - 1)
+    edge: Grid<Edge>,              // (2 * height) * (2 * width)
     inconsistent: bool,
     disallow_unused_cell: bool,
     history: Vec<History>,
 
     // for cut-based pruning
-    undecided_count: Vec<i32>,   // width - 1
+    undecided_count: Vec<i32>,   // width
     open_end_count: Vec<i32>,    // width
     number_end: Vec<(i32, i32)>, // max clue
 }
@@ -39,14 +50,42 @@ struct SolverField {
 const CLOSED_END: i32 = -1;
 
 impl SolverField {
-    fn new(problem: &Grid<Clue>, disallow_unused_cell: bool) -> SolverField {
+    fn new(
+        problem: &Grid<Clue>,
+        topology: Option<&Topology>,
+        disallow_unused_cell: bool,
+    ) -> SolverField {
         let height = problem.height();
         let width = problem.width();
-        let mut another_end = Grid::new(height, width, 0);
-        let mut edge = Grid::new(height * 2 - 1, width * 2 - 1, Edge::Undecided);
+        let plain_topology;
+        let topology = match topology {
+            Some(topology) => {
+                assert_eq!(topology.height(), height);
+                assert_eq!(topology.width(), width);
+                topology
+            }
+            None => {
+                plain_topology = Topology::new(height, width);
+                &plain_topology
+            }
+        };
+        let mut another_end = vec![0; (height * width) as usize];
+        let mut bridge_vertical = Grid::new(height, width, -1);
+        let mut bridge_cells = vec![];
+        let mut edge = Grid::new(height * 2, width * 2, Edge::Undecided);
         let mut has_clue = Grid::new(height, width, false);
         let mut unused = Grid::new(height, width, false);
         let mut max_clue = 0;
+        for y in 0..(height * 2) {
+            for x in 0..(width * 2) {
+                // edges beyond the bottom or right border, which exist only as warps
+                if (y == height * 2 - 1 || x == width * 2 - 1)
+                    && topology.canonical_edge(LP(y, x)).is_none()
+                {
+                    edge[LP(y, x)] = Edge::Blank;
+                }
+            }
+        }
         for y in 0..height {
             for x in 0..width {
                 let pos = P(y, x);
@@ -55,26 +94,26 @@ impl SolverField {
                     has_clue[pos] = true;
                     unused[pos] = true;
 
-                    if y > 0 {
-                        edge[LP::of_vertex(pos) + D(-1, 0)] = Edge::Blank;
-                    }
-                    if x > 0 {
-                        edge[LP::of_vertex(pos) + D(0, -1)] = Edge::Blank;
-                    }
-                    if y < height - 1 {
-                        edge[LP::of_vertex(pos) + D(1, 0)] = Edge::Blank;
-                    }
-                    if x < width - 1 {
-                        edge[LP::of_vertex(pos) + D(0, 1)] = Edge::Blank;
+                    for &d in &FOUR_NEIGHBOURS {
+                        if let Some(e) = topology.canonical_edge(LP::of_vertex(pos) + d) {
+                            edge[e] = Edge::Blank;
+                        }
                     }
                 } else if c == NO_CLUE {
-                    let id = another_end.index_p(pos) as i32;
-                    another_end[pos] = id;
+                    let id = has_clue.index_p(pos) as i32;
+                    another_end[id as usize] = id;
                 } else {
                     max_clue = ::std::cmp::max(max_clue, c.0);
-                    another_end[pos] = -(c.0 + 1);
+                    another_end[has_clue.index_p(pos)] = -(c.0 + 1);
                     has_clue[pos] = true;
                 }
+                if topology.bridge(pos) {
+                    // the horizontal pass of a bridge is the cell itself, and the vertical one
+                    // is given another id
+                    bridge_vertical[pos] = another_end.len() as i32;
+                    another_end.push(another_end.len() as i32);
+                    bridge_cells.push(pos);
+                }
             }
         }
         let mut down_left = Grid::new(height, width, false);
@@ -105,7 +144,7 @@ impl SolverField {
                 }
             }
         }
-        let undecided_count = vec![height; (width - 1) as usize];
+        let undecided_count = vec![height; width as usize];
         let open_end_count = vec![0; width as usize];
         let mut number_end = vec![(-1, -1); (max_clue + 1) as usize];
         for y in 0..height {
@@ -123,6 +162,10 @@ impl SolverField {
         }
         let mut ret = SolverField {
             another_end,
+            bridge_vertical,
+            bridge_cells,
+            plain: topology.is_plain(),
+            canonical: topology.is_plain() && !disallow_unused_cell,
             has_clue,
             unused,
             down_left,
@@ -136,6 +179,19 @@ impl SolverField {
             open_end_count,
             number_end,
         };
+        for i in 0..ret.bridge_cells.len() {
+            let pos = ret.bridge_cells[i];
+            if problem[pos] != NO_CLUE {
+                ret.set_inconsistent();
+                return ret;
+            }
+            // both lines go straight through a bridge
+            for &d in &FOUR_NEIGHBOURS {
+                if ret.decide_edge(LP::of_vertex(pos) + d, Edge::Line) {
+                    return ret;
+                }
+            }
+        }
         if disallow_unused_cell {
             for y in 0..height {
                 for x in 0..width {
@@ -145,7 +201,17 @@ impl SolverField {
         }
         ret
     }
+    /// Returns the position where edge `pos` is stored.
+    /// The warps are stored as edges beyond the bottom or right border.
+    fn wrap_edge(&self, pos: LP) -> LP {
+        let LP(y, x) = pos;
+        LP(
+            if y == -1 { self.edge.height() - 1 } else { y },
+            if x == -1 { self.edge.width() - 1 } else { x },
+        )
+    }
     fn get_edge(&self, pos: LP) -> Edge {
+        let pos = self.wrap_edge(pos);
         if self.edge.is_valid_lp(pos) {
             self.edge[pos]
         } else {
@@ -153,10 +219,31 @@ impl SolverField {
         }
     }
     fn height(&self) -> i32 {
-        self.another_end.height()
+        self.has_clue.height()
     }
     fn width(&self) -> i32 {
-        self.another_end.width()
+        self.has_clue.width()
+    }
+    fn is_bridge(&self, pos: P) -> bool {
+        self.bridge_vertical[pos] >= 0
+    }
+    /// Returns the id of the vertex at cell `pos` to which a `vertical` (or horizontal) edge
+    /// is incident.
+    fn vertex_id(&self, pos: P, vertical: bool) -> i32 {
+        if vertical && self.bridge_vertical[pos] >= 0 {
+            self.bridge_vertical[pos]
+        } else {
+            self.has_clue.index_p(pos) as i32
+        }
+    }
+    /// Returns the column of the vertex `id`.
+    fn vertex_x(&self, id: i32) -> i32 {
+        let n_cells = self.height() * self.width();
+        if id < n_cells {
+            id % self.width()
+        } else {
+            self.bridge_cells[(id - n_cells) as usize].x()
+        }
     }
     fn get_line_placement(&self) -> LinePlacement {
         let height = self.height();
@@ -165,16 +252,27 @@ impl SolverField {
         for y in 0..height {
             for x in 0..width {
                 let pos = P(y, x);
-                if y != height - 1 && self.get_edge(LP::of_vertex(pos) + D(1, 0)) == Edge::Line {
+                if self.get_edge(LP::of_vertex(pos) + D(1, 0)) == Edge::Line {
                     ret.set_down(pos, true);
                 }
-                if x != width - 1 && self.get_edge(LP::of_vertex(pos) + D(0, 1)) == Edge::Line {
+                if self.get_edge(LP::of_vertex(pos) + D(0, 1)) == Edge::Line {
                     ret.set_right(pos, true);
                 }
             }
         }
         ret
     }
+    fn n_undecided_edges(&self) -> i32 {
+        let mut ret = 0;
+        for y in 0..self.edge.height() {
+            for x in 0..self.edge.width() {
+                if y % 2 != x % 2 && self.edge[LP(y, x)] == Edge::Undecided {
+                    ret += 1;
+                }
+            }
+        }
+        ret
+    }
 
     fn set_inconsistent(&mut self) -> bool {
         self.history.push(History::Inconsistent(self.inconsistent));
@@ -240,6 +338,7 @@ impl SolverField {
     /// Decide edge `cd`.
     /// `cd` must be in universal-coordination.
     fn decide_edge(&mut self, pos: LP, state: Edge) -> bool {
+        let pos = self.wrap_edge(pos);
         let current_state = self.get_edge(pos);
         if current_state != Edge::Undecided {
             if current_state != state {
@@ -255,17 +354,17 @@ impl SolverField {
         let end2;
         if y % 2 == 0 {
             end1 = P(y / 2, x / 2);
-            end2 = P(y / 2, x / 2 + 1);
+            end2 = P(y / 2, (x / 2 + 1) % self.width());
         } else {
             end1 = P(y / 2, x / 2);
-            end2 = P(y / 2 + 1, x / 2);
+            end2 = P((y / 2 + 1) % self.height(), x / 2);
         }
-        let end1_id = self.another_end.index_p(end1) as i32;
-        let end2_id = self.another_end.index_p(end2) as i32;
+        let end1_id = self.vertex_id(end1, y % 2 == 1);
+        let end2_id = self.vertex_id(end2, y % 2 == 1);
 
         if state == Edge::Line {
-            let another_end1_id = self.another_end[end1];
-            let another_end2_id = self.another_end[end2];
+            let another_end1_id = self.another_end[end1_id as usize];
+            let another_end2_id = self.another_end[end2_id as usize];
 
             // connecting closed ends / closing single chain
             if another_end1_id == CLOSED_END
@@ -285,7 +384,7 @@ impl SolverField {
                     }
                 }
                 (false, true) => {
-                    let ae1_x = self.another_end.p(another_end1_id as usize).x();
+                    let ae1_x = self.vertex_x(another_end1_id);
                     self.update_open_end_count(ae1_x, end1.1, -1);
                     self.update_number_end(-another_end2_id - 1, end2.1, ae1_x);
                     if end1_id != another_end1_id {
@@ -295,7 +394,7 @@ impl SolverField {
                     self.update_another_end(end2_id, CLOSED_END);
                 }
                 (true, false) => {
-                    let ae2_x = self.another_end.p(another_end2_id as usize).x();
+                    let ae2_x = self.vertex_x(another_end2_id);
                     self.update_open_end_count(ae2_x, end2.1, -1);
                     self.update_number_end(-another_end1_id - 1, end1.1, ae2_x);
                     if end2_id != another_end2_id {
@@ -305,8 +404,8 @@ impl SolverField {
                     self.update_another_end(end1_id, CLOSED_END);
                 }
                 (false, false) => {
-                    let ae1_x = self.another_end.p(another_end1_id as usize).x();
-                    let ae2_x = self.another_end.p(another_end2_id as usize).x();
+                    let ae1_x = self.vertex_x(another_end1_id);
+                    let ae2_x = self.vertex_x(another_end2_id);
                     self.update_open_end_count(ae1_x, end1.1, -1);
                     self.update_open_end_count(ae2_x, end2.1, -1);
                     self.update_open_end_count(ae1_x, ae2_x, 1);
@@ -330,7 +429,7 @@ impl SolverField {
         }
 
         // ensure canonical form
-        if state == Edge::Line {
+        if state == Edge::Line && self.canonical {
             if y % 2 == 0 {
                 if !self.down_right[P(y / 2, x / 2)] && self.get_edge(pos + D(1, -1)) == Edge::Line
                 {
@@ -499,7 +598,7 @@ impl SolverField {
     /// Inspect vertex `cd`.
     /// `cd` must be in vertex-coordination.
     fn inspect(&mut self, pos: P) -> bool {
-        if self.unused[pos] {
+        if self.unused[pos] || self.is_bridge(pos) {
             return false;
         }
 
@@ -515,13 +614,14 @@ impl SolverField {
             }
         }
 
-        let another_end = self.another_end[pos];
+        let another_end = self.another_end[self.has_clue.index_p(pos)];
         if another_end < -1 {
             for &d in &FOUR_NEIGHBOURS {
                 let pos2 = pos + d;
-                if self.another_end.is_valid_p(pos2) {
-                    let another_end2 = self.another_end[pos2];
-                    if another_end2 < -1 {
+                if self.has_clue.is_valid_p(pos2) && !self.is_bridge(pos2) {
+                    let another_end2 = self.another_end[self.has_clue.index_p(pos2)];
+                    // adjacent ends of the same number are connected only in the canonical form
+                    if another_end2 < -1 && (self.canonical || another_end != another_end2) {
                         if self.decide_edge(
                             LP::of_vertex(pos) + d,
                             if another_end == another_end2 {
@@ -606,7 +706,7 @@ impl fmt::Debug for SolverField {
                     (0, 0) => write!(
                         f,
                         "{}",
-                        match self.another_end[P(y / 2, x / 2)] {
+                        match self.another_end[self.has_clue.index_p(P(y / 2, x / 2))] {
                             n @ -100...-2 => trans[((-n) - 2) as usize],
                             _ => '+',
                         }
@@ -639,43 +739,585 @@ impl fmt::Debug for SolverField {
         Ok(())
     }
 }
-struct AnswerInfo {
-    answers: Vec<LinePlacement>,
-    limit: Option<usize>,
+/// Options of `solve2_streaming`.
+pub struct SearchOption<'a> {
+    /// Bridges and warps of the board, or `None` for a plain board.
+    pub topology: Option<&'a Topology>,
+    /// Whether every cell which is not `UNUSED` must be passed by a line.
+    /// Answers differing only in detours are reported separately if this is set or
+    /// the board is not plain; otherwise only the answers without detours are reported.
+    pub disallow_unused_cell: bool,
+    /// Stops the search when an answer with a cell not filled by any line is found.
+    pub terminate_on_not_fully_filled: bool,
+    /// Maximum number of search steps, or `None` if unlimited.
+    pub step_limit: Option<u64>,
+    /// The search stops soon after this flag is set (e.g. by another thread).
+    pub cancel: Option<&'a AtomicBool>,
+}
+
+/// Result of `solve2_streaming`.
+pub struct SearchSummary {
+    pub n_answers: usize,
+    pub n_steps: u64,
+    /// Whether the search finished without being stopped, i.e. all answers were reported.
+    pub exhaustive: bool,
+    pub found_not_fully_filled: bool,
+}
+
+struct AnswerInfo<'a, F> {
+    callback: F,
+    n_answers: usize,
     terminate_on_not_fully_filled: bool,
     found_not_fully_filled: bool,
+    step_limit: Option<u64>,
+    cancel: Option<&'a AtomicBool>,
+    /// Set by a thread of `search_parallel` when the other threads should stop.
+    shared_stop: Option<&'a AtomicBool>,
+    stopped: bool,
+    /// If given, the nodes at depth 1 and the answers above them are collected here
+    /// instead of being searched.
+    frontier: Option<Vec<SearchNode>>,
+}
+
+/// A node of the search tree from which `search` can be resumed.
+#[derive(Clone)]
+struct SearchNode {
+    field: SolverField,
+    y: i32,
+    x: i32,
+    line_chain: i32,
 }
 
+impl SearchNode {
+    fn is_answer(&self) -> bool {
+        self.y == self.field.height()
+    }
+}
+
+/// The number of frontier nodes per thread aimed at in `solve2_parallel`, so that the threads
+/// finishing their subtrees early can take the remaining ones.
+const PARALLEL_FRONTIER_PER_THREAD: usize = 16;
+
 pub fn solve2(
     problem: &Grid<Clue>,
     limit: Option<usize>,
     disallow_unused_cell: bool,
     terminate_on_not_fully_filled: bool,
 ) -> AnswerDetail {
-    let mut solver_field = SolverField::new(problem, disallow_unused_cell);
-    let mut answer_info = AnswerInfo {
-        answers: Vec::new(),
-        limit,
+    let option = SearchOption {
+        topology: None,
+        disallow_unused_cell,
         terminate_on_not_fully_filled,
-        found_not_fully_filled: false,
+        step_limit: None,
+        cancel: None,
     };
+    collect_answers(problem, &option, limit)
+}
+
+/// Collects at most `limit` answers of `problem` by `solve2_streaming`.
+fn collect_answers(
+    problem: &Grid<Clue>,
+    option: &SearchOption,
+    limit: Option<usize>,
+) -> AnswerDetail {
+    let mut answers = vec![];
+    let summary = solve2_streaming(problem, option, |answer| {
+        answers.push(answer.clone());
+        match limit {
+            Some(limit) => answers.len() < limit,
+            None => true,
+        }
+    });
+
+    let fully_checked = if let Some(limit) = limit {
+        limit == answers.len()
+    } else {
+        true
+    };
+
+    AnswerDetail {
+        answers,
+        fully_checked,
+        found_not_fully_filled: summary.found_not_fully_filled,
+        n_steps: summary.n_steps,
+    }
+}
+
+/// Searches answers of `problem`, passing each of them to `callback` as soon as it is found.
+/// The search stops when `callback` returns `false`, the step limit is exceeded or
+/// `option.cancel` is set.
+pub fn solve2_streaming<F>(
+    problem: &Grid<Clue>,
+    option: &SearchOption,
+    callback: F,
+) -> SearchSummary
+where
+    F: FnMut(&LinePlacement) -> bool,
+{
+    run_search(problem, option, callback)
+}
+
+/// Parallel version of `solve2` using `n_threads` threads, which also supports variant boards.
+/// The search tree is cut at the shallowest depth with at least `PARALLEL_FRONTIER_PER_THREAD`
+/// nodes per thread, and each thread repeatedly takes a subtree not taken yet.
+/// All threads stop once `limit` answers are found or `cancel` is set.
+/// `n_steps` of the result is the total over the threads and the search above the cut.
+pub fn solve2_parallel(
+    problem: &Grid<Clue>,
+    topology: Option<&Topology>,
+    limit: Option<usize>,
+    disallow_unused_cell: bool,
+    terminate_on_not_fully_filled: bool,
+    n_threads: usize,
+    cancel: Option<&AtomicBool>,
+) -> AnswerDetail {
+    let option = SearchOption {
+        topology,
+        disallow_unused_cell,
+        terminate_on_not_fully_filled,
+        step_limit: None,
+        cancel,
+    };
+    if n_threads <= 1 {
+        return collect_answers(problem, &option, limit);
+    }
+    search_parallel(
+        problem,
+        &option,
+        limit,
+        n_threads,
+        n_threads * PARALLEL_FRONTIER_PER_THREAD,
+    )
+}
+
+/// Returns the nodes at the shallowest depth of the search tree with at least `target` nodes,
+/// together with the answers above it, in the order visited by `search`.
+/// If the tree has fewer nodes, its leaves are returned. The second value is the number
+/// of steps spent.
+fn build_frontier(
+    problem: &Grid<Clue>,
+    option: &SearchOption,
+    target: usize,
+) -> (Vec<SearchNode>, u64) {
+    let field = SolverField::new(problem, option.topology, option.disallow_unused_cell);
+    if field.inconsistent {
+        return (vec![], 0);
+    }
+    let mut frontier = vec![SearchNode {
+        field,
+        y: 0,
+        x: 0,
+        line_chain: 0,
+    }];
     let mut n_steps = 0u64;
+    while frontier.len() < target && frontier.iter().any(|node| !node.is_answer()) {
+        if is_cancelled(option.cancel) {
+            break;
+        }
+        // each node is expanded by one level, so the search above it is not repeated
+        let mut next = vec![];
+        for mut node in frontier {
+            if node.is_answer() {
+                next.push(node);
+                continue;
+            }
+            let mut answer_info = AnswerInfo {
+                callback: |_: &LinePlacement| true,
+                n_answers: 0,
+                terminate_on_not_fully_filled: false,
+                found_not_fully_filled: false,
+                step_limit: None,
+                cancel: option.cancel,
+                shared_stop: None,
+                stopped: false,
+                frontier: Some(next),
+            };
+            search(
+                node.y,
+                node.x,
+                &mut node.field,
+                &mut answer_info,
+                &mut n_steps,
+                node.line_chain,
+                0,
+            );
+            next = answer_info.frontier.unwrap();
+        }
+        frontier = next;
+    }
+    (frontier, n_steps)
+}
+
+/// Runs `solve2_parallel` with the frontier of `target` nodes.
+fn search_parallel(
+    problem: &Grid<Clue>,
+    option: &SearchOption,
+    limit: Option<usize>,
+    n_threads: usize,
+    target: usize,
+) -> AnswerDetail {
+    let (frontier, n_frontier_steps) = build_frontier(problem, option, target);
+    let next_node = AtomicUsize::new(0);
 
-    search(0, 0, &mut solver_field, &mut answer_info, &mut n_steps, 0);
+    let cancel = AtomicBool::new(false);
+    let found_not_fully_filled = AtomicBool::new(false);
+    let n_steps = AtomicU64::new(n_frontier_steps);
+    let answers = Mutex::new(vec![]);
 
+    thread::scope(|s| {
+        for _ in 0..n_threads {
+            let frontier = &frontier;
+            let next_node = &next_node;
+            let cancel = &cancel;
+            let found_not_fully_filled = &found_not_fully_filled;
+            let n_steps = &n_steps;
+            let answers = &answers;
+            s.spawn(move || {
+                let mut answer_info = AnswerInfo {
+                    callback: |answer: &LinePlacement| {
+                        let mut answers = answers.lock().unwrap();
+                        let proceed = match limit {
+                            Some(limit) => {
+                                if answers.len() < limit {
+                                    answers.push(answer.clone());
+                                }
+                                answers.len() < limit
+                            }
+                            None => {
+                                answers.push(answer.clone());
+                                true
+                            }
+                        };
+                        if !proceed {
+                            cancel.store(true, Ordering::Relaxed);
+                        }
+                        proceed
+                    },
+                    n_answers: 0,
+                    terminate_on_not_fully_filled: option.terminate_on_not_fully_filled,
+                    found_not_fully_filled: false,
+                    step_limit: None,
+                    cancel: option.cancel,
+                    shared_stop: Some(cancel),
+                    stopped: false,
+                    frontier: None,
+                };
+                let mut steps = 0u64;
+                loop {
+                    let idx = next_node.fetch_add(1, Ordering::Relaxed);
+                    if idx >= frontier.len() {
+                        break;
+                    }
+                    let mut node = frontier[idx].clone();
+                    if search(
+                        node.y,
+                        node.x,
+                        &mut node.field,
+                        &mut answer_info,
+                        &mut steps,
+                        node.line_chain,
+                        0,
+                    ) {
+                        break;
+                    }
+                }
+                if answer_info.found_not_fully_filled {
+                    found_not_fully_filled.store(true, Ordering::Relaxed);
+                    cancel.store(true, Ordering::Relaxed);
+                }
+                n_steps.fetch_add(steps, Ordering::Relaxed);
+            });
+        }
+    });
+
+    let answers = answers.into_inner().unwrap();
     let fully_checked = if let Some(limit) = limit {
-        limit == answer_info.answers.len()
+        limit == answers.len()
     } else {
         true
     };
 
     AnswerDetail {
-        answers: answer_info.answers,
+        answers,
         fully_checked,
-        found_not_fully_filled: answer_info.found_not_fully_filled,
+        found_not_fully_filled: found_not_fully_filled.into_inner(),
+        n_steps: n_steps.into_inner(),
+    }
+}
+
+fn is_cancelled(flag: Option<&AtomicBool>) -> bool {
+    match flag {
+        Some(flag) => flag.load(Ordering::Relaxed),
+        None => false,
+    }
+}
+
+/// Runs the search for `solve2_streaming`.
+fn run_search<F>(problem: &Grid<Clue>, option: &SearchOption, callback: F) -> SearchSummary
+where
+    F: FnMut(&LinePlacement) -> bool,
+{
+    let mut solver_field = SolverField::new(problem, option.topology, option.disallow_unused_cell);
+    let mut answer_info = AnswerInfo {
+        callback,
+        n_answers: 0,
+        terminate_on_not_fully_filled: option.terminate_on_not_fully_filled,
+        found_not_fully_filled: false,
+        step_limit: option.step_limit,
+        cancel: option.cancel,
+        shared_stop: None,
+        stopped: false,
+        frontier: None,
+    };
+    let mut n_steps = 0u64;
+
+    // the initial inspection may already have found a contradiction
+    if !solver_field.inconsistent {
+        search(
+            0,
+            0,
+            &mut solver_field,
+            &mut answer_info,
+            &mut n_steps,
+            0,
+            0,
+        );
+    }
+
+    SearchSummary {
+        n_answers: answer_info.n_answers,
         n_steps,
+        exhaustive: !answer_info.stopped,
+        found_not_fully_filled: answer_info.found_not_fully_filled,
+    }
+}
+
+/// Searches answers of `problem` under `rule` until it turns out whether the answer is unique.
+/// Under `Rule::Classic`, the search also stops at an answer leaving some cell empty.
+/// The search is not exhaustive if `cancel` is set before it finishes.
+pub fn test_uniqueness(
+    problem: &Grid<Clue>,
+    topology: Option<&Topology>,
+    rule: Rule,
+    cancel: Option<&AtomicBool>,
+) -> SearchSummary {
+    let option = SearchOption {
+        topology,
+        disallow_unused_cell: rule == Rule::FillAll,
+        terminate_on_not_fully_filled: rule == Rule::Classic,
+        step_limit: None,
+        cancel,
+    };
+    let mut n_answers = 0;
+    solve2_streaming(problem, &option, |_| {
+        n_answers += 1;
+        n_answers < 2
+    })
+}
+
+/// Returns whether `problem` is a valid problem under `rule`, i.e. it has a unique answer
+/// (which fills every cell under `Rule::Classic`).
+pub fn has_unique_answer(problem: &Grid<Clue>, topology: Option<&Topology>, rule: Rule) -> bool {
+    let summary = test_uniqueness(problem, topology, rule, None);
+    summary.exhaustive && summary.n_answers == 1
+}
+
+/// Estimated difficulty of a problem for human solvers.
+pub struct Difficulty {
+    /// The hardest technique needed to solve the problem:
+    /// - 1: forced moves (a line end with only one way to go, or a cell already passed)
+    /// - 2: corner moves (a cell with only two ways left must be passed through them)
+    /// - 3: dead-end avoidance (an edge is decided if the other choice immediately leads to
+    ///   a contradiction such as a dead end or a closed loop)
+    /// - 4: search (the contradiction is found only by assuming two edges)
+    /// - 5: exhaustive search (also when the search above examines too many hypotheses)
+    pub level: i32,
+    /// Weighted sum of the number of edges decided by each technique, where harder
+    /// techniques are weighted more. The steps of exhaustive search are also added.
+    pub score: u64,
+    /// The number of edges decided by each technique and the number of examined hypotheses.
+    pub stats: SolverStats,
+}
+
+const DIFFICULTY_TECHNIQUES: [(&str, u64); 4] = [
+    ("forced", 1),
+    ("corner", 2),
+    ("dead_end", 10),
+    ("search", 40),
+];
+
+/// Maximum number of hypotheses examined by the trial techniques in `estimate_difficulty`.
+/// Problems needing more are left to the exhaustive search (level 5).
+/// 90% of the generated 10x10 problems solved by search (level 4) are within this limit.
+const DIFFICULTY_TRIAL_LIMIT: u64 = 400_000;
+/// Maximum number of steps of the exhaustive search in `estimate_difficulty`.
+const DIFFICULTY_SEARCH_STEP_LIMIT: u64 = 200_000;
+
+/// Estimates the difficulty of `problem` by solving it with the techniques in increasing order
+/// of difficulty, each tried only when all the easier ones are stuck.
+/// Lines are assumed to fill every cell, which holds for valid problems under both rules.
+/// Under `Rule::Classic`, lines are also assumed to make no detours (e.g. a U-turn next to
+/// the line itself), since the answer would not be unique otherwise.
+/// Returns `None` if a contradiction is found, the answer is not unique, the exhaustive
+/// search exceeds `DIFFICULTY_SEARCH_STEP_LIMIT` steps or `cancel` is set.
+pub fn estimate_difficulty(
+    problem: &Grid<Clue>,
+    topology: Option<&Topology>,
+    rule: Rule,
+    cancel: Option<&AtomicBool>,
+) -> Option<Difficulty> {
+    let mut field = SolverField::new(problem, topology, false);
+    if rule == Rule::FillAll {
+        // answers under the Flow rule may need detours to fill the cells
+        field.canonical = false;
+    }
+    let mut stats = SolverStats::new();
+    stats.set_enabled(true);
+    let mut level = 1;
+
+    let height = field.height();
+    let width = field.width();
+    let undecided = field.n_undecided_edges();
+    for y in 0..height {
+        for x in 0..width {
+            field.inspect(P(y, x));
+        }
+    }
+    stats.record("forced", undecided - field.n_undecided_edges());
+
+    let undecided = field.n_undecided_edges();
+    field.disallow_unused_cell = true;
+    for y in 0..height {
+        for x in 0..width {
+            field.inspect(P(y, x));
+        }
+    }
+    let n_corner = undecided - field.n_undecided_edges();
+    stats.record("corner", n_corner);
+    if n_corner > 0 {
+        level = 2;
+    }
+
+    while !field.inconsistent && field.n_undecided_edges() > 0 {
+        if is_cancelled(cancel) {
+            return None;
+        }
+        let n_dead_end = trial_step(&mut field, 1, false, &mut stats);
+        if n_dead_end > 0 {
+            stats.record("dead_end", n_dead_end);
+            level = ::std::cmp::max(level, 3);
+            continue;
+        }
+        if stats.n_trials() >= DIFFICULTY_TRIAL_LIMIT {
+            break;
+        }
+        let n_search = trial_step(&mut field, 2, true, &mut stats);
+        if n_search > 0 {
+            stats.record("search", n_search);
+            level = 4;
+        } else {
+            break;
+        }
+    }
+    if field.inconsistent {
+        return None;
+    }
+
+    let mut score = 0;
+    for &(name, weight) in &DIFFICULTY_TECHNIQUES {
+        score += stats.technique(name).n_decided * weight;
+    }
+
+    let n_undecided = field.n_undecided_edges();
+    if n_undecided > 0 {
+        let mut n_answers = 0;
+        let mut answer_info = AnswerInfo {
+            callback: |_: &LinePlacement| {
+                n_answers += 1;
+                n_answers < 2
+            },
+            n_answers: 0,
+            terminate_on_not_fully_filled: false,
+            found_not_fully_filled: false,
+            step_limit: Some(DIFFICULTY_SEARCH_STEP_LIMIT),
+            cancel,
+            shared_stop: None,
+            stopped: false,
+            frontier: None,
+        };
+        let mut n_steps = 0u64;
+        search(0, 0, &mut field, &mut answer_info, &mut n_steps, 0, 0);
+        if answer_info.stopped || answer_info.n_answers != 1 {
+            return None;
+        }
+        stats.record("exhaustive_search", n_undecided);
+        score += n_undecided as u64 * 100 + n_steps;
+        level = 5;
+    }
+    if is_cancelled(cancel) {
+        return None;
+    }
+
+    Some(Difficulty {
+        level,
+        score,
+        stats,
+    })
+}
+
+/// Decides the undecided edges one of whose states leads to a contradiction, where the
+/// contradiction is searched by assuming `depth` edges in total, in a sweep over the edges.
+/// The sweep stops at the first decided edge if `first_only` is set, or when the number of
+/// hypotheses reaches `DIFFICULTY_TRIAL_LIMIT`.
+/// Returns the number of edges decided, including the ones decided by propagation.
+fn trial_step(
+    field: &mut SolverField,
+    depth: i32,
+    first_only: bool,
+    stats: &mut SolverStats,
+) -> i32 {
+    let undecided = field.n_undecided_edges();
+    for y in 0..field.edge.height() {
+        for x in 0..field.edge.width() {
+            let pos = LP(y, x);
+            if y % 2 == x % 2 {
+                continue;
+            }
+            if stats.n_trials() >= DIFFICULTY_TRIAL_LIMIT {
+                return undecided - field.n_undecided_edges();
+            }
+            for &state in &[Edge::Line, Edge::Blank] {
+                if field.inconsistent || field.edge[pos] != Edge::Undecided {
+                    break;
+                }
+                field.add_checkpoint();
+                let mut refuted = field.decide_edge(pos, state);
+                if !refuted && depth > 1 {
+                    trial_step(field, depth - 1, false, stats);
+                    refuted = field.inconsistent;
+                }
+                field.rollback();
+                stats.record_trial(refuted);
+                if refuted {
+                    let other = if state == Edge::Line {
+                        Edge::Blank
+                    } else {
+                        Edge::Line
+                    };
+                    field.decide_edge(pos, other);
+                    if first_only {
+                        return undecided - field.n_undecided_edges();
+                    }
+                }
+            }
+            if field.inconsistent {
+                return undecided - field.n_undecided_edges();
+            }
+        }
     }
+    undecided - field.n_undecided_edges()
 }
+
 fn prune_cut(field: &SolverField) -> bool {
     let width = field.width();
     let mut accsum = vec![0; width as usize];
@@ -706,13 +1348,14 @@ fn prune_cut(field: &SolverField) -> bool {
     }
     false
 }
-fn search(
+fn search<F: FnMut(&LinePlacement) -> bool>(
     y: i32,
     x: i32,
     field: &mut SolverField,
-    answer_info: &mut AnswerInfo,
+    answer_info: &mut AnswerInfo<F>,
     n_steps: &mut u64,
     line_chain: i32,
+    depth: u32,
 ) -> bool {
     let mut y = y;
     let mut x = x;
@@ -731,7 +1374,7 @@ fn search(
             x = 0;
         } else {
             x += 1;
-            if y > 0 {
+            if field.canonical && y > 0 {
                 if field.get_edge(LP(y * 2, x * 2 - 1)) == Edge::Line {
                     if field.get_edge(LP(y * 2 - 2, x * 2 - 1)) == Edge::Line {
                         line_chain = -field.width();
@@ -751,11 +1394,32 @@ fn search(
             }
         }
     }
+    if let Some(ref mut frontier) = answer_info.frontier {
+        if depth == 1 || y == field.height() {
+            frontier.push(SearchNode {
+                field: field.clone(),
+                y,
+                x,
+                line_chain,
+            });
+            return false;
+        }
+    }
+    let out_of_steps = match answer_info.step_limit {
+        Some(limit) => *n_steps >= limit,
+        None => false,
+    };
+    let cancelled = is_cancelled(answer_info.cancel) || is_cancelled(answer_info.shared_stop);
+    if out_of_steps || cancelled {
+        answer_info.stopped = true;
+        return true;
+    }
     *n_steps += 1;
 
     if y == field.height() {
         // answer found
-        answer_info.answers.push(field.get_line_placement());
+        answer_info.n_answers += 1;
+        let proceed = (answer_info.callback)(&field.get_line_placement());
         if answer_info.terminate_on_not_fully_filled {
             let mut full = true;
             for y in 0..field.height() {
@@ -772,13 +1436,13 @@ fn search(
             }
             if !full {
                 answer_info.found_not_fully_filled = true;
+                answer_info.stopped = true;
                 return true;
             }
         }
-        if let Some(lim) = answer_info.limit {
-            if answer_info.answers.len() >= lim {
-                return true;
-            }
+        if !proceed {
+            answer_info.stopped = true;
+            return true;
         }
         return false;
     }
@@ -818,13 +1482,14 @@ fn search(
         }
 
         let degree = degree_common + if right { 1 } else { 0 } + if down { 1 } else { 0 };
-        if degree != 0 && degree != 2 {
+        // with warps, the edges on the left or above may be still undecided
+        if degree > 2 || (degree == 1 && field.plain) {
             continue;
         }
 
         let right_effective = right || (field.get_edge(LP(y * 2, x * 2 + 1)) == Edge::Line);
         let down_effective = down || (field.get_edge(LP(y * 2 + 1, x * 2)) == Edge::Line);
-        if right_effective && down_effective {
+        if right_effective && down_effective && field.canonical {
             if !field.down_right[P(y, x)] {
                 continue;
             }
@@ -841,21 +1506,30 @@ fn search(
         field.add_checkpoint();
         let mut inconsistent = false;
 
+        // with warps, these edges may have been decided by lines coming around the board
         inconsistent |= field.decide_edge(
             LP(y * 2, x * 2 + 1),
-            if right { Edge::Line } else { Edge::Blank },
+            if right_effective {
+                Edge::Line
+            } else {
+                Edge::Blank
+            },
         );
         if !inconsistent {
             inconsistent |= field.decide_edge(
                 LP(y * 2 + 1, x * 2),
-                if down { Edge::Line } else { Edge::Blank },
+                if down_effective {
+                    Edge::Line
+                } else {
+                    Edge::Blank
+                },
             );
         }
-        if !inconsistent {
+        if !inconsistent && field.plain {
             inconsistent |= prune_cut(field);
         }
         if !inconsistent {
-            let line_chain2 = if right_effective {
+            let line_chain2 = if right_effective && field.canonical {
                 if field.get_edge(LP(y * 2 - 2, x * 2 + 1)) == Edge::Line {
                     -field.width()
                 } else {
@@ -864,7 +1538,15 @@ fn search(
             } else {
                 0
             };
-            if search(y, x + 1, field, answer_info, n_steps, line_chain2) {
+            if search(
+                y,
+                x + 1,
+                field,
+                answer_info,
+                n_steps,
+                line_chain2,
+                depth + 1,
+            ) {
                 return true;
             }
         }
@@ -898,4 +1580,167 @@ mod tests {
         let ans = solve2(&problem, None, false, false);
         assert_eq!(ans.len(), 1);
     }
+    #[test]
+    fn test_solver_streaming() {
+        let mut problem = Grid::new(4, 4, NO_CLUE);
+        problem[P(0, 0)] = Clue(1);
+        problem[P(0, 2)] = Clue(1);
+        problem[P(1, 1)] = Clue(2);
+        problem[P(1, 2)] = Clue(2);
+
+        let all = solve2(&problem, None, false, false);
+        assert_eq!(all.len(), 2);
+
+        let mut option = SearchOption {
+            topology: None,
+            disallow_unused_cell: false,
+            terminate_on_not_fully_filled: false,
+            step_limit: None,
+            cancel: None,
+        };
+        let mut n_answers = 0;
+        let summary = solve2_streaming(&problem, &option, |_| {
+            n_answers += 1;
+            true
+        });
+        assert!(summary.exhaustive);
+        assert_eq!(summary.n_answers, all.len());
+        assert_eq!(n_answers, all.len());
+        assert_eq!(summary.n_steps, all.n_steps);
+
+        let summary = solve2_streaming(&problem, &option, |_| false);
+        assert!(!summary.exhaustive);
+        assert_eq!(summary.n_answers, 1);
+
+        option.step_limit = Some(3);
+        let summary = solve2_streaming(&problem, &option, |_| true);
+        assert!(!summary.exhaustive);
+        assert_eq!(summary.n_steps, 3);
+
+        for n_threads in 1..4 {
+            let found = solve2_parallel(&problem, None, Some(1), false, false, n_threads, None);
+            assert_eq!(found.len(), 1);
+            assert!(found.fully_checked);
+            let found = solve2_parallel(&problem, None, None, false, false, n_threads, None);
+            assert_eq!(found.len(), 2);
+        }
+
+        let cancel = AtomicBool::new(true);
+        option.step_limit = None;
+        option.cancel = Some(&cancel);
+        let summary = solve2_streaming(&problem, &option, |_| true);
+        assert!(!summary.exhaustive);
+        assert_eq!(summary.n_answers, 0);
+    }
+    #[test]
+    fn test_solver_parallel_split() {
+        // bench/numberlink/medium_12x12_02.txt
+        let src = "12 12
+. . . 8 . . . . . . . .
+. 5 . . . . . . . 2 17 .
+. 8 9 5 . . 10 . 17 . . .
+. . . . . . . . . . . .
+. . . . 1 . . . . . . .
+. 14 . . 16 . . . 1 . . .
+9 . . . 13 . . . . . 10 .
+. . 11 . . . 12 . . 4 . .
+. . . . . . . 15 . . 14 6
+. . 12 . 15 . 3 . 2 . . .
+. . . . 13 . 4 3 . . 6 7
+16 11 . . . . . . . 7 . .
+";
+        let unique = read_penciloid_problem(&mut src.as_bytes()).unwrap().clues;
+        // 562 answers filling the board
+        let mut multiple = Grid::new(5, 6, NO_CLUE);
+        multiple[P(0, 0)] = Clue(1);
+        multiple[P(4, 0)] = Clue(1);
+        multiple[P(0, 5)] = Clue(2);
+        multiple[P(4, 5)] = Clue(2);
+
+        for &(ref problem, fill) in &[(unique, false), (multiple, true)] {
+            let option = SearchOption {
+                topology: None,
+                disallow_unused_cell: fill,
+                terminate_on_not_fully_filled: false,
+                step_limit: None,
+                cancel: None,
+            };
+            let n_threads = 3;
+            let target = n_threads * PARALLEL_FRONTIER_PER_THREAD;
+            let (frontier, _) = build_frontier(problem, &option, target);
+            assert!(frontier.len() >= target);
+            assert!(frontier.iter().any(|node| !node.is_answer()));
+
+            let expected = solve2(problem, None, fill, false);
+            let found = solve2_parallel(problem, None, None, fill, false, n_threads, None);
+            assert_eq!(found.len(), expected.len());
+            if expected.len() == 1 {
+                let (found, expected) = (&found.answers[0], &expected.answers[0]);
+                for y in 0..problem.height() {
+                    for x in 0..problem.width() {
+                        assert_eq!(found.right(P(y, x)), expected.right(P(y, x)));
+                        assert_eq!(found.down(P(y, x)), expected.down(P(y, x)));
+                    }
+                }
+            }
+            let found = solve2_parallel(problem, None, Some(2), fill, false, n_threads, None);
+            assert_eq!(found.len(), ::std::cmp::min(2, expected.len()));
+
+            let cancel = AtomicBool::new(true);
+            let found = solve2_parallel(problem, None, None, fill, false, n_threads, Some(&cancel));
+            assert_eq!(found.len(), 0);
+            let summary = test_uniqueness(problem, None, Rule::Classic, Some(&cancel));
+            assert!(!summary.exhaustive);
+        }
+    }
+    #[test]
+    fn test_estimate_difficulty() {
+        let mut problem = Grid::new(2, 3, NO_CLUE);
+        problem[P(0, 0)] = Clue(1);
+        problem[P(0, 2)] = Clue(1);
+        problem[P(1, 0)] = Clue(2);
+        problem[P(1, 2)] = Clue(2);
+        let difficulty = estimate_difficulty(&problem, None, Rule::Classic, None).unwrap();
+        assert_eq!(difficulty.level, 1);
+        assert_eq!(difficulty.stats.technique("forced").n_decided, 7);
+        assert_eq!(difficulty.score, 7);
+        let cancel = AtomicBool::new(true);
+        assert!(estimate_difficulty(&problem, None, Rule::Classic, Some(&cancel)).is_none());
+
+        problem[P(0, 2)] = Clue(2);
+        problem[P(1, 2)] = Clue(1);
+        assert!(estimate_difficulty(&problem, None, Rule::Classic, None).is_none());
+    }
+    #[test]
+    fn test_estimate_difficulty_levels() {
+        // bench/numberlink/easy_8x8_01.txt, solved by forced and corner moves
+        let easy = "8 8
+. 4 . . . . . .
+4 . . 7 . . 8 5
+1 . . . . 5 . .
+9 . . 6 . . 3 .
+2 9 . . . 1 . 8
+. . . . . . . .
+7 . 2 . 3 . 6 .
+. . . . . . . .
+";
+        // bench/numberlink/easy_8x8_02.txt, which needs dead-end avoidance
+        let hard = "8 8
+. . . . . . . .
+. . 5 . . . 1 .
+8 . 7 . 9 8 . .
+. . 7 . . . . .
+. 3 . . 6 . . 9
+. . 1 5 2 4 . .
+. 3 . . . . 4 .
+. . . 2 . 6 . .
+";
+        let easy = read_penciloid_problem(&mut easy.as_bytes()).unwrap().clues;
+        let hard = read_penciloid_problem(&mut hard.as_bytes()).unwrap().clues;
+        let easy = estimate_difficulty(&easy, None, Rule::Classic, None).unwrap();
+        let hard = estimate_difficulty(&hard, None, Rule::Classic, None).unwrap();
+        assert_eq!(easy.level, 2);
+        assert_eq!(hard.level, 3);
+        assert!(easy.score < hard.score);
+    }
 }
@@ -0,0 +1,147 @@
+use super::super::{Grid, D, LP, P};
+
+/// Board shape of Flow-style variants.
+///
+/// A bridge cell is passed by two lines crossing each other: one goes straight horizontally
+/// and the other vertically. A warp of row `y` makes `P(y, width - 1)` and `P(y, 0)` adjacent,
+/// and a warp of column `x` makes `P(height - 1, x)` and `P(0, x)` adjacent.
+/// The edge of a warp is represented by `LP(y * 2, width * 2 - 1)` (row `y`) or
+/// `LP(height * 2 - 1, x * 2)` (column `x`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Topology {
+    bridge: Grid<bool>,
+    warp_row: Vec<bool>,
+    warp_column: Vec<bool>,
+}
+
+impl Topology {
+    /// Creates a plain board without any bridges or warps.
+    pub fn new(height: i32, width: i32) -> Topology {
+        Topology {
+            bridge: Grid::new(height, width, false),
+            warp_row: vec![false; height as usize],
+            warp_column: vec![false; width as usize],
+        }
+    }
+    /// Creates a board whose rows and columns all wrap around.
+    pub fn torus(height: i32, width: i32) -> Topology {
+        Topology {
+            bridge: Grid::new(height, width, false),
+            warp_row: vec![true; height as usize],
+            warp_column: vec![true; width as usize],
+        }
+    }
+    pub fn height(&self) -> i32 {
+        self.bridge.height()
+    }
+    pub fn width(&self) -> i32 {
+        self.bridge.width()
+    }
+    /// Returns whether the board has neither bridges nor warps.
+    pub fn is_plain(&self) -> bool {
+        !(self.warp_row.iter().any(|&w| w)
+            || self.warp_column.iter().any(|&w| w)
+            || (0..self.height()).any(|y| (0..self.width()).any(|x| self.bridge[P(y, x)])))
+    }
+    pub fn bridge(&self, pos: P) -> bool {
+        self.bridge.is_valid_p(pos) && self.bridge[pos]
+    }
+    pub fn set_bridge(&mut self, pos: P, bridge: bool) {
+        self.bridge[pos] = bridge;
+    }
+    pub fn warp_row(&self, y: i32) -> bool {
+        self.warp_row[y as usize]
+    }
+    pub fn set_warp_row(&mut self, y: i32, warp: bool) {
+        self.warp_row[y as usize] = warp;
+    }
+    pub fn warp_column(&self, x: i32) -> bool {
+        self.warp_column[x as usize]
+    }
+    pub fn set_warp_column(&mut self, x: i32, warp: bool) {
+        self.warp_column[x as usize] = warp;
+    }
+    /// Returns the cell adjacent to `pos` in direction `d` (one of `FOUR_NEIGHBOURS`),
+    /// following warps.
+    pub fn neighbour(&self, pos: P, d: D) -> Option<P> {
+        let height = self.height();
+        let width = self.width();
+        let P(y, x) = pos + d;
+        if 0 <= y && y < height && 0 <= x && x < width {
+            Some(P(y, x))
+        } else if 0 <= y && y < height && (x == -1 || x == width) && self.warp_row(y) {
+            Some(P(y, (x + width) % width))
+        } else if 0 <= x && x < width && (y == -1 || y == height) && self.warp_column(x) {
+            Some(P((y + height) % height, x))
+        } else {
+            None
+        }
+    }
+    /// Returns the representation of edge `pos`, or `None` if there is no such edge.
+    pub fn canonical_edge(&self, pos: LP) -> Option<LP> {
+        let height = self.height();
+        let width = self.width();
+        let LP(y, x) = pos;
+        if y % 2 == 0 && (x == -1 || x == width * 2 - 1) {
+            if 0 <= y && y < height * 2 && self.warp_row(y / 2) {
+                Some(LP(y, width * 2 - 1))
+            } else {
+                None
+            }
+        } else if x % 2 == 0 && (y == -1 || y == height * 2 - 1) {
+            if 0 <= x && x < width * 2 && self.warp_column(x / 2) {
+                Some(LP(height * 2 - 1, x))
+            } else {
+                None
+            }
+        } else if 0 <= y && y < height * 2 - 1 && 0 <= x && x < width * 2 - 1 && y % 2 != x % 2 {
+            Some(pos)
+        } else {
+            None
+        }
+    }
+    /// Returns the cells at both ends of edge `pos`, which must be given by `canonical_edge`.
+    pub fn edge_ends(&self, pos: LP) -> (P, P) {
+        let LP(y, x) = pos;
+        if y % 2 == 0 {
+            (P(y / 2, x / 2), P(y / 2, (x / 2 + 1) % self.width()))
+        } else {
+            (P(y / 2, x / 2), P((y / 2 + 1) % self.height(), x / 2))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topology() {
+        let mut topology = Topology::new(3, 4);
+        assert!(topology.is_plain());
+        assert_eq!(topology.neighbour(P(0, 3), D(0, 1)), None);
+        assert_eq!(topology.canonical_edge(LP(0, -1)), None);
+        assert_eq!(topology.canonical_edge(LP(0, 1)), Some(LP(0, 1)));
+        assert_eq!(topology.canonical_edge(LP(1, 1)), None);
+
+        topology.set_warp_row(1, true);
+        topology.set_warp_column(0, true);
+        assert!(!topology.is_plain());
+        assert_eq!(topology.neighbour(P(1, 3), D(0, 1)), Some(P(1, 0)));
+        assert_eq!(topology.neighbour(P(1, 0), D(0, -1)), Some(P(1, 3)));
+        assert_eq!(topology.neighbour(P(0, 3), D(0, 1)), None);
+        assert_eq!(topology.neighbour(P(0, 0), D(-1, 0)), Some(P(2, 0)));
+        assert_eq!(topology.canonical_edge(LP(2, -1)), Some(LP(2, 7)));
+        assert_eq!(topology.canonical_edge(LP(2, 7)), Some(LP(2, 7)));
+        assert_eq!(topology.canonical_edge(LP(0, 7)), None);
+        assert_eq!(topology.canonical_edge(LP(-1, 0)), Some(LP(5, 0)));
+        assert_eq!(topology.edge_ends(LP(2, 7)), (P(1, 3), P(1, 0)));
+        assert_eq!(topology.edge_ends(LP(5, 0)), (P(2, 0), P(0, 0)));
+
+        let mut topology = Topology::new(3, 3);
+        topology.set_bridge(P(1, 1), true);
+        assert!(!topology.is_plain());
+        assert!(topology.bridge(P(1, 1)));
+        assert!(!topology.bridge(P(1, 3)));
+    }
+}
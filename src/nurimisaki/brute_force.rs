@@ -0,0 +1,158 @@
+use super::*;
+use {Grid, D, FOUR_NEIGHBOURS, P};
+
+use rand::prng::XorShiftRng;
+use rand::{Rng, SeedableRng};
+
+fn n_white_neighbours(is_white: &Grid<bool>, pos: P) -> i32 {
+    FOUR_NEIGHBOURS
+        .iter()
+        .filter(|&&d| is_white.get_or_default_p(pos + d, false))
+        .count() as i32
+}
+
+fn is_connected(is_white: &Grid<bool>) -> bool {
+    let height = is_white.height();
+    let width = is_white.width();
+    let mut visited = Grid::new(height, width, false);
+    let whites = (0..(height * width))
+        .map(|i| P(i / width, i % width))
+        .filter(|&pos| is_white[pos])
+        .collect::<Vec<_>>();
+    let mut stack = vec![];
+    if let Some(&start) = whites.first() {
+        visited[start] = true;
+        stack.push(start);
+    }
+    let mut n_visited = 0;
+    while let Some(pos) = stack.pop() {
+        n_visited += 1;
+        for &d in &FOUR_NEIGHBOURS {
+            let pos2 = pos + d;
+            if is_white.get_or_default_p(pos2, false) && !visited[pos2] {
+                visited[pos2] = true;
+                stack.push(pos2);
+            }
+        }
+    }
+    n_visited == whites.len()
+}
+
+/// Returns the number of white cells seen straight from the cape at `pos`, including itself.
+fn cape_length(is_white: &Grid<bool>, pos: P) -> i32 {
+    for &d in &FOUR_NEIGHBOURS {
+        if is_white.get_or_default_p(pos + d, false) {
+            let mut n = 1;
+            while is_white.get_or_default_p(pos + d * n, false) {
+                n += 1;
+            }
+            return n;
+        }
+    }
+    0
+}
+
+/// Enumerates all the colourings of a `height` x `width` board satisfying the rules
+/// except for the clue numbers, assuming that every cape has a clue.
+pub fn enumerate_shapes(height: i32, width: i32) -> Vec<Grid<bool>> {
+    let mut ret = vec![];
+    for mask in 0u32..(1u32 << (height * width)) {
+        let mut is_white = Grid::new(height, width, false);
+        for i in 0..(height * width) {
+            is_white[P(i / width, i % width)] = (mask >> i) & 1 != 0;
+        }
+        let no_2x2 = (0..(height - 1)).all(|y| {
+            (0..(width - 1)).all(|x| {
+                let cells = [D(0, 0), D(0, 1), D(1, 0), D(1, 1)]
+                    .iter()
+                    .filter(|&&d| is_white[P(y, x) + d])
+                    .count();
+                cells != 0 && cells != 4
+            })
+        });
+        if no_2x2 && is_connected(&is_white) {
+            ret.push(is_white);
+        }
+    }
+    ret
+}
+
+/// Enumerates all the answers of `problem` among `shapes`.
+pub fn enumerate_answers(problem: &Grid<Option<i32>>, shapes: &[Grid<bool>]) -> Vec<Grid<bool>> {
+    shapes
+        .iter()
+        .filter(|is_white| {
+            (0..problem.height()).all(|y| {
+                (0..problem.width()).all(|x| {
+                    let pos = P(y, x);
+                    match problem[pos] {
+                        Some(n) => {
+                            is_white[pos]
+                                && n_white_neighbours(is_white, pos) == 1
+                                && (n <= 0 || cape_length(is_white, pos) == n)
+                        }
+                        None => !is_white[pos] || n_white_neighbours(is_white, pos) >= 2,
+                    }
+                })
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn test_random_against_brute_force() {
+    let mut rng = XorShiftRng::from_seed([42u8; 16]);
+
+    for &(height, width) in &[(2, 2), (2, 3), (3, 3), (3, 4), (4, 4)] {
+        let shapes = enumerate_shapes(height, width);
+        for _ in 0..100 {
+            // capes of a random shape, some of which have no number
+            let base = &shapes[rng.gen_range(0, shapes.len())];
+            let mut problem = Grid::new(height, width, None);
+            for y in 0..height {
+                for x in 0..width {
+                    let pos = P(y, x);
+                    if base[pos] && n_white_neighbours(base, pos) == 1 {
+                        problem[pos] = Some(if rng.gen::<f64>() < 0.5 {
+                            cape_length(base, pos)
+                        } else {
+                            0
+                        });
+                    }
+                }
+            }
+            let answers = enumerate_answers(&problem, &shapes);
+            for answer in &answers {
+                assert!(check_answer(&problem, answer).is_empty());
+            }
+            for _ in 0..10 {
+                let candidate = &shapes[rng.gen_range(0, shapes.len())];
+                let violations = check_answer(&problem, candidate);
+                assert_eq!(violations.is_empty(), answers.contains(candidate));
+            }
+            if answers.is_empty() {
+                // `base` has a white cell with no white neighbour
+                continue;
+            }
+
+            let mut field = Field::new(&problem);
+            field.trial_and_error(1);
+            assert!(!field.inconsistent());
+            for answer in &answers {
+                for y in 0..height {
+                    for x in 0..width {
+                        let actual = field.get_cell(P(y, x));
+                        if answer[P(y, x)] {
+                            assert!(actual != Cell::Black);
+                        } else {
+                            assert!(actual == Cell::Black || actual == Cell::Undecided);
+                        }
+                        assert!(!field.fully_solved() || actual != Cell::Undecided);
+                    }
+                }
+            }
+            assert!(!field.fully_solved() || answers.len() == 1);
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use super::super::{check_connectivity, Grid, Violation, ViolationKind, P};
+use FOUR_NEIGHBOURS;
+
+/// Checks whether the colouring `is_white` is an answer of `problem`, where `problem[pos]` is
+/// `Some(n)` for a cape clue (`n <= 0` if the clue has no number).
+/// The violations reported are:
+/// - `NotCape`: a clue cell which is not a white cell with exactly one white neighbour.
+/// - `Clue`: a cape whose length differs from its number.
+/// - `CapeWithoutClue`: a white cell without clues which has less than two white neighbours.
+/// - `White2x2` / `Black2x2`: a 2x2 block of white / black cells.
+/// - `Disconnected`: a group of connected white cells other than the largest one.
+pub fn check_answer(problem: &Grid<Option<i32>>, is_white: &Grid<bool>) -> Vec<Violation> {
+    let height = problem.height();
+    let width = problem.width();
+    let mut ret = vec![];
+
+    for y in 0..height {
+        for x in 0..width {
+            let pos = P(y, x);
+            let n_white = FOUR_NEIGHBOURS
+                .iter()
+                .filter(|&&d| is_white.get_or_default_p(pos + d, false))
+                .count();
+            match problem[pos] {
+                Some(n) => {
+                    if !is_white[pos] || n_white != 1 {
+                        ret.push(Violation::at_cells(ViolationKind::NotCape, vec![pos]));
+                    } else if n > 0 && cape_length(is_white, pos) != n {
+                        ret.push(Violation::at_cells(ViolationKind::Clue, vec![pos]));
+                    }
+                }
+                None => {
+                    if is_white[pos] && n_white < 2 {
+                        ret.push(Violation::at_cells(
+                            ViolationKind::CapeWithoutClue,
+                            vec![pos],
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    for y in 0..(height - 1) {
+        for x in 0..(width - 1) {
+            let block = vec![P(y, x), P(y, x + 1), P(y + 1, x), P(y + 1, x + 1)];
+            let n_white = block.iter().filter(|&&p| is_white[p]).count();
+            if n_white == 4 {
+                ret.push(Violation::at_cells(ViolationKind::White2x2, block));
+            } else if n_white == 0 {
+                ret.push(Violation::at_cells(ViolationKind::Black2x2, block));
+            }
+        }
+    }
+    ret.extend(check_connectivity(is_white, ViolationKind::Disconnected));
+    ret
+}
+
+/// Returns the number of white cells seen straight from the cape at `pos`, including itself.
+fn cape_length(is_white: &Grid<bool>, pos: P) -> i32 {
+    for &d in &FOUR_NEIGHBOURS {
+        if is_white.get_or_default_p(pos + d, false) {
+            let mut n = 1;
+            while is_white.get_or_default_p(pos + d * n, false) {
+                n += 1;
+            }
+            return n;
+        }
+    }
+    0
+}
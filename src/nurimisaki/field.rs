@@ -1,13 +1,14 @@
 use super::*;
 use std::fmt;
 use GraphSeparation;
-use {Grid, D, FOUR_NEIGHBOURS, P};
+use {Grid, SolverStats, D, FOUR_NEIGHBOURS, P};
 
 #[derive(Clone)]
 pub struct Field {
     cell: Grid<Cell>,
     decided_cells: i32,
     inconsistent: bool,
+    stats: SolverStats,
 }
 
 impl Field {
@@ -32,6 +33,7 @@ impl Field {
             cell,
             decided_cells,
             inconsistent: false,
+            stats: SolverStats::new(),
         }
     }
     pub fn height(&self) -> i32 {
@@ -49,6 +51,14 @@ impl Field {
     pub fn decided_cells(&self) -> i32 {
         self.decided_cells
     }
+    /// Returns the statistics of the techniques (in decided cells) and the trial and error,
+    /// including the work in the discarded trials.
+    pub fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+    pub fn set_stats_enabled(&mut self, enabled: bool) {
+        self.stats.set_enabled(enabled);
+    }
     pub fn fully_solved(&self) -> bool {
         self.decided_cells == self.height() * self.width()
     }
@@ -90,11 +100,16 @@ impl Field {
         loop {
             let last_decided_cells = self.decided_cells();
 
-            self.inspect_all_cell();
-            self.avoid_forbidden_pattern_simple();
-            self.avoid_forbidden_pattern_with_cape();
-            self.ensure_connectivity(false);
-            self.ensure_connectivity(true);
+            self.apply_technique("local", Field::inspect_all_cell);
+            self.apply_technique("forbidden_pattern", Field::avoid_forbidden_pattern_simple);
+            self.apply_technique(
+                "forbidden_pattern_with_cape",
+                Field::avoid_forbidden_pattern_with_cape,
+            );
+            self.apply_technique("connectivity", |field| field.ensure_connectivity(false));
+            self.apply_technique("connectivity_ignoring_capes", |field| {
+                field.ensure_connectivity(true)
+            });
 
             if last_decided_cells == self.decided_cells() {
                 break;
@@ -120,9 +135,10 @@ impl Field {
                         continue;
                     }
                     {
-                        let mut field_blocked = self.clone();
+                        let mut field_blocked = self.clone_for_trial();
                         field_blocked.decide_cell(pos, Cell::Black);
                         field_blocked.trial_and_error(depth - 1);
+                        self.record_trial(&field_blocked);
 
                         if field_blocked.inconsistent() {
                             updated = true;
@@ -131,9 +147,10 @@ impl Field {
                         }
                     }
                     {
-                        let mut field_line = self.clone();
+                        let mut field_line = self.clone_for_trial();
                         field_line.decide_cell(pos, Cell::White);
                         field_line.trial_and_error(depth - 1);
+                        self.record_trial(&field_line);
 
                         if field_line.inconsistent() {
                             updated = true;
@@ -151,6 +168,24 @@ impl Field {
             }
         }
     }
+    /// Applies `technique` and records the number of cells decided by it.
+    fn apply_technique<F: FnOnce(&mut Field)>(&mut self, name: &'static str, technique: F) {
+        let decided_cells = self.decided_cells;
+        technique(self);
+        let n_decided = self.decided_cells - decided_cells;
+        self.stats.record(name, n_decided);
+    }
+    /// Returns a clone of this field for a trial, whose statistics start from zero.
+    fn clone_for_trial(&self) -> Field {
+        let mut ret = self.clone();
+        ret.stats.clear();
+        ret
+    }
+    /// Adds the work done in `trial`, made by `clone_for_trial`, to the statistics.
+    fn record_trial(&mut self, trial: &Field) {
+        self.stats.merge(&trial.stats);
+        self.stats.record_trial(trial.inconsistent());
+    }
     fn avoid_2x2_cluster(&mut self, top: P) {
         let P(y, x) = top;
         if !(0 <= y && y < self.height() - 1 && 0 <= x && x < self.width() - 1) {
@@ -440,7 +475,8 @@ impl Field {
             match c {
                 Cell::Black => return true,
                 Cell::Cape(n2) => {
-                    if i != n - 1 || n != n2 {
+                    // the opposite cape, which may have no number
+                    if i != n - 1 || (n2 > 0 && n != n2) {
                         return true;
                     }
                 }
@@ -0,0 +1,70 @@
+use super::*;
+use grid_loop::brute_force::{assert_consistent_edges, enumerate_loops, is_on_loop};
+use {Grid, P};
+
+use rand::prng::XorShiftRng;
+use rand::{Rng, SeedableRng};
+
+/// Enumerates all the answers of the problem given by `is_black`.
+pub fn enumerate_answers(is_black: &Grid<bool>) -> Vec<Grid<bool>> {
+    let height = is_black.height();
+    let width = is_black.width();
+    let mut allowed = Grid::new(height, width, false);
+    for y in 0..height {
+        for x in 0..width {
+            allowed[P(y, x)] = !is_black[P(y, x)];
+        }
+    }
+    enumerate_loops(&allowed)
+        .into_iter()
+        .filter(|answer| {
+            (0..height)
+                .all(|y| (0..width).all(|x| is_black[P(y, x)] || is_on_loop(answer, P(y, x))))
+        })
+        .collect()
+}
+
+#[test]
+fn test_random_against_brute_force() {
+    let mut rng = XorShiftRng::from_seed([42u8; 16]);
+
+    for &(height, width) in &[(2, 2), (2, 3), (3, 3), (3, 4), (4, 4), (4, 5), (5, 5)] {
+        let loops = enumerate_loops(&Grid::new(height, width, true));
+        for _ in 0..100 {
+            // black cells are the ones not passed by a random loop, or are chosen randomly
+            let mut is_black = Grid::new(height, width, false);
+            if rng.gen::<f64>() < 0.8 {
+                let base = &loops[rng.gen_range(0, loops.len())];
+                for y in 0..height {
+                    for x in 0..width {
+                        is_black[P(y, x)] = !is_on_loop(base, P(y, x));
+                    }
+                }
+            } else {
+                for y in 0..height {
+                    for x in 0..width {
+                        is_black[P(y, x)] = rng.gen::<f64>() < 0.2;
+                    }
+                }
+            }
+            let answers = enumerate_answers(&is_black);
+            for answer in &answers {
+                assert!(check_answer(&is_black, answer).is_empty());
+            }
+            for _ in 0..10 {
+                let candidate = &loops[rng.gen_range(0, loops.len())];
+                let violations = check_answer(&is_black, candidate);
+                assert_eq!(violations.is_empty(), answers.contains(candidate));
+            }
+
+            let mut field = Field::new(&is_black);
+            field.trial_and_error(1);
+            if answers.is_empty() {
+                assert!(!field.fully_solved() || field.inconsistent());
+                continue;
+            }
+            assert!(!field.inconsistent());
+            assert_consistent_edges(|lp| field.get_edge(lp), field.fully_solved(), &answers);
+        }
+    }
+}
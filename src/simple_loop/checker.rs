@@ -0,0 +1,29 @@
+use super::super::{Grid, Violation, ViolationKind, P};
+use grid_loop::{check_loop, line_degree};
+
+/// Checks whether `answer` is an answer of the problem given by `is_black`.
+/// `answer` has the size `(2 * height - 1) x (2 * width - 1)` and is indexed by lattice
+/// positions (`LP::of_vertex(pos)` for the cell `pos`); it is true exactly on the edges of the loop.
+/// The violations reported are `Unvisited` (a white cell not passed by the loop),
+/// `BlackVisited` (a black cell passed by the loop) and those of `grid_loop::check_loop`.
+pub fn check_answer(is_black: &Grid<bool>, answer: &Grid<bool>) -> Vec<Violation> {
+    let height = is_black.height();
+    let width = is_black.width();
+    assert_eq!(answer.height(), height * 2 - 1);
+    assert_eq!(answer.width(), width * 2 - 1);
+
+    let mut ret = vec![];
+    for y in 0..height {
+        for x in 0..width {
+            let pos = P(y, x);
+            let visited = line_degree(answer, pos) > 0;
+            if is_black[pos] && visited {
+                ret.push(Violation::at_cells(ViolationKind::BlackVisited, vec![pos]));
+            } else if !is_black[pos] && !visited {
+                ret.push(Violation::at_cells(ViolationKind::Unvisited, vec![pos]));
+            }
+        }
+    }
+    ret.extend(check_loop(answer));
+    ret
+}
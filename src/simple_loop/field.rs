@@ -0,0 +1,413 @@
+use super::super::{Grid, SolverStats, D, LP, P};
+use grid_loop::{Edge, GridLoop, GridLoopField, LineChain};
+use std::fmt;
+use FOUR_NEIGHBOURS;
+
+#[derive(Clone)]
+pub struct Field {
+    grid_loop: GridLoop,
+    is_black: Grid<bool>,
+    stats: SolverStats,
+}
+
+impl Field {
+    pub fn new(is_black: &Grid<bool>) -> Field {
+        Field {
+            grid_loop: GridLoop::new(is_black.height() - 1, is_black.width() - 1),
+            is_black: is_black.clone(),
+            stats: SolverStats::new(),
+        }
+    }
+    pub fn height(&self) -> i32 {
+        self.is_black.height()
+    }
+    pub fn width(&self) -> i32 {
+        self.is_black.width()
+    }
+    pub fn inconsistent(&self) -> bool {
+        self.grid_loop.inconsistent()
+    }
+    pub fn set_inconsistent(&mut self) {
+        self.grid_loop.set_inconsistent()
+    }
+    pub fn fully_solved(&self) -> bool {
+        self.grid_loop.fully_solved()
+    }
+    pub fn is_black(&self, pos: P) -> bool {
+        self.is_black[pos]
+    }
+    pub fn get_edge(&self, pos: LP) -> Edge {
+        self.grid_loop.get_edge(pos)
+    }
+    pub fn get_edge_safe(&self, pos: LP) -> Edge {
+        self.grid_loop.get_edge_safe(pos)
+    }
+    pub fn line_chains(&self) -> Vec<LineChain> {
+        self.grid_loop.line_chains()
+    }
+    pub fn solved_loop(&self) -> Option<Vec<P>> {
+        self.grid_loop.solved_loop()
+    }
+    pub fn num_decided_edges(&self) -> i32 {
+        self.grid_loop.num_decided_edges()
+    }
+    /// Returns the statistics of the work done so far (in decided edges).
+    pub fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+    pub fn set_stats_enabled(&mut self, enabled: bool) {
+        self.stats.set_enabled(enabled);
+    }
+    /// Add a checkpoint. See `GridLoop::add_checkpoint`.
+    pub fn add_checkpoint(&mut self) {
+        self.grid_loop.add_checkpoint();
+    }
+    /// Rollback until the last checkpoint.
+    pub fn rollback(&mut self) {
+        self.grid_loop.rollback();
+    }
+
+    pub fn check_all_cell(&mut self) {
+        let height = self.height();
+        let width = self.width();
+        let mut handle = GridLoop::get_handle(self);
+        for y in 0..height {
+            for x in 0..width {
+                GridLoop::check(&mut *handle, LP::of_vertex(P(y, x)));
+            }
+        }
+    }
+    pub fn solve(&mut self) {
+        loop {
+            let current_decided_edges = self.grid_loop.num_decided_edges();
+            self.apply_technique("local", Field::check_all_cell);
+            self.apply_technique("inout_rule", GridLoop::apply_inout_rule);
+            self.apply_technique("connectability", GridLoop::check_connectability);
+            if !self.inconsistent() && !self.fully_solved() {
+                self.apply_technique("parity_rule", Field::apply_parity_rule);
+            }
+            if self.inconsistent() || current_decided_edges == self.grid_loop.num_decided_edges() {
+                break;
+            }
+        }
+    }
+    pub fn trial_and_error(&mut self, depth: i32) {
+        let height = self.height();
+        let width = self.width();
+
+        if depth == 0 {
+            self.solve();
+            return;
+        }
+        self.trial_and_error(depth - 1);
+
+        loop {
+            let mut updated = false;
+            for y in 0..(height * 2 - 1) {
+                for x in 0..(width * 2 - 1) {
+                    if y % 2 == x % 2 {
+                        continue;
+                    }
+                    let pos = LP(y, x);
+                    if self.get_edge(pos) != Edge::Undecided || !self.grid_loop.is_root(pos) {
+                        continue;
+                    }
+
+                    if self.is_inconsistent_with_edge(pos, Edge::Line, depth - 1) {
+                        updated = true;
+                        GridLoop::decide_edge(self, pos, Edge::Blank);
+                        self.trial_and_error(depth - 1);
+                    }
+                    if self.get_edge(pos) == Edge::Undecided
+                        && self.is_inconsistent_with_edge(pos, Edge::Blank, depth - 1)
+                    {
+                        updated = true;
+                        GridLoop::decide_edge(self, pos, Edge::Line);
+                        self.trial_and_error(depth - 1);
+                    }
+                    if self.inconsistent() || self.fully_solved() {
+                        return;
+                    }
+                }
+            }
+            if !updated {
+                break;
+            }
+        }
+    }
+    /// Checks whether deciding edge `pos` to `status` leads to an inconsistency
+    /// within trial-and-error of depth `depth`. The field is left unchanged.
+    fn is_inconsistent_with_edge(&mut self, pos: LP, status: Edge, depth: i32) -> bool {
+        self.add_checkpoint();
+        GridLoop::decide_edge(self, pos, status);
+        self.trial_and_error(depth);
+        let ret = self.inconsistent();
+        self.rollback();
+        self.stats.record_trial(ret);
+        ret
+    }
+    /// Applies `technique` and records the number of edges decided by it.
+    fn apply_technique<F: FnOnce(&mut Field)>(&mut self, name: &'static str, technique: F) {
+        let decided_edges = self.num_decided_edges();
+        technique(self);
+        let n_decided = self.num_decided_edges() - decided_edges;
+        self.stats.record(name, n_decided);
+    }
+    /// Applies the parity rule.
+    ///
+    /// Cells are colored in a checkerboard pattern, and the loop alternates the colors.
+    /// Consider a group of white cells connected by undecided edges. Removing the decided lines,
+    /// the loop is split into paths in groups ending at the cells the decided lines are incident
+    /// to, and a path having both ends on cells of the same color contains one more cell of
+    /// that color than the other. Therefore the difference of the number of cells of each color
+    /// in a group is determined by the colors of the ends of the decided lines in the group.
+    ///
+    /// Moreover, if an undecided edge is a bridge of a group, the same condition applied to
+    /// one side of the bridge determines whether the bridge is a line or not.
+    pub fn apply_parity_rule(&mut self) {
+        let height = self.height();
+        let width = self.width();
+
+        // the sign of the color of the cell, multiplied by
+        // 2 - (the number of lines incident to the cell)
+        let mut weight = Grid::new(height, width, 0);
+        for y in 0..height {
+            for x in 0..width {
+                let pos = P(y, x);
+                if !self.is_black[pos] {
+                    let (n_line, _) = self.grid_loop.neighbor_summary(LP::of_vertex(pos));
+                    weight[pos] = (2 - n_line) * parity_sign(pos);
+                }
+            }
+        }
+
+        let mut order = Grid::new(height, width, -1);
+        let mut n_visited = 0;
+        let mut decisions = vec![];
+        for y in 0..height {
+            for x in 0..width {
+                let pos = P(y, x);
+                if self.is_black[pos] || order[pos] != -1 {
+                    continue;
+                }
+                let (_, total) = self.parity_bridge_dfs(
+                    pos,
+                    None,
+                    &weight,
+                    &mut order,
+                    &mut n_visited,
+                    &mut decisions,
+                );
+                if total != 0 {
+                    self.set_inconsistent();
+                    return;
+                }
+            }
+        }
+
+        for (edge, status) in decisions {
+            match status {
+                Some(status) => GridLoop::decide_edge(self, edge, status),
+                None => {
+                    self.set_inconsistent();
+                    return;
+                }
+            }
+        }
+    }
+    /// Finds bridges by lowlink and decides them by the parity rule (`None` if neither works).
+    /// Returns the lowlink of `pos` and the total weight
+    /// of the DFS subtree rooted at `pos`.
+    fn parity_bridge_dfs(
+        &self,
+        pos: P,
+        parent: Option<P>,
+        weight: &Grid<i32>,
+        order: &mut Grid<i32>,
+        n_visited: &mut i32,
+        decisions: &mut Vec<(LP, Option<Edge>)>,
+    ) -> (i32, i32) {
+        order[pos] = *n_visited;
+        *n_visited += 1;
+
+        let mut low = order[pos];
+        let mut total = weight[pos];
+        for &d in &FOUR_NEIGHBOURS {
+            let pos2 = pos + d;
+            let edge = LP::of_vertex(pos) + d;
+            if !order.is_valid_p(pos2)
+                || self.is_black[pos2]
+                || Some(pos2) == parent
+                || self.get_edge(edge) != Edge::Undecided
+            {
+                continue;
+            }
+            if order[pos2] != -1 {
+                low = ::std::cmp::min(low, order[pos2]);
+                continue;
+            }
+            let (low2, total2) =
+                self.parity_bridge_dfs(pos2, Some(pos), weight, order, n_visited, decisions);
+            low = ::std::cmp::min(low, low2);
+            total += total2;
+
+            if low2 > order[pos] {
+                // `edge` is a bridge separating the subtree from the rest of the group
+                decisions.push((
+                    edge,
+                    if total2 == 0 {
+                        Some(Edge::Blank)
+                    } else if total2 == parity_sign(pos2) {
+                        Some(Edge::Line)
+                    } else {
+                        None
+                    },
+                ));
+            }
+        }
+        (low, total)
+    }
+}
+
+fn parity_sign(pos: P) -> i32 {
+    if (pos.0 + pos.1) % 2 == 0 {
+        1
+    } else {
+        -1
+    }
+}
+
+impl GridLoopField for Field {
+    fn grid_loop(&mut self) -> &mut GridLoop {
+        &mut self.grid_loop
+    }
+    fn check_neighborhood(&mut self, pos: LP) {
+        if pos.0 % 2 == 0 {
+            GridLoop::check(self, pos + D(0, -1));
+            GridLoop::check(self, pos + D(0, 1));
+        } else {
+            GridLoop::check(self, pos + D(-1, 0));
+            GridLoop::check(self, pos + D(1, 0));
+        }
+    }
+    fn inspect(&mut self, pos: LP) {
+        if !pos.is_vertex() {
+            return;
+        }
+        if self.is_black[pos.as_vertex()] {
+            for &d in &FOUR_NEIGHBOURS {
+                GridLoop::decide_edge(self, pos + d, Edge::Blank);
+            }
+            return;
+        }
+        // every white cell is visited by the loop
+        let (n_line, n_undecided) = self.grid_loop.neighbor_summary(pos);
+        if n_line + n_undecided < 2 {
+            self.set_inconsistent();
+        } else if n_line + n_undecided == 2 {
+            for &d in &FOUR_NEIGHBOURS {
+                if self.get_edge_safe(pos + d) == Edge::Undecided {
+                    GridLoop::decide_edge(self, pos + d, Edge::Line);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let height = self.height();
+        let width = self.width();
+        for y in 0..(2 * height - 1) {
+            for x in 0..(2 * width - 1) {
+                match (y % 2, x % 2) {
+                    (0, 0) => {
+                        if self.is_black[P(y / 2, x / 2)] {
+                            write!(f, "#")?;
+                        } else {
+                            write!(f, "+")?;
+                        }
+                    }
+                    (0, 1) => match self.get_edge(LP(y, x)) {
+                        Edge::Line => write!(f, "---")?,
+                        Edge::Blank => write!(f, " x ")?,
+                        Edge::Undecided => write!(f, "   ")?,
+                    },
+                    (1, 0) => match self.get_edge(LP(y, x)) {
+                        Edge::Line => write!(f, "|")?,
+                        Edge::Blank => write!(f, "x")?,
+                        Edge::Undecided => write!(f, " ")?,
+                    },
+                    (1, 1) => write!(f, "   ")?,
+                    _ => unreachable!(),
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problem_from_str(rows: &[&str]) -> Grid<bool> {
+        let height = rows.len() as i32;
+        let width = rows[0].len() as i32;
+        let mut ret = Grid::new(height, width, false);
+        for y in 0..height {
+            let mut row_iter = rows[y as usize].chars();
+            for x in 0..width {
+                ret[P(y, x)] = row_iter.next().unwrap() == '#';
+            }
+        }
+        ret
+    }
+
+    #[test]
+    fn test_parity() {
+        {
+            // 5 even cells and 4 odd cells
+            let problem = problem_from_str(&["...", "...", "..."]);
+            let mut field = Field::new(&problem);
+            field.apply_parity_rule();
+            assert!(field.inconsistent());
+        }
+        {
+            let problem = problem_from_str(&["...", ".#.", "..."]);
+            let mut field = Field::new(&problem);
+            field.apply_parity_rule();
+            assert!(!field.inconsistent());
+            assert_eq!(field.num_decided_edges(), 0);
+        }
+        {
+            let problem = problem_from_str(&["....", "....", "....", "...."]);
+            let mut field = Field::new(&problem);
+            GridLoop::decide_edge(&mut field, LP(2, 3), Edge::Blank);
+            GridLoop::decide_edge(&mut field, LP(4, 3), Edge::Blank);
+            GridLoop::decide_edge(&mut field, LP(6, 3), Edge::Line);
+            field.apply_parity_rule();
+
+            // the edge between (0, 1) and (0, 2) is a bridge, and the left side of it
+            // has 4 even cells and 4 odd cells with a line incident to the even cell (3, 1)
+            assert!(!field.inconsistent());
+            assert_eq!(field.get_edge(LP(0, 3)), Edge::Line);
+        }
+    }
+
+    #[test]
+    fn test_problem() {
+        let problem =
+            problem_from_str(&["...#..", "......", "..#...", "...#..", "......", "..#..."]);
+        let mut field = Field::new(&problem);
+        field.solve();
+        assert!(!field.inconsistent());
+        assert!(!field.fully_solved());
+
+        field.trial_and_error(1);
+        assert!(!field.inconsistent());
+        assert!(field.fully_solved());
+        assert_eq!(field.solved_loop().map(|l| l.len()), Some(33));
+    }
+}
@@ -0,0 +1,199 @@
+use super::super::{Grid, Symmetry, P};
+use super::*;
+
+use rand::Rng;
+
+/// Generates a Simple Loop problem, which is given as the positions of the black cells.
+/// Black cells are placed in groups respecting `symmetry`, and the placement is determined by
+/// simulated annealing so that the problem is solved by `Field::solve` only.
+pub fn generate<R: Rng>(
+    height: i32,
+    width: i32,
+    symmetry: Symmetry,
+    rng: &mut R,
+) -> Option<Grid<bool>> {
+    let max_step = height * width * 10;
+    let groups = symmetric_groups(height, width, symmetry);
+
+    let mut current_problem = Grid::new(height, width, false);
+    let mut prev_score = 0;
+    let mut temperature = 5.0f64;
+    let mut n_black = 0;
+
+    for _ in 0..max_step {
+        let mut group_cand = (0..groups.len()).collect::<Vec<_>>();
+        rng.shuffle(&mut group_cand);
+
+        let mut updated = false;
+        for &g in &group_cand {
+            if !current_problem[groups[g][0]] && has_black_nearby(&current_problem, &groups[g]) {
+                continue;
+            }
+
+            // the loop alternates the colors of the checkerboard, so the numbers of white cells
+            // of each color must be equal; another group is toggled to keep the balance if needed
+            let delta = parity_delta(&current_problem, &groups[g]);
+            let mut moved = vec![g];
+            if delta != 0 {
+                let cand = (0..groups.len())
+                    .filter(|&h| {
+                        h != g
+                            && parity_delta(&current_problem, &groups[h]) == -delta
+                            && (current_problem[groups[h][0]]
+                                || !has_black_nearby(&current_problem, &groups[h]))
+                    })
+                    .collect::<Vec<_>>();
+                if cand.is_empty() {
+                    continue;
+                }
+                moved.push(cand[rng.gen_range(0, cand.len())]);
+            }
+
+            let mut new_n_black = n_black;
+            for &h in &moved {
+                for &pos in &groups[h] {
+                    current_problem[pos] = !current_problem[pos];
+                    new_n_black += if current_problem[pos] { 1 } else { -1 };
+                }
+            }
+
+            let mut field = Field::new(&current_problem);
+            field.solve();
+
+            let current_score = field.num_decided_edges() - new_n_black * 4;
+
+            if field.inconsistent()
+                || (prev_score >= current_score
+                    && rng.gen::<f64>()
+                        >= ((current_score - prev_score) as f64 / temperature).exp())
+            {
+                for &h in &moved {
+                    for &pos in &groups[h] {
+                        current_problem[pos] = !current_problem[pos];
+                    }
+                }
+                continue;
+            }
+
+            updated = true;
+            prev_score = current_score;
+            n_black = new_n_black;
+
+            if field.fully_solved() {
+                return Some(current_problem);
+            }
+            break;
+        }
+
+        if !updated {
+            break;
+        }
+
+        temperature *= 0.995f64;
+    }
+
+    None
+}
+
+/// Returns the change of (the number of white cells at even positions) - (those at odd ones)
+/// caused by toggling the colors of the cells in `group`.
+fn parity_delta(problem: &Grid<bool>, group: &[P]) -> i32 {
+    let mut ret = 0;
+    for &P(y, x) in group {
+        let sign = if (y + x) % 2 == 0 { 1 } else { -1 };
+        ret += if problem[P(y, x)] { sign } else { -sign };
+    }
+    ret
+}
+
+/// Returns whether any cell in `group` is adjacent to a black cell.
+/// Adjacent black cells are allowed in Simple Loop, but they tend to make problems dull.
+fn has_black_nearby(problem: &Grid<bool>, group: &[P]) -> bool {
+    for &pos in group {
+        for &d in &::FOUR_NEIGHBOURS {
+            if problem.get_or_default_p(pos + d, false) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Splits the cells into groups of cells which are mapped to each other by `symmetry`.
+fn symmetric_groups(height: i32, width: i32, symmetry: Symmetry) -> Vec<Vec<P>> {
+    let mut symmetry = symmetry;
+    symmetry.dyad |= symmetry.tetrad;
+    symmetry.tetrad &= height == width;
+
+    let mut visited = Grid::new(height, width, false);
+    let mut ret = vec![];
+    for y in 0..height {
+        for x in 0..width {
+            if visited[P(y, x)] {
+                continue;
+            }
+            let mut group = vec![];
+            let mut stack = vec![P(y, x)];
+            while let Some(pos) = stack.pop() {
+                if visited[pos] {
+                    continue;
+                }
+                visited[pos] = true;
+                group.push(pos);
+
+                let P(y, x) = pos;
+                if symmetry.tetrad {
+                    stack.push(P(height - 1 - x, y));
+                } else if symmetry.dyad {
+                    stack.push(P(height - 1 - y, width - 1 - x));
+                }
+                if symmetry.horizontal {
+                    stack.push(P(height - 1 - y, x));
+                }
+                if symmetry.vertical {
+                    stack.push(P(y, width - 1 - x));
+                }
+            }
+            ret.push(group);
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+
+    #[test]
+    fn test_generator() {
+        let mut rng = rand::thread_rng();
+        let symmetry = Symmetry {
+            dyad: true,
+            tetrad: false,
+            horizontal: false,
+            vertical: false,
+        };
+
+        let mut succeeded = false;
+        for _ in 0..10 {
+            if let Some(problem) = generate(6, 6, symmetry, &mut rng) {
+                succeeded = true;
+
+                for y in 0..6 {
+                    for x in 0..6 {
+                        assert_eq!(problem[P(y, x)], problem[P(5 - y, 5 - x)]);
+                    }
+                }
+
+                let mut field = Field::new(&problem);
+                field.solve();
+                assert!(!field.inconsistent());
+                assert!(field.fully_solved());
+
+                break;
+            }
+        }
+        assert!(succeeded);
+    }
+}
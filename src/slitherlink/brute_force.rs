@@ -0,0 +1,102 @@
+use super::*;
+use grid_loop::brute_force::{
+    assert_consistent_edges, enumerate_loops, has_edge, loop_from_vertices,
+};
+use {Grid, ViolationKind, FOUR_NEIGHBOURS, LP, P};
+
+use rand::prng::XorShiftRng;
+use rand::{Rng, SeedableRng};
+
+fn count_lines(answer: &Grid<bool>, pos: P) -> i32 {
+    FOUR_NEIGHBOURS
+        .iter()
+        .filter(|&&d| has_edge(answer, LP::of_cell(pos) + d))
+        .count() as i32
+}
+
+/// Enumerates all the answers of `problem` among `loops`, which should be all the loops
+/// on the board.
+pub fn enumerate_answers(problem: &Grid<Clue>, loops: &[Grid<bool>]) -> Vec<Grid<bool>> {
+    loops
+        .iter()
+        .filter(|answer| {
+            (0..problem.height()).all(|y| {
+                (0..problem.width()).all(|x| {
+                    let Clue(c) = problem[P(y, x)];
+                    c < 0 || c == count_lines(answer, P(y, x))
+                })
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn test_random_against_brute_force() {
+    let dic = Dictionary::complete();
+    let two_cell_dic = TwoCellDictionary::complete();
+    let mut rng = XorShiftRng::from_seed([42u8; 16]);
+    let mut technique = Technique::new();
+    technique.sound = true;
+
+    for &(height, width) in &[
+        (1, 1),
+        (1, 2),
+        (1, 3),
+        (2, 2),
+        (2, 3),
+        (3, 3),
+        (3, 4),
+        (4, 4),
+    ] {
+        let loops = enumerate_loops(&Grid::new(height + 1, width + 1, true));
+        for _ in 0..200 {
+            // clues from a random loop, some of which are hidden
+            let base = &loops[rng.gen_range(0, loops.len())];
+            let mut problem = Grid::new(height, width, NO_CLUE);
+            for y in 0..height {
+                for x in 0..width {
+                    let n = count_lines(base, P(y, x));
+                    if n <= 3 && rng.gen::<f64>() < 0.6 {
+                        problem[P(y, x)] = Clue(n);
+                    }
+                }
+            }
+            let answers = enumerate_answers(&problem, &loops);
+            for answer in &answers {
+                assert!(check_answer(&problem, answer).is_empty());
+            }
+            for _ in 0..10 {
+                let candidate = &loops[rng.gen_range(0, loops.len())];
+                let violations = check_answer(&problem, candidate);
+                assert_eq!(violations.is_empty(), answers.contains(candidate));
+            }
+
+            let mut field = Field::new(&problem, &dic);
+            field.set_technique(technique);
+            field.set_two_cell_dictionary(&two_cell_dic);
+            let found = field.search(answers.len() + 1);
+            assert_eq!(found.len(), answers.len());
+            for vertices in &found {
+                let lp = loop_from_vertices(height + 1, width + 1, vertices);
+                assert!(answers.contains(&lp));
+            }
+
+            field.trial_and_error(1);
+            assert!(!field.inconsistent());
+            assert_consistent_edges(|lp| field.get_edge(lp), field.fully_solved(), &answers);
+        }
+    }
+}
+
+#[test]
+fn test_check_answer() {
+    let mut problem = Grid::new(1, 1, NO_CLUE);
+    problem[P(0, 0)] = Clue(3);
+    let answer = loop_from_vertices(2, 2, &[P(0, 0), P(0, 1), P(1, 1), P(1, 0)]);
+    let violations = check_answer(&problem, &answer);
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].kind, ViolationKind::Clue);
+    assert_eq!(violations[0].cells, vec![P(0, 0)]);
+    assert_eq!(violations[0].edges.len(), 4);
+}
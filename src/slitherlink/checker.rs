@@ -0,0 +1,41 @@
+use super::super::{Grid, Violation, ViolationKind, LP, P};
+use super::*;
+use grid_loop::check_loop;
+use FOUR_NEIGHBOURS;
+
+/// Checks whether `answer` is an answer of `problem`.
+/// `answer` has the size `(2 * height + 1) x (2 * width + 1)` and is indexed by lattice
+/// positions (`LP::of_cell(pos)` for the cell `pos`); it is true exactly on the edges of the loop.
+/// The violations reported are `Clue` (a cell whose clue differs from the number of lines
+/// around it) and those of `grid_loop::check_loop`, whose vertices are given only by `edges`.
+pub fn check_answer(problem: &Grid<Clue>, answer: &Grid<bool>) -> Vec<Violation> {
+    let height = problem.height();
+    let width = problem.width();
+    assert_eq!(answer.height(), height * 2 + 1);
+    assert_eq!(answer.width(), width * 2 + 1);
+
+    let mut ret = vec![];
+    for y in 0..height {
+        for x in 0..width {
+            let pos = P(y, x);
+            let Clue(c) = problem[pos];
+            if c < 0 {
+                continue;
+            }
+            let lines = FOUR_NEIGHBOURS
+                .iter()
+                .map(|&d| LP::of_cell(pos) + d)
+                .filter(|&e| answer[e])
+                .collect::<Vec<_>>();
+            if lines.len() as i32 != c {
+                ret.push(Violation::new(ViolationKind::Clue, vec![pos], lines));
+            }
+        }
+    }
+    for mut violation in check_loop(answer) {
+        // vertices of the loop are not cells in Slitherlink
+        violation.cells.clear();
+        ret.push(violation);
+    }
+    ret
+}
@@ -1,6 +1,9 @@
 use super::super::D;
 use super::Clue;
 use grid_loop::Edge;
+use io::{read_binary_header, read_u32_table, write_binary_header, write_u32_table, ReadError};
+use std::io::{self, Read, Write};
+use std::sync::{Arc, OnceLock};
 
 pub const DICTIONARY_NEIGHBOR_SIZE: usize = 12;
 pub const DICTIONARY_EDGE_OFFSET: [D; DICTIONARY_NEIGHBOR_SIZE] = [
@@ -22,6 +25,13 @@ const DICTIONARY_NEIGHBOR_PATTERN_COUNT: usize = 531441; // 3^12
 const DICTIONARY_SIZE: usize = DICTIONARY_NEIGHBOR_PATTERN_COUNT * 4;
 pub const DICTIONARY_INCONSISTENT: u32 = 0xffffffff;
 
+const DICTIONARY_BINARY_TAG: &[u8; 4] = b"SLDC";
+/// Version of the contents of the binary dictionary; bump it whenever `complete` changes.
+const DICTIONARY_BINARY_VERSION: u32 = 1;
+
+static SHARED_DICTIONARY: OnceLock<Arc<Dictionary>> = OnceLock::new();
+
+#[derive(PartialEq, Eq)]
 pub struct Dictionary {
     dic: Vec<u32>,
 }
@@ -91,6 +101,28 @@ impl Dictionary {
         }
         Dictionary { dic }
     }
+    /// Returns the process-wide dictionary, which is built by `complete` on the first call.
+    pub fn shared() -> Arc<Dictionary> {
+        SHARED_DICTIONARY
+            .get_or_init(|| Arc::new(Dictionary::complete()))
+            .clone()
+    }
+    /// Makes `dic` (e.g. one loaded by `read_binary`) the dictionary returned by `shared`.
+    /// Returns `false` without doing anything if `shared` has already been initialized.
+    pub fn set_shared(dic: Dictionary) -> bool {
+        SHARED_DICTIONARY.set(Arc::new(dic)).is_ok()
+    }
+    /// Writes the dictionary in a binary format which can be loaded by `read_binary`.
+    pub fn write_binary<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_binary_header(writer, DICTIONARY_BINARY_TAG, DICTIONARY_BINARY_VERSION)?;
+        write_u32_table(writer, &self.dic)
+    }
+    /// Loads a dictionary written by `write_binary` instead of building it by `complete`.
+    pub fn read_binary<R: Read>(reader: &mut R) -> Result<Dictionary, ReadError> {
+        read_binary_header(reader, DICTIONARY_BINARY_TAG, DICTIONARY_BINARY_VERSION)?;
+        let dic = read_u32_table(reader, DICTIONARY_SIZE)?;
+        Ok(Dictionary { dic })
+    }
     pub fn consult_raw(&self, Clue(c): Clue, neighbor_code: u32) -> u32 {
         self.dic[c as usize * DICTIONARY_NEIGHBOR_PATTERN_COUNT + neighbor_code as usize]
     }
@@ -164,3 +196,145 @@ impl Dictionary {
         ret
     }
 }
+
+pub const TWO_CELL_NEIGHBOR_SIZE: usize = 17;
+/// Offsets of the edges around a domino of 2 cells, relative to the center of the first cell.
+/// The second cell is located at `D(0, 2)`.
+pub const TWO_CELL_EDGE_OFFSET: [D; TWO_CELL_NEIGHBOR_SIZE] = [
+    D(-2, -1),
+    D(-2, 1),
+    D(-2, 3),
+    D(-1, -2),
+    D(-1, 0),
+    D(-1, 2),
+    D(-1, 4),
+    D(0, -1),
+    D(0, 1),
+    D(0, 3),
+    D(1, -2),
+    D(1, 0),
+    D(1, 2),
+    D(1, 4),
+    D(2, -1),
+    D(2, 1),
+    D(2, 3),
+];
+const TWO_CELL_SMALL_LOOP: u32 = 1 << 31;
+
+/// Dictionary of the valid line placements around a domino of 2 clue cells.
+/// Since 3^17 patterns are too many to be tabulated, all the valid placements are listed for
+/// each pair of clues instead, and they are filtered by the current status on consultation.
+pub struct TwoCellDictionary {
+    patterns: Vec<Vec<u32>>,
+}
+impl TwoCellDictionary {
+    pub fn complete() -> TwoCellDictionary {
+        let edge_index = |d: D| TWO_CELL_EDGE_OFFSET.iter().position(|&e| e == d);
+        let mut vertices = vec![];
+        for &y in &[-1, 1] {
+            for &x in &[-1, 1, 3] {
+                let mut mask = 0u32;
+                for &d in &::FOUR_NEIGHBOURS {
+                    mask |= 1 << edge_index(D(y, x) + d).unwrap();
+                }
+                vertices.push(mask);
+            }
+        }
+        let mut cell_masks = [0u32; 2];
+        for (i, &ofs) in [0, 2].iter().enumerate() {
+            for &d in &::FOUR_NEIGHBOURS {
+                cell_masks[i] |= 1 << edge_index(D(0, ofs) + d).unwrap();
+            }
+        }
+        let perimeter = (cell_masks[0] | cell_masks[1]) & !(cell_masks[0] & cell_masks[1]);
+
+        let mut patterns = vec![vec![]; 16];
+        for pat in 0..(1u32 << TWO_CELL_NEIGHBOR_SIZE) {
+            if vertices.iter().any(|&v| {
+                let n = (pat & v).count_ones();
+                n != 0 && n != 2
+            }) {
+                continue;
+            }
+            let clue1 = (pat & cell_masks[0]).count_ones() as usize;
+            let clue2 = (pat & cell_masks[1]).count_ones() as usize;
+            if clue1 == 4 || clue2 == 4 {
+                continue;
+            }
+            let small_loop = pat & perimeter == perimeter;
+            patterns[clue1 * 4 + clue2].push(if small_loop {
+                pat | TWO_CELL_SMALL_LOOP
+            } else {
+                pat
+            });
+        }
+        TwoCellDictionary { patterns }
+    }
+    /// Returns the process-wide dictionary, which is built by `complete` on the first call.
+    pub fn shared() -> Arc<TwoCellDictionary> {
+        static SHARED: OnceLock<Arc<TwoCellDictionary>> = OnceLock::new();
+        SHARED
+            .get_or_init(|| Arc::new(TwoCellDictionary::complete()))
+            .clone()
+    }
+    /// Returns the edges which are decided to be line and blank respectively, given the pair of
+    /// clues and the edges which are currently line and blank.
+    /// `None` is returned if no valid placement is consistent with the current status.
+    /// Placements forming a loop encircling the 2 cells are ignored if `avoid_small_loop` is set.
+    pub fn consult(
+        &self,
+        Clue(c1): Clue,
+        Clue(c2): Clue,
+        line: u32,
+        blank: u32,
+        avoid_small_loop: bool,
+    ) -> Option<(u32, u32)> {
+        let full = (1u32 << TWO_CELL_NEIGHBOR_SIZE) - 1;
+        let mut always_line = full;
+        let mut always_blank = full;
+        let mut found = false;
+        for &pat in &self.patterns[c1 as usize * 4 + c2 as usize] {
+            if avoid_small_loop && (pat & TWO_CELL_SMALL_LOOP) != 0 {
+                continue;
+            }
+            let pat = pat & full;
+            if (pat & line) != line || (pat & blank) != 0 {
+                continue;
+            }
+            found = true;
+            always_line &= pat;
+            always_blank &= !pat;
+        }
+        if found {
+            Some((always_line & !line, always_blank & full & !blank))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binary_dictionary() {
+        let dic = Dictionary::complete();
+        let mut buf = vec![];
+        dic.write_binary(&mut buf).unwrap();
+
+        let loaded = Dictionary::read_binary(&mut &buf[..]).unwrap();
+        assert!(loaded == dic);
+
+        assert!(Dictionary::read_binary(&mut &buf[..buf.len() - 1]).is_err());
+        assert!(Dictionary::read_binary(&mut &buf[1..]).is_err());
+
+        // a cache written by a build with another version of the dictionary
+        let mut stale = buf.clone();
+        stale[4] ^= 0xff;
+        assert!(Dictionary::read_binary(&mut &stale[..]).is_err());
+        let mut corrupted = buf.clone();
+        corrupted[100] ^= 1;
+        assert!(Dictionary::read_binary(&mut &corrupted[..]).is_err());
+    }
+}
@@ -1,24 +1,54 @@
-use super::super::{Grid, D, LP, P};
+use super::super::{Grid, SharedRef, SolverStats, D, LP, P};
 use super::*;
-use grid_loop::{Edge, GridLoop, GridLoopField};
+use grid_loop::{Edge, GridLoop, GridLoopField, LineChain};
+use std::sync::atomic::{AtomicBool, Ordering};
 use FOUR_NEIGHBOURS;
 
+#[derive(Clone)]
+enum History {
+    Clue(P),
+    Checkpoint,
+}
+
 #[derive(Clone)]
 pub struct Field<'a> {
     grid_loop: GridLoop,
     clue: Grid<Clue>,
-    dic: &'a Dictionary,
+    dic: SharedRef<'a, Dictionary>,
+    two_cell_dic: Option<SharedRef<'a, TwoCellDictionary>>,
+    technique: Technique,
+    history: Vec<History>,
+    stats: SolverStats,
 }
 impl<'a> Field<'a> {
-    pub fn new(clue: &Grid<Clue>, dic: &'a Dictionary) -> Field<'a> {
+    /// Creates a field with a borrowed dictionary (`&Dictionary`) or a shared one
+    /// (`Arc<Dictionary>`, e.g. `Dictionary::shared()`).
+    /// The latter gives `Field<'static>`, which can be sent across threads.
+    pub fn new<T: Into<SharedRef<'a, Dictionary>>>(clue: &Grid<Clue>, dic: T) -> Field<'a> {
         let grid_loop = GridLoop::new(clue.height(), clue.width());
 
         Field {
             grid_loop: grid_loop,
             clue: clue.clone(),
-            dic: dic,
+            dic: dic.into(),
+            two_cell_dic: None,
+            technique: Technique::new(),
+            history: Vec::new(),
+            stats: SolverStats::new(),
         }
     }
+    pub fn get_technique(&self) -> Technique {
+        self.technique
+    }
+    pub fn set_technique(&mut self, technique: Technique) {
+        self.technique = technique;
+        self.grid_loop
+            .set_avoid_small_loop(technique.avoid_small_loop);
+    }
+    /// Enables the deduction by `TwoCellDictionary` when `technique.two_cell_dictionary` is set.
+    pub fn set_two_cell_dictionary<T: Into<SharedRef<'a, TwoCellDictionary>>>(&mut self, dic: T) {
+        self.two_cell_dic = Some(dic.into());
+    }
     pub fn height(&self) -> i32 {
         self.clue.height()
     }
@@ -59,6 +89,9 @@ impl<'a> Field<'a> {
             }
         } else {
             self.clue[pos] = clue;
+            if !self.history.is_empty() {
+                self.history.push(History::Clue(pos));
+            }
 
             let mut handle = GridLoop::get_handle(self);
             handle.inspect_technique(LP::of_cell(pos));
@@ -71,6 +104,194 @@ impl<'a> Field<'a> {
     pub fn get_edge_safe(&self, pos: LP) -> Edge {
         self.grid_loop.get_edge_safe(pos)
     }
+    pub fn line_chains(&self) -> Vec<LineChain> {
+        self.grid_loop.line_chains()
+    }
+    pub fn solved_loop(&self) -> Option<Vec<P>> {
+        self.grid_loop.solved_loop()
+    }
+    pub fn num_decided_edges(&self) -> i32 {
+        self.grid_loop.num_decided_edges()
+    }
+    /// Returns the statistics of the techniques (in decided edges) and the trial and error.
+    pub fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+    pub fn set_stats_enabled(&mut self, enabled: bool) {
+        self.stats.set_enabled(enabled);
+    }
+    /// Add a checkpoint.
+    /// Subsequent updates of the field (including the underlying `GridLoop`) are recorded
+    /// until `rollback` is called.
+    pub fn add_checkpoint(&mut self) {
+        self.grid_loop.add_checkpoint();
+        self.history.push(History::Checkpoint);
+    }
+    /// Rollback until the last checkpoint.
+    pub fn rollback(&mut self) {
+        self.grid_loop.rollback();
+        while let Some(entry) = self.history.pop() {
+            match entry {
+                History::Clue(pos) => self.clue[pos] = NO_CLUE,
+                History::Checkpoint => break,
+            }
+        }
+    }
+
+    pub fn solve(&mut self) {
+        loop {
+            let current_decided_edges = self.grid_loop.num_decided_edges();
+            self.apply_technique("local", Field::check_all_cell);
+            if self.technique.inout_rule {
+                self.apply_technique("inout_rule", GridLoop::apply_inout_rule);
+            }
+            if self.technique.connectability {
+                self.apply_technique("connectability", GridLoop::check_connectability);
+            }
+            if self.inconsistent() || current_decided_edges == self.grid_loop.num_decided_edges() {
+                break;
+            }
+        }
+    }
+    pub fn trial_and_error(&mut self, depth: i32) {
+        self.trial_and_error_cancellable(depth, &AtomicBool::new(false))
+    }
+    /// Same as `trial_and_error`, but gives up when `cancel` is set, keeping the edges
+    /// decided so far.
+    pub fn trial_and_error_cancellable(&mut self, depth: i32, cancel: &AtomicBool) {
+        let height = self.height();
+        let width = self.width();
+
+        if depth == 0 {
+            self.solve();
+            return;
+        }
+        self.trial_and_error_cancellable(depth - 1, cancel);
+
+        loop {
+            let mut updated = false;
+            for y in 0..(height * 2 + 1) {
+                for x in 0..(width * 2 + 1) {
+                    if y % 2 == x % 2 {
+                        continue;
+                    }
+                    if cancel.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    let pos = LP(y, x);
+                    if self.get_edge(pos) != Edge::Undecided || !self.grid_loop.is_root(pos) {
+                        continue;
+                    }
+
+                    if self.is_inconsistent_with_edge(pos, Edge::Line, depth - 1) {
+                        updated = true;
+                        GridLoop::decide_edge(self, pos, Edge::Blank);
+                        self.trial_and_error_cancellable(depth - 1, cancel);
+                    }
+                    if self.get_edge(pos) == Edge::Undecided
+                        && self.is_inconsistent_with_edge(pos, Edge::Blank, depth - 1)
+                    {
+                        updated = true;
+                        GridLoop::decide_edge(self, pos, Edge::Line);
+                        self.trial_and_error_cancellable(depth - 1, cancel);
+                    }
+                    if self.inconsistent() || self.fully_solved() {
+                        return;
+                    }
+                }
+            }
+            if !updated {
+                break;
+            }
+        }
+    }
+    /// Enumerates the answers of the problem, up to `limit` answers.
+    /// Each answer is given as the vertices on the loop in order.
+    /// The field is left unchanged.
+    pub fn search(&mut self, limit: usize) -> Vec<Vec<P>> {
+        self.search_cancellable(limit, &AtomicBool::new(false))
+    }
+    /// Same as `search`, but gives up when `cancel` is set, returning the answers found so far.
+    pub fn search_cancellable(&mut self, limit: usize, cancel: &AtomicBool) -> Vec<Vec<P>> {
+        let mut answers = vec![];
+        self.add_checkpoint();
+        self.search_dfs(limit, cancel, &mut answers);
+        self.rollback();
+        answers
+    }
+    /// Checks whether the problem has exactly one answer.
+    pub fn has_unique_answer(&mut self) -> bool {
+        self.search(2).len() == 1
+    }
+    fn search_dfs(&mut self, limit: usize, cancel: &AtomicBool, answers: &mut Vec<Vec<P>>) {
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        self.solve();
+        if self.inconsistent() {
+            return;
+        }
+        if self.fully_solved() {
+            if let Some(answer) = self.solved_loop() {
+                answers.push(answer);
+            }
+            return;
+        }
+        let edge = match self.find_branching_edge() {
+            Some(edge) => edge,
+            None => return,
+        };
+        for &status in &[Edge::Line, Edge::Blank] {
+            if answers.len() >= limit {
+                return;
+            }
+            self.add_checkpoint();
+            GridLoop::decide_edge(self, edge, status);
+            self.search_dfs(limit, cancel, answers);
+            self.rollback();
+        }
+    }
+    /// Finds an undecided edge to branch on, preferring one extending an existing line.
+    fn find_branching_edge(&self) -> Option<LP> {
+        let height = self.height();
+        let width = self.width();
+        let mut ret = None;
+        for y in 0..(height + 1) {
+            for x in 0..(width + 1) {
+                let vertex = LP::of_vertex(P(y, x));
+                let (n_line, _) = self.grid_loop.neighbor_summary(vertex);
+                for &d in &FOUR_NEIGHBOURS {
+                    if self.get_edge_safe(vertex + d) == Edge::Undecided {
+                        if n_line == 1 {
+                            return Some(vertex + d);
+                        }
+                        if ret.is_none() {
+                            ret = Some(vertex + d);
+                        }
+                    }
+                }
+            }
+        }
+        ret
+    }
+    /// Checks whether deciding edge `pos` to `status` leads to an inconsistency
+    /// within trial-and-error of depth `depth`. The field is left unchanged.
+    fn is_inconsistent_with_edge(&mut self, pos: LP, status: Edge, depth: i32) -> bool {
+        self.add_checkpoint();
+        GridLoop::decide_edge(self, pos, status);
+        self.trial_and_error(depth);
+        let ret = self.inconsistent();
+        self.rollback();
+        self.stats.record_trial(ret);
+        ret
+    }
+    /// Applies `technique` and records the number of edges decided by it.
+    fn apply_technique<F: FnOnce(&mut Field<'a>)>(&mut self, name: &'static str, technique: F) {
+        let decided_edges = self.num_decided_edges();
+        technique(self);
+        let n_decided = self.num_decided_edges() - decided_edges;
+        self.stats.record(name, n_decided);
+    }
 
     fn inspect_technique(&mut self, pos: LP) {
         if pos.is_cell() {
@@ -81,14 +302,18 @@ impl<'a> Field<'a> {
                     GridLoop::decide_edge(self, pos + d, Edge::Blank);
                 }
             }
-            if clue == Clue(3) {
+            if clue == Clue(3) && self.technique.adjacent_three {
                 // adjacent 3
                 //for d in 0..4 {
                 //    let (Y(dy), X(dx)) = neighbor[d];
                 for &d in &FOUR_NEIGHBOURS {
                     let cell2 = cell_pos + d;
-                    if self.clue.is_valid_p(cell2) && self.clue[cell2] == Clue(3) {
+                    if self.clue.is_valid_p(cell2)
+                        && self.clue[cell2] == Clue(3)
+                        && !(self.technique.sound && self.is_small_loop_answer(&[cell_pos, cell2]))
+                    {
                         // Deriberately ignoring the possible small loop encircling the two 3's
+                        // unless in the soundness mode
                         GridLoop::decide_edge(self, pos - d, Edge::Line);
                         GridLoop::decide_edge(self, pos + d, Edge::Line);
                         GridLoop::decide_edge(self, pos + d * 3, Edge::Line);
@@ -104,7 +329,8 @@ impl<'a> Field<'a> {
                         );
                     }
                 }
-
+            }
+            if clue == Clue(3) && self.technique.diagonal_three {
                 // diagonal 3
                 for &d in &FOUR_NEIGHBOURS {
                     let dr = d.rotate_clockwise();
@@ -117,6 +343,131 @@ impl<'a> Field<'a> {
                     }
                 }
             }
+            if (clue == Clue(1) || clue == Clue(3)) && self.technique.corner {
+                // 1 or 3 in a corner
+                for &d in &FOUR_NEIGHBOURS {
+                    let dr = d.rotate_clockwise();
+                    if !self.clue.is_valid_p(cell_pos + d) && !self.clue.is_valid_p(cell_pos + dr) {
+                        let status = if clue == Clue(1) {
+                            Edge::Blank
+                        } else {
+                            Edge::Line
+                        };
+                        GridLoop::decide_edge(self, pos + d, status);
+                        GridLoop::decide_edge(self, pos + dr, status);
+                    }
+                }
+            }
+        }
+    }
+    /// Decides edges around a 2 on the border of the field.
+    /// If the border edge next to a corner of the 2 is blank (e.g. the 2 is in a corner),
+    /// the loop passes either both or none of the 2 edges of the cell meeting at the corner,
+    /// and in both cases the loop goes along the border beyond the opposite corner.
+    fn inspect_border_two(&mut self, pos: LP) {
+        let cell_pos = pos.as_cell();
+        for &d in &FOUR_NEIGHBOURS {
+            if self.clue.is_valid_p(cell_pos + d) {
+                continue;
+            }
+            let dr = d.rotate_clockwise();
+            for &sign in &[1, -1] {
+                let s = dr * sign;
+                if self.get_edge_safe(pos + d + s * 2) == Edge::Blank {
+                    GridLoop::decide_edge(self, pos + d - s * 2, Edge::Line);
+                }
+            }
+        }
+    }
+    /// Consults `TwoCellDictionary` for the dominoes consisting of the clue at `pos` and
+    /// each of the adjacent clues.
+    fn inspect_two_cell(&mut self, pos: LP, clue: Clue) {
+        if self.two_cell_dic.is_none() {
+            return;
+        }
+        let cell_pos = pos.as_cell();
+        for &d in &FOUR_NEIGHBOURS {
+            let cell2 = cell_pos + d;
+            if !self.clue.is_valid_p(cell2) || self.clue[cell2] == NO_CLUE {
+                continue;
+            }
+            let clue2 = self.clue[cell2];
+            let dr = d.rotate_clockwise();
+            let mut edges = [pos; TWO_CELL_NEIGHBOR_SIZE];
+            let mut line = 0u32;
+            let mut blank = 0u32;
+            for i in 0..TWO_CELL_NEIGHBOR_SIZE {
+                let D(y, x) = TWO_CELL_EDGE_OFFSET[i];
+                edges[i] = pos + d * x + dr * y;
+                match self.grid_loop.get_edge_safe(edges[i]) {
+                    Edge::Line => line |= 1 << i,
+                    Edge::Blank => blank |= 1 << i,
+                    Edge::Undecided => (),
+                }
+            }
+            let avoid_small_loop = self.technique.avoid_small_loop
+                && !(self.technique.sound && self.is_small_loop_answer(&[cell_pos, cell2]));
+            let consulted = match self.two_cell_dic {
+                Some(ref dic) => dic.consult(clue, clue2, line, blank, avoid_small_loop),
+                None => return,
+            };
+            match consulted {
+                Some((new_line, new_blank)) => {
+                    for (i, &e) in edges.iter().enumerate() {
+                        if (new_line >> i) & 1 != 0 {
+                            GridLoop::decide_edge(self, e, Edge::Line);
+                        } else if (new_blank >> i) & 1 != 0 {
+                            GridLoop::decide_edge(self, e, Edge::Blank);
+                        }
+                    }
+                }
+                None => {
+                    self.set_inconsistent();
+                    return;
+                }
+            }
+        }
+    }
+    /// Returns whether the loop encircling `cells` satisfies all the clues,
+    /// that is, whether the loop can be the answer of the problem.
+    fn is_small_loop_answer(&self, cells: &[P]) -> bool {
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let pos = P(y, x);
+                let Clue(clue) = self.clue[pos];
+                if clue < 0 {
+                    continue;
+                }
+                let inside = cells.contains(&pos);
+                let n_line = FOUR_NEIGHBOURS
+                    .iter()
+                    .filter(|&&d| cells.contains(&(pos + d)) != inside)
+                    .count();
+                if n_line as i32 != clue {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+    /// Checks the number of lines around a clue without consulting the dictionary.
+    fn inspect_count(&mut self, pos: LP, Clue(clue): Clue) {
+        let (n_line, n_undecided) = self.grid_loop.neighbor_summary(pos);
+        if n_line > clue || n_line + n_undecided < clue {
+            self.set_inconsistent();
+            return;
+        }
+        if n_line == clue || n_line + n_undecided == clue {
+            let status = if n_line == clue {
+                Edge::Blank
+            } else {
+                Edge::Line
+            };
+            for &d in &FOUR_NEIGHBOURS {
+                if self.get_edge(pos + d) == Edge::Undecided {
+                    GridLoop::decide_edge(self, pos + d, status);
+                }
+            }
         }
     }
 }
@@ -153,6 +504,16 @@ impl<'a> GridLoopField for Field<'a> {
             if clue == NO_CLUE || clue == Clue(0) {
                 return;
             }
+            if clue == Clue(2) && self.technique.border_two {
+                self.inspect_border_two(pos);
+            }
+            if self.technique.two_cell_dictionary {
+                self.inspect_two_cell(pos, clue);
+            }
+            if !self.technique.dictionary {
+                self.inspect_count(pos, clue);
+                return;
+            }
 
             let mut neighbors_code = 0;
             let mut pow3 = 1;
@@ -192,6 +553,8 @@ impl<'a> GridLoopField for Field<'a> {
 mod tests {
     use super::*;
     use common;
+    use std::sync::Arc;
+    use std::thread;
 
     fn run_problem_test(dic: &Dictionary, input: &[&str], fully_solved: bool) {
         let height = (input.len() / 2) as i32;
@@ -244,6 +607,194 @@ mod tests {
         }
     }
 
+    fn problem_from_str(rows: &[&str]) -> Grid<Clue> {
+        let height = rows.len() as i32;
+        let width = rows[0].len() as i32;
+        let mut ret = Grid::new(height, width, NO_CLUE);
+        for y in 0..height {
+            let mut row_iter = rows[y as usize].chars();
+            for x in 0..width {
+                if let c @ '0'..='3' = row_iter.next().unwrap() {
+                    ret[P(y, x)] = Clue((c as u8 - b'0') as i32);
+                }
+            }
+        }
+        ret
+    }
+
+    #[test]
+    fn test_technique() {
+        let dic = Dictionary::complete();
+        {
+            let problem = problem_from_str(&["3.1", "...", "..."]);
+            let mut technique = Technique::disabled_all();
+            technique.corner = true;
+
+            let mut field = Field::new(&problem, &dic);
+            field.set_technique(technique);
+            field.check_all_cell();
+
+            assert!(!field.inconsistent());
+            assert_eq!(field.get_edge(LP(0, 1)), Edge::Line);
+            assert_eq!(field.get_edge(LP(1, 0)), Edge::Line);
+            assert_eq!(field.get_edge(LP(0, 5)), Edge::Blank);
+            assert_eq!(field.get_edge(LP(1, 6)), Edge::Blank);
+        }
+        {
+            let problem = problem_from_str(&["2..", "...", "..."]);
+            let mut technique = Technique::disabled_all();
+            technique.border_two = true;
+
+            let mut field = Field::new(&problem, &dic);
+            field.set_technique(technique);
+            field.check_all_cell();
+
+            assert!(!field.inconsistent());
+            assert_eq!(field.get_edge(LP(0, 3)), Edge::Line);
+            assert_eq!(field.get_edge(LP(3, 0)), Edge::Line);
+            assert_eq!(field.get_edge(LP(0, 1)), Edge::Undecided);
+        }
+        {
+            let problem = problem_from_str(&["...", ".2.", "..."]);
+            let mut field = Field::new(&problem, &dic);
+            field.set_technique(Technique::disabled_all());
+            GridLoop::decide_edge(&mut field, LP(2, 3), Edge::Blank);
+            GridLoop::decide_edge(&mut field, LP(3, 2), Edge::Blank);
+
+            assert!(!field.inconsistent());
+            assert_eq!(field.get_edge(LP(4, 3)), Edge::Line);
+            assert_eq!(field.get_edge(LP(3, 4)), Edge::Line);
+        }
+    }
+
+    #[test]
+    fn test_two_cell_dictionary() {
+        let dic = Dictionary::complete();
+        let two_cell_dic = TwoCellDictionary::complete();
+        let problem = problem_from_str(&["....", ".33.", "...."]);
+        let mut technique = Technique::new();
+        technique.adjacent_three = false;
+
+        let mut field = Field::new(&problem, &dic);
+        field.set_technique(technique);
+        field.check_all_cell();
+        assert_eq!(field.get_edge(LP(3, 4)), Edge::Undecided);
+
+        let mut field = Field::new(&problem, &dic);
+        field.set_technique(technique);
+        field.set_two_cell_dictionary(&two_cell_dic);
+        field.check_all_cell();
+        assert_eq!(field.get_edge(LP(3, 2)), Edge::Line);
+        assert_eq!(field.get_edge(LP(3, 4)), Edge::Line);
+        assert_eq!(field.get_edge(LP(3, 6)), Edge::Line);
+        assert_eq!(field.get_edge(LP(1, 4)), Edge::Blank);
+        assert_eq!(field.get_edge(LP(5, 4)), Edge::Blank);
+
+        // the loop encircling the two 3's is not excluded
+        technique.avoid_small_loop = false;
+        let mut field = Field::new(&problem, &dic);
+        field.set_technique(technique);
+        field.set_two_cell_dictionary(&two_cell_dic);
+        field.check_all_cell();
+        assert_eq!(field.get_edge(LP(3, 4)), Edge::Undecided);
+    }
+
+    #[test]
+    fn test_shared_dictionary() {
+        assert!(Arc::ptr_eq(&Dictionary::shared(), &Dictionary::shared()));
+
+        let problem = problem_from_str(&["3...", "..1.", ".1..", "...3"]);
+        let mut field: Field<'static> = Field::new(&problem, Dictionary::shared());
+        field.set_two_cell_dictionary(TwoCellDictionary::shared());
+        let field = thread::spawn(move || {
+            field.solve();
+            field
+        })
+        .join()
+        .unwrap();
+
+        let dic = Dictionary::complete();
+        let mut expected = Field::new(&problem, &dic);
+        expected.solve();
+        assert_eq!(field.num_decided_edges(), expected.num_decided_edges());
+    }
+
+    #[test]
+    fn test_sound_mode() {
+        let dic = Dictionary::complete();
+        let problem = problem_from_str(&["33"]);
+
+        let mut field = Field::new(&problem, &dic);
+        field.solve();
+        assert!(field.inconsistent());
+
+        let mut technique = Technique::new();
+        technique.sound = true;
+        let mut field = Field::new(&problem, &dic);
+        field.set_technique(technique);
+        field.solve();
+        assert!(field.fully_solved());
+        assert_eq!(field.get_edge(LP(1, 2)), Edge::Blank);
+
+        let problem = problem_from_str(&["33", "0."]);
+        let mut field = Field::new(&problem, &dic);
+        field.set_technique(technique);
+        field.check_all_cell();
+        assert_eq!(field.get_edge(LP(1, 2)), Edge::Line);
+    }
+
+    #[test]
+    fn test_trial_and_error() {
+        let dic = Dictionary::complete();
+        let problem = problem_from_str(&[".....", ".3.22", ".0.2.", "...11", "1..1."]);
+
+        let mut field = Field::new(&problem, &dic);
+        field.solve();
+        assert!(!field.inconsistent());
+        assert!(!field.fully_solved());
+
+        let mut field = Field::new(&problem, &dic);
+        field.trial_and_error(1);
+        assert!(!field.inconsistent());
+        assert!(field.fully_solved());
+
+        let mut field = Field::new(&problem, &dic);
+        field.trial_and_error_cancellable(1, &AtomicBool::new(true));
+        assert!(!field.inconsistent());
+        assert!(!field.fully_solved());
+    }
+
+    #[test]
+    fn test_search() {
+        let dic = Dictionary::complete();
+        let problem = problem_from_str(&[".....", ".3.22", ".0.2.", "...11", "1..1."]);
+
+        let mut field = Field::new(&problem, &dic);
+        let answers = field.search(2);
+        assert_eq!(answers.len(), 1);
+
+        let mut solved = Field::new(&problem, &dic);
+        solved.trial_and_error(1);
+        let expected = solved.solved_loop().unwrap();
+        assert_eq!(answers[0].len(), expected.len());
+        for w in answers[0].windows(2) {
+            let lp = LP(w[0].0 + w[1].0, w[0].1 + w[1].1);
+            assert_eq!(solved.get_edge(lp), Edge::Line);
+        }
+
+        // `search` leaves the field unchanged
+        assert_eq!(field.num_decided_edges(), 0);
+        assert!(!field.inconsistent());
+
+        let cancel = AtomicBool::new(true);
+        assert!(field.search_cancellable(2, &cancel).is_empty());
+
+        let problem = problem_from_str(&[".....", ".3.22", ".0.2.", "...11", "...1."]);
+        let mut field = Field::new(&problem, &dic);
+        assert!(!field.has_unique_answer());
+        assert_eq!(field.search(3).len(), 3);
+    }
+
     #[test]
     fn test_problem() {
         let dic = Dictionary::complete();
@@ -5,10 +5,43 @@ use grid_loop::{Edge, GridLoop, GridLoopField};
 use rand::Rng;
 use FOUR_NEIGHBOURS;
 
+#[derive(Clone, Copy)]
+pub struct GeneratorOption {
+    pub technique: Technique,
+    pub search_depth: i32,
+}
+
+impl GeneratorOption {
+    pub fn new() -> GeneratorOption {
+        GeneratorOption {
+            technique: Technique::new(),
+            search_depth: 0,
+        }
+    }
+}
+
+impl Default for GeneratorOption {
+    fn default() -> GeneratorOption {
+        GeneratorOption::new()
+    }
+}
+
 pub fn generate<R: Rng>(
     has_clue: &Grid<bool>,
     dic: &Dictionary,
     rng: &mut R,
+) -> Option<Grid<Clue>> {
+    generate_with_option(has_clue, dic, &GeneratorOption::new(), rng)
+}
+
+/// Generates a problem which is solved with the techniques in `opts.technique` by
+/// `Field::trial_and_error(opts.search_depth)`.
+/// If `opts.search_depth` is 0, the problem is solved by `Field::check_all_cell` only.
+pub fn generate_with_option<R: Rng>(
+    has_clue: &Grid<bool>,
+    dic: &Dictionary,
+    opts: &GeneratorOption,
+    rng: &mut R,
 ) -> Option<Grid<Clue>> {
     let height = has_clue.height();
     let width = has_clue.width();
@@ -27,7 +60,7 @@ pub fn generate<R: Rng>(
         }
     }
 
-    let mut last_field = Field::new(&current_problem, dic);
+    let mut last_field = new_field(&current_problem, dic, opts.technique);
 
     for _ in 0..max_step {
         let mut pos_cand = vec![];
@@ -76,7 +109,7 @@ pub fn generate<R: Rng>(
                 if pos_with_clue_idx % 2 == 0 {
                     if pos_with_clue_idx == pos_with_clue.len() - 1 {
                         current_problem[pos] = NO_CLUE;
-                        common = Field::new(&current_problem, dic);
+                        common = new_field(&current_problem, dic, opts.technique);
                         common.check_all_cell();
                     } else {
                         let c1 = current_problem[pos_with_clue[pos_with_clue_idx]];
@@ -84,7 +117,7 @@ pub fn generate<R: Rng>(
                         let c2 = current_problem[pos_with_clue[pos_with_clue_idx + 1]];
                         current_problem[pos_with_clue[pos_with_clue_idx + 1]] = NO_CLUE;
 
-                        interpos_common = Field::new(&current_problem, dic);
+                        interpos_common = new_field(&current_problem, dic, opts.technique);
                         interpos_common.check_all_cell();
 
                         current_problem[pos_with_clue[pos_with_clue_idx]] = c1;
@@ -108,6 +141,9 @@ pub fn generate<R: Rng>(
 
                 let mut field = common.clone();
                 field.add_clue(pos, c);
+                if opts.search_depth > 0 {
+                    field.trial_and_error(opts.search_depth);
+                }
 
                 if field.inconsistent() {
                     continue;
@@ -156,6 +192,12 @@ pub fn generate<R: Rng>(
     None
 }
 
+fn new_field<'a>(problem: &Grid<Clue>, dic: &'a Dictionary, technique: Technique) -> Field<'a> {
+    let mut ret = Field::new(problem, dic);
+    ret.set_technique(technique);
+    ret
+}
+
 fn has_undecided_nearby(field: &Field, pos: P) -> bool {
     let lp = LP::of_cell(pos);
 
@@ -356,12 +398,17 @@ mod tests {
     use super::*;
     use rand;
 
-    fn run_placement_test<R: Rng>(placement: Vec<Vec<bool>>, dic: &Dictionary, rng: &mut R) {
+    fn run_placement_test<R: Rng>(
+        placement: Vec<Vec<bool>>,
+        dic: &Dictionary,
+        opts: &GeneratorOption,
+        rng: &mut R,
+    ) {
         let placement = ::common::vec_to_grid(&placement);
         let mut succeeded = false;
 
         for _ in 0..10 {
-            let problem = generate(&placement, dic, rng);
+            let problem = generate_with_option(&placement, dic, opts, rng);
 
             if let Some(problem) = problem {
                 succeeded = true;
@@ -394,8 +441,13 @@ mod tests {
                     }
                 }
 
-                let mut field = Field::new(&problem, &dic);
-                field.check_all_cell();
+                let mut field = Field::new(&problem, dic);
+                field.set_technique(opts.technique);
+                if opts.search_depth == 0 {
+                    field.check_all_cell();
+                } else {
+                    field.trial_and_error(opts.search_depth);
+                }
                 assert!(!field.inconsistent());
                 assert!(field.fully_solved());
 
@@ -420,6 +472,7 @@ mod tests {
                 vec![true, true, true, true, true],
             ],
             &dic,
+            &GeneratorOption::new(),
             &mut rng,
         );
 
@@ -432,6 +485,41 @@ mod tests {
                 vec![true, true, true, false, true],
             ],
             &dic,
+            &GeneratorOption::new(),
+            &mut rng,
+        );
+
+        run_placement_test(
+            vec![
+                vec![false, false, false, false, false],
+                vec![false, true, false, true, true],
+                vec![false, true, false, true, false],
+                vec![false, false, false, true, true],
+                vec![true, false, false, true, false],
+            ],
+            &dic,
+            &GeneratorOption {
+                technique: Technique::new(),
+                search_depth: 1,
+            },
+            &mut rng,
+        );
+
+        let mut technique = Technique::new();
+        technique.dictionary = false;
+        run_placement_test(
+            vec![
+                vec![true, true, true, true, true],
+                vec![true, false, false, false, true],
+                vec![true, false, false, false, true],
+                vec![true, false, false, false, true],
+                vec![true, true, true, true, true],
+            ],
+            &dic,
+            &GeneratorOption {
+                technique,
+                search_depth: 0,
+            },
             &mut rng,
         );
     }
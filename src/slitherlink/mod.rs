@@ -1,8 +1,12 @@
+#[cfg(test)]
+mod brute_force;
+mod checker;
 mod dictionary;
 mod field;
 mod generator;
 mod io;
 
+pub use self::checker::*;
 pub use self::dictionary::*;
 pub use self::field::*;
 pub use self::generator::*;
@@ -11,3 +15,50 @@ pub use self::io::*;
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Clue(pub i32);
 const NO_CLUE: Clue = Clue(-1);
+
+#[derive(Clone, Copy)]
+pub struct Technique {
+    pub dictionary: bool,
+    /// Takes effect only if a `TwoCellDictionary` is given to the field.
+    pub two_cell_dictionary: bool,
+    pub adjacent_three: bool,
+    pub diagonal_three: bool,
+    pub corner: bool,
+    pub border_two: bool,
+    pub avoid_small_loop: bool,
+    pub inout_rule: bool,
+    pub connectability: bool,
+    /// Soundness mode: the rules assuming that the answer is not a small loop around a few
+    /// clues (adjacent 3's and `TwoCellDictionary`) are suppressed if the small loop can be
+    /// the answer. This mode is not affected by `new` or `disabled_all`.
+    pub sound: bool,
+}
+
+impl Technique {
+    pub fn new() -> Technique {
+        Technique::with_all(true)
+    }
+    pub fn disabled_all() -> Technique {
+        Technique::with_all(false)
+    }
+    fn with_all(val: bool) -> Technique {
+        Technique {
+            dictionary: val,
+            two_cell_dictionary: val,
+            adjacent_three: val,
+            diagonal_three: val,
+            corner: val,
+            border_two: val,
+            avoid_small_loop: val,
+            inout_rule: val,
+            connectability: val,
+            sound: false,
+        }
+    }
+}
+
+impl Default for Technique {
+    fn default() -> Technique {
+        Technique::new()
+    }
+}
@@ -0,0 +1,185 @@
+use super::*;
+use {Grid, ViolationKind, D, FOUR_NEIGHBOURS, P};
+
+use rand::prng::XorShiftRng;
+use rand::{Rng, SeedableRng};
+
+/// The 8 neighbours of a cell in the clockwise order.
+const EIGHT_NEIGHBOURS: [D; 8] = [
+    D(-1, -1),
+    D(-1, 0),
+    D(-1, 1),
+    D(0, 1),
+    D(1, 1),
+    D(1, 0),
+    D(1, -1),
+    D(0, -1),
+];
+
+/// Computes the clue at `pos` given by the black cells in `is_black`.
+fn compute_clue(is_black: &Grid<bool>, pos: P) -> Clue {
+    let black = EIGHT_NEIGHBOURS
+        .iter()
+        .map(|&d| is_black.get_or_default_p(pos + d, false))
+        .collect::<Vec<_>>();
+    let mut pattern = vec![];
+    match black.iter().position(|&b| !b) {
+        None => pattern.push(8),
+        Some(start) => {
+            let mut run = 0;
+            for i in 1..9 {
+                if black[(start + i) % 8] {
+                    run += 1;
+                } else if run > 0 {
+                    pattern.push(run);
+                    run = 0;
+                }
+            }
+        }
+    }
+    clue_pattern_to_id(&pattern).unwrap()
+}
+
+fn is_connected(is_black: &Grid<bool>) -> bool {
+    let height = is_black.height();
+    let width = is_black.width();
+    let mut visited = Grid::new(height, width, false);
+    let blacks = (0..(height * width))
+        .map(|i| P(i / width, i % width))
+        .filter(|&pos| is_black[pos])
+        .collect::<Vec<_>>();
+    let mut stack = vec![];
+    if let Some(&start) = blacks.first() {
+        visited[start] = true;
+        stack.push(start);
+    }
+    let mut n_visited = 0;
+    while let Some(pos) = stack.pop() {
+        n_visited += 1;
+        for &d in &FOUR_NEIGHBOURS {
+            let pos2 = pos + d;
+            if is_black.get_or_default_p(pos2, false) && !visited[pos2] {
+                visited[pos2] = true;
+                stack.push(pos2);
+            }
+        }
+    }
+    n_visited == blacks.len()
+}
+
+/// Enumerates all the colourings of a `height` x `width` board in which black cells are
+/// connected and have no 2x2 cluster.
+pub fn enumerate_shapes(height: i32, width: i32) -> Vec<Grid<bool>> {
+    let mut ret = vec![];
+    for mask in 0u32..(1u32 << (height * width)) {
+        let mut is_black = Grid::new(height, width, false);
+        for i in 0..(height * width) {
+            is_black[P(i / width, i % width)] = (mask >> i) & 1 != 0;
+        }
+        let no_2x2 = (0..(height - 1)).all(|y| {
+            (0..(width - 1)).all(|x| {
+                !([D(0, 0), D(0, 1), D(1, 0), D(1, 1)]
+                    .iter()
+                    .all(|&d| is_black[P(y, x) + d]))
+            })
+        });
+        if no_2x2 && is_connected(&is_black) {
+            ret.push(is_black);
+        }
+    }
+    ret
+}
+
+/// Enumerates all the answers of `problem` among `shapes`.
+pub fn enumerate_answers(problem: &Grid<Clue>, shapes: &[Grid<bool>]) -> Vec<Grid<bool>> {
+    shapes
+        .iter()
+        .filter(|is_black| {
+            (0..problem.height()).all(|y| {
+                (0..problem.width()).all(|x| {
+                    let pos = P(y, x);
+                    problem[pos] == NO_CLUE
+                        || (!is_black[pos] && compute_clue(is_black, pos) == problem[pos])
+                })
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn test_random_against_brute_force() {
+    let mut rng = XorShiftRng::from_seed([42u8; 16]);
+    let dic = Dictionary::shared();
+    let consecutive_dic = ConsecutiveRegionDictionary::shared();
+
+    for &(height, width) in &[(2, 2), (2, 3), (3, 3), (3, 4), (4, 4)] {
+        let shapes = enumerate_shapes(height, width);
+        for _ in 0..100 {
+            // clues on some white cells of a random shape
+            let base = &shapes[rng.gen_range(0, shapes.len())];
+            let mut problem = Grid::new(height, width, NO_CLUE);
+            for y in 0..height {
+                for x in 0..width {
+                    let pos = P(y, x);
+                    if !base[pos] && rng.gen::<f64>() < 0.4 {
+                        problem[pos] = compute_clue(base, pos);
+                    }
+                }
+            }
+            let answers = enumerate_answers(&problem, &shapes);
+            for answer in &answers {
+                assert!(check_answer(&problem, answer).is_empty());
+            }
+            for _ in 0..10 {
+                let candidate = &shapes[rng.gen_range(0, shapes.len())];
+                let violations = check_answer(&problem, candidate);
+                assert_eq!(violations.is_empty(), answers.contains(candidate));
+            }
+
+            let mut field = Field::new(height, width, &dic, &consecutive_dic);
+            for y in 0..height {
+                for x in 0..width {
+                    if problem[P(y, x)] != NO_CLUE {
+                        field.add_clue(P(y, x), problem[P(y, x)]);
+                    }
+                }
+            }
+            field.trial_and_error();
+            assert!(!field.inconsistent());
+            for answer in &answers {
+                for y in 0..height {
+                    for x in 0..width {
+                        let expected = if answer[P(y, x)] {
+                            Cell::Black
+                        } else {
+                            Cell::White
+                        };
+                        let actual = field.cell(P(y, x));
+                        assert!(actual == Cell::Undecided || actual == expected);
+                        assert!(!field.fully_solved() || actual == expected);
+                    }
+                }
+            }
+            assert!(!field.fully_solved() || answers.len() == 1);
+        }
+    }
+}
+
+#[test]
+fn test_check_answer() {
+    let problem = Grid::new(3, 3, NO_CLUE);
+    let mut is_black = Grid::new(3, 3, false);
+    for &p in &[P(0, 0), P(0, 1), P(1, 0), P(1, 1), P(2, 2)] {
+        is_black[p] = true;
+    }
+    let violations = check_answer(&problem, &is_black);
+    assert_eq!(violations.len(), 2);
+    assert_eq!(violations[0].kind, ViolationKind::Block2x2);
+    assert_eq!(
+        violations[0].cells,
+        vec![P(0, 0), P(0, 1), P(1, 0), P(1, 1)]
+    );
+    assert_eq!(violations[1].kind, ViolationKind::Disconnected);
+    assert_eq!(violations[1].cells, vec![P(2, 2)]);
+}
@@ -0,0 +1,74 @@
+use super::super::{check_connectivity, Grid, Violation, ViolationKind, D, P};
+use super::*;
+
+/// The 8 neighbours of a cell in the clockwise order.
+const EIGHT_NEIGHBOURS: [D; 8] = [
+    D(-1, -1),
+    D(-1, 0),
+    D(-1, 1),
+    D(0, 1),
+    D(1, 1),
+    D(1, 0),
+    D(1, -1),
+    D(0, -1),
+];
+
+/// Checks whether the colouring `is_black` is an answer of `problem`.
+/// The violations reported are:
+/// - `BlackClue`: a clue cell painted black.
+/// - `Clue`: a clue which differs from the black cells around it.
+/// - `Block2x2`: a 2x2 block of black cells.
+/// - `Disconnected`: a group of connected black cells other than the largest one.
+pub fn check_answer(problem: &Grid<Clue>, is_black: &Grid<bool>) -> Vec<Violation> {
+    let height = problem.height();
+    let width = problem.width();
+    let mut ret = vec![];
+
+    for y in 0..height {
+        for x in 0..width {
+            let pos = P(y, x);
+            if problem[pos] == NO_CLUE {
+                continue;
+            }
+            if is_black[pos] {
+                ret.push(Violation::at_cells(ViolationKind::BlackClue, vec![pos]));
+            } else if compute_clue(is_black, pos) != Some(problem[pos]) {
+                ret.push(Violation::at_cells(ViolationKind::Clue, vec![pos]));
+            }
+        }
+    }
+    for y in 0..(height - 1) {
+        for x in 0..(width - 1) {
+            let block = [P(y, x), P(y, x + 1), P(y + 1, x), P(y + 1, x + 1)];
+            if block.iter().all(|&p| is_black[p]) {
+                ret.push(Violation::at_cells(ViolationKind::Block2x2, block.to_vec()));
+            }
+        }
+    }
+    ret.extend(check_connectivity(is_black, ViolationKind::Disconnected));
+    ret
+}
+
+/// Computes the clue at `pos` given by the black cells in `is_black`.
+fn compute_clue(is_black: &Grid<bool>, pos: P) -> Option<Clue> {
+    let black = EIGHT_NEIGHBOURS
+        .iter()
+        .map(|&d| is_black.get_or_default_p(pos + d, false))
+        .collect::<Vec<_>>();
+    let mut pattern = vec![];
+    match black.iter().position(|&b| !b) {
+        None => pattern.push(8),
+        Some(start) => {
+            let mut run = 0;
+            for i in 1..9 {
+                if black[(start + i) % 8] {
+                    run += 1;
+                } else if run > 0 {
+                    pattern.push(run);
+                    run = 0;
+                }
+            }
+        }
+    }
+    clue_pattern_to_id(&pattern)
+}
@@ -1,5 +1,8 @@
 use super::super::D;
 use super::{Cell, Clue, CLUE_TYPES, CLUE_VALUES};
+use io::{read_binary_header, read_u32_table, write_binary_header, write_u32_table, ReadError};
+use std::io::{self, Read, Write};
+use std::sync::{Arc, OnceLock};
 
 pub const DICTIONARY_NEIGHBOR_SIZE: usize = 8;
 pub const DICTIONARY_NEIGHBOR_OFFSET: [D; DICTIONARY_NEIGHBOR_SIZE] = [
@@ -24,6 +27,11 @@ const DICTIONARY_VIRTUALLY_IGNORED_CELL_COUNT: usize = 1 << 16; // 4^8 = 2^16
 const DICTIONARY_VIRTUALLY_IGNORED_TOTAL_SIZE: usize =
     DICTIONARY_VIRTUALLY_IGNORED_CELL_COUNT * CLUE_TYPES;
 
+const DICTIONARY_BINARY_TAG: &[u8; 4] = b"TPDC";
+/// Version of the contents of the binary dictionary; bump it whenever `new` changes.
+const DICTIONARY_BINARY_VERSION: u32 = 1;
+
+#[derive(PartialEq, Eq)]
 pub struct Dictionary {
     neighbor_pattern: Vec<u32>,
     virtually_ignored_cell: Vec<u32>,
@@ -169,6 +177,32 @@ impl Dictionary {
         }
     }
 
+    /// Returns the process-wide dictionary, which is built by `new` on the first call.
+    pub fn shared() -> Arc<Dictionary> {
+        static SHARED: OnceLock<Arc<Dictionary>> = OnceLock::new();
+        SHARED.get_or_init(|| Arc::new(Dictionary::new())).clone()
+    }
+    /// Writes the dictionary in a binary format which can be loaded by `read_binary`.
+    pub fn write_binary<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_binary_header(writer, DICTIONARY_BINARY_TAG, DICTIONARY_BINARY_VERSION)?;
+        write_u32_table(writer, &self.neighbor_pattern)?;
+        write_u32_table(writer, &self.virtually_ignored_cell)?;
+        write_u32_table(writer, &self.virtual_disconnection)
+    }
+    /// Loads a dictionary written by `write_binary` instead of building it by `new`.
+    pub fn read_binary<R: Read>(reader: &mut R) -> Result<Dictionary, ReadError> {
+        read_binary_header(reader, DICTIONARY_BINARY_TAG, DICTIONARY_BINARY_VERSION)?;
+        let neighbor_pattern = read_u32_table(reader, DICTIONARY_NEIGHBOR_PATTERN_TOTAL_SIZE)?;
+        let virtually_ignored_cell =
+            read_u32_table(reader, DICTIONARY_VIRTUALLY_IGNORED_TOTAL_SIZE)?;
+        let virtual_disconnection = read_u32_table(reader, DICTIONARY_NEIGHBOR_PATTERN_TOTAL_SIZE)?;
+        Ok(Dictionary {
+            neighbor_pattern,
+            virtually_ignored_cell,
+            virtual_disconnection,
+        })
+    }
+
     pub fn neighbor_pattern_raw(&self, c: Clue, neighbor_code: u32) -> u32 {
         let Clue(c) = c;
         self.neighbor_pattern
@@ -277,6 +311,13 @@ pub const CONSECUTIVE_DICTIONARY_ADJACENCY_OFFSET: [D; CONSECUTIVE_DICTIONARY_AD
 const CONSECUTIVE_DICTIONARY_REMOVAL_SIZE: usize = CONSECUTIVE_DICTIONARY_NEIGHBOR_PATTERN_COUNT
     * DICTIONARY_NEIGHBOR_SIZE
     * CONSECUTIVE_DICTIONARY_ADJACENCY_SIZE;
+const CONSECUTIVE_DICTIONARY_CHAIN_SIZE: usize =
+    CONSECUTIVE_DICTIONARY_NEIGHBOR_PATTERN_COUNT * DICTIONARY_NEIGHBOR_SIZE;
+const CONSECUTIVE_DICTIONARY_BINARY_TAG: &[u8; 4] = b"TPCR";
+/// Version of the contents of the binary dictionary; bump it whenever `new` changes.
+const CONSECUTIVE_DICTIONARY_BINARY_VERSION: u32 = 1;
+
+#[derive(PartialEq, Eq)]
 pub struct ConsecutiveRegionDictionary {
     dic: Vec<u32>,
     removal_dic: Vec<i32>,
@@ -361,8 +402,7 @@ impl ConsecutiveRegionDictionary {
         }
 
         let mut removal_dic = vec![0i32; CONSECUTIVE_DICTIONARY_REMOVAL_SIZE];
-        let mut chain_size_dic =
-            vec![0i32; CONSECUTIVE_DICTIONARY_NEIGHBOR_PATTERN_COUNT * DICTIONARY_NEIGHBOR_SIZE];
+        let mut chain_size_dic = vec![0i32; CONSECUTIVE_DICTIONARY_CHAIN_SIZE];
         for pat_id in 0..CONSECUTIVE_DICTIONARY_NEIGHBOR_PATTERN_COUNT {
             for nb in 0..DICTIONARY_NEIGHBOR_SIZE {
                 if (pat_id >> nb) & 1 == 0 {
@@ -408,6 +448,57 @@ impl ConsecutiveRegionDictionary {
             chain_size_dic,
         }
     }
+    /// Returns the process-wide dictionary, which is built by `new` from `Dictionary::shared()`
+    /// on the first call.
+    pub fn shared() -> Arc<ConsecutiveRegionDictionary> {
+        static SHARED: OnceLock<Arc<ConsecutiveRegionDictionary>> = OnceLock::new();
+        SHARED
+            .get_or_init(|| Arc::new(ConsecutiveRegionDictionary::new(&Dictionary::shared())))
+            .clone()
+    }
+    /// Writes the dictionary in a binary format which can be loaded by `read_binary`.
+    pub fn write_binary<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write_binary_header(
+            writer,
+            CONSECUTIVE_DICTIONARY_BINARY_TAG,
+            CONSECUTIVE_DICTIONARY_BINARY_VERSION,
+        )?;
+        write_u32_table(writer, &self.dic)?;
+        let removal_dic = self
+            .removal_dic
+            .iter()
+            .map(|&v| v as u32)
+            .collect::<Vec<_>>();
+        write_u32_table(writer, &removal_dic)?;
+        let chain_size_dic = self
+            .chain_size_dic
+            .iter()
+            .map(|&v| v as u32)
+            .collect::<Vec<_>>();
+        write_u32_table(writer, &chain_size_dic)
+    }
+    /// Loads a dictionary written by `write_binary` instead of building it by `new`.
+    pub fn read_binary<R: Read>(reader: &mut R) -> Result<ConsecutiveRegionDictionary, ReadError> {
+        read_binary_header(
+            reader,
+            CONSECUTIVE_DICTIONARY_BINARY_TAG,
+            CONSECUTIVE_DICTIONARY_BINARY_VERSION,
+        )?;
+        let dic = read_u32_table(reader, CONSECUTIVE_DICTIONARY_SIZE)?;
+        let removal_dic = read_u32_table(reader, CONSECUTIVE_DICTIONARY_REMOVAL_SIZE)?
+            .into_iter()
+            .map(|v| v as i32)
+            .collect();
+        let chain_size_dic = read_u32_table(reader, CONSECUTIVE_DICTIONARY_CHAIN_SIZE)?
+            .into_iter()
+            .map(|v| v as i32)
+            .collect();
+        Ok(ConsecutiveRegionDictionary {
+            dic,
+            removal_dic,
+            chain_size_dic,
+        })
+    }
     pub fn consult(&self, c: Clue, neighbor_code: u32) -> u32 {
         let Clue(c) = c;
         self.dic
@@ -525,4 +616,27 @@ mod tests {
             assert_eq!(dic.consult(clue, original), expected);
         }
     }
+
+    #[test]
+    fn test_tapa_binary_dictionary() {
+        let dic = Dictionary::new();
+        let consecutive_dic = ConsecutiveRegionDictionary::new(&dic);
+        let mut buf = vec![];
+        dic.write_binary(&mut buf).unwrap();
+        consecutive_dic.write_binary(&mut buf).unwrap();
+
+        let mut src = &buf[..];
+        let loaded = Dictionary::read_binary(&mut src).unwrap();
+        let loaded_consecutive = ConsecutiveRegionDictionary::read_binary(&mut src).unwrap();
+        assert!(loaded == dic);
+        assert!(loaded_consecutive == consecutive_dic);
+        assert!(src.is_empty());
+
+        let mut src = &buf[..];
+        assert!(ConsecutiveRegionDictionary::read_binary(&mut src).is_err());
+
+        let mut stale = buf.clone();
+        stale[4] ^= 0xff;
+        assert!(Dictionary::read_binary(&mut &stale[..]).is_err());
+    }
 }
@@ -1,11 +1,12 @@
-use super::super::{GraphSeparation, Grid, D, P};
+use super::super::{GraphSeparation, Grid, SharedRef, SolverStats, D, P};
 use super::{
-    Cell, Clue, ConsecutiveRegionDictionary, Dictionary, CLUE_MAX, CLUE_VALUES,
+    check_answer, Cell, Clue, ConsecutiveRegionDictionary, Dictionary, CLUE_MAX, CLUE_VALUES,
     CONSECUTIVE_DICTIONARY_ADJACENCY_OFFSET, CONSECUTIVE_DICTIONARY_ADJACENCY_SIZE,
     DICTIONARY_INCONSISTENT, DICTIONARY_NEIGHBOR_OFFSET, DICTIONARY_NEIGHBOR_SIZE, NO_CLUE,
 };
 use std::cmp;
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 #[derive(Clone)]
 pub struct Field<'a, 'b> {
@@ -13,26 +14,30 @@ pub struct Field<'a, 'b> {
     clue: Grid<Clue>,
     inconsistent: bool,
     decided_cells: i32,
-    dic: &'a Dictionary,
-    consecutive_dic: &'b ConsecutiveRegionDictionary,
+    dic: SharedRef<'a, Dictionary>,
+    consecutive_dic: SharedRef<'b, ConsecutiveRegionDictionary>,
     checking_region: Option<(P, P)>,
+    stats: SolverStats,
 }
 
 impl<'a, 'b> Field<'a, 'b> {
-    pub fn new(
-        height: i32,
-        width: i32,
-        dic: &'a Dictionary,
-        consecutive_dic: &'b ConsecutiveRegionDictionary,
-    ) -> Field<'a, 'b> {
+    /// Creates a field with borrowed dictionaries or shared ones (`Arc`).
+    /// Shared dictionaries (e.g. `Dictionary::shared()`) give `Field<'static, 'static>`,
+    /// which can be sent across threads.
+    pub fn new<T, U>(height: i32, width: i32, dic: T, consecutive_dic: U) -> Field<'a, 'b>
+    where
+        T: Into<SharedRef<'a, Dictionary>>,
+        U: Into<SharedRef<'b, ConsecutiveRegionDictionary>>,
+    {
         Field {
             cell: Grid::new(height, width, Cell::Undecided),
             clue: Grid::new(height, width, NO_CLUE),
             inconsistent: false,
             decided_cells: 0,
-            dic,
-            consecutive_dic,
+            dic: dic.into(),
+            consecutive_dic: consecutive_dic.into(),
             checking_region: None,
+            stats: SolverStats::new(),
         }
     }
     pub fn height(&self) -> i32 {
@@ -50,6 +55,14 @@ impl<'a, 'b> Field<'a, 'b> {
     pub fn decided_cells(&self) -> i32 {
         self.decided_cells
     }
+    /// Returns the statistics of the connectivity techniques (in decided cells) and the
+    /// trial and error, including the work in the discarded trials.
+    pub fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+    pub fn set_stats_enabled(&mut self, enabled: bool) {
+        self.stats.set_enabled(enabled);
+    }
     pub fn fully_solved(&self) -> bool {
         self.decided_cells == self.height() * self.width()
     }
@@ -593,14 +606,21 @@ impl<'a, 'b> Field<'a, 'b> {
     pub fn solve(&mut self) {
         while !self.inconsistent {
             let decided_cells = self.decided_cells;
-            self.inspect_connectivity();
-            self.inspect_connectivity_advanced();
+            self.apply_technique("connectivity", |field| field.inspect_connectivity());
+            self.apply_technique("connectivity_advanced", |field| {
+                field.inspect_connectivity_advanced()
+            });
             if self.decided_cells == decided_cells {
                 break;
             }
         }
     }
     pub fn trial_and_error(&mut self) {
+        self.trial_and_error_cancellable(&AtomicBool::new(false))
+    }
+    /// Same as `trial_and_error`, but gives up when `cancel` is set, keeping the cells
+    /// decided so far.
+    pub fn trial_and_error_cancellable(&mut self, cancel: &AtomicBool) {
         let height = self.height();
         let width = self.width();
         let mut updated = true;
@@ -612,13 +632,17 @@ impl<'a, 'b> Field<'a, 'b> {
             updated = false;
             for y in 0..height {
                 for x in 0..width {
+                    if cancel.load(Ordering::Relaxed) {
+                        return;
+                    }
                     let pos = P(y, x);
                     if self.cell(pos) == Cell::Undecided {
                         let checking_region = (pos + D(-2, -2), pos + D(3, 3));
-                        let mut trial_black = self.clone();
+                        let mut trial_black = self.clone_for_trial();
                         trial_black.checking_region = Some(checking_region);
                         trial_black.decide(pos, Cell::Black);
                         trial_black.solve();
+                        self.record_trial(&trial_black);
 
                         if trial_black.inconsistent() {
                             self.decide(pos, Cell::White);
@@ -626,10 +650,11 @@ impl<'a, 'b> Field<'a, 'b> {
                             updated = true;
                         }
 
-                        let mut trial_white = self.clone();
+                        let mut trial_white = self.clone_for_trial();
                         trial_white.checking_region = Some(checking_region);
                         trial_white.decide(pos, Cell::White);
                         trial_white.solve();
+                        self.record_trial(&trial_white);
 
                         if trial_white.inconsistent() {
                             self.decide(pos, Cell::Black);
@@ -644,6 +669,79 @@ impl<'a, 'b> Field<'a, 'b> {
             }
         }
     }
+    /// Enumerates the answers of the problem, up to `limit` answers.
+    /// Each answer is given as whether each cell is black.
+    pub fn search(&self, limit: usize) -> Vec<Grid<bool>> {
+        self.search_cancellable(limit, &AtomicBool::new(false))
+    }
+    /// Same as `search`, but gives up when `cancel` is set, returning the answers found so far.
+    pub fn search_cancellable(&self, limit: usize, cancel: &AtomicBool) -> Vec<Grid<bool>> {
+        let mut answers = vec![];
+        self.clone_for_trial()
+            .search_dfs(limit, cancel, &mut answers);
+        answers
+    }
+    fn search_dfs(&mut self, limit: usize, cancel: &AtomicBool, answers: &mut Vec<Grid<bool>>) {
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        self.solve();
+        if self.inconsistent {
+            return;
+        }
+        let height = self.height();
+        let width = self.width();
+        let mut branch = None;
+        for y in 0..height {
+            for x in 0..width {
+                if branch.is_none() && self.cell(P(y, x)) == Cell::Undecided {
+                    branch = Some(P(y, x));
+                }
+            }
+        }
+        let pos = match branch {
+            Some(pos) => pos,
+            None => {
+                let mut is_black = Grid::new(height, width, false);
+                for y in 0..height {
+                    for x in 0..width {
+                        is_black[P(y, x)] = self.cell(P(y, x)) == Cell::Black;
+                    }
+                }
+                // the techniques do not detect all violations of a fully decided board
+                if check_answer(&self.clue, &is_black).is_empty() {
+                    answers.push(is_black);
+                }
+                return;
+            }
+        };
+        for &v in &[Cell::Black, Cell::White] {
+            if answers.len() >= limit {
+                return;
+            }
+            let mut trial = self.clone_for_trial();
+            trial.decide(pos, v);
+            trial.search_dfs(limit, cancel, answers);
+        }
+    }
+    /// Applies `technique` and records the number of cells decided by it.
+    fn apply_technique<F: FnOnce(&mut Field<'a, 'b>)>(&mut self, name: &'static str, technique: F) {
+        let decided_cells = self.decided_cells;
+        technique(self);
+        let n_decided = self.decided_cells - decided_cells;
+        self.stats.record(name, n_decided);
+    }
+    /// Returns a clone of this field for a trial, whose statistics start from zero.
+    fn clone_for_trial(&self) -> Field<'a, 'b> {
+        let mut ret = self.clone();
+        ret.stats.clear();
+        ret
+    }
+    /// Adds the work done in `trial`, made by `clone_for_trial`, to the statistics.
+    fn record_trial(&mut self, trial: &Field<'a, 'b>) {
+        self.stats.merge(&trial.stats);
+        self.stats.record_trial(trial.inconsistent());
+    }
 }
 
 impl<'a, 'b> fmt::Display for Field<'a, 'b> {
@@ -689,6 +787,28 @@ impl<'a, 'b> fmt::Display for Field<'a, 'b> {
 mod tests {
     use super::super::clue_pattern_to_id;
     use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_tapa_shared_dictionary() {
+        let mut field: Field<'static, 'static> = Field::new(
+            5,
+            6,
+            Dictionary::shared(),
+            ConsecutiveRegionDictionary::shared(),
+        );
+        let field = thread::spawn(move || {
+            field.add_clue(P(2, 1), clue_pattern_to_id(&[]).unwrap());
+            field.add_clue(P(2, 3), clue_pattern_to_id(&[4]).unwrap());
+            field
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(field.cell(P(2, 0)), Cell::White);
+        assert_eq!(field.cell(P(2, 4)), Cell::Black);
+        assert!(!field.inconsistent());
+    }
 
     #[test]
     fn test_tapa_field_clues() {
@@ -854,6 +974,29 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tapa_field_search() {
+        let dic = Dictionary::new();
+        let consecutive_dic = ConsecutiveRegionDictionary::new(&dic);
+
+        let mut field = Field::new(6, 5, &dic, &consecutive_dic);
+        field.add_clue(P(1, 0), clue_pattern_to_id(&[1, 3]).unwrap());
+        field.add_clue(P(1, 2), clue_pattern_to_id(&[2, 4]).unwrap());
+        field.add_clue(P(3, 1), clue_pattern_to_id(&[3, 3]).unwrap());
+        field.add_clue(P(4, 3), clue_pattern_to_id(&[4]).unwrap());
+
+        let decided_cells = field.decided_cells();
+        let answers = field.search(2);
+        assert_eq!(answers.len(), 1);
+        assert!(answers[0][P(0, 0)]);
+        assert!(!answers[0][P(5, 4)]);
+        // `search` leaves the field unchanged
+        assert_eq!(field.decided_cells(), decided_cells);
+
+        let cancel = AtomicBool::new(true);
+        assert!(field.search_cancellable(2, &cancel).is_empty());
+    }
+
     #[test]
     fn test_tapa_field_connectivity_clue_aware() {
         let dic = Dictionary::new();
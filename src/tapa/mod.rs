@@ -1,8 +1,12 @@
+#[cfg(test)]
+mod brute_force;
+mod checker;
 mod field;
 mod dictionary;
 mod generator;
 mod io;
 
+pub use self::checker::*;
 pub use self::field::*;
 pub use self::dictionary::*;
 pub use self::generator::*;
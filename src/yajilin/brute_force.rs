@@ -0,0 +1,160 @@
+use super::*;
+use grid_loop::brute_force::{assert_consistent_edges, enumerate_loops, is_on_loop};
+use {Grid, FOUR_NEIGHBOURS, P};
+
+use rand::prng::XorShiftRng;
+use rand::{Rng, SeedableRng};
+
+/// Returns whether `pos` is a blocked cell in the answer `answer` of `problem`.
+fn is_blocked(problem: &Grid<Clue>, answer: &Grid<bool>, pos: P) -> bool {
+    problem[pos] == Clue::NoClue && !is_on_loop(answer, pos)
+}
+
+/// Returns whether the clue at `pos` is satisfied by the blocked cells in `blocked`.
+fn is_satisfied(problem: &Grid<Clue>, blocked: &Grid<bool>, pos: P) -> bool {
+    let clue = problem[pos];
+    if clue == Clue::NoClue || clue == Clue::Empty {
+        return true;
+    }
+    let d = clue.get_direction();
+    let mut n_blocked = 0;
+    let mut p = pos + d;
+    while problem.is_valid_p(p) {
+        if blocked[p] {
+            n_blocked += 1;
+        }
+        p = p + d;
+    }
+    n_blocked == clue.clue_number()
+}
+
+fn blocked_cells(problem: &Grid<Clue>, answer: &Grid<bool>) -> Grid<bool> {
+    let mut ret = Grid::new(problem.height(), problem.width(), false);
+    for y in 0..problem.height() {
+        for x in 0..problem.width() {
+            ret[P(y, x)] = is_blocked(problem, answer, P(y, x));
+        }
+    }
+    ret
+}
+
+/// Enumerates all the answers of `problem`.
+pub fn enumerate_answers(problem: &Grid<Clue>) -> Vec<Grid<bool>> {
+    let height = problem.height();
+    let width = problem.width();
+    let mut allowed = Grid::new(height, width, false);
+    for y in 0..height {
+        for x in 0..width {
+            allowed[P(y, x)] = problem[P(y, x)] == Clue::NoClue;
+        }
+    }
+    enumerate_loops(&allowed)
+        .into_iter()
+        .filter(|answer| {
+            let blocked = blocked_cells(problem, answer);
+            (0..height).all(|y| {
+                (0..width).all(|x| {
+                    let pos = P(y, x);
+                    is_satisfied(problem, &blocked, pos)
+                        && (!blocked[pos]
+                            || FOUR_NEIGHBOURS
+                                .iter()
+                                .all(|&d| !blocked.get_or_default_p(pos + d, false)))
+                })
+            })
+        })
+        .collect()
+}
+
+#[test]
+fn test_random_against_brute_force() {
+    let mut rng = XorShiftRng::from_seed([42u8; 16]);
+
+    for &(height, width) in &[(2, 2), (2, 3), (3, 3), (3, 4), (4, 4), (4, 5), (5, 5)] {
+        let loops = enumerate_loops(&Grid::new(height, width, true));
+        for _ in 0..100 {
+            // cells not passed by a random loop are blocked, or become clues if a neighbour
+            // is already blocked
+            let base = &loops[rng.gen_range(0, loops.len())];
+            let mut problem = Grid::new(height, width, Clue::NoClue);
+            let mut blocked = Grid::new(height, width, false);
+            for y in 0..height {
+                for x in 0..width {
+                    let pos = P(y, x);
+                    if is_on_loop(base, pos) {
+                        continue;
+                    }
+                    if FOUR_NEIGHBOURS
+                        .iter()
+                        .any(|&d| blocked.get_or_default_p(pos + d, false))
+                        || rng.gen::<f64>() < 0.3
+                    {
+                        problem[pos] = Clue::Empty;
+                    } else {
+                        blocked[pos] = true;
+                    }
+                }
+            }
+            for y in 0..height {
+                for x in 0..width {
+                    let pos = P(y, x);
+                    if problem[pos] != Clue::Empty || rng.gen::<f64>() < 0.3 {
+                        continue;
+                    }
+                    let clue = match rng.gen_range(0, 4) {
+                        0 => Clue::Up(0),
+                        1 => Clue::Left(0),
+                        2 => Clue::Down(0),
+                        _ => Clue::Right(0),
+                    };
+                    let d = clue.get_direction();
+                    let mut n = 0;
+                    let mut p = pos + d;
+                    while problem.is_valid_p(p) {
+                        if blocked[p] {
+                            n += 1;
+                        }
+                        p = p + d;
+                    }
+                    problem[pos] = match clue {
+                        Clue::Up(_) => Clue::Up(n),
+                        Clue::Left(_) => Clue::Left(n),
+                        Clue::Down(_) => Clue::Down(n),
+                        _ => Clue::Right(n),
+                    };
+                }
+            }
+            let answers = enumerate_answers(&problem);
+            assert!(!answers.is_empty());
+            for answer in &answers {
+                assert!(check_answer(&problem, answer).is_empty());
+            }
+            for _ in 0..10 {
+                let candidate = &loops[rng.gen_range(0, loops.len())];
+                let violations = check_answer(&problem, candidate);
+                assert_eq!(violations.is_empty(), answers.contains(candidate));
+            }
+
+            let mut field = Field::new(&problem);
+            field.trial_and_error(1);
+            assert!(!field.inconsistent());
+            assert_consistent_edges(|lp| field.get_edge(lp), field.fully_solved(), &answers);
+            for answer in &answers {
+                let blocked = blocked_cells(&problem, answer);
+                for y in 0..height {
+                    for x in 0..width {
+                        let expected = if problem[P(y, x)] != Clue::NoClue {
+                            Cell::Clue
+                        } else if blocked[P(y, x)] {
+                            Cell::Blocked
+                        } else {
+                            Cell::Line
+                        };
+                        let actual = field.get_cell(P(y, x));
+                        assert!(actual == Cell::Undecided || actual == expected);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use super::super::{Grid, Violation, ViolationKind, D, LP, P};
+use super::*;
+use grid_loop::{check_loop, line_degree};
+use FOUR_NEIGHBOURS;
+
+/// Checks whether `answer` is an answer of `problem`.
+/// `answer` has the size `(2 * height - 1) x (2 * width - 1)` and is indexed by lattice
+/// positions (`LP::of_vertex(pos)` for the cell `pos`); it is true exactly on the edges of the loop.
+/// Every cell without clues not passed by the loop is regarded as blocked.
+/// The violations reported are:
+/// - `LineOnClue`: a clue cell passed by the loop.
+/// - `Clue`: an arrow whose number differs from the number of blocked cells it points to.
+/// - `AdjacentBlocks`: two adjacent blocked cells.
+/// - those of `grid_loop::check_loop`.
+pub fn check_answer(problem: &Grid<Clue>, answer: &Grid<bool>) -> Vec<Violation> {
+    let height = problem.height();
+    let width = problem.width();
+    assert_eq!(answer.height(), height * 2 - 1);
+    assert_eq!(answer.width(), width * 2 - 1);
+
+    let mut blocked = Grid::new(height, width, false);
+    let mut ret = vec![];
+    for y in 0..height {
+        for x in 0..width {
+            let pos = P(y, x);
+            let degree = line_degree(answer, pos);
+            if problem[pos] == Clue::NoClue {
+                blocked[pos] = degree == 0;
+            } else if degree > 0 {
+                let edges = FOUR_NEIGHBOURS
+                    .iter()
+                    .map(|&d| LP::of_vertex(pos) + d)
+                    .filter(|&e| answer.is_valid_lp(e) && answer[e])
+                    .collect();
+                ret.push(Violation::new(ViolationKind::LineOnClue, vec![pos], edges));
+            }
+        }
+    }
+    for y in 0..height {
+        for x in 0..width {
+            let pos = P(y, x);
+            let clue = problem[pos];
+            if clue.clue_number() >= 0 {
+                let d = clue.get_direction();
+                let mut n_blocked = 0;
+                let mut p = pos + d;
+                while problem.is_valid_p(p) {
+                    if blocked[p] {
+                        n_blocked += 1;
+                    }
+                    p = p + d;
+                }
+                if n_blocked != clue.clue_number() {
+                    ret.push(Violation::at_cells(ViolationKind::Clue, vec![pos]));
+                }
+            }
+            if blocked[pos] {
+                for &d in &[D(0, 1), D(1, 0)] {
+                    if blocked.get_or_default_p(pos + d, false) {
+                        ret.push(Violation::at_cells(
+                            ViolationKind::AdjacentBlocks,
+                            vec![pos, pos + d],
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    ret.extend(check_loop(answer));
+    ret
+}
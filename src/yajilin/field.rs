@@ -1,10 +1,18 @@
-use super::super::{Grid, D, LP, P};
+use super::super::{Grid, SolverStats, D, LP, P};
 use super::*;
-use grid_loop::{Edge, GridLoop, GridLoopField};
+use grid_loop::{Edge, GridLoop, GridLoopField, LineChain};
 use std::cmp;
 use std::fmt;
 use FOUR_NEIGHBOURS;
 
+#[derive(Clone)]
+enum History {
+    Cell(P),
+    BlockedEitherDown(P),
+    BlockedEitherRight(P),
+    Checkpoint,
+}
+
 #[derive(Clone)]
 pub struct Field {
     grid_loop: GridLoop,
@@ -14,6 +22,8 @@ pub struct Field {
     blocked_either_right: Grid<bool>,
     decided_cells: i32,
     technique: Technique,
+    history: Vec<History>,
+    stats: SolverStats,
 }
 
 impl Field {
@@ -50,6 +60,8 @@ impl Field {
             blocked_either_right: Grid::new(height, width - 1, false),
             decided_cells,
             technique: Technique::new(),
+            history: Vec::new(),
+            stats: SolverStats::new(),
         }
     }
     pub fn get_technique(&self) -> Technique {
@@ -90,9 +102,47 @@ impl Field {
     pub fn get_edge_safe(&self, pos: LP) -> Edge {
         self.grid_loop.get_edge_safe(pos)
     }
+    pub fn line_chains(&self) -> Vec<LineChain> {
+        self.grid_loop.line_chains()
+    }
+    pub fn solved_loop(&self) -> Option<Vec<P>> {
+        self.grid_loop.solved_loop()
+    }
     pub fn num_decided_cells(&self) -> i32 {
         self.decided_cells
     }
+    /// Returns the statistics of the techniques and the trial and error.
+    /// The progress is the total number of decided cells and edges. Local rules such as
+    /// `two_by_two` are counted separately as well as a part of the pass triggering them.
+    pub fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+    pub fn set_stats_enabled(&mut self, enabled: bool) {
+        self.stats.set_enabled(enabled);
+    }
+
+    /// Add a checkpoint.
+    /// Subsequent updates of the field (including the underlying `GridLoop`) are recorded
+    /// until `rollback` is called.
+    pub fn add_checkpoint(&mut self) {
+        self.grid_loop.add_checkpoint();
+        self.history.push(History::Checkpoint);
+    }
+    /// Rollback until the last checkpoint.
+    pub fn rollback(&mut self) {
+        self.grid_loop.rollback();
+        while let Some(entry) = self.history.pop() {
+            match entry {
+                History::Cell(pos) => {
+                    self.cell[pos] = Cell::Undecided;
+                    self.decided_cells -= 1;
+                }
+                History::BlockedEitherDown(pos) => self.blocked_either_down[pos] = false,
+                History::BlockedEitherRight(pos) => self.blocked_either_right[pos] = false,
+                History::Checkpoint => break,
+            }
+        }
+    }
 
     pub fn set_cell(&mut self, pos: P, v: Cell) {
         let mut handle = GridLoop::get_handle(self);
@@ -112,13 +162,13 @@ impl Field {
         loop {
             let current_decided_lines = self.grid_loop.num_decided_lines();
             let current_decided_cells = self.num_decided_cells();
-            self.check_all_cell();
-            GridLoop::apply_inout_rule(self);
-            GridLoop::check_connectability(self);
-            self.apply_inout_rule_advanced();
-            self.check_local_parity();
-            self.two_rows_entire_board();
-            self.clue_counting();
+            self.apply_technique("local", Field::check_all_cell);
+            self.apply_technique("inout_rule", GridLoop::apply_inout_rule);
+            self.apply_technique("connectability", GridLoop::check_connectability);
+            self.apply_technique("inout_advanced", Field::apply_inout_rule_advanced);
+            self.apply_technique("local_parity", Field::check_local_parity);
+            self.apply_technique("two_rows", Field::two_rows_entire_board);
+            self.apply_technique("clue_counting", Field::clue_counting);
             if current_decided_lines == self.grid_loop.num_decided_lines()
                 && current_decided_cells == self.num_decided_cells()
             {
@@ -344,27 +394,17 @@ impl Field {
                         continue;
                     }
 
-                    {
-                        let mut field_line = self.clone();
-                        GridLoop::decide_edge(&mut field_line, pos, Edge::Line);
-                        field_line.trial_and_error(depth - 1);
-
-                        if field_line.inconsistent() {
-                            updated = true;
-                            GridLoop::decide_edge(self, pos, Edge::Blank);
-                            self.trial_and_error(depth - 1);
-                        }
+                    if self.is_inconsistent_with_edge(pos, Edge::Line, depth - 1) {
+                        updated = true;
+                        GridLoop::decide_edge(self, pos, Edge::Blank);
+                        self.trial_and_error(depth - 1);
                     }
+                    if self.get_edge(pos) == Edge::Undecided
+                        && self.is_inconsistent_with_edge(pos, Edge::Blank, depth - 1)
                     {
-                        let mut field_blank = self.clone();
-                        GridLoop::decide_edge(&mut field_blank, pos, Edge::Blank);
-                        field_blank.trial_and_error(depth - 1);
-
-                        if field_blank.inconsistent() {
-                            updated = true;
-                            GridLoop::decide_edge(self, pos, Edge::Line);
-                            self.trial_and_error(depth - 1);
-                        }
+                        updated = true;
+                        GridLoop::decide_edge(self, pos, Edge::Line);
+                        self.trial_and_error(depth - 1);
                     }
                     if self.inconsistent() {
                         return;
@@ -377,27 +417,17 @@ impl Field {
                     if self.get_cell(pos) != Cell::Undecided {
                         continue;
                     }
-                    {
-                        let mut field_blocked = self.clone();
-                        field_blocked.set_cell(pos, Cell::Blocked);
-                        field_blocked.trial_and_error(depth - 1);
-
-                        if field_blocked.inconsistent() {
-                            updated = true;
-                            self.set_cell(pos, Cell::Line);
-                            self.trial_and_error(depth - 1);
-                        }
+                    if self.is_inconsistent_with_cell(pos, Cell::Blocked, depth - 1) {
+                        updated = true;
+                        self.set_cell(pos, Cell::Line);
+                        self.trial_and_error(depth - 1);
                     }
+                    if self.get_cell(pos) == Cell::Undecided
+                        && self.is_inconsistent_with_cell(pos, Cell::Line, depth - 1)
                     {
-                        let mut field_line = self.clone();
-                        field_line.set_cell(pos, Cell::Line);
-                        field_line.trial_and_error(depth - 1);
-
-                        if field_line.inconsistent() {
-                            updated = true;
-                            self.set_cell(pos, Cell::Blocked);
-                            self.trial_and_error(depth - 1);
-                        }
+                        updated = true;
+                        self.set_cell(pos, Cell::Blocked);
+                        self.trial_and_error(depth - 1);
                     }
                     if self.inconsistent() {
                         return;
@@ -409,7 +439,44 @@ impl Field {
             }
         }
     }
+    /// Checks whether deciding edge `pos` to `status` leads to an inconsistency
+    /// within trial-and-error of depth `depth`. The field is left unchanged.
+    fn is_inconsistent_with_edge(&mut self, pos: LP, status: Edge, depth: i32) -> bool {
+        self.add_checkpoint();
+        GridLoop::decide_edge(self, pos, status);
+        self.trial_and_error(depth);
+        let ret = self.inconsistent();
+        self.rollback();
+        self.stats.record_trial(ret);
+        ret
+    }
+    /// Checks whether setting cell `pos` to `v` leads to an inconsistency
+    /// within trial-and-error of depth `depth`. The field is left unchanged.
+    fn is_inconsistent_with_cell(&mut self, pos: P, v: Cell, depth: i32) -> bool {
+        self.add_checkpoint();
+        self.set_cell(pos, v);
+        self.trial_and_error(depth);
+        let ret = self.inconsistent();
+        self.rollback();
+        self.stats.record_trial(ret);
+        ret
+    }
 
+    fn num_decided(&self) -> i32 {
+        self.grid_loop.num_decided_edges() + self.decided_cells
+    }
+    /// Records the technique `name` which has been applied since `num_decided` was
+    /// `decided_before`.
+    fn record_technique(&mut self, name: &'static str, decided_before: i32) {
+        let n_decided = self.num_decided() - decided_before;
+        self.stats.record(name, n_decided);
+    }
+    /// Applies `technique` and records the number of cells and edges decided by it.
+    fn apply_technique<F: FnOnce(&mut Field)>(&mut self, name: &'static str, technique: F) {
+        let decided = self.num_decided();
+        technique(self);
+        self.record_technique(name, decided);
+    }
     fn set_cell_internal(&mut self, pos: P, v: Cell) {
         let current = self.cell[pos];
         if current != Cell::Undecided {
@@ -421,6 +488,9 @@ impl Field {
 
         self.decided_cells += 1;
         self.cell[pos] = v;
+        if !self.history.is_empty() {
+            self.history.push(History::Cell(pos));
+        }
         match v {
             Cell::Undecided => (),
             Cell::Clue => (), // don't do this!
@@ -451,17 +521,17 @@ impl Field {
 
         if y1 == y2 {
             if x2 == x1 + 1 {
-                self.blocked_either_right[P(y1, x1)] = true;
+                self.set_blocked_either_right(P(y1, x1));
             } else if x1 == x2 + 1 {
-                self.blocked_either_right[P(y1, x2)] = true;
+                self.set_blocked_either_right(P(y1, x2));
             } else {
                 panic!();
             }
         } else if x1 == x2 {
             if y2 == y1 + 1 {
-                self.blocked_either_down[P(y1, x1)] = true;
+                self.set_blocked_either_down(P(y1, x1));
             } else if y1 == y2 + 1 {
-                self.blocked_either_down[P(y2, x1)] = true;
+                self.set_blocked_either_down(P(y2, x1));
             } else {
                 panic!();
             }
@@ -469,10 +539,27 @@ impl Field {
             panic!();
         }
     }
+    fn set_blocked_either_right(&mut self, pos: P) {
+        if !self.blocked_either_right[pos] {
+            self.blocked_either_right[pos] = true;
+            if !self.history.is_empty() {
+                self.history.push(History::BlockedEitherRight(pos));
+            }
+        }
+    }
+    fn set_blocked_either_down(&mut self, pos: P) {
+        if !self.blocked_either_down[pos] {
+            self.blocked_either_down[pos] = true;
+            if !self.history.is_empty() {
+                self.history.push(History::BlockedEitherDown(pos));
+            }
+        }
+    }
     fn two_by_two(&mut self, top: P) {
         if !self.technique.two_by_two {
             return;
         }
+        let decided = self.num_decided();
         // 2x2 square (y, x) -- (y+1, x+1) has 2 blocked cells
         for &d in &[
             D(-1, 0),
@@ -486,6 +573,7 @@ impl Field {
         ] {
             self.set_cell_internal_unless_clue(top + d, Cell::Line);
         }
+        self.record_technique("two_by_two", decided);
     }
     fn check_two_by_three_rr(
         &mut self,
@@ -551,6 +639,7 @@ impl Field {
         if !self.technique.two_by_three {
             return;
         }
+        let decided = self.num_decided();
         let P(y, x) = top;
 
         if y <= self.height() - 2 && x <= self.width() - 3 {
@@ -609,6 +698,7 @@ impl Field {
             // bb
             self.check_two_by_three_rd(y + 2, x, y, x + 1, y, x, y + 1, x);
         }
+        self.record_technique("two_by_three", decided);
     }
     fn around_blocked_either(&mut self, pos: P) {
         if pos.0 < self.height() - 1 && self.blocked_either_down[pos] {
@@ -998,6 +1088,7 @@ impl Field {
         if !self.technique.avoid_branching {
             return;
         }
+        let decided = self.num_decided();
         for &d in &FOUR_NEIGHBOURS {
             let dr = d.rotate_clockwise();
             let edge1 = self.get_edge_safe(LP::of_vertex(center) + d);
@@ -1019,6 +1110,7 @@ impl Field {
                 self.set_cell_internal_unless_clue(center - d - dr, Cell::Line);
             }
         }
+        self.record_technique("avoid_branching", decided);
     }
     fn inspect_clue(&mut self, cell_cd: P) {
         let (d, involving_cells, n) = self.clue_detail(cell_cd);
@@ -1348,7 +1440,7 @@ impl GridLoopField for Field {
                 }
             }
         } else if cell == Cell::Clue {
-            self.inspect_clue(cell_cd);
+            self.apply_technique("clue", |field| field.inspect_clue(cell_cd));
         }
 
         if cell_cd.0 != self.height() - 1 && self.blocked_either_down[cell_cd] {
@@ -1567,6 +1659,37 @@ mod tests {
         }
     }
     #[test]
+    fn test_rollback() {
+        let mut problem = Grid::new(6, 6, Clue::NoClue);
+        problem[P(1, 2)] = Clue::Right(1);
+        problem[P(3, 4)] = Clue::Left(2);
+
+        let mut field = Field::new(&problem);
+        field.check_all_cell();
+        let reference = field.clone();
+
+        field.add_checkpoint();
+        field.set_cell(P(3, 3), Cell::Line);
+        field.solve();
+        assert!(field.inconsistent());
+        field.rollback();
+
+        assert_eq!(field.inconsistent(), reference.inconsistent());
+        assert_eq!(field.num_decided_cells(), reference.num_decided_cells());
+        for y in 0..problem.height() {
+            for x in 0..problem.width() {
+                assert_eq!(field.get_cell(P(y, x)), reference.get_cell(P(y, x)));
+            }
+        }
+        for y in 0..(problem.height() * 2 - 1) {
+            for x in 0..(problem.width() * 2 - 1) {
+                if y % 2 != x % 2 {
+                    assert_eq!(field.get_edge(LP(y, x)), reference.get_edge(LP(y, x)));
+                }
+            }
+        }
+    }
+    #[test]
     fn test_one_in_three_remote() {
         {
             let mut problem = Grid::new(4, 8, Clue::NoClue);
@@ -1648,4 +1771,26 @@ mod tests {
             assert_eq!(field.get_cell(P(3, 7)), Cell::Line);
         }
     }
+    #[test]
+    fn test_stats() {
+        let mut problem = Grid::new(6, 6, Clue::NoClue);
+        problem[P(1, 2)] = Clue::Right(1);
+        problem[P(3, 4)] = Clue::Left(2);
+
+        let mut field = Field::new(&problem);
+        field.trial_and_error(1);
+        assert_eq!(field.stats().techniques().len(), 0);
+        assert_eq!(field.stats().n_trials(), 0);
+
+        let mut field = Field::new(&problem);
+        field.set_stats_enabled(true);
+        field.trial_and_error(1);
+        let stats = field.stats();
+        assert!(stats.technique("local").n_applied > 0);
+        assert!(stats.n_trials() > 0);
+        assert!(stats.n_refuted() <= stats.n_trials());
+        for &(_, s) in stats.techniques() {
+            assert!(s.n_fired <= s.n_applied);
+        }
+    }
 }
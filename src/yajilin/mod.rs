@@ -1,6 +1,10 @@
+#[cfg(test)]
+mod brute_force;
+mod checker;
 mod field;
 mod generator;
 
+pub use self::checker::*;
 pub use self::field::*;
 pub use self::generator::*;
 use super::D;
@@ -33,6 +33,8 @@ class PuzrsBridge {
             constraints.corner_clue ? constraints.corner_clue.high : -1,
             constraints.minimum_chain_length || 3,
             constraints.forbid_adjacent_clue,
+            constraints.fill_all_cells || false,
+            ({ random: 0, reading: 1, length: 2 })[constraints.numbering] || 0,
             seed1,
             seed2
         );